@@ -0,0 +1,86 @@
+//! Moves the left side of `??` and `..` into the operator node.
+//!
+//! For example, `x ?? 0` is parsed as `x` followed by a `coalesce` node containing `0`.
+//! This is rewritten into a `coalesce` node with two expressions,
+//! such that the lifetime checker and the AST conversion see both sides.
+//! Ranges such as `0..n` are rewritten the same way into a `range` node.
+//! Method calls must be rewritten first, since they might contain `??` or `..`.
+
+use piston_meta::MetaData;
+use range::Range;
+use std::sync::Arc;
+
+use super::method_call::end_of_node;
+
+lazy_static! {
+    static ref COALESCE: Arc<String> = Arc::new("coalesce".into());
+    static ref RANGE: Arc<String> = Arc::new("range".into());
+    static ref EXPR: Arc<String> = Arc::new("expr".into());
+}
+
+/// Returns meta data where `coalesce` and `range` nodes contain both sides.
+pub(crate) fn desugar(data: &[Range<MetaData>]) -> Vec<Range<MetaData>> {
+    let mut res = Vec::with_capacity(data.len());
+    desugar_into(data, &mut res);
+    res
+}
+
+fn desugar_into(data: &[Range<MetaData>], res: &mut Vec<Range<MetaData>>) {
+    let mut i = 0;
+    while i < data.len() {
+        match data[i].data {
+            MetaData::StartNode(_) => {
+                let end = end_of_node(data, i);
+                let mut inner = vec![];
+                desugar_into(&data[i + 1..end], &mut inner);
+                res.push(data[i].clone());
+                coalesce(inner, res);
+                res.push(data[end].clone());
+                i = end + 1;
+            }
+            _ => {
+                res.push(data[i].clone());
+                i += 1;
+            }
+        }
+    }
+}
+
+// Rewrites the content of a node ending with `??` or `..`.
+fn coalesce(mut inner: Vec<Range<MetaData>>, res: &mut Vec<Range<MetaData>>) {
+    // Find the last child node, which might be followed by values such as `?`.
+    let mut last = None;
+    let mut j = 0;
+    while j < inner.len() {
+        match inner[j].data {
+            MetaData::StartNode(_) => {
+                last = Some(j);
+                j = end_of_node(&inner, j) + 1;
+            }
+            _ => j += 1,
+        }
+    }
+    let (k, op) = match last.map(|k| (k, &inner[k].data)) {
+        Some((k, MetaData::StartNode(op))) if k > 0 && (*op == *COALESCE || *op == *RANGE) => {
+            (k, op.clone())
+        }
+        _ => {
+            res.append(&mut inner);
+            return;
+        }
+    };
+    let end = end_of_node(&inner, k);
+
+    let start = inner[0].offset;
+    let left_end = inner[k - 1].next_offset();
+    let right_end = inner[end].next_offset();
+    res.push(Range::empty(start).wrap(MetaData::StartNode(op.clone())));
+    res.push(Range::empty(start).wrap(MetaData::StartNode(EXPR.clone())));
+    res.extend_from_slice(&inner[..k]);
+    res.push(Range::new(start, left_end - start).wrap(MetaData::EndNode(EXPR.clone())));
+    res.push(inner[k].range().wrap(MetaData::StartNode(EXPR.clone())));
+    res.extend_from_slice(&inner[k + 1..end]);
+    res.push(inner[end].range().wrap(MetaData::EndNode(EXPR.clone())));
+    res.push(Range::new(start, right_end - start).wrap(MetaData::EndNode(op)));
+    res.extend_from_slice(&inner[end + 1..]);
+}
@@ -15,6 +15,7 @@ pub fn infer(block: &Block, name: &str) -> Option<Expression> {
             info: Box::new(CallInfo {
                 alias: None,
                 name: Arc::new("len".into()),
+                pipe: false,
                 source_range,
             }),
         }))
@@ -58,6 +59,26 @@ fn infer_expr(expr: &Expression, name: &str, decls: &mut Vec<Arc<String>>) -> Op
                 }
             }
         }
+        Map(ref map) => {
+            for (k, v) in &map.key_values {
+                let res = infer_expr(k, name, decls);
+                if res.is_some() {
+                    return res;
+                }
+                let res = infer_expr(v, name, decls);
+                if res.is_some() {
+                    return res;
+                }
+            }
+        }
+        Set(ref set) => {
+            for expr in &set.items {
+                let res = infer_expr(expr, name, decls);
+                if res.is_some() {
+                    return res;
+                }
+            }
+        }
         Array(ref arr) => {
             for expr in &arr.items {
                 let res = infer_expr(expr, name, decls);
@@ -91,6 +112,22 @@ fn infer_expr(expr: &Expression, name: &str, decls: &mut Vec<Arc<String>>) -> Op
                 return res;
             }
         }
+        Defer(ref defer) => {
+            let res = infer_block(&defer.block, name, decls);
+            if res.is_some() {
+                return res;
+            }
+        }
+        TryCatch(ref try_catch) => {
+            let res = infer_block(&try_catch.block, name, decls);
+            if res.is_some() {
+                return res;
+            }
+            let res = infer_block(&try_catch.catch_block, name, decls);
+            if res.is_some() {
+                return res;
+            }
+        }
         #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         Go(ref go) => {
             let res = infer_call(&go.call, name, decls);
@@ -100,6 +137,15 @@ fn infer_expr(expr: &Expression, name: &str, decls: &mut Vec<Arc<String>>) -> Op
         }
         #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
         Go(ref go) => match **go {},
+        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+        Yield(ref y) => {
+            let res = infer_expr(&y.expr, name, decls);
+            if res.is_some() {
+                return res;
+            }
+        }
+        #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+        Yield(ref y) => match **y {},
         Call(ref call) => {
             let res = infer_call(call, name, decls);
             if res.is_some() {
@@ -148,74 +194,55 @@ fn infer_expr(expr: &Expression, name: &str, decls: &mut Vec<Arc<String>>) -> Op
             }
         }
         ForN(ref for_n_expr) => return infer_for_n(for_n_expr, name, decls),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         ForIn(ref for_in_expr) => {
             let res = infer_expr(&for_in_expr.iter, name, decls);
             if res.is_some() {
                 return res;
             }
         }
-        #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-        ForIn(ref for_in_expr) |
-        SumIn(ref for_in_expr) |
-        ProdIn(ref for_in_expr) |
-        MinIn(ref for_in_expr) |
-        MaxIn(ref for_in_expr) |
-        SiftIn(ref for_in_expr) |
-        AnyIn(ref for_in_expr) |
-        AllIn(ref for_in_expr) |
-        LinkIn(ref for_in_expr) => match **for_in_expr {},
         Sum(ref for_n_expr) => return infer_for_n(for_n_expr, name, decls),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         SumIn(ref for_in_expr) => {
             let res = infer_expr(&for_in_expr.iter, name, decls);
             if res.is_some() {
                 return res;
             }
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         ProdIn(ref for_in_expr) => {
             let res = infer_expr(&for_in_expr.iter, name, decls);
             if res.is_some() {
                 return res;
             }
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         MinIn(ref for_in_expr) => {
             let res = infer_expr(&for_in_expr.iter, name, decls);
             if res.is_some() {
                 return res;
             }
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         MaxIn(ref for_in_expr) => {
             let res = infer_expr(&for_in_expr.iter, name, decls);
             if res.is_some() {
                 return res;
             }
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         AnyIn(ref for_in_expr) => {
             let res = infer_expr(&for_in_expr.iter, name, decls);
             if res.is_some() {
                 return res;
             }
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         AllIn(ref for_in_expr) => {
             let res = infer_expr(&for_in_expr.iter, name, decls);
             if res.is_some() {
                 return res;
             }
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         SiftIn(ref for_in_expr) => {
             let res = infer_expr(&for_in_expr.iter, name, decls);
             if res.is_some() {
                 return res;
             }
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         LinkIn(ref for_in_expr) => {
             let res = infer_expr(&for_in_expr.iter, name, decls);
             if res.is_some() {
@@ -289,6 +316,38 @@ fn infer_expr(expr: &Expression, name: &str, decls: &mut Vec<Arc<String>>) -> Op
             }
         }
         In(_) => {}
+        Match(ref match_expr) => {
+            let res = infer_expr(&match_expr.expr, name, decls);
+            if res.is_some() {
+                return res;
+            }
+            for arm in &match_expr.arms {
+                let res = infer_expr(&arm.expr, name, decls);
+                if res.is_some() {
+                    return res;
+                }
+            }
+        }
+        Variant(ref variant) => {
+            let res = infer_expr(&variant.expr, name, decls);
+            if res.is_some() {
+                return res;
+            }
+        }
+        Destructure(ref destructure) => {
+            let res = infer_expr(&destructure.right, name, decls);
+            if res.is_some() {
+                return res;
+            }
+        }
+        Interpolation(ref interp) => {
+            for expr in &interp.exprs {
+                let res = infer_expr(expr, name, decls);
+                if res.is_some() {
+                    return res;
+                }
+            }
+        }
     };
     None
 }
@@ -407,6 +466,20 @@ fn infer_block(block: &Block, name: &str, decls: &mut Vec<Arc<String>>) -> Optio
                 if left.is_some() {
                     return left;
                 }
+            } else if let Expression::Destructure(ref destructure) = *expr {
+                let right = infer_expr(&destructure.right, name, decls);
+                if right.is_some() {
+                    return right;
+                }
+                // Check for declaration of same name.
+                let mut binds = vec![];
+                destructure.pattern.get_locals(&mut binds);
+                for bind in binds.into_iter().flatten() {
+                    if &**bind == name {
+                        return None;
+                    }
+                    decls.push(bind);
+                }
             } else {
                 let res = infer_expr(expr, name, decls);
                 if res.is_some() {
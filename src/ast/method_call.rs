@@ -0,0 +1,103 @@
+//! Rewrites method calls into ordinary calls.
+//!
+//! For example, `x.f(a).g(b)` becomes `g(f(x, a), b)`.
+//! This is done on meta data, such that the lifetime checker
+//! and the AST conversion see the same calls.
+
+use piston_meta::MetaData;
+use range::Range;
+use std::sync::Arc;
+
+lazy_static! {
+    static ref CALL: Arc<String> = Arc::new("call".into());
+    static ref CALL_ARG: Arc<String> = Arc::new("call_arg".into());
+}
+
+/// Returns meta data where method calls are rewritten into calls.
+pub(crate) fn desugar(data: &[Range<MetaData>]) -> Vec<Range<MetaData>> {
+    let mut res = Vec::with_capacity(data.len());
+    desugar_into(data, &mut res);
+    res
+}
+
+fn desugar_into(data: &[Range<MetaData>], res: &mut Vec<Range<MetaData>>) {
+    let mut i = 0;
+    while i < data.len() {
+        match data[i].data {
+            MetaData::StartNode(ref name) if &***name == "method_call" => {
+                let end = end_of_node(data, i);
+                method_call(&data[i..=end], res);
+                i = end + 1;
+            }
+            _ => {
+                res.push(data[i].clone());
+                i += 1;
+            }
+        }
+    }
+}
+
+// Rewrites a single method call node.
+fn method_call(data: &[Range<MetaData>], res: &mut Vec<Range<MetaData>>) {
+    let start = data[0].offset;
+    let inner = &data[1..data.len() - 1];
+    // Methods follow the receiver, so skip nodes until the first method.
+    let mut first_method = 0;
+    while first_method < inner.len() {
+        match inner[first_method].data {
+            MetaData::StartNode(ref n) if &***n == "method" => break,
+            MetaData::StartNode(_) => first_method = end_of_node(inner, first_method) + 1,
+            _ => first_method += 1,
+        }
+    }
+
+    // The receiver is the first argument of the first method call.
+    let mut expr = vec![];
+    desugar_into(&inner[..first_method], &mut expr);
+
+    let mut j = first_method;
+    while j < inner.len() {
+        let end = end_of_node(inner, j);
+        let method = &inner[j + 1..end];
+
+        let mut call = vec![Range::empty(start).wrap(MetaData::StartNode(CALL.clone()))];
+        let mut k = 0;
+        // Copy alias and name.
+        while k < method.len() {
+            if let MetaData::StartNode(_) = method[k].data {
+                break;
+            }
+            call.push(method[k].clone());
+            k += 1;
+        }
+        let expr_end = expr.iter().map(|d| d.next_offset()).max().unwrap_or(start);
+        call.push(Range::empty(start).wrap(MetaData::StartNode(CALL_ARG.clone())));
+        call.append(&mut expr);
+        call.push(Range::new(start, expr_end - start).wrap(MetaData::EndNode(CALL_ARG.clone())));
+        desugar_into(&method[k..], &mut call);
+        let call_end = inner[end].next_offset();
+        call.push(Range::new(start, call_end - start).wrap(MetaData::EndNode(CALL.clone())));
+
+        expr = call;
+        j = end + 1;
+    }
+    res.append(&mut expr);
+}
+
+// Finds the index of the end node matching the start node at index `i`.
+pub(super) fn end_of_node(data: &[Range<MetaData>], i: usize) -> usize {
+    let mut depth = 0;
+    for (j, d) in data.iter().enumerate().skip(i) {
+        match d.data {
+            MetaData::StartNode(_) => depth += 1,
+            MetaData::EndNode(_) => {
+                depth -= 1;
+                if depth == 0 {
+                    return j;
+                }
+            }
+            _ => {}
+        }
+    }
+    data.len() - 1
+}
@@ -3,7 +3,7 @@
 use piston_meta::{Convert, MetaData};
 use range::Range;
 use std::cell::Cell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::{self, Arc};
 
 use crate::{
@@ -14,10 +14,23 @@ use crate::{
     Variable
 };
 
+mod coalesce;
 mod infer_len;
+mod method_call;
+mod pipe;
 mod replace;
 
+/// Rewrites syntax sugar in meta data, e.g. method calls, `|>`, `??` and ranges.
+///
+/// This is done before lifetime checking and converting to AST.
+pub(crate) fn desugar(data: &[Range<MetaData>]) -> Vec<Range<MetaData>> {
+    coalesce::desugar(&pipe::desugar(&method_call::desugar(data)))
+}
+
 /// Convert meta data and load it into a module.
+///
+/// Operator overloads are picked by the type checker,
+/// so this uses the standard binary operators.
 pub fn convert(
     file: Arc<String>,
     source: Arc<String>,
@@ -25,6 +38,21 @@ pub fn convert(
     ignored: &mut Vec<Range>,
     module: &mut Module,
 ) -> Result<(), ()> {
+    let use_lookup = parse(file, source, data, ignored, module)?;
+    resolve(&use_lookup, module);
+    Ok(())
+}
+
+/// Parses meta data and registers functions and constants in module.
+///
+/// Returns the use lookup, which is used to resolve calls afterwards.
+pub(crate) fn parse(
+    file: Arc<String>,
+    source: Arc<String>,
+    data: &[Range<MetaData>],
+    ignored: &mut Vec<Range>,
+    module: &mut Module,
+) -> Result<UseLookup, ()> {
     let mut convert = Convert::new(data);
 
     let namespace = if let Ok((range, val)) = Namespace::from_meta_data(convert, ignored) {
@@ -34,31 +62,66 @@ pub fn convert(
         Arc::new(vec![])
     };
 
-    let use_lookup = if let Ok((range, val)) = Uses::from_meta_data(convert, ignored) {
+    let uses = if let Ok((range, val)) = Uses::from_meta_data(convert, ignored) {
         convert.update(range);
-        UseLookup::from_uses_module(&val, module)
+        val
     } else {
-        UseLookup::new()
+        Uses { use_imports: vec![] }
     };
+    let use_lookup = UseLookup::from_uses_module(&uses, module);
 
+    let functions_len = module.functions.len();
+    let constants_len = module.constants.len();
+    let type_aliases_len = module.type_aliases.len();
     loop {
         if let Ok((range, function)) =
             Function::from_meta_data(&namespace, &file, &source, "fn", convert, ignored)
         {
             convert.update(range);
             module.register(function);
+        } else if let Ok((range, val)) = Enum::from_meta_data(convert, ignored) {
+            convert.update(range);
+            for function in val.constructors(&namespace, &file, &source) {
+                module.register(function);
+            }
+        } else if let Ok((range, val)) = Const::from_meta_data(&file, &source, convert, ignored) {
+            convert.update(range);
+            module.constants.push(val);
+        } else if let Ok((range, val)) = TypeAlias::from_meta_data(&namespace, convert, ignored) {
+            convert.update(range);
+            module.type_aliases.push(val);
         } else if convert.remaining_data_len() > 0 {
             return Err(());
         } else {
             break;
         }
     }
+
+    // Resolve type aliases, where each alias can refer to the ones declared before it.
+    let mut aliases = TypeAlias::lookup(&module.type_aliases[..type_aliases_len], &uses);
+    for alias in &mut module.type_aliases[type_aliases_len..] {
+        alias.ty.insert_aliases(&aliases);
+        aliases.insert(alias.name.clone(), alias.ty.clone());
+    }
+    for f in &mut module.functions[functions_len..] {
+        for arg in &mut f.args {
+            arg.ty.insert_aliases(&aliases);
+        }
+        f.ret.insert_aliases(&aliases);
+    }
+    for c in &mut module.constants[constants_len..] {
+        c.ty.insert_aliases(&aliases);
+    }
+    Ok(use_lookup)
+}
+
+/// Resolves locals and calls of functions that are not looked up yet.
+pub(crate) fn resolve(use_lookup: &UseLookup, module: &mut Module) {
     let mut new_functions = module.functions.clone();
     for i in 0..new_functions.len() {
-        new_functions[i].get_locals(i, module, &use_lookup);
+        new_functions[i].get_locals(i, module, use_lookup);
     }
     module.functions = new_functions;
-    Ok(())
 }
 
 /// Function alias.
@@ -76,6 +139,9 @@ pub struct UseLookup {
     /// The first key is the alias to namespace.
     /// The second key is the alias to the function.
     pub aliases: HashMap<Arc<String>, HashMap<Arc<String>, FnAlias>>,
+    /// Stores operator overloads picked by the type checker.
+    /// The key is the range of the operator or call in source.
+    pub(crate) overloads: HashMap<Range, Arc<String>>,
 }
 
 impl Default for UseLookup {
@@ -89,6 +155,7 @@ impl UseLookup {
     pub fn new() -> UseLookup {
         UseLookup {
             aliases: HashMap::new(),
+            overloads: HashMap::new(),
         }
     }
 
@@ -165,7 +232,10 @@ impl UseLookup {
                 }
             }
         }
-        UseLookup { aliases }
+        UseLookup {
+            aliases,
+            overloads: HashMap::new(),
+        }
     }
 
     /// This is called from lifetime/type checker.
@@ -218,7 +288,10 @@ impl UseLookup {
                 }
             }
         }
-        UseLookup { aliases }
+        UseLookup {
+            aliases,
+            overloads: HashMap::new(),
+        }
     }
 }
 
@@ -390,6 +463,14 @@ pub struct Function {
         sync::atomic::AtomicBool,
         sync::Mutex<Vec<sync::mpsc::Sender<Variable>>>,
     )>,
+    /// Whether the function is a generator, which contains `yield`.
+    ///
+    /// Calling a generator returns an in-type, which requires the `threading` feature.
+    /// The generator runs until the next `yield` each time a value is taken.
+    pub generator: bool,
+    /// Names of calls leaving out arguments with default values,
+    /// together with the number of arguments in the call.
+    pub default_calls: Vec<(Arc<String>, usize)>,
 }
 
 impl Function {
@@ -416,6 +497,7 @@ impl Function {
         let mut expr: Option<Expression> = None;
         let mut ret: Option<Type> = None;
         let mut lazy_inv: Vec<Vec<Lazy>> = vec![];
+        let mut params: Vec<Arc<String>> = vec![];
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
@@ -423,6 +505,9 @@ impl Function {
             } else if let Ok((range, val)) = convert.meta_string("name") {
                 convert.update(range);
                 name = Some(val);
+            } else if let Ok((range, val)) = convert.meta_string("ty_param") {
+                convert.update(range);
+                params.push(val);
             } else if let Ok((range, val, lazy)) =
                 Arg::from_meta_data(file, source, convert, ignored)
             {
@@ -462,6 +547,14 @@ impl Function {
         }
 
         let mut name = name.ok_or(())?;
+        let mut ret = ret.ok_or(())?;
+        if !params.is_empty() {
+            for arg in &mut args {
+                arg.ty.insert_params(&params);
+            }
+            ret.insert_params(&params);
+        }
+        let generator = has_yield(start, convert);
         let block = match expr {
             None => block.ok_or(())?,
             Some(expr) => {
@@ -486,12 +579,24 @@ impl Function {
             }
             name_plus_args.push(')');
             name = Arc::new(name_plus_args);
+        } else {
+            let tys: Vec<Type> = args.iter().map(|arg| arg.ty.clone()).collect();
+            if let Some(overload) = overload_name(&name, &tys) {
+                name = overload;
+            }
         }
-        let ret = ret.ok_or(())?;
         // Remove empty lazy invariants.
         while let Some(true) = lazy_inv.last().map(|lz| lz.is_empty()) {
             lazy_inv.pop();
         }
+        let defaults = args.iter().rev().take_while(|arg| arg.default.is_some()).count();
+        let default_calls = if defaults > 0 {
+            let arg_names: Vec<(&str, bool)> =
+                args.iter().map(|arg| (&**arg.name, arg.mutable)).collect();
+            default_call_names(&name, &arg_names, defaults)
+        } else {
+            vec![]
+        };
         Ok((
             convert.subtract(start),
             Function {
@@ -507,6 +612,8 @@ impl Function {
                 ret,
                 source_range: convert.source(start).unwrap(),
                 senders: Arc::new((AtomicBool::new(false), Mutex::new(vec![]))),
+                generator,
+                default_calls,
             },
         ))
     }
@@ -540,65 +647,120 @@ impl Function {
     }
 }
 
-/// Closure.
+// Returns `true` if there is a `yield` expression in meta data between `start` and `end`.
+fn has_yield(start: Convert, end: Convert) -> bool {
+    let mut convert = start;
+    while convert.remaining_data_len() > end.remaining_data_len() {
+        if convert.start_node("yield").is_ok() {
+            return true;
+        }
+        let offset = convert.subtract(start).next_offset();
+        convert.update(Range::new(offset, 1));
+    }
+    false
+}
+
+/// Parses the digits of an integer literal, e.g. `1_000` in `1_000i`.
+pub(crate) fn parse_int(text: &str) -> Option<i64> {
+    text.replace('_', "").parse().ok()
+}
+
+/// Parses the start of a range literal, e.g. `1` in `1..3`.
+pub(crate) fn parse_range_num(text: &str) -> Option<f64> {
+    text.replace('_', "").parse().ok()
+}
+
+/// Gets the name of a binary operator overload, e.g. `add(Money f64,Money f64)`.
+///
+/// Returns `None` unless the function is named after a binary operator
+/// and some argument has an ad-hoc type.
+/// The type checker picks the overload from the ad-hoc types of the arguments.
+pub(crate) fn overload_name(name: &str, tys: &[Type]) -> Option<Arc<String>> {
+    match name {
+        "add" | "sub" | "mul" | "div" | "rem" | "pow" | "dot" | "cross" | "less"
+        | "less_or_equal" | "greater" | "greater_or_equal" | "equal" | "not_equal" => {}
+        _ => return None,
+    }
+    if tys.len() != 2 || !tys.iter().any(|ty| matches!(ty, Type::AdHoc(_, _))) {
+        return None;
+    }
+    let tys: Vec<String> = tys.iter().map(|ty| ty.description()).collect();
+    Some(Arc::new(format!("{}({})", name, tys.join(","))))
+}
+
+/// Gets the names of calls that leave out trailing arguments with default values.
+///
+/// Takes the name and mutability of each argument.
+/// The mutability information appended to function names depends on the arguments in the call.
+/// Named calls, e.g. `foo(x: 1)` calling `foo__x_y`, also leave out the argument names.
+pub(crate) fn default_call_names(
+    name: &str,
+    args: &[(&str, bool)],
+    defaults: usize,
+) -> Vec<(Arc<String>, usize)> {
+    let base = name.split('(').next().unwrap_or(name);
+    let words: Vec<&str> = args.iter().map(|arg| arg.0).collect();
+    let named = base.strip_suffix(&*format!("__{}", words.join("_")));
+    let mut res = vec![];
+    for n in args.len() - defaults..args.len() {
+        let mut names = vec![String::from(base)];
+        if let Some(f) = named {
+            names.push(if n == 0 {
+                String::from(f)
+            } else {
+                format!("{}__{}", f, words[..n].join("_"))
+            });
+        }
+        for mut call_name in names {
+            if args[..n].iter().any(|arg| arg.1) {
+                let mutable: Vec<&str> =
+                    args[..n].iter().map(|arg| if arg.1 { "mut" } else { "_" }).collect();
+                call_name.push_str(&format!("({})", mutable.join(",")));
+            }
+            if call_name != name {
+                res.push((Arc::new(call_name), n));
+            }
+        }
+    }
+    res
+}
+
+/// Enum declaration, e.g. `enum Shape = Circle {} | Rect {}`.
+///
+/// Each variant becomes a constructor function that tags its argument.
 #[derive(Debug, Clone)]
-pub struct Closure {
-    /// The file where the closure was declared/created.
-    pub file: Arc<String>,
-    /// The source of the closure.
-    pub source: Arc<String>,
-    /// Closure arguments.
-    pub args: Vec<Arg>,
-    /// Current object references.
-    pub currents: Vec<Current>,
-    /// Closure expression.
-    pub expr: Expression,
-    /// The return type of the closure.
-    pub ret: Type,
+pub struct Enum {
+    /// The name of the enum.
+    pub name: Arc<String>,
+    /// The variants.
+    pub variants: Vec<EnumVariant>,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Closure {
-    /// Create closure from meta data.
-    pub fn from_meta_data(
-        file: &Arc<String>,
-        source: &Arc<String>,
-        node: &str,
+impl Enum {
+    /// Creates enum declaration from meta data.
+    pub(crate) fn from_meta_data(
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Closure), ()> {
+    ) -> Result<(Range, Enum), ()> {
         let start = convert;
+        let node = "enum";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut args: Vec<Arg> = vec![];
-        let mut currents: Vec<Current> = vec![];
-        let mut expr: Option<Expression> = None;
-        let mut ret: Option<Type> = None;
+        let mut name: Option<Arc<String>> = None;
+        let mut variants: Vec<EnumVariant> = vec![];
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val, _)) = Arg::from_meta_data(file, source, convert, ignored)
-            {
-                convert.update(range);
-                args.push(val);
-            } else if let Ok((range, val)) = Current::from_meta_data(convert, ignored) {
-                convert.update(range);
-                currents.push(val);
-            } else if let Ok((range, val)) = convert.meta_bool("returns") {
-                convert.update(range);
-                ret = Some(if val { Type::Any } else { Type::Void })
-            } else if let Ok((range, val)) = Type::from_meta_data("ret_type", convert, ignored) {
+            } else if let Ok((range, val)) = convert.meta_string("name") {
                 convert.update(range);
-                ret = Some(val);
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "expr", convert, ignored)
-            {
+                name = Some(val);
+            } else if let Ok((range, val)) = EnumVariant::from_meta_data(convert, ignored) {
                 convert.update(range);
-                expr = Some(val);
-                ret = Some(Type::Any);
+                variants.push(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -606,93 +768,123 @@ impl Closure {
             }
         }
 
-        let ret = ret.ok_or(())?;
-        let expr = expr.ok_or(())?;
+        let name = name.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Closure {
-                file: file.clone(),
-                source: source.clone(),
-                args,
-                currents,
-                expr,
-                ret,
+            Enum {
+                name,
+                variants,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
-    /// Returns `true` if the closure return something.
-    pub fn returns(&self) -> bool {
-        self.ret != Type::Void
-    }
+    /// Creates constructor functions for the variants.
+    pub fn constructors(
+        &self,
+        namespace: &Arc<Vec<Arc<String>>>,
+        file: &Arc<String>,
+        source: &Arc<String>,
+    ) -> Vec<Function> {
+        use std::sync::atomic::AtomicBool;
+        use std::sync::Mutex;
 
-    fn get_locals(
-        &mut self,
-        relative: usize,
-        stack: &mut Vec<Option<Arc<String>>>,
-        closure_stack: &mut Vec<usize>,
-        module: &Module,
-        use_lookup: &UseLookup,
-    ) {
-        // Start new closure.
-        // Need a closure stack because `grab` expressions are relative
-        // to closure environment, not the locals inside the closure.
-        let cs = closure_stack.len();
-        closure_stack.push(stack.len());
-        if self.returns() {
-            // Use return type because it has the same name.
-            stack.push(Some(crate::runtime::RETURN_TYPE.clone()));
-        }
-        for arg in &self.args {
-            stack.push(Some(arg.name.clone()));
-        }
-        for current in &self.currents {
-            stack.push(Some(current.name.clone()));
+        let mut res = vec![];
+        for variant in &self.variants {
+            let source_range = variant.source_range;
+            let (args, expr) = match variant.ty {
+                Some(ref ty) => {
+                    let arg_name: Arc<String> = Arc::new("value".into());
+                    (
+                        vec![Arg {
+                            name: arg_name.clone(),
+                            lifetime: None,
+                            ty: ty.clone(),
+                            default: None,
+                            source_range,
+                            mutable: false,
+                        }],
+                        Expression::Variant(Box::new(Variant {
+                            name: variant.name.clone(),
+                            expr: Expression::Item(Box::new(Item::from_variable(
+                                arg_name,
+                                source_range,
+                            ))),
+                            source_range,
+                        })),
+                    )
+                }
+                // Unit variants have no value.
+                None => (
+                    vec![],
+                    Expression::Variable(Box::new((
+                        source_range,
+                        Variable::Variant(variant.name.clone(), None),
+                    ))),
+                ),
+            };
+            res.push(Function {
+                namespace: namespace.clone(),
+                looked_up: Arc::new(AtomicBool::new(false)),
+                name: variant.name.clone(),
+                file: file.clone(),
+                source: source.clone(),
+                args,
+                lazy_inv: vec![],
+                currents: vec![],
+                block: Block {
+                    expressions: vec![Expression::Return(Box::new(expr))],
+                    source_range,
+                },
+                ret: Type::AdHoc(self.name.clone(), Box::new(Type::Any)),
+                source_range,
+                senders: Arc::new((AtomicBool::new(false), Mutex::new(vec![]))),
+                generator: false,
+                default_calls: vec![],
+            });
         }
-        self.expr.get_locals(relative, stack, closure_stack, module, use_lookup);
-        closure_stack.truncate(cs);
+        res
     }
 }
 
-/// Grab expression.
+/// Enum variant.
 #[derive(Debug, Clone)]
-pub struct Grab {
-    /// Grab level.
-    pub level: u16,
-    /// The sub-expression to compute.
-    pub expr: Expression,
+pub struct EnumVariant {
+    /// The name of the variant.
+    pub name: Arc<String>,
+    /// The type of the tagged value, if any.
+    pub ty: Option<Type>,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Grab {
-    /// Creates grab expression from meta data.
-    pub fn from_meta_data(
-        file: &Arc<String>,
-        source: &Arc<String>,
+impl EnumVariant {
+    /// Creates enum variant from meta data.
+    pub(crate) fn from_meta_data(
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Grab), ()> {
+    ) -> Result<(Range, EnumVariant), ()> {
         let start = convert;
-        let node = "grab";
+        let node = "variant";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut level: Option<u16> = None;
-        let mut expr: Option<Expression> = None;
+        let mut name: Option<Arc<String>> = None;
+        let mut ty: Option<Type> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = convert.meta_f64("grab_level") {
+            } else if let Ok((range, val)) = convert.meta_string("name") {
                 convert.update(range);
-                level = Some(val as u16);
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "expr", convert, ignored)
-            {
+                name = Some(val);
+            } else if let Ok(range) = convert.start_node("arg") {
                 convert.update(range);
-                expr = Some(val);
+                let (range, val) = Type::from_meta_data("type", convert, ignored)?;
+                convert.update(range);
+                let range = convert.end_node("arg")?;
+                convert.update(range);
+                ty = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -700,82 +892,68 @@ impl Grab {
             }
         }
 
-        let level = level.unwrap_or(1);
-        let expr = expr.ok_or(())?;
+        let name = name.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Grab {
-                level,
-                expr,
+            EnumVariant {
+                name,
+                ty,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
-
-    fn precompute(&self) -> Option<Variable> {
-        self.expr.precompute()
-    }
-
-    fn get_locals(
-        &mut self,
-        relative: usize,
-        stack: &mut Vec<Option<Arc<String>>>,
-        closure_stack: &mut Vec<usize>,
-        module: &Module,
-        use_lookup: &UseLookup,
-    ) {
-        // Get closure environment.
-        let d = if closure_stack.len() < self.level as usize {
-            // Ignore negative difference, because
-            // lifetime checker will detect too high grab level.
-            0
-        } else {
-            closure_stack.len() - self.level as usize
-        };
-        let last = match closure_stack.get(d) {
-            None => return,
-            Some(&x) => x,
-        };
-        // Use environment outside closure.
-        let mut tmp_stack: Vec<_> = stack[0..last].into();
-        self.expr.get_locals(relative, &mut tmp_stack, closure_stack, module, use_lookup)
-    }
 }
 
-/// Try-expression, catches run-time errors from sub-expression.
+/// Module-level constant, e.g. `const N: f64 = 3`.
 ///
-/// E.g. `try foo()`.
+/// The value is computed once when loading.
 #[derive(Debug, Clone)]
-pub struct TryExpr {
-    /// Sub-expression to catch run-time errors from.
-    pub expr: Expression,
+pub struct Const {
+    /// The name of the constant.
+    pub name: Arc<String>,
+    /// The type of the constant.
+    pub ty: Type,
+    /// The value.
+    pub value: Variable,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl TryExpr {
-    /// Creates try expression from meta data.
-    pub fn from_meta_data(
+// Required because the `Sync` impl of `Variable` is unsafe.
+// Constants never contain `UnsafeRef`.
+unsafe impl Sync for Const {}
+
+impl Const {
+    /// Creates constant from meta data.
+    pub(crate) fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, TryExpr), ()> {
+    ) -> Result<(Range, Const), ()> {
         let start = convert;
-        let node = "try_expr";
+        let node = "const";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut expr: Option<Expression> = None;
+        let mut name: Option<Arc<String>> = None;
+        let mut ty: Option<Type> = None;
+        let mut value: Option<Variable> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
+            } else if let Ok((range, val)) = convert.meta_string("name") {
+                convert.update(range);
+                name = Some(val);
+            } else if let Ok((range, val)) = Type::from_meta_data("type", convert, ignored) {
+                convert.update(range);
+                ty = Some(val);
             } else if let Ok((range, val)) =
                 Expression::from_meta_data(file, source, "expr", convert, ignored)
             {
                 convert.update(range);
-                expr = Some(val);
+                value = Some(val.precompute().ok_or(())?);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -783,111 +961,69 @@ impl TryExpr {
             }
         }
 
-        let expr = expr.ok_or(())?;
+        let name = name.ok_or(())?;
+        let value = value.ok_or(())?;
+        let ty = ty.unwrap_or_else(|| match value {
+            Variable::F64(_, _) => Type::F64,
+            Variable::Bool(_, _) => Type::Bool,
+            Variable::Str(_) => Type::Str,
+            Variable::Vec4(_) => Type::Vec4,
+            Variable::Link(_) => Type::Link,
+            Variable::Array(_) => Type::array(),
+            Variable::Object(_) => Type::object(),
+            Variable::Map(_) => Type::map(),
+            Variable::Set(_) => Type::set(),
+            _ => Type::Any,
+        });
         Ok((
             convert.subtract(start),
-            TryExpr {
-                expr,
+            Const {
+                name,
+                ty,
+                value,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
-
-    fn get_locals(
-        &mut self,
-        relative: usize,
-        stack: &mut Vec<Option<Arc<String>>>,
-        closure_stack: &mut Vec<usize>,
-        module: &Module,
-        use_lookup: &UseLookup,
-    ) {
-        self.expr.get_locals(relative, stack, closure_stack, module, use_lookup)
-    }
-}
-
-/// Lazy invariant.
-#[derive(Debug, Clone, PartialEq)]
-pub enum Lazy {
-    /// Return a variable if result of argument is equal to the variable.
-    Variable(Variable),
-    /// Unwrap the Ok result.
-    UnwrapOk,
-    /// Unwrap the Err result.
-    UnwrapErr,
-    /// Unwrap the Some option.
-    UnwrapSome,
 }
 
-/// This is requires because `UnsafeRef(*mut Variable)` can not be sent across threads.
-/// The lack of `UnsafeRef` variant when sending across threads is guaranteed at language level.
-/// The interior of `UnsafeRef` can not be accessed outside this library.
-unsafe impl Sync for Lazy {}
-
-/// Function argument.
+/// Type alias, e.g. `type Point = {x: f64, y: f64}`.
 #[derive(Debug, Clone)]
-pub struct Arg {
-    /// The name of the argument.
+pub struct TypeAlias {
+    /// The namespace of the type alias.
+    pub namespace: Arc<Vec<Arc<String>>>,
+    /// The name of the type alias.
     pub name: Arc<String>,
-    /// The lifetime of the argument.
-    pub lifetime: Option<Arc<String>>,
-    /// The type of the argument.
+    /// The type it refers to.
     pub ty: Type,
     /// The range in source.
     pub source_range: Range,
-    /// Whether the argument is mutable.
-    pub mutable: bool,
 }
 
-impl Arg {
-    /// Creates function argument from meta data.
-    pub fn from_meta_data(
-        file: &Arc<String>,
-        source: &Arc<String>,
+impl TypeAlias {
+    /// Creates type alias from meta data.
+    pub(crate) fn from_meta_data(
+        namespace: &Arc<Vec<Arc<String>>>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Arg, Vec<Lazy>), ()> {
+    ) -> Result<(Range, TypeAlias), ()> {
         let start = convert;
-        let node = "arg";
+        let node = "type_alias";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
         let mut name: Option<Arc<String>> = None;
-        let mut lifetime: Option<Arc<String>> = None;
         let mut ty: Option<Type> = None;
-        let mut mutable = false;
-        let mut lazy: Vec<Lazy> = vec![];
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = convert.meta_bool("mut") {
-                convert.update(range);
-                mutable = val;
             } else if let Ok((range, val)) = convert.meta_string("name") {
                 convert.update(range);
                 name = Some(val);
-            } else if let Ok((range, val)) = convert.meta_string("lifetime") {
-                convert.update(range);
-                lifetime = Some(val);
             } else if let Ok((range, val)) = Type::from_meta_data("type", convert, ignored) {
                 convert.update(range);
                 ty = Some(val);
-            } else if let Ok((range, val)) = Grab::from_meta_data(file, source, convert, ignored) {
-                convert.update(range);
-                if let Some(val) = val.precompute() {
-                    lazy.push(Lazy::Variable(val));
-                } else {
-                    return Err(());
-                }
-            } else if let Ok((range, _)) = convert.meta_bool("ok(_)") {
-                convert.update(range);
-                lazy.push(Lazy::UnwrapOk);
-            } else if let Ok((range, _)) = convert.meta_bool("err(_)") {
-                convert.update(range);
-                lazy.push(Lazy::UnwrapErr);
-            } else if let Ok((range, _)) = convert.meta_bool("some(_)") {
-                convert.update(range);
-                lazy.push(Lazy::UnwrapSome);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -896,63 +1032,117 @@ impl Arg {
         }
 
         let name = name.ok_or(())?;
-        let ty = match ty {
-            None => Type::Any,
-            Some(ty) => ty,
-        };
+        let ty = ty.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Arg {
+            TypeAlias {
+                namespace: namespace.clone(),
                 name,
-                lifetime,
                 ty,
                 source_range: convert.source(start).unwrap(),
-                mutable,
             },
-            lazy,
         ))
     }
+
+    /// Looks up type aliases visible through use imports.
+    ///
+    /// Aliases are available by name, or with the namespace alias, e.g. `geom::Point`.
+    pub fn lookup(aliases: &[TypeAlias], uses: &Uses) -> HashMap<Arc<String>, Type> {
+        let mut res: HashMap<Arc<String>, Type> = HashMap::new();
+        for alias in aliases {
+            res.insert(alias.name.clone(), alias.ty.clone());
+        }
+        for use_import in &uses.use_imports {
+            for alias in aliases {
+                if *alias.namespace != use_import.names {
+                    continue;
+                }
+                let name = Arc::new(format!("{}::{}", use_import.alias, alias.name));
+                res.insert(name, alias.ty.clone());
+                for use_fn in &use_import.fns {
+                    if use_fn.0 == alias.name {
+                        let name = use_fn.1.as_ref().unwrap_or(&use_fn.0).clone();
+                        res.insert(name, alias.ty.clone());
+                    }
+                }
+            }
+        }
+        res
+    }
 }
 
-/// Current object reference.
+/// Tags a value with an enum variant.
 ///
-/// This puts the current object into scope for a function.
+/// This is created by enum constructors and has no syntax of its own.
 #[derive(Debug, Clone)]
-pub struct Current {
-    /// The name of the current object.
+pub struct Variant {
+    /// The name of the variant.
     pub name: Arc<String>,
+    /// The value to tag.
+    pub expr: Expression,
     /// The range in source.
     pub source_range: Range,
-    /// Whether the current object is mutable.
-    pub mutable: bool,
 }
 
-impl Current {
-    /// Creates current object reference from meta data.
+/// Closure.
+#[derive(Debug, Clone)]
+pub struct Closure {
+    /// The file where the closure was declared/created.
+    pub file: Arc<String>,
+    /// The source of the closure.
+    pub source: Arc<String>,
+    /// Closure arguments.
+    pub args: Vec<Arg>,
+    /// Current object references.
+    pub currents: Vec<Current>,
+    /// Closure expression.
+    pub expr: Expression,
+    /// The return type of the closure.
+    pub ret: Type,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl Closure {
+    /// Create closure from meta data.
     pub fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Current), ()> {
+    ) -> Result<(Range, Closure), ()> {
         let start = convert;
-        let node = "current";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut name: Option<Arc<String>> = None;
-        let mut mutable = false;
+        let mut args: Vec<Arg> = vec![];
+        let mut currents: Vec<Current> = vec![];
+        let mut expr: Option<Expression> = None;
+        let mut ret: Option<Type> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = convert.meta_bool("mut") {
+            } else if let Ok((range, val, _)) = Arg::from_meta_data(file, source, convert, ignored)
+            {
                 convert.update(range);
-                mutable = val;
-            } else if let Ok((range, val)) = convert.meta_string("name") {
+                args.push(val);
+            } else if let Ok((range, val)) = Current::from_meta_data(convert, ignored) {
                 convert.update(range);
-                name = Some(val);
-            } else if let Ok((range, _)) = Type::from_meta_data("type", convert, ignored) {
+                currents.push(val);
+            } else if let Ok((range, val)) = convert.meta_bool("returns") {
                 convert.update(range);
-                // Just ignore type for now.
+                ret = Some(if val { Type::Any } else { Type::Void })
+            } else if let Ok((range, val)) = Type::from_meta_data("ret_type", convert, ignored) {
+                convert.update(range);
+                ret = Some(val);
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
+            {
+                convert.update(range);
+                expr = Some(val);
+                ret = Some(Type::Any);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -960,50 +1150,93 @@ impl Current {
             }
         }
 
-        let name = name.ok_or(())?;
+        let ret = ret.ok_or(())?;
+        let expr = expr.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Current {
-                name,
+            Closure {
+                file: file.clone(),
+                source: source.clone(),
+                args,
+                currents,
+                expr,
+                ret,
                 source_range: convert.source(start).unwrap(),
-                mutable,
             },
         ))
     }
+
+    /// Returns `true` if the closure return something.
+    pub fn returns(&self) -> bool {
+        self.ret != Type::Void
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        // Start new closure.
+        // Need a closure stack because `grab` expressions are relative
+        // to closure environment, not the locals inside the closure.
+        let cs = closure_stack.len();
+        closure_stack.push(stack.len());
+        if self.returns() {
+            // Use return type because it has the same name.
+            stack.push(Some(crate::runtime::RETURN_TYPE.clone()));
+        }
+        for arg in &self.args {
+            stack.push(Some(arg.name.clone()));
+        }
+        for current in &self.currents {
+            stack.push(Some(current.name.clone()));
+        }
+        self.expr.get_locals(relative, stack, closure_stack, module, use_lookup);
+        closure_stack.truncate(cs);
+    }
 }
 
-/// Block.
+/// Grab expression.
 #[derive(Debug, Clone)]
-pub struct Block {
-    /// Sub-expression.
-    pub expressions: Vec<Expression>,
+pub struct Grab {
+    /// Grab level.
+    pub level: u16,
+    /// The sub-expression to compute.
+    pub expr: Expression,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Block {
-    /// Creates block from meta data.
+impl Grab {
+    /// Creates grab expression from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
-        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Block), ()> {
+    ) -> Result<(Range, Grab), ()> {
         let start = convert;
+        let node = "grab";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut expressions = vec![];
+        let mut level: Option<u16> = None;
+        let mut expr: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
+            } else if let Ok((range, val)) = convert.meta_f64("grab_level") {
+                convert.update(range);
+                level = Some(val as u16);
             } else if let Ok((range, val)) =
                 Expression::from_meta_data(file, source, "expr", convert, ignored)
             {
                 convert.update(range);
-                expressions.push(val);
+                expr = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -1011,15 +1244,22 @@ impl Block {
             }
         }
 
+        let level = level.unwrap_or(1);
+        let expr = expr.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Block {
-                expressions,
+            Grab {
+                level,
+                expr,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
+    fn precompute(&self) -> Option<Variable> {
+        self.expr.precompute()
+    }
+
     fn get_locals(
         &mut self,
         relative: usize,
@@ -1028,432 +1268,839 @@ impl Block {
         module: &Module,
         use_lookup: &UseLookup,
     ) {
-        let st = stack.len();
-        for expr in &mut self.expressions {
-            expr.get_locals(relative, stack, closure_stack, module, use_lookup);
-        }
-        stack.truncate(st);
-    }
-}
-
-/// Expression.
-#[derive(Debug, Clone)]
-pub enum Expression {
-    /// Link expression.
-    Link(Box<Link>),
-    /// Object expression.
-    Object(Box<Object>),
-    /// Array expression.
-    Array(Box<Array>),
-    /// Array fill expression.
-    ArrayFill(Box<ArrayFill>),
-    /// Return expression.
-    Return(Box<Expression>),
-    /// Returns with value expression.
-    ReturnVoid(Box<Range>),
-    /// Break expression.
-    Break(Box<Break>),
-    /// Continue expression.
-    Continue(Box<Continue>),
-    /// Block expression.
-    Block(Box<Block>),
-    /// Go call expression.
-    Go(Box<Go>),
-    /// Call expression.
-    Call(Box<Call>),
-    /// Call external function.
-    CallVoid(Box<CallVoid>),
-    /// Call external function that returns something.
-    CallReturn(Box<CallReturn>),
-    /// Call external function with lazy invariant.
-    CallLazy(Box<CallLazy>),
-    /// Call loaded function.
-    CallLoaded(Box<CallLoaded>),
-    /// Binary operator.
-    CallBinOp(Box<CallBinOp>),
-    /// Unary operator.
-    CallUnOp(Box<CallUnOp>),
-    /// Item expression.
-    Item(Box<Item>),
-    /// Assignment expression.
-    Assign(Box<Assign>),
-    /// 4D vector expression.
-    Vec4(Box<Vec4>),
-    /// 4D matrix expression.
-    Mat4(Box<Mat4>),
-    /// For expression, e.g. `for i := 0; i < 10; i += 1 { ... }`.
-    For(Box<For>),
-    /// For-n expression.
-    ForN(Box<ForN>),
-    /// For-in expression.
-    ForIn(Box<ForIn>),
-    /// Sum for-n expression.
-    Sum(Box<ForN>),
-    /// Sum-in for-n expression.
-    SumIn(Box<ForIn>),
-    /// Component-wise 4D vector sum for-n-loop.
-    SumVec4(Box<ForN>),
-    /// Product for-n expression.
-    Prod(Box<ForN>),
-    /// Product-in for-n loop.
-    ProdIn(Box<ForIn>),
-    /// Component-wise 4D vector product for-n-loop.
-    ProdVec4(Box<ForN>),
-    /// Min for-n expression.
-    Min(Box<ForN>),
-    /// Min-in for-n expression.
-    MinIn(Box<ForIn>),
-    /// Max for-n expression.
-    Max(Box<ForN>),
-    /// Max-in for-n expression.
-    MaxIn(Box<ForIn>),
-    /// Sift for-n expression.
-    Sift(Box<ForN>),
-    /// Sift-in expression.
-    SiftIn(Box<ForIn>),
-    /// Any expression.
-    Any(Box<ForN>),
-    /// Any-in expression.
-    AnyIn(Box<ForIn>),
-    /// All-for expression.
-    All(Box<ForN>),
-    /// All-in expression.
-    AllIn(Box<ForIn>),
-    /// Link-for expression.
-    LinkFor(Box<ForN>),
-    /// Link-in expression.
-    LinkIn(Box<ForIn>),
-    /// If-expression.
-    If(Box<If>),
-    /// Variable.
-    ///
-    /// This means it contains no members that depends on other expressions.
-    Variable(Box<(Range, Variable)>),
-    /// Try expression using `?`.
-    Try(Box<Expression>),
-    /// 4D vector swizzle expression.
-    Swizzle(Box<Swizzle>),
-    /// Closure expression.
-    Closure(Arc<Closure>),
-    /// Call closure expression.
-    CallClosure(Box<CallClosure>),
-    /// Grab expression.
-    Grab(Box<Grab>),
-    /// Try expression, e.g. `try x`.
-    TryExpr(Box<TryExpr>),
-    /// In-type expression.
-    In(Box<In>),
+        // Get closure environment.
+        let d = if closure_stack.len() < self.level as usize {
+            // Ignore negative difference, because
+            // lifetime checker will detect too high grab level.
+            0
+        } else {
+            closure_stack.len() - self.level as usize
+        };
+        let last = match closure_stack.get(d) {
+            None => return,
+            Some(&x) => x,
+        };
+        // Use environment outside closure.
+        let mut tmp_stack: Vec<_> = stack[0..last].into();
+        self.expr.get_locals(relative, &mut tmp_stack, closure_stack, module, use_lookup)
+    }
 }
 
-// Required because the `Sync` impl of `Variable` is unsafe.
-unsafe impl Sync for Expression {}
+/// Try-expression, catches run-time errors from sub-expression.
+///
+/// E.g. `try foo()`.
+#[derive(Debug, Clone)]
+pub struct TryExpr {
+    /// Sub-expression to catch run-time errors from.
+    pub expr: Expression,
+    /// The range in source.
+    pub source_range: Range,
+}
 
-impl Expression {
-    /// Creates expression from meta data.
+impl TryExpr {
+    /// Creates try expression from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
-        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Expression), ()> {
+    ) -> Result<(Range, TryExpr), ()> {
         let start = convert;
+        let node = "try_expr";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut result: Option<Expression> = None;
+        let mut expr: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, _)) = convert.meta_bool("mut") {
-                // Ignore `mut` since it is handled by lifetime checker.
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
+            {
                 convert.update(range);
-            } else if let Ok((range, val)) = Link::from_meta_data(file, source, convert, ignored) {
+                expr = Some(val);
+            } else {
+                let range = convert.ignore();
                 convert.update(range);
-                result = Some(Expression::Link(Box::new(val)));
-            } else if let Ok((range, val)) = Object::from_meta_data(file, source, convert, ignored)
-            {
+                ignored.push(range);
+            }
+        }
+
+        let expr = expr.ok_or(())?;
+        Ok((
+            convert.subtract(start),
+            TryExpr {
+                expr,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        self.expr.get_locals(relative, stack, closure_stack, module, use_lookup)
+    }
+}
+
+/// Lazy invariant.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lazy {
+    /// Return a variable if result of argument is equal to the variable.
+    Variable(Variable),
+    /// Unwrap the Ok result.
+    UnwrapOk,
+    /// Unwrap the Err result.
+    UnwrapErr,
+    /// Unwrap the Some option.
+    UnwrapSome,
+}
+
+/// This is requires because `UnsafeRef(*mut Variable)` can not be sent across threads.
+/// The lack of `UnsafeRef` variant when sending across threads is guaranteed at language level.
+/// The interior of `UnsafeRef` can not be accessed outside this library.
+unsafe impl Sync for Lazy {}
+
+/// Function argument.
+#[derive(Debug, Clone)]
+pub struct Arg {
+    /// The name of the argument.
+    pub name: Arc<String>,
+    /// The lifetime of the argument.
+    pub lifetime: Option<Arc<String>>,
+    /// The type of the argument.
+    pub ty: Type,
+    /// The value to use when the argument is left out of a call.
+    pub default: Option<Variable>,
+    /// The range in source.
+    pub source_range: Range,
+    /// Whether the argument is mutable.
+    pub mutable: bool,
+}
+
+// Required because the `Sync` impl of `Variable` is unsafe.
+// Default values are constants, so they never contain `UnsafeRef`.
+unsafe impl Sync for Arg {}
+
+impl Arg {
+    /// Creates function argument from meta data.
+    pub fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Arg, Vec<Lazy>), ()> {
+        let start = convert;
+        let node = "arg";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut name: Option<Arc<String>> = None;
+        let mut lifetime: Option<Arc<String>> = None;
+        let mut ty: Option<Type> = None;
+        let mut default: Option<Variable> = None;
+        let mut mutable = false;
+        let mut lazy: Vec<Lazy> = vec![];
+        loop {
+            if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
-                result = Some(Expression::Object(Box::new(val)));
-            } else if let Ok((range, val)) = Array::from_meta_data(file, source, convert, ignored) {
+                break;
+            } else if let Ok((range, val)) = convert.meta_bool("mut") {
                 convert.update(range);
-                result = Some(Expression::Array(Box::new(val)));
-            } else if let Ok((range, val)) =
-                ArrayFill::from_meta_data(file, source, convert, ignored)
-            {
+                mutable = val;
+            } else if let Ok((range, val)) = convert.meta_string("name") {
                 convert.update(range);
-                result = Some(Expression::ArrayFill(Box::new(val)));
+                name = Some(val);
+            } else if let Ok((range, val)) = convert.meta_string("lifetime") {
+                convert.update(range);
+                lifetime = Some(val);
+            } else if let Ok((range, val)) = Type::from_meta_data("type", convert, ignored) {
+                convert.update(range);
+                ty = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "return", convert, ignored)
+                Expression::from_meta_data(file, source, "default", convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::Return(Box::new(val)));
-            } else if let Ok((range, _)) = convert.meta_bool("return_void") {
+                default = Some(val.precompute().ok_or(())?);
+            } else if let Ok((range, val)) = Grab::from_meta_data(file, source, convert, ignored) {
                 convert.update(range);
-                result = Some(Expression::ReturnVoid(Box::new(
-                    convert.source(start).unwrap(),
-                )));
-            } else if let Ok((range, val)) = Break::from_meta_data(convert, ignored) {
-                convert.update(range);
-                result = Some(Expression::Break(Box::new(val)));
-            } else if let Ok((range, val)) = Continue::from_meta_data(convert, ignored) {
-                convert.update(range);
-                result = Some(Expression::Continue(Box::new(val)));
-            } else if let Ok((range, val)) =
-                Block::from_meta_data(file, source, "block", convert, ignored)
-            {
-                convert.update(range);
-                result = Some(Expression::Block(Box::new(val)));
-            } else if let Ok((range, val)) =
-                BinOpSeq::from_meta_data(file, source, "add", convert, ignored)
-            {
-                convert.update(range);
-                result = Some(val.into_expression());
-            } else if let Ok((range, val)) =
-                UnOpExpression::from_meta_data("not", file, source, convert, ignored)
-            {
-                convert.update(range);
-                result = Some(val);
-            } else if let Ok((range, val)) =
-                BinOpSeq::from_meta_data(file, source, "mul", convert, ignored)
-            {
-                convert.update(range);
-                result = Some(val.into_expression());
-            } else if let Ok((range, val)) =
-                BinOpSeq::from_meta_data(file, source, "compare", convert, ignored)
-            {
-                convert.update(range);
-                result = Some(val.into_expression());
-            } else if let Ok((range, val)) = Item::from_meta_data(file, source, convert, ignored) {
-                convert.update(range);
-                result = Some(Expression::Item(Box::new(val)));
-            } else if let Ok((range, val)) = Norm::from_meta_data(file, source, convert, ignored) {
-                convert.update(range);
-                result = Some(val.into_call_expr());
-            } else if let Ok((range, val)) = convert.meta_string("text") {
-                convert.update(range);
-                result = Some(Expression::Variable(Box::new((
-                    convert.source(start).unwrap(),
-                    Variable::Str(val),
-                ))));
-            } else if let Ok((range, val)) = convert.meta_f64("num") {
-                convert.update(range);
-                result = Some(Expression::Variable(Box::new((
-                    convert.source(start).unwrap(),
-                    Variable::f64(val),
-                ))));
-            } else if let Ok((range, val)) = Vec4::from_meta_data(file, source, convert, ignored) {
-                convert.update(range);
-                result = Some(Expression::Vec4(Box::new(val)));
-            } else if let Ok((range, val)) = Mat4::from_meta_data(file, source, convert, ignored) {
-                convert.update(range);
-                result = Some(Expression::Mat4(Box::new(val)));
-            } else if let Ok((range, val)) =
-                Vec4UnLoop::from_meta_data(file, source, convert, ignored)
-            {
-                convert.update(range);
-                result = Some(val.into_expression());
-            } else if let Ok((range, val)) = convert.meta_bool("bool") {
-                convert.update(range);
-                result = Some(Expression::Variable(Box::new((
-                    convert.source(start).unwrap(),
-                    Variable::bool(val),
-                ))));
-            } else if let Ok((range, val)) = convert.meta_string("color") {
-                convert.update(range);
-                if let Some((rgb, a)) = read_color::rgb_maybe_a(&mut val.chars()) {
-                    let v = [
-                        f32::from(rgb[0]) / 255.0,
-                        f32::from(rgb[1]) / 255.0,
-                        f32::from(rgb[2]) / 255.0,
-                        f32::from(a.unwrap_or(255)) / 255.0,
-                    ];
-                    result = Some(Expression::Variable(Box::new((range, Variable::Vec4(v)))));
+                if let Some(val) = val.precompute() {
+                    lazy.push(Lazy::Variable(val));
                 } else {
                     return Err(());
                 }
-            } else if let Ok((range, val)) = Go::from_meta_data(file, source, convert, ignored) {
-                convert.update(range);
-                result = Some(Expression::Go(Box::new(val)));
-            } else if let Ok((range, val)) = Call::from_meta_data(file, source, convert, ignored) {
-                convert.update(range);
-                result = Some(Expression::Call(Box::new(val)));
-            } else if let Ok((range, val)) =
-                Call::named_from_meta_data(file, source, convert, ignored)
-            {
-                convert.update(range);
-                result = Some(Expression::Call(Box::new(val)));
-            } else if let Ok((range, val)) = Assign::from_meta_data(file, source, convert, ignored)
-            {
-                convert.update(range);
-                result = Some(Expression::Assign(Box::new(val)));
-            } else if let Ok((range, val)) = For::from_meta_data(file, source, convert, ignored) {
-                convert.update(range);
-                result = Some(Expression::For(Box::new(val)));
-            } else if let Ok((range, val)) =
-                ForN::from_meta_data(file, source, "for_n", convert, ignored)
-            {
+            } else if let Ok((range, _)) = convert.meta_bool("ok(_)") {
                 convert.update(range);
-                result = Some(Expression::ForN(Box::new(val)));
-            } else if let Ok((range, val)) =
-                ForN::from_meta_data(file, source, "sum", convert, ignored)
-            {
+                lazy.push(Lazy::UnwrapOk);
+            } else if let Ok((range, _)) = convert.meta_bool("err(_)") {
                 convert.update(range);
-                result = Some(Expression::Sum(Box::new(val)));
-            } else if let Ok((range, val)) =
-                ForN::from_meta_data(file, source, "sum_vec4", convert, ignored)
-            {
+                lazy.push(Lazy::UnwrapErr);
+            } else if let Ok((range, _)) = convert.meta_bool("some(_)") {
                 convert.update(range);
-                result = Some(Expression::SumVec4(Box::new(val)));
-            } else if let Ok((range, val)) =
-                ForN::from_meta_data(file, source, "prod", convert, ignored)
-            {
+                lazy.push(Lazy::UnwrapSome);
+            } else {
+                let range = convert.ignore();
                 convert.update(range);
-                result = Some(Expression::Prod(Box::new(val)));
-            } else if let Ok((range, val)) =
-                ForN::from_meta_data(file, source, "prod_vec4", convert, ignored)
-            {
+                ignored.push(range);
+            }
+        }
+
+        let name = name.ok_or(())?;
+        let ty = match ty {
+            None => Type::Any,
+            Some(ty) => ty,
+        };
+        Ok((
+            convert.subtract(start),
+            Arg {
+                name,
+                lifetime,
+                ty,
+                default,
+                source_range: convert.source(start).unwrap(),
+                mutable,
+            },
+            lazy,
+        ))
+    }
+}
+
+/// Current object reference.
+///
+/// This puts the current object into scope for a function.
+#[derive(Debug, Clone)]
+pub struct Current {
+    /// The name of the current object.
+    pub name: Arc<String>,
+    /// The range in source.
+    pub source_range: Range,
+    /// Whether the current object is mutable.
+    pub mutable: bool,
+}
+
+impl Current {
+    /// Creates current object reference from meta data.
+    pub fn from_meta_data(
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Current), ()> {
+        let start = convert;
+        let node = "current";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut name: Option<Arc<String>> = None;
+        let mut mutable = false;
+        loop {
+            if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
-                result = Some(Expression::ProdVec4(Box::new(val)));
-            } else if let Ok((range, val)) =
-                ForN::from_meta_data(file, source, "min", convert, ignored)
-            {
+                break;
+            } else if let Ok((range, val)) = convert.meta_bool("mut") {
                 convert.update(range);
-                result = Some(Expression::Min(Box::new(val)));
-            } else if let Ok((range, val)) =
-                ForN::from_meta_data(file, source, "max", convert, ignored)
-            {
+                mutable = val;
+            } else if let Ok((range, val)) = convert.meta_string("name") {
                 convert.update(range);
-                result = Some(Expression::Max(Box::new(val)));
-            } else if let Ok((range, val)) =
-                ForN::from_meta_data(file, source, "sift", convert, ignored)
-            {
+                name = Some(val);
+            } else if let Ok((range, _)) = Type::from_meta_data("type", convert, ignored) {
                 convert.update(range);
-                result = Some(Expression::Sift(Box::new(val)));
-            } else if let Ok((range, val)) =
-                ForN::from_meta_data(file, source, "any", convert, ignored)
-            {
+                // Just ignore type for now.
+            } else {
+                let range = convert.ignore();
                 convert.update(range);
-                result = Some(Expression::Any(Box::new(val)));
-            } else if let Ok((range, val)) =
-                ForN::from_meta_data(file, source, "all", convert, ignored)
-            {
+                ignored.push(range);
+            }
+        }
+
+        let name = name.ok_or(())?;
+        Ok((
+            convert.subtract(start),
+            Current {
+                name,
+                source_range: convert.source(start).unwrap(),
+                mutable,
+            },
+        ))
+    }
+}
+
+/// Block.
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// Sub-expression.
+    pub expressions: Vec<Expression>,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl Block {
+    /// Creates block from meta data.
+    pub fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        node: &str,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Block), ()> {
+        let start = convert;
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut expressions = vec![];
+        loop {
+            if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
-                result = Some(Expression::All(Box::new(val)));
+                break;
             } else if let Ok((range, val)) =
-                ForN::from_meta_data(file, source, "link_for", convert, ignored)
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::LinkFor(Box::new(val)));
-            } else if let Ok((range, val)) = Loop::from_meta_data(file, source, convert, ignored) {
+                expressions.push(val);
+            } else {
+                let range = convert.ignore();
                 convert.update(range);
-                result = Some(val.into_expression());
-            } else if let Ok((range, val)) = If::from_meta_data(file, source, convert, ignored) {
+                ignored.push(range);
+            }
+        }
+
+        Ok((
+            convert.subtract(start),
+            Block {
+                expressions,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        let st = stack.len();
+        for expr in &mut self.expressions {
+            expr.get_locals(relative, stack, closure_stack, module, use_lookup);
+        }
+        stack.truncate(st);
+    }
+}
+
+/// Expression.
+#[derive(Debug, Clone)]
+pub enum Expression {
+    /// Link expression.
+    Link(Box<Link>),
+    /// Object expression.
+    Object(Box<Object>),
+    /// Map expression.
+    Map(Box<Map>),
+    /// Set expression.
+    Set(Box<Set>),
+    /// Array expression.
+    Array(Box<Array>),
+    /// Array fill expression.
+    ArrayFill(Box<ArrayFill>),
+    /// Return expression.
+    Return(Box<Expression>),
+    /// Returns with value expression.
+    ReturnVoid(Box<Range>),
+    /// Break expression.
+    Break(Box<Break>),
+    /// Continue expression.
+    Continue(Box<Continue>),
+    /// Block expression.
+    Block(Box<Block>),
+    /// Defer expression.
+    Defer(Box<Defer>),
+    /// Try-catch expression.
+    TryCatch(Box<TryCatch>),
+    /// Go call expression.
+    Go(Box<Go>),
+    /// Yield expression in generator function.
+    Yield(Box<Yield>),
+    /// Call expression.
+    Call(Box<Call>),
+    /// Call external function.
+    CallVoid(Box<CallVoid>),
+    /// Call external function that returns something.
+    CallReturn(Box<CallReturn>),
+    /// Call external function with lazy invariant.
+    CallLazy(Box<CallLazy>),
+    /// Call loaded function.
+    CallLoaded(Box<CallLoaded>),
+    /// Binary operator.
+    CallBinOp(Box<CallBinOp>),
+    /// Unary operator.
+    CallUnOp(Box<CallUnOp>),
+    /// Item expression.
+    Item(Box<Item>),
+    /// Assignment expression.
+    Assign(Box<Assign>),
+    /// 4D vector expression.
+    Vec4(Box<Vec4>),
+    /// 4D matrix expression.
+    Mat4(Box<Mat4>),
+    /// For expression, e.g. `for i := 0; i < 10; i += 1 { ... }`.
+    For(Box<For>),
+    /// For-n expression.
+    ForN(Box<ForN>),
+    /// For-in expression.
+    ForIn(Box<ForIn>),
+    /// Sum for-n expression.
+    Sum(Box<ForN>),
+    /// Sum-in for-n expression.
+    SumIn(Box<ForIn>),
+    /// Component-wise 4D vector sum for-n-loop.
+    SumVec4(Box<ForN>),
+    /// Product for-n expression.
+    Prod(Box<ForN>),
+    /// Product-in for-n loop.
+    ProdIn(Box<ForIn>),
+    /// Component-wise 4D vector product for-n-loop.
+    ProdVec4(Box<ForN>),
+    /// Min for-n expression.
+    Min(Box<ForN>),
+    /// Min-in for-n expression.
+    MinIn(Box<ForIn>),
+    /// Max for-n expression.
+    Max(Box<ForN>),
+    /// Max-in for-n expression.
+    MaxIn(Box<ForIn>),
+    /// Sift for-n expression.
+    Sift(Box<ForN>),
+    /// Sift-in expression.
+    SiftIn(Box<ForIn>),
+    /// Any expression.
+    Any(Box<ForN>),
+    /// Any-in expression.
+    AnyIn(Box<ForIn>),
+    /// All-for expression.
+    All(Box<ForN>),
+    /// All-in expression.
+    AllIn(Box<ForIn>),
+    /// Link-for expression.
+    LinkFor(Box<ForN>),
+    /// Link-in expression.
+    LinkIn(Box<ForIn>),
+    /// If-expression.
+    If(Box<If>),
+    /// Variable.
+    ///
+    /// This means it contains no members that depends on other expressions.
+    Variable(Box<(Range, Variable)>),
+    /// Try expression using `?`.
+    Try(Box<Expression>),
+    /// 4D vector swizzle expression.
+    Swizzle(Box<Swizzle>),
+    /// Closure expression.
+    Closure(Arc<Closure>),
+    /// Call closure expression.
+    CallClosure(Box<CallClosure>),
+    /// Grab expression.
+    Grab(Box<Grab>),
+    /// Try expression, e.g. `try x`.
+    TryExpr(Box<TryExpr>),
+    /// In-type expression.
+    In(Box<In>),
+    /// Match expression.
+    Match(Box<Match>),
+    /// Enum variant constructor.
+    Variant(Box<Variant>),
+    /// String interpolation.
+    Interpolation(Box<Interpolation>),
+    /// Destructuring declaration.
+    Destructure(Box<Destructure>),
+}
+
+// Required because the `Sync` impl of `Variable` is unsafe.
+unsafe impl Sync for Expression {}
+
+impl Expression {
+    /// Creates expression from meta data.
+    pub fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        node: &str,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Expression), ()> {
+        let start = convert;
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut result: Option<Expression> = None;
+        loop {
+            if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
-                result = Some(Expression::If(Box::new(val)));
-            } else if let Ok((range, _)) = convert.meta_bool("try") {
+                break;
+            } else if let Ok((range, _)) = convert.meta_bool("mut") {
+                // Ignore `mut` since it is handled by lifetime checker.
                 convert.update(range);
-                result = Some(Expression::Try(Box::new(result.unwrap())));
-            } else if let Ok((range, val)) = Swizzle::from_meta_data(file, source, convert, ignored)
-            {
+            } else if let Ok((range, val)) = Link::from_meta_data(file, source, convert, ignored) {
                 convert.update(range);
-                result = Some(Expression::Swizzle(Box::new(val)));
-            } else if let Ok((range, val)) =
-                Closure::from_meta_data(file, source, "closure", convert, ignored)
+                result = Some(Expression::Link(Box::new(val)));
+            } else if let Ok((range, val)) = Object::from_meta_data(file, source, convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::Closure(Arc::new(val)));
+                result = Some(Expression::Object(Box::new(val)));
+            } else if let Ok((range, val)) = Map::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::Map(Box::new(val)));
+            } else if let Ok((range, val)) = Set::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::Set(Box::new(val)));
+            } else if let Ok((range, val)) = Array::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::Array(Box::new(val)));
             } else if let Ok((range, val)) =
-                CallClosure::from_meta_data(file, source, convert, ignored)
+                ArrayFill::from_meta_data(file, source, convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::CallClosure(Box::new(val)));
+                result = Some(Expression::ArrayFill(Box::new(val)));
             } else if let Ok((range, val)) =
-                CallClosure::named_from_meta_data(file, source, convert, ignored)
+                Expression::from_meta_data(file, source, "return", convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::CallClosure(Box::new(val)));
-            } else if let Ok((range, val)) = Grab::from_meta_data(file, source, convert, ignored) {
+                result = Some(Expression::Return(Box::new(val)));
+            } else if let Ok((range, _)) = convert.meta_bool("return_void") {
                 convert.update(range);
-                if let Some(v) = val.precompute() {
-                    result = Some(Expression::Variable(Box::new((val.source_range, v))));
-                } else {
-                    result = Some(Expression::Grab(Box::new(val)));
-                }
-            } else if let Ok((range, val)) = TryExpr::from_meta_data(file, source, convert, ignored)
-            {
+                result = Some(Expression::ReturnVoid(Box::new(
+                    convert.source(start).unwrap(),
+                )));
+            } else if let Ok((range, val)) = Break::from_meta_data(convert, ignored) {
                 convert.update(range);
-                result = Some(Expression::TryExpr(Box::new(val)));
-            } else if let Ok((range, val)) = In::from_meta_data("in", convert, ignored) {
+                result = Some(Expression::Break(Box::new(val)));
+            } else if let Ok((range, val)) = Continue::from_meta_data(convert, ignored) {
                 convert.update(range);
-                result = Some(Expression::In(Box::new(val)));
+                result = Some(Expression::Continue(Box::new(val)));
             } else if let Ok((range, val)) =
-                ForIn::from_meta_data(file, source, "for_in", convert, ignored)
+                Block::from_meta_data(file, source, "block", convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::ForIn(Box::new(val)));
+                result = Some(Expression::Block(Box::new(val)));
+            } else if let Ok((range, val)) = Defer::from_meta_data(file, source, convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Defer(Box::new(val)));
             } else if let Ok((range, val)) =
-                ForIn::from_meta_data(file, source, "sum_in", convert, ignored)
+                TryCatch::from_meta_data(file, source, convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::SumIn(Box::new(val)));
+                result = Some(Expression::TryCatch(Box::new(val)));
             } else if let Ok((range, val)) =
-                ForIn::from_meta_data(file, source, "prod_in", convert, ignored)
+                BinOpSeq::from_meta_data(file, source, "add", convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::ProdIn(Box::new(val)));
+                result = Some(val.into_expression());
             } else if let Ok((range, val)) =
-                ForIn::from_meta_data(file, source, "min_in", convert, ignored)
+                UnOpExpression::from_meta_data("not", file, source, convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::MinIn(Box::new(val)));
+                result = Some(val);
             } else if let Ok((range, val)) =
-                ForIn::from_meta_data(file, source, "max_in", convert, ignored)
+                BinOpSeq::from_meta_data(file, source, "mul", convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::MaxIn(Box::new(val)));
+                result = Some(val.into_expression());
             } else if let Ok((range, val)) =
-                ForIn::from_meta_data(file, source, "sift_in", convert, ignored)
+                BinOpSeq::from_meta_data(file, source, "compare", convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::SiftIn(Box::new(val)));
+                result = Some(val.into_expression());
+            } else if let Ok((range, val)) = Item::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::Item(Box::new(val)));
+            } else if let Ok((range, val)) = Norm::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(val.into_call_expr());
             } else if let Ok((range, val)) =
-                ForIn::from_meta_data(file, source, "any_in", convert, ignored)
+                Coalesce::from_meta_data(file, source, convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::AnyIn(Box::new(val)));
+                result = Some(val.into_call_expr());
             } else if let Ok((range, val)) =
-                ForIn::from_meta_data(file, source, "all_in", convert, ignored)
+                RangeExpr::from_meta_data(file, source, convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::AllIn(Box::new(val)));
+                result = Some(val.into_call_expr());
             } else if let Ok((range, val)) =
-                ForIn::from_meta_data(file, source, "link_in", convert, ignored)
+                Interpolation::from_meta_data(file, source, convert, ignored)
             {
                 convert.update(range);
-                result = Some(Expression::LinkIn(Box::new(val)));
-            } else {
-                let range = convert.ignore();
+                if val.exprs.is_empty() {
+                    result = Some(Expression::Variable(Box::new((
+                        val.source_range,
+                        Variable::Str(val.texts[0].clone()),
+                    ))));
+                } else {
+                    result = Some(Expression::Interpolation(Box::new(val)));
+                }
+            } else if let Ok((range, val)) = convert.meta_string("text") {
                 convert.update(range);
-                ignored.push(range);
-            }
-        }
-
-        let result = result.ok_or(())?;
-        Ok((convert.subtract(start), result))
+                result = Some(Expression::Variable(Box::new((
+                    convert.source(start).unwrap(),
+                    Variable::Str(val),
+                ))));
+            } else if let Ok((range, val)) = convert.meta_f64("num") {
+                convert.update(range);
+                result = Some(Expression::Variable(Box::new((
+                    convert.source(start).unwrap(),
+                    Variable::f64(val),
+                ))));
+            } else if let Ok((range, val)) = convert.meta_string("int") {
+                convert.update(range);
+                result = Some(Expression::Variable(Box::new((
+                    convert.source(start).unwrap(),
+                    Variable::I64(parse_int(&val).ok_or(())?),
+                ))));
+            } else if let Ok((range, val)) = convert.meta_string("range_num") {
+                convert.update(range);
+                result = Some(Expression::Variable(Box::new((
+                    convert.source(start).unwrap(),
+                    Variable::f64(parse_range_num(&val).ok_or(())?),
+                ))));
+            } else if let Ok((range, val)) = Vec4::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::Vec4(Box::new(val)));
+            } else if let Ok((range, val)) = Mat4::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::Mat4(Box::new(val)));
+            } else if let Ok((range, val)) =
+                Vec4UnLoop::from_meta_data(file, source, convert, ignored)
+            {
+                convert.update(range);
+                result = Some(val.into_expression());
+            } else if let Ok((range, val)) = convert.meta_bool("bool") {
+                convert.update(range);
+                result = Some(Expression::Variable(Box::new((
+                    convert.source(start).unwrap(),
+                    Variable::bool(val),
+                ))));
+            } else if let Ok((range, val)) = convert.meta_string("color") {
+                convert.update(range);
+                if let Some((rgb, a)) = read_color::rgb_maybe_a(&mut val.chars()) {
+                    let v = [
+                        f32::from(rgb[0]) / 255.0,
+                        f32::from(rgb[1]) / 255.0,
+                        f32::from(rgb[2]) / 255.0,
+                        f32::from(a.unwrap_or(255)) / 255.0,
+                    ];
+                    result = Some(Expression::Variable(Box::new((range, Variable::Vec4(v)))));
+                } else {
+                    return Err(());
+                }
+            } else if let Ok((range, val)) = Go::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::Go(Box::new(val)));
+            } else if let Ok((range, val)) = Yield::from_meta_data(file, source, convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Yield(Box::new(val)));
+            } else if let Ok((range, val)) = Call::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::Call(Box::new(val)));
+            } else if let Ok((range, val)) =
+                Call::named_from_meta_data(file, source, convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Call(Box::new(val)));
+            } else if let Ok((range, val)) = Assign::from_meta_data(file, source, convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Assign(Box::new(val)));
+            } else if let Ok((range, val)) =
+                Destructure::from_meta_data(file, source, convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Destructure(Box::new(val)));
+            } else if let Ok((range, val)) = For::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::For(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForN::from_meta_data(file, source, "for_n", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::ForN(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForN::from_meta_data(file, source, "sum", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Sum(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForN::from_meta_data(file, source, "sum_vec4", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::SumVec4(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForN::from_meta_data(file, source, "prod", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Prod(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForN::from_meta_data(file, source, "prod_vec4", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::ProdVec4(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForN::from_meta_data(file, source, "min", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Min(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForN::from_meta_data(file, source, "max", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Max(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForN::from_meta_data(file, source, "sift", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Sift(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForN::from_meta_data(file, source, "any", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Any(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForN::from_meta_data(file, source, "all", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::All(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForN::from_meta_data(file, source, "link_for", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::LinkFor(Box::new(val)));
+            } else if let Ok((range, val)) = Loop::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(val.into_expression());
+            } else if let Ok((range, val)) = If::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::If(Box::new(val)));
+            } else if let Ok((range, _)) = convert.meta_bool("try") {
+                convert.update(range);
+                result = Some(Expression::Try(Box::new(result.unwrap())));
+            } else if let Ok((range, val)) = Swizzle::from_meta_data(file, source, convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Swizzle(Box::new(val)));
+            } else if let Ok((range, val)) =
+                Closure::from_meta_data(file, source, "closure", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::Closure(Arc::new(val)));
+            } else if let Ok((range, val)) =
+                CallClosure::from_meta_data(file, source, convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::CallClosure(Box::new(val)));
+            } else if let Ok((range, val)) =
+                CallClosure::named_from_meta_data(file, source, convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::CallClosure(Box::new(val)));
+            } else if let Ok((range, val)) = Grab::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                if let Some(v) = val.precompute() {
+                    result = Some(Expression::Variable(Box::new((val.source_range, v))));
+                } else {
+                    result = Some(Expression::Grab(Box::new(val)));
+                }
+            } else if let Ok((range, val)) = TryExpr::from_meta_data(file, source, convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::TryExpr(Box::new(val)));
+            } else if let Ok((range, val)) = In::from_meta_data("in", convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::In(Box::new(val)));
+            } else if let Ok((range, val)) = Match::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                result = Some(Expression::Match(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForIn::from_meta_data(file, source, "for_in", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::ForIn(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForIn::from_meta_data(file, source, "sum_in", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::SumIn(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForIn::from_meta_data(file, source, "prod_in", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::ProdIn(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForIn::from_meta_data(file, source, "min_in", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::MinIn(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForIn::from_meta_data(file, source, "max_in", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::MaxIn(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForIn::from_meta_data(file, source, "sift_in", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::SiftIn(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForIn::from_meta_data(file, source, "any_in", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::AnyIn(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForIn::from_meta_data(file, source, "all_in", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::AllIn(Box::new(val)));
+            } else if let Ok((range, val)) =
+                ForIn::from_meta_data(file, source, "link_in", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Expression::LinkIn(Box::new(val)));
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
+
+        let result = result.ok_or(())?;
+        Ok((convert.subtract(start), result))
     }
 
-    fn precompute(&self) -> Option<Variable> {
+    pub(crate) fn precompute(&self) -> Option<Variable> {
         use self::Expression::*;
 
         match *self {
             ArrayFill(ref array_fill) => array_fill.precompute(),
             Array(ref array) => array.precompute(),
             Object(ref obj) => obj.precompute(),
+            Map(ref map) => map.precompute(),
+            Set(ref set) => set.precompute(),
             Vec4(ref vec4) => vec4.precompute(),
             Link(ref link) => link.precompute(),
             Variable(ref range_var) => Some(range_var.1.clone()),
+            // Negative numbers are parsed as calls to `neg`.
+            Call(ref call) if call.info.name == *crate::NEG && call.args.len() == 1 => {
+                match call.args[0].precompute()? {
+                    crate::Variable::F64(val, sec) => Some(crate::Variable::F64(-val, sec)),
+                    crate::Variable::I64(val) => Some(crate::Variable::I64(val.checked_neg()?)),
+                    crate::Variable::Vec4(val) => Some(crate::Variable::Vec4(val.map(|x| -x))),
+                    _ => None,
+                }
+            }
             _ => None,
         }
     }
@@ -1465,6 +2112,8 @@ impl Expression {
         match *self {
             Link(ref link) => link.source_range,
             Object(ref obj) => obj.source_range,
+            Map(ref map) => map.source_range,
+            Set(ref set) => set.source_range,
             Array(ref arr) => arr.source_range,
             ArrayFill(ref arr_fill) => arr_fill.source_range,
             Return(ref expr) => expr.source_range(),
@@ -1472,10 +2121,16 @@ impl Expression {
             Break(ref br) => br.source_range,
             Continue(ref c) => c.source_range,
             Block(ref bl) => bl.source_range,
+            Defer(ref defer) => defer.source_range,
+            TryCatch(ref try_catch) => try_catch.source_range,
             #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             Go(ref go) => go.source_range,
             #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
             Go(ref go) => match **go {},
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Yield(ref y) => y.source_range,
+            #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+            Yield(ref y) => match **y {},
             Call(ref call) => call.info.source_range,
             CallVoid(ref call) => call.info.source_range,
             CallReturn(ref call) => call.info.source_range,
@@ -1489,43 +2144,24 @@ impl Expression {
             Mat4(ref mat4) => mat4.source_range,
             For(ref for_expr) => for_expr.source_range,
             ForN(ref for_n_expr) => for_n_expr.source_range,
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             ForIn(ref for_in_expr) => for_in_expr.source_range,
-            #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-            ForIn(ref for_in_expr) |
-            SumIn(ref for_in_expr) |
-            ProdIn(ref for_in_expr) |
-            MinIn(ref for_in_expr) |
-            MaxIn(ref for_in_expr) |
-            SiftIn(ref for_in_expr) |
-            AnyIn(ref for_in_expr) |
-            AllIn(ref for_in_expr) |
-            LinkIn(ref for_in_expr) => match **for_in_expr {},
             Sum(ref for_n_expr) => for_n_expr.source_range,
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             SumIn(ref for_in_expr) => for_in_expr.source_range,
             SumVec4(ref for_n_expr) => for_n_expr.source_range,
             Prod(ref for_n_expr) => for_n_expr.source_range,
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             ProdIn(ref for_in_expr) => for_in_expr.source_range,
             ProdVec4(ref for_n_expr) => for_n_expr.source_range,
             Min(ref for_n_expr) => for_n_expr.source_range,
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             MinIn(ref for_in_expr) => for_in_expr.source_range,
             Max(ref for_n_expr) => for_n_expr.source_range,
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             MaxIn(ref for_in_expr) => for_in_expr.source_range,
             Sift(ref for_n_expr) => for_n_expr.source_range,
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             SiftIn(ref for_in_expr) => for_in_expr.source_range,
             Any(ref for_n_expr) => for_n_expr.source_range,
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             AnyIn(ref for_in_expr) => for_in_expr.source_range,
             All(ref for_n_expr) => for_n_expr.source_range,
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             AllIn(ref for_in_expr) => for_in_expr.source_range,
             LinkFor(ref for_n_expr) => for_n_expr.source_range,
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             LinkIn(ref for_in_expr) => for_in_expr.source_range,
             If(ref if_expr) => if_expr.source_range,
             Variable(ref range_var) => range_var.0,
@@ -1539,6 +2175,10 @@ impl Expression {
             In(ref in_expr) => in_expr.source_range,
             #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
             In(ref in_expr) => match **in_expr {},
+            Match(ref match_expr) => match_expr.source_range,
+            Variant(ref variant) => variant.source_range,
+            Interpolation(ref interp) => interp.source_range,
+            Destructure(ref destructure) => destructure.source_range,
         }
     }
 
@@ -1557,6 +2197,10 @@ impl Expression {
                 link.get_locals(relative, stack, closure_stack, module, use_lookup),
             Object(ref mut obj) =>
                 obj.get_locals(relative, stack, closure_stack, module, use_lookup),
+            Map(ref mut map) =>
+                map.get_locals(relative, stack, closure_stack, module, use_lookup),
+            Set(ref mut set) =>
+                set.get_locals(relative, stack, closure_stack, module, use_lookup),
             Array(ref mut arr) =>
                 arr.get_locals(relative, stack, closure_stack, module, use_lookup),
             ArrayFill(ref mut arr_fill) =>
@@ -1571,7 +2215,12 @@ impl Expression {
             Continue(_) => {}
             Block(ref mut bl) =>
                 bl.get_locals(relative, stack, closure_stack, module, use_lookup),
+            Defer(ref mut defer) =>
+                defer.block.get_locals(relative, stack, closure_stack, module, use_lookup),
+            TryCatch(ref mut try_catch) =>
+                try_catch.get_locals(relative, stack, closure_stack, module, use_lookup),
             Go(ref mut go) => go.get_locals(relative, stack, closure_stack, module, use_lookup),
+            Yield(ref mut y) => y.get_locals(relative, stack, closure_stack, module, use_lookup),
             Call(ref mut call) => {
                 call.get_locals(relative, stack, closure_stack, module, use_lookup);
                 match call.f_index {
@@ -1629,8 +2278,18 @@ impl Expression {
             CallLoaded(_) => unimplemented!("`CallLoaded` is transformed from `Call`"),
             CallBinOp(_) => unimplemented!("`CallBinOp` is transformed from `Call`"),
             CallUnOp(_) => unimplemented!("`CallUnOp` is transformed from `Call`"),
-            Item(ref mut it) =>
-                it.get_locals(relative, stack, closure_stack, module, use_lookup),
+            Item(ref mut it) => {
+                it.get_locals(relative, stack, closure_stack, module, use_lookup);
+                match module.find_constant(&it.name) {
+                    Some(_) if it.static_stack_id.get().is_some() => {}
+                    // Replace constant with its value when there is nothing to look up.
+                    Some(c) if it.ids.is_empty() && !it.try_flag => {
+                        *self = Variable(Box::new((it.source_range, c.value.clone())));
+                    }
+                    Some(c) => it.constant = Some(c.value.clone()),
+                    None => {}
+                }
+            }
             Assign(ref mut assign) =>
                 assign.get_locals(relative, stack, closure_stack, module, use_lookup),
             Vec4(ref mut vec4) =>
@@ -1684,42 +2343,1142 @@ impl Expression {
             TryExpr(ref mut try_expr) =>
                 try_expr.get_locals(relative, stack, closure_stack, module, use_lookup),
             In(ref mut in_expr) => in_expr.get_locals(relative, module, use_lookup),
+            Match(ref mut match_expr) =>
+                match_expr.get_locals(relative, stack, closure_stack, module, use_lookup),
+            Variant(ref mut variant) => {
+                variant.expr.get_locals(relative, stack, closure_stack, module, use_lookup)
+            }
+            Interpolation(ref mut interp) =>
+                interp.get_locals(relative, stack, closure_stack, module, use_lookup),
+            Destructure(ref mut destructure) =>
+                destructure.get_locals(relative, stack, closure_stack, module, use_lookup),
+        }
+    }
+}
+
+/// Link expression, e.g. `link {a b}`.
+#[derive(Debug, Clone)]
+pub struct Link {
+    /// Link item expressions.
+    pub items: Vec<Expression>,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl Link {
+    /// Creates link expression from meta data.
+    pub fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Link), ()> {
+        let start = convert;
+        let node = "link";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut items: Vec<Expression> = vec![];
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "link_item", convert, ignored)
+            {
+                convert.update(range);
+                items.push(val);
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
+
+        Ok((
+            convert.subtract(start),
+            Link {
+                items,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn precompute(&self) -> Option<Variable> {
+        let mut link = crate::link::Link::new();
+        for it in &self.items {
+            if let Some(v) = it.precompute() {
+                if link.push(&v).is_err() {
+                    return None;
+                };
+            } else {
+                return None;
+            }
+        }
+        Some(Variable::Link(Box::new(link)))
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        let st = stack.len();
+        for expr in &mut self.items {
+            expr.get_locals(relative, stack, closure_stack, module, use_lookup);
+        }
+        stack.truncate(st);
+    }
+}
+
+/// Object expression.
+#[derive(Debug, Clone)]
+pub struct Object {
+    /// Key-value pair expressions.
+    pub key_values: Vec<(Arc<String>, Expression)>,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl Object {
+    /// Creates object expression from meta data.
+    pub fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Object), ()> {
+        let start = convert;
+        let node = "object";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut key_values = vec![];
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) =
+                Object::key_value_from_meta_data(file, source, convert, ignored)
+            {
+                convert.update(range);
+                key_values.push(val);
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
+
+        Ok((
+            convert.subtract(start),
+            Object {
+                key_values,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn precompute(&self) -> Option<Variable> {
+        let mut object: HashMap<_, _> = HashMap::new();
+        for &(ref key, ref value) in &self.key_values {
+            if let Some(v) = value.precompute() {
+                object.insert(key.clone(), v);
+            } else {
+                return None;
+            }
+        }
+        Some(Variable::Object(Arc::new(object)))
+    }
+
+    fn key_value_from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, (Arc<String>, Expression)), ()> {
+        let start = convert;
+        let node = "key_value";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut key: Option<Arc<String>> = None;
+        let mut value: Option<Expression> = None;
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) = convert.meta_string("key") {
+                convert.update(range);
+                key = Some(val);
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "val", convert, ignored)
+            {
+                convert.update(range);
+                value = Some(val);
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
+
+        let key = key.ok_or(())?;
+        let value = value.ok_or(())?;
+        Ok((convert.subtract(start), (key, value)))
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        let st = stack.len();
+        for &mut (_, ref mut expr) in &mut self.key_values {
+            expr.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(st);
+        }
+    }
+}
+
+/// Map expression, e.g. `map {1: "one", 2: "two"}`.
+#[derive(Debug, Clone)]
+pub struct Map {
+    /// Key-value pair expressions.
+    pub key_values: Vec<(Expression, Expression)>,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl Map {
+    /// Creates map expression from meta data.
+    pub(crate) fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Map), ()> {
+        let start = convert;
+        let node = "map";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut key: Option<Expression> = None;
+        let mut key_values = vec![];
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "key", convert, ignored)
+            {
+                convert.update(range);
+                key = Some(val);
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "val", convert, ignored)
+            {
+                convert.update(range);
+                key_values.push((key.take().ok_or(())?, val));
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
+
+        Ok((
+            convert.subtract(start),
+            Map {
+                key_values,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn precompute(&self) -> Option<Variable> {
+        let mut map: HashMap<_, _> = HashMap::new();
+        for (key, value) in &self.key_values {
+            let key = crate::Key::new(&key.precompute()?, &[]).ok()?;
+            if map.insert(key, value.precompute()?).is_some() {
+                // Duplicate keys are reported at runtime.
+                return None;
+            }
+        }
+        Some(Variable::Map(map.into()))
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        let st = stack.len();
+        for &mut (ref mut key, ref mut val) in &mut self.key_values {
+            key.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(st);
+            val.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(st);
+        }
+    }
+}
+
+/// Set expression, e.g. `set {a, b, c}`.
+#[derive(Debug, Clone)]
+pub struct Set {
+    /// Set item expressions.
+    pub items: Vec<Expression>,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl Set {
+    /// Creates set expression from meta data.
+    pub(crate) fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Set), ()> {
+        let start = convert;
+        let node = "set";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut items = vec![];
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "key", convert, ignored)
+            {
+                convert.update(range);
+                items.push(val);
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
+
+        Ok((
+            convert.subtract(start),
+            Set {
+                items,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn precompute(&self) -> Option<Variable> {
+        let mut res = HashSet::with_capacity(self.items.len());
+        for item in &self.items {
+            res.insert(crate::Key::new(&item.precompute()?, &[]).ok()?);
+        }
+        Some(Variable::Set(Arc::new(res)))
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        let st = stack.len();
+        for item in &mut self.items {
+            item.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(st);
+        }
+    }
+}
+
+/// Array expression, e.g. `[a, b, c]`.
+#[derive(Debug, Clone)]
+pub struct Array {
+    /// Array item expressions.
+    pub items: Vec<Expression>,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl Array {
+    /// Creates array expression from meta data.
+    pub fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Array), ()> {
+        let start = convert;
+        let node = "array";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut items = vec![];
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "array_item", convert, ignored)
+            {
+                convert.update(range);
+                items.push(val);
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
+
+        Ok((
+            convert.subtract(start),
+            Array {
+                items,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn precompute(&self) -> Option<Variable> {
+        let mut res = Vec::with_capacity(self.items.len());
+        for item in &self.items {
+            if let Some(v) = item.precompute() {
+                res.push(v);
+            } else {
+                return None;
+            }
+        }
+        Some(Variable::Array(Arc::new(res)))
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        let st = stack.len();
+        for item in &mut self.items {
+            item.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(st);
+        }
+    }
+}
+
+/// String interpolation, e.g. `$"pos: {x}, {y}"`.
+#[derive(Debug, Clone)]
+pub struct Interpolation {
+    /// Text segments.
+    ///
+    /// There is one more text segment than expressions,
+    /// such that expressions are written between the text segments.
+    pub texts: Vec<Arc<String>>,
+    /// Embedded expressions.
+    pub exprs: Vec<Expression>,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl Interpolation {
+    /// Creates string interpolation from meta data.
+    pub(crate) fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Interpolation), ()> {
+        let start = convert;
+        let node = "interp";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut text = String::new();
+        let mut texts = vec![];
+        let mut exprs = vec![];
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
+            {
+                convert.update(range);
+                texts.push(Arc::new(std::mem::take(&mut text)));
+                exprs.push(val);
+            } else if let Ok((range, val)) = convert.meta_string("text") {
+                convert.update(range);
+                text.push_str(&val);
+            } else if let Ok((range, _)) = convert.meta_bool("esc_n") {
+                convert.update(range);
+                text.push('\n');
+            } else if let Ok((range, _)) = convert.meta_bool("esc_t") {
+                convert.update(range);
+                text.push('\t');
+            } else if let Ok((range, _)) = convert.meta_bool("esc_bs") {
+                convert.update(range);
+                text.push('\\');
+            } else if let Ok((range, _)) = convert.meta_bool("esc_dq") {
+                convert.update(range);
+                text.push('"');
+            } else if let Ok((range, _)) = convert.meta_bool("esc_lb") {
+                convert.update(range);
+                text.push('{');
+            } else if let Ok((range, _)) = convert.meta_bool("esc_rb") {
+                convert.update(range);
+                text.push('}');
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
+        texts.push(Arc::new(text));
+
+        Ok((
+            convert.subtract(start),
+            Interpolation {
+                texts,
+                exprs,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        let st = stack.len();
+        for expr in &mut self.exprs {
+            expr.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(st);
+        }
+    }
+}
+
+/// Array fill expression, e.g. `[a; n]`.
+#[derive(Debug, Clone)]
+pub struct ArrayFill {
+    /// The `a` in `[a; n]`.
+    pub fill: Expression,
+    /// The `n` in `[a; n]`.
+    pub n: Expression,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl ArrayFill {
+    /// Creates array fill expression from meta data.
+    pub fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, ArrayFill), ()> {
+        let start = convert;
+        let node = "array_fill";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut fill: Option<Expression> = None;
+        let mut n: Option<Expression> = None;
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "fill", convert, ignored)
+            {
+                convert.update(range);
+                fill = Some(val);
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "n", convert, ignored)
+            {
+                convert.update(range);
+                n = Some(val);
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
+
+        let fill = fill.ok_or(())?;
+        let n = n.ok_or(())?;
+        Ok((
+            convert.subtract(start),
+            ArrayFill {
+                fill,
+                n,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn precompute(&self) -> Option<Variable> {
+        if let Expression::Variable(ref range_var) = self.n {
+            if let (_, Variable::F64(n, _)) = **range_var {
+                if let Expression::Variable(ref x) = self.fill {
+                    return Some(Variable::Array(Arc::new(vec![x.1.clone(); n as usize])));
+                }
+            }
+        }
+        None
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        let st = stack.len();
+        self.fill.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(st);
+        self.n.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(st);
+    }
+}
+
+/// Parse sequence of binary operators.
+#[derive(Debug, Clone)]
+pub struct BinOpSeq {
+    /// Item expressions.
+    pub items: Vec<Expression>,
+    /// Binary operators.
+    pub ops: Vec<BinOp>,
+    /// The range of each binary operator in source.
+    pub op_ranges: Vec<Range>,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl BinOpSeq {
+    /// Creates multiply expression from meta data.
+    pub fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        node: &str,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, BinOpSeq), ()> {
+        let start = convert;
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut items = vec![];
+        let mut ops = vec![];
+        let mut op_ranges = vec![];
+        loop {
+            let op_start = convert;
+            let n = ops.len();
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) =
+                UnOpExpression::from_meta_data("neg", file, source, convert, ignored)
+            {
+                convert.update(range);
+                items.push(val);
+            } else if let Ok((range, val)) =
+                BinOpSeq::from_meta_data(file, source, "pow", convert, ignored)
+            {
+                convert.update(range);
+                items.push(val.into_expression());
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
+            {
+                convert.update(range);
+                items.push(val);
+            } else if let Ok((range, _)) = convert.meta_bool("*.") {
+                convert.update(range);
+                ops.push(BinOp::Dot);
+            } else if let Ok((range, _)) = convert.meta_bool("x") {
+                convert.update(range);
+                ops.push(BinOp::Cross);
+            } else if let Ok((range, _)) = convert.meta_bool("*") {
+                convert.update(range);
+                ops.push(BinOp::Mul);
+            } else if let Ok((range, _)) = convert.meta_bool("/") {
+                convert.update(range);
+                ops.push(BinOp::Div);
+            } else if let Ok((range, _)) = convert.meta_bool("%") {
+                convert.update(range);
+                ops.push(BinOp::Rem);
+            } else if let Ok((range, _)) = convert.meta_bool("+") {
+                convert.update(range);
+                ops.push(BinOp::Add);
+            } else if let Ok((range, _)) = convert.meta_bool("-") {
+                convert.update(range);
+                ops.push(BinOp::Sub);
+            } else if let Ok((range, _)) = convert.meta_bool("||") {
+                convert.update(range);
+                ops.push(BinOp::OrElse);
+            } else if let Ok((range, _)) = convert.meta_bool("^") {
+                convert.update(range);
+                ops.push(BinOp::Pow);
+            } else if let Ok((range, _)) = convert.meta_bool("&&") {
+                convert.update(range);
+                ops.push(BinOp::AndAlso);
+            } else if let Ok((range, _)) = convert.meta_bool("<") {
+                convert.update(range);
+                ops.push(BinOp::Less);
+            } else if let Ok((range, _)) = convert.meta_bool("<=") {
+                convert.update(range);
+                ops.push(BinOp::LessOrEqual);
+            } else if let Ok((range, _)) = convert.meta_bool(">") {
+                convert.update(range);
+                ops.push(BinOp::Greater);
+            } else if let Ok((range, _)) = convert.meta_bool(">=") {
+                convert.update(range);
+                ops.push(BinOp::GreaterOrEqual);
+            } else if let Ok((range, _)) = convert.meta_bool("==") {
+                convert.update(range);
+                ops.push(BinOp::Equal);
+            } else if let Ok((range, _)) = convert.meta_bool("!=") {
+                convert.update(range);
+                ops.push(BinOp::NotEqual);
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+            if ops.len() > n {
+                op_ranges.push(convert.source(op_start).unwrap());
+            }
+        }
+
+        if items.is_empty() {
+            return Err(());
+        }
+        Ok((
+            convert.subtract(start),
+            BinOpSeq {
+                items,
+                ops,
+                op_ranges,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn into_expression(mut self) -> Expression {
+        if self.items.len() == 1 {
+            self.items[0].clone()
+        } else {
+            let op = self.ops.pop().expect("Expected a binary operation");
+            let op_range = self.op_ranges.pop().expect("Expected operator range");
+            let last = self.items.pop().expect("Expected argument");
+            let source_range = self.source_range;
+            BinOpExpression {
+                op,
+                left: self.into_expression(),
+                right: last,
+                source_range,
+                op_range,
+            }
+            .into_expression()
+        }
+    }
+}
+
+/// Binary operator.
+#[derive(Debug, Copy, Clone)]
+pub enum BinOp {
+    /// Addition operator (`+`).
+    Add,
+    /// Subtraction operator (`-`).
+    Sub,
+    /// Multiply operator (`*`).
+    Mul,
+    /// Dot product operator (`*.`).
+    Dot,
+    /// Cross product operator (`x`).
+    Cross,
+    /// Division operator (`/`).
+    Div,
+    /// Remainder operator (`%`).
+    Rem,
+    /// Power operator (`^`).
+    Pow,
+    /// Lazy OR operator (`||`).
+    OrElse,
+    /// Lazy AND operator (`&&`).
+    AndAlso,
+    /// Less.
+    Less,
+    /// Less or equal.
+    LessOrEqual,
+    /// Greater.
+    Greater,
+    /// Greater or equal.
+    GreaterOrEqual,
+    /// Equal.
+    Equal,
+    /// Not equal.
+    NotEqual,
+    /// Pipe operator (`|>`).
+    ///
+    /// This is rewritten into a call, e.g. `x |> f(a)` becomes `f(x, a)`.
+    Pipe,
+}
+
+pub(crate) const BINOP_PREC_POW: u8 = 4;
+pub(crate) const BINOP_PREC_MUL: u8 = 3;
+pub(crate) const BINOP_PREC_ADD: u8 = 2;
+pub(crate) const BINOP_PREC_EQ: u8 = 1;
+pub(crate) const BINOP_PREC_PIPE: u8 = 0;
+
+impl BinOp {
+    /// Returns symbol of binary operator.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Dot => "*.",
+            BinOp::Cross => "x",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::Pow => "^",
+            BinOp::OrElse => "||",
+            BinOp::AndAlso => "&&",
+            BinOp::Less => "<",
+            BinOp::LessOrEqual => "<=",
+            BinOp::Greater => ">",
+            BinOp::GreaterOrEqual => ">=",
+            BinOp::Equal => "==",
+            BinOp::NotEqual => "!=",
+            BinOp::Pipe => "|>",
+        }
+    }
+
+    /// Returns symbol of binary operator in boolean variant.
+    pub fn symbol_bool(self) -> &'static str {
+        match self {
+            BinOp::Add => "or",
+            BinOp::Mul => "and",
+            _ => self.symbol(),
+        }
+    }
+
+    /// Returns the operator precedence level.
+    /// Used to put parentheses in right places when printing out closures.
+    ///
+    /// From highest to lowest:
+    ///
+    /// - `^`
+    /// - `*`, `*.`, `x`, `/`, `%`, `&&`
+    /// - `+`, `-`, `||`
+    /// - `<`, `<=`, `>`, `>=`, `==`, `!=`
+    /// - `|>`, which takes the whole expression on the left,
+    ///   e.g. `a + b |> f()` is `f(a + b)`
+    pub fn precedence(self) -> u8 {
+        match self {
+            BinOp::Less
+            | BinOp::LessOrEqual
+            | BinOp::Greater
+            | BinOp::GreaterOrEqual
+            | BinOp::Equal
+            | BinOp::NotEqual => BINOP_PREC_EQ,
+            BinOp::OrElse => BINOP_PREC_ADD,
+            BinOp::AndAlso => BINOP_PREC_MUL,
+            BinOp::Add | BinOp::Sub => BINOP_PREC_ADD,
+            BinOp::Mul | BinOp::Dot | BinOp::Cross | BinOp::Div | BinOp::Rem => BINOP_PREC_MUL,
+            BinOp::Pow => BINOP_PREC_POW,
+            BinOp::Pipe => BINOP_PREC_PIPE,
+        }
+    }
+}
+
+/// An item id.
+///
+/// This is the thing that's inside the square brackets, e.g. `foo[i]`.
+#[derive(Debug, Clone)]
+pub enum Id {
+    /// A string.
+    String(Range, Arc<String>),
+    /// A number.
+    F64(Range, f64),
+    /// An expression.
+    Expression(Expression),
+}
+
+impl Id {
+    /// Gets the range in source.
+    pub fn source_range(&self) -> Range {
+        match *self {
+            Id::String(range, _) => range,
+            Id::F64(range, _) => range,
+            Id::Expression(ref expr) => expr.source_range(),
+        }
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) -> bool {
+        match *self {
+            Id::String(_, _) => false,
+            Id::F64(_, _) => false,
+            Id::Expression(ref mut expr) => {
+                let st = stack.len();
+                expr.get_locals(relative, stack, closure_stack, module, use_lookup);
+                stack.truncate(st);
+                true
+            }
+        }
+    }
+}
+
+/// Item.
+#[derive(Debug, Clone)]
+pub struct Item {
+    /// The name of item.
+    pub name: Arc<String>,
+    /// Dynamical looked up stack id.
+    ///
+    /// This is checked against the static stack id when
+    /// the Cargo feature "debug_lookup" is enabled.
+    pub stack_id: Cell<Option<usize>>,
+    /// Statical looked up stack id.
+    ///
+    /// This is used when the Cargo feature "debug_lookup" is disabled.
+    pub static_stack_id: Cell<Option<usize>>,
+    /// Whether the item is a current object.
+    pub current: bool,
+    /// Whether there is a `?` after the item.
+    pub try_flag: bool,
+    /// Item ids.
+    pub ids: Vec<Id>,
+    /// Stores indices of ids that should propagate errors.
+    pub try_ids: Vec<usize>,
+    /// Stores the number of ids looked up before each `?.`.
+    ///
+    /// When any of these values is `none()`, the item evaluates to `none()`.
+    /// Otherwise, the item is wrapped in `some(_)`.
+    pub opt_ids: Vec<usize>,
+    /// Module constant the item refers to, resolved when loading.
+    pub constant: Option<Variable>,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl Item {
+    /// Creates item from variable.
+    pub fn from_variable(name: Arc<String>, source_range: Range) -> Item {
+        Item {
+            name,
+            current: false,
+            stack_id: Cell::new(None),
+            static_stack_id: Cell::new(None),
+            try_flag: false,
+            ids: vec![],
+            try_ids: vec![],
+            opt_ids: vec![],
+            constant: None,
+            source_range,
+        }
+    }
+
+    /// Truncates item extra to a given length.
+    fn trunc(&self, n: usize) -> Item {
+        Item {
+            name: self.name.clone(),
+            current: self.current,
+            stack_id: Cell::new(None),
+            static_stack_id: Cell::new(None),
+            try_flag: self.try_flag,
+            ids: self.ids.iter().take(n).cloned().collect(),
+            try_ids: {
+                let mut try_ids = vec![];
+                for &ind in &self.try_ids {
+                    if ind >= n {
+                        break;
+                    }
+                    try_ids.push(ind);
+                }
+                try_ids
+            },
+            opt_ids: self.opt_ids.iter().copied().filter(|&ind| ind < n).collect(),
+            constant: None,
+            source_range: self.source_range,
+        }
+    }
+
+    /// Creates item from meta data.
+    pub fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Item), ()> {
+        let start = convert;
+        let node = "item";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut name: Option<Arc<String>> = None;
+        let mut current = false;
+        let mut ids = vec![];
+        let mut try_ids = vec![];
+        let mut opt_ids = vec![];
+        let mut try_flag = false;
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) = convert.meta_string("name") {
+                convert.update(range);
+                name = Some(val);
+            } else if let Ok((range, _)) = convert.meta_bool("current") {
+                convert.update(range);
+                current = true;
+            } else if let Ok((range, _)) = convert.meta_bool("try_item") {
+                convert.update(range);
+                try_flag = true;
+                // Ignore item extra node, which is there to help the type checker.
+            } else if let Ok(range) = convert.start_node("item_extra") {
+                convert.update(range);
+            } else if let Ok(range) = convert.end_node("item_extra") {
+                convert.update(range);
+            } else if let Ok((range, val)) = convert.meta_string("id") {
+                let start_id = convert;
+                convert.update(range);
+                ids.push(Id::String(convert.source(start_id).unwrap(), val));
+            } else if let Ok((range, val)) = convert.meta_f64("id") {
+                let start_id = convert;
+                convert.update(range);
+                ids.push(Id::F64(convert.source(start_id).unwrap(), val));
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "id", convert, ignored)
+            {
+                convert.update(range);
+                ids.push(Id::Expression(val));
+            } else if let Ok((range, _)) = convert.meta_bool("try_id") {
+                convert.update(range);
+                // id is pushed before the `?` operator, therefore subtract 1.
+                try_ids.push(ids.len() - 1);
+            } else if let Ok((range, _)) = convert.meta_bool("opt_id") {
+                convert.update(range);
+                opt_ids.push(ids.len());
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
+
+        let name = name.ok_or(())?;
+        Ok((
+            convert.subtract(start),
+            Item {
+                name,
+                stack_id: Cell::new(None),
+                static_stack_id: Cell::new(None),
+                current,
+                try_flag,
+                ids,
+                try_ids,
+                opt_ids,
+                constant: None,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        let st = stack.len();
+        for (i, n) in stack.iter().rev().enumerate() {
+            if let Some(ref n) = *n {
+                if **n == **self.name {
+                    self.static_stack_id.set(Some(i + 1));
+                    break;
+                }
+            }
+        }
+        for id in &mut self.ids {
+            if id.get_locals(relative, stack, closure_stack, module, use_lookup) {
+                stack.push(None);
+            }
         }
+        stack.truncate(st);
     }
 }
 
-/// Link expression, e.g. `link {a b}`.
+/// Go call.
+#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
 #[derive(Debug, Clone)]
-pub struct Link {
-    /// Link item expressions.
-    pub items: Vec<Expression>,
+pub struct Go {
+    /// Function call.
+    pub call: Call,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Link {
-    /// Creates link expression from meta data.
+/// Go call.
+#[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+#[derive(Debug, Clone)]
+pub enum Go {}
+
+impl Go {
+    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+    /// Creates go call from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Link), ()> {
+    ) -> Result<(Range, Go), ()> {
         let start = convert;
-        let node = "link";
+        let node = "go";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut items: Vec<Expression> = vec![];
+        let mut call: Option<Call> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
+            } else if let Ok((range, val)) = Call::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                call = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "link_item", convert, ignored)
+                Call::named_from_meta_data(file, source, convert, ignored)
             {
                 convert.update(range);
-                items.push(val);
+                call = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -1727,29 +3486,28 @@ impl Link {
             }
         }
 
+        let call = call.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Link {
-                items,
+            Go {
+                call,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
-    fn precompute(&self) -> Option<Variable> {
-        let mut link = crate::link::Link::new();
-        for it in &self.items {
-            if let Some(v) = it.precompute() {
-                if link.push(&v).is_err() {
-                    return None;
-                };
-            } else {
-                return None;
-            }
-        }
-        Some(Variable::Link(Box::new(link)))
+    #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+    /// Creates go call from meta data.
+    pub fn from_meta_data(
+        _file: &Arc<String>,
+        _source: &Arc<String>,
+        _convert: Convert,
+        _ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Go), ()> {
+        Err(())
     }
 
+    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
     fn get_locals(
         &mut self,
         relative: usize,
@@ -1759,45 +3517,61 @@ impl Link {
         use_lookup: &UseLookup,
     ) {
         let st = stack.len();
-        for expr in &mut self.items {
-            expr.get_locals(relative, stack, closure_stack, module, use_lookup);
+        for arg in &mut self.call.args {
+            let st = stack.len();
+            arg.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(st);
         }
         stack.truncate(st);
     }
+
+    #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+    fn get_locals(
+        &mut self,
+        _relative: usize,
+        _stack: &mut Vec<Option<Arc<String>>>,
+        _closure_stack: &mut Vec<usize>,
+        _module: &Module,
+        _use_lookup: &UseLookup,
+    ) {}
 }
 
-/// Object expression.
+/// Defer expression, which runs a block when the enclosing block exits.
+///
+/// Deferred blocks run in reverse order, also on `return`, `break`, `continue` and errors.
+/// An error returned by `?` in a deferred block replaces an `ok` value being returned,
+/// or is added to the trace of an error being returned.
 #[derive(Debug, Clone)]
-pub struct Object {
-    /// Key-value pair expressions.
-    pub key_values: Vec<(Arc<String>, Expression)>,
+pub struct Defer {
+    /// Block to run on exit.
+    pub block: Block,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Object {
-    /// Creates object expression from meta data.
-    pub fn from_meta_data(
+impl Defer {
+    /// Creates defer expression from meta data.
+    pub(crate) fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Object), ()> {
+    ) -> Result<(Range, Defer), ()> {
         let start = convert;
-        let node = "object";
+        let node = "defer";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut key_values = vec![];
+        let mut block: Option<Block> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
             } else if let Ok((range, val)) =
-                Object::key_value_from_meta_data(file, source, convert, ignored)
+                Block::from_meta_data(file, source, "block", convert, ignored)
             {
                 convert.update(range);
-                key_values.push(val);
+                block = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -1805,52 +3579,61 @@ impl Object {
             }
         }
 
+        let block = block.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Object {
-                key_values,
+            Defer {
+                block,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
+}
 
-    fn precompute(&self) -> Option<Variable> {
-        let mut object: HashMap<_, _> = HashMap::new();
-        for &(ref key, ref value) in &self.key_values {
-            if let Some(v) = value.precompute() {
-                object.insert(key.clone(), v);
-            } else {
-                return None;
-            }
-        }
-        Some(Variable::Object(Arc::new(object)))
-    }
+/// Try-catch expression, which turns runtime errors in a block into an error value.
+///
+/// The value is the result of the try block, or else the result of the catch block.
+/// The caught error has the message of the runtime error,
+/// with the location in source and the functions that were running in the trace.
+#[derive(Debug, Clone)]
+pub struct TryCatch {
+    /// Block to try.
+    pub block: Block,
+    /// Name of error variable in catch block.
+    pub name: Arc<String>,
+    /// Block to run on error.
+    pub catch_block: Block,
+    /// The range in source.
+    pub source_range: Range,
+}
 
-    fn key_value_from_meta_data(
+impl TryCatch {
+    /// Creates try-catch expression from meta data.
+    pub(crate) fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, (Arc<String>, Expression)), ()> {
+    ) -> Result<(Range, TryCatch), ()> {
         let start = convert;
-        let node = "key_value";
+        let node = "try_catch";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut key: Option<Arc<String>> = None;
-        let mut value: Option<Expression> = None;
+        let mut block: Option<Block> = None;
+        let mut catch: Option<(Arc<String>, Block)> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = convert.meta_string("key") {
-                convert.update(range);
-                key = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "val", convert, ignored)
+                Block::from_meta_data(file, source, "block", convert, ignored)
             {
                 convert.update(range);
-                value = Some(val);
+                block = Some(val);
+            } else if let Ok((range, val)) = TryCatch::catch(file, source, convert, ignored) {
+                convert.update(range);
+                catch = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -1858,59 +3641,45 @@ impl Object {
             }
         }
 
-        let key = key.ok_or(())?;
-        let value = value.ok_or(())?;
-        Ok((convert.subtract(start), (key, value)))
-    }
-
-    fn get_locals(
-        &mut self,
-        relative: usize,
-        stack: &mut Vec<Option<Arc<String>>>,
-        closure_stack: &mut Vec<usize>,
-        module: &Module,
-        use_lookup: &UseLookup,
-    ) {
-        let st = stack.len();
-        for &mut (_, ref mut expr) in &mut self.key_values {
-            expr.get_locals(relative, stack, closure_stack, module, use_lookup);
-            stack.truncate(st);
-        }
+        let block = block.ok_or(())?;
+        let (name, catch_block) = catch.ok_or(())?;
+        Ok((
+            convert.subtract(start),
+            TryCatch {
+                block,
+                name,
+                catch_block,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
     }
-}
-
-/// Array expression, e.g. `[a, b, c]`.
-#[derive(Debug, Clone)]
-pub struct Array {
-    /// Array item expressions.
-    pub items: Vec<Expression>,
-    /// The range in source.
-    pub source_range: Range,
-}
 
-impl Array {
-    /// Creates array expression from meta data.
-    pub fn from_meta_data(
+    // Reads the name of the error variable and the catch block.
+    fn catch(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Array), ()> {
+    ) -> Result<(Range, (Arc<String>, Block)), ()> {
         let start = convert;
-        let node = "array";
+        let node = "catch";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut items = vec![];
+        let mut name: Option<Arc<String>> = None;
+        let mut block: Option<Block> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
+            } else if let Ok((range, val)) = convert.meta_string("name") {
+                convert.update(range);
+                name = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "array_item", convert, ignored)
+                Block::from_meta_data(file, source, "block", convert, ignored)
             {
                 convert.update(range);
-                items.push(val);
+                block = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -1918,25 +3687,9 @@ impl Array {
             }
         }
 
-        Ok((
-            convert.subtract(start),
-            Array {
-                items,
-                source_range: convert.source(start).unwrap(),
-            },
-        ))
-    }
-
-    fn precompute(&self) -> Option<Variable> {
-        let mut res = Vec::with_capacity(self.items.len());
-        for item in &self.items {
-            if let Some(v) = item.precompute() {
-                res.push(v);
-            } else {
-                return None;
-            }
-        }
-        Some(Variable::Array(Arc::new(res)))
+        let name = name.ok_or(())?;
+        let block = block.ok_or(())?;
+        Ok((convert.subtract(start), (name, block)))
     }
 
     fn get_locals(
@@ -1948,53 +3701,53 @@ impl Array {
         use_lookup: &UseLookup,
     ) {
         let st = stack.len();
-        for item in &mut self.items {
-            item.get_locals(relative, stack, closure_stack, module, use_lookup);
-            stack.truncate(st);
-        }
+        self.block.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(st);
+        stack.push(Some(self.name.clone()));
+        self.catch_block.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(st);
     }
 }
 
-/// Array fill expression, e.g. `[a; n]`.
+/// Yield expression, which sends a value from a generator function.
+#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
 #[derive(Debug, Clone)]
-pub struct ArrayFill {
-    /// The `a` in `[a; n]`.
-    pub fill: Expression,
-    /// The `n` in `[a; n]`.
-    pub n: Expression,
+pub struct Yield {
+    /// The value to send.
+    pub expr: Expression,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl ArrayFill {
-    /// Creates array fill expression from meta data.
-    pub fn from_meta_data(
+/// Yield expression, which sends a value from a generator function.
+#[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+#[derive(Debug, Clone)]
+pub enum Yield {}
+
+impl Yield {
+    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+    /// Creates yield expression from meta data.
+    pub(crate) fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, ArrayFill), ()> {
+    ) -> Result<(Range, Yield), ()> {
         let start = convert;
-        let node = "array_fill";
+        let node = "yield";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut fill: Option<Expression> = None;
-        let mut n: Option<Expression> = None;
+        let mut expr: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
-                break;
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "fill", convert, ignored)
-            {
-                convert.update(range);
-                fill = Some(val);
+                break;
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "n", convert, ignored)
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
             {
                 convert.update(range);
-                n = Some(val);
+                expr = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -2002,29 +3755,28 @@ impl ArrayFill {
             }
         }
 
-        let fill = fill.ok_or(())?;
-        let n = n.ok_or(())?;
+        let expr = expr.ok_or(())?;
         Ok((
             convert.subtract(start),
-            ArrayFill {
-                fill,
-                n,
+            Yield {
+                expr,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
-    fn precompute(&self) -> Option<Variable> {
-        if let Expression::Variable(ref range_var) = self.n {
-            if let (_, Variable::F64(n, _)) = **range_var {
-                if let Expression::Variable(ref x) = self.fill {
-                    return Some(Variable::Array(Arc::new(vec![x.1.clone(); n as usize])));
-                }
-            }
-        }
-        None
+    #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+    /// Creates yield expression from meta data.
+    pub(crate) fn from_meta_data(
+        _file: &Arc<String>,
+        _source: &Arc<String>,
+        _convert: Convert,
+        _ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Yield), ()> {
+        Err(())
     }
 
+    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
     fn get_locals(
         &mut self,
         relative: usize,
@@ -2034,106 +3786,249 @@ impl ArrayFill {
         use_lookup: &UseLookup,
     ) {
         let st = stack.len();
-        self.fill.get_locals(relative, stack, closure_stack, module, use_lookup);
-        stack.truncate(st);
-        self.n.get_locals(relative, stack, closure_stack, module, use_lookup);
+        self.expr.get_locals(relative, stack, closure_stack, module, use_lookup);
         stack.truncate(st);
     }
+
+    #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+    fn get_locals(
+        &mut self,
+        _relative: usize,
+        _stack: &mut Vec<Option<Arc<String>>>,
+        _closure_stack: &mut Vec<usize>,
+        _module: &Module,
+        _use_lookup: &UseLookup,
+    ) {}
 }
 
-/// Parse sequence of binary operators.
+/// Call info.
 #[derive(Debug, Clone)]
-pub struct BinOpSeq {
-    /// Item expressions.
-    pub items: Vec<Expression>,
-    /// Binary operators.
-    pub ops: Vec<BinOp>,
+pub struct CallInfo {
+    /// Name of function.
+    pub name: Arc<String>,
+    /// Alias.
+    pub alias: Option<Arc<String>>,
+    /// Whether the first argument is piped, e.g. `x |> f(a)`.
+    pub pipe: bool,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl BinOpSeq {
-    /// Creates multiply expression from meta data.
+/// Loaded function call.
+#[derive(Debug, Clone)]
+pub struct CallLoaded {
+    /// Arguments.
+    pub args: Vec<Expression>,
+    /// Relative to function you call from.
+    pub fun: isize,
+    /// Info about the call.
+    pub info: Box<CallInfo>,
+    /// A custom source, such as when calling a function inside a loaded module.
+    pub custom_source: Option<Arc<String>>,
+}
+
+/// External function call.
+#[derive(Debug, Clone)]
+pub struct CallLazy {
+    /// Arguments.
+    pub args: Vec<Expression>,
+    /// Function pointer.
+    pub fun: crate::FnReturnRef,
+    /// Lazy invariant.
+    pub lazy_inv: crate::LazyInvariant,
+    /// Info about the call.
+    pub info: Box<CallInfo>,
+}
+
+/// External function call.
+#[derive(Debug, Clone)]
+pub struct CallBinOp {
+    /// Left argument.
+    pub left: Expression,
+    /// Right argument.
+    pub right: Expression,
+    /// Function pointer.
+    pub fun: crate::FnBinOpRef,
+    /// Info about the call.
+    pub info: Box<CallInfo>,
+}
+
+/// External function call.
+#[derive(Debug, Clone)]
+pub struct CallUnOp {
+    /// Argument.
+    pub arg: Expression,
+    /// Function pointer.
+    pub fun: crate::FnUnOpRef,
+    /// Info about the call.
+    pub info: Box<CallInfo>,
+}
+
+/// External function call.
+#[derive(Debug, Clone)]
+pub struct CallReturn {
+    /// Arguments.
+    pub args: Vec<Expression>,
+    /// Function pointer.
+    pub fun: crate::FnReturnRef,
+    /// Info about the call.
+    pub info: Box<CallInfo>,
+}
+
+/// External function call.
+#[derive(Debug, Clone)]
+pub struct CallVoid {
+    /// Arguments.
+    pub args: Vec<Expression>,
+    /// Function pointer.
+    pub fun: crate::FnVoidRef,
+    /// Info about the call.
+    pub info: Box<CallInfo>,
+}
+
+/// Function call.
+#[derive(Debug, Clone)]
+pub struct Call {
+    /// Arguments.
+    pub args: Vec<Expression>,
+    /// Function index.
+    pub f_index: FnIndex,
+    /// Info about the call.
+    pub info: Box<CallInfo>,
+    /// A custom source, such as when calling a function inside a loaded module.
+    pub custom_source: Option<Arc<String>>,
+}
+
+impl Call {
+    /// Creates call from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
-        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, BinOpSeq), ()> {
+    ) -> Result<(Range, Call), ()> {
         let start = convert;
+        let node = "call";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut items = vec![];
-        let mut ops = vec![];
+        let mut alias: Option<Arc<String>> = None;
+        let mut name: Option<Arc<String>> = None;
+        let mut pipe = false;
+        let mut args = vec![];
+        let mut mutable: Vec<bool> = vec![];
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) =
-                UnOpExpression::from_meta_data("neg", file, source, convert, ignored)
-            {
+            } else if let Ok((range, val)) = convert.meta_string("alias") {
                 convert.update(range);
-                items.push(val);
-            } else if let Ok((range, val)) =
-                BinOpSeq::from_meta_data(file, source, "pow", convert, ignored)
-            {
+                alias = Some(val);
+            } else if let Ok((range, val)) = convert.meta_string("name") {
                 convert.update(range);
-                items.push(val.into_expression());
+                name = Some(val);
+            } else if let Ok((range, val)) = convert.meta_bool("pipe") {
+                convert.update(range);
+                pipe = val;
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "expr", convert, ignored)
+                Expression::from_meta_data(file, source, "call_arg", convert, ignored)
             {
+                let mut peek = convert;
+                mutable.push(match peek.start_node("call_arg") {
+                    Ok(r) => {
+                        peek.update(r);
+                        peek.meta_bool("mut").is_ok()
+                    }
+                    _ => unreachable!(),
+                });
                 convert.update(range);
-                items.push(val);
-            } else if let Ok((range, _)) = convert.meta_bool("*.") {
-                convert.update(range);
-                ops.push(BinOp::Dot);
-            } else if let Ok((range, _)) = convert.meta_bool("x") {
-                convert.update(range);
-                ops.push(BinOp::Cross);
-            } else if let Ok((range, _)) = convert.meta_bool("*") {
-                convert.update(range);
-                ops.push(BinOp::Mul);
-            } else if let Ok((range, _)) = convert.meta_bool("/") {
-                convert.update(range);
-                ops.push(BinOp::Div);
-            } else if let Ok((range, _)) = convert.meta_bool("%") {
-                convert.update(range);
-                ops.push(BinOp::Rem);
-            } else if let Ok((range, _)) = convert.meta_bool("+") {
-                convert.update(range);
-                ops.push(BinOp::Add);
-            } else if let Ok((range, _)) = convert.meta_bool("-") {
-                convert.update(range);
-                ops.push(BinOp::Sub);
-            } else if let Ok((range, _)) = convert.meta_bool("||") {
-                convert.update(range);
-                ops.push(BinOp::OrElse);
-            } else if let Ok((range, _)) = convert.meta_bool("^") {
-                convert.update(range);
-                ops.push(BinOp::Pow);
-            } else if let Ok((range, _)) = convert.meta_bool("&&") {
-                convert.update(range);
-                ops.push(BinOp::AndAlso);
-            } else if let Ok((range, _)) = convert.meta_bool("<") {
-                convert.update(range);
-                ops.push(BinOp::Less);
-            } else if let Ok((range, _)) = convert.meta_bool("<=") {
+                args.push(val);
+            } else {
+                let range = convert.ignore();
                 convert.update(range);
-                ops.push(BinOp::LessOrEqual);
-            } else if let Ok((range, _)) = convert.meta_bool(">") {
+                ignored.push(range);
+            }
+        }
+
+        let mut name = name.ok_or(())?;
+
+        // Append mutability information to function name.
+        if mutable.iter().any(|&arg| arg) {
+            let mut name_plus_args = String::from(&**name);
+            name_plus_args.push('(');
+            let mut first = true;
+            for &arg in &mutable {
+                if !first {
+                    name_plus_args.push(',');
+                }
+                name_plus_args.push_str(if arg { "mut" } else { "_" });
+                first = false;
+            }
+            name_plus_args.push(')');
+            name = Arc::new(name_plus_args);
+        }
+
+        Ok((
+            convert.subtract(start),
+            Call {
+                args,
+                f_index: FnIndex::None,
+                custom_source: None,
+                info: Box::new(CallInfo {
+                    alias,
+                    name,
+                    pipe,
+                    source_range: convert.source(start).unwrap(),
+                }),
+            },
+        ))
+    }
+
+    /// Creates named argument call from meta data.
+    pub fn named_from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, Call), ()> {
+        let start = convert;
+        let node = "named_call";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut alias: Option<Arc<String>> = None;
+        let mut name = String::new();
+        let mut args = vec![];
+        let mut mutable: Vec<bool> = vec![];
+        loop {
+            if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
-                ops.push(BinOp::Greater);
-            } else if let Ok((range, _)) = convert.meta_bool(">=") {
+                break;
+            } else if let Ok((range, val)) = convert.meta_string("alias") {
                 convert.update(range);
-                ops.push(BinOp::GreaterOrEqual);
-            } else if let Ok((range, _)) = convert.meta_bool("==") {
+                alias = Some(val);
+            } else if let Ok((range, val)) = convert.meta_string("word") {
                 convert.update(range);
-                ops.push(BinOp::Equal);
-            } else if let Ok((range, _)) = convert.meta_bool("!=") {
+                if !name.is_empty() {
+                    name.push('_');
+                    name.push_str(&val);
+                } else {
+                    name.push_str(&val);
+                    name.push('_');
+                }
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "call_arg", convert, ignored)
+            {
+                let mut peek = convert;
+                mutable.push(match peek.start_node("call_arg") {
+                    Ok(r) => {
+                        peek.update(r);
+                        peek.meta_bool("mut").is_ok()
+                    }
+                    _ => unreachable!(),
+                });
                 convert.update(range);
-                ops.push(BinOp::NotEqual);
+                args.push(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -2141,151 +4036,282 @@ impl BinOpSeq {
             }
         }
 
-        if items.is_empty() {
-            return Err(());
+        // Append mutability information to function name.
+        if mutable.iter().any(|&arg| arg) {
+            name.push('(');
+            let mut first = true;
+            for &arg in &mutable {
+                if !first {
+                    name.push(',');
+                }
+                name.push_str(if arg { "mut" } else { "_" });
+                first = false;
+            }
+            name.push(')');
         }
+
         Ok((
             convert.subtract(start),
-            BinOpSeq {
-                items,
-                ops,
-                source_range: convert.source(start).unwrap(),
+            Call {
+                args,
+                f_index: FnIndex::None,
+                custom_source: None,
+                info: Box::new(CallInfo {
+                    alias,
+                    name: Arc::new(name),
+                    pipe: false,
+                    source_range: convert.source(start).unwrap(),
+                }),
             },
         ))
     }
 
-    fn into_expression(mut self) -> Expression {
-        if self.items.len() == 1 {
-            self.items[0].clone()
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        use crate::{
+            FnBinOpRef,
+            FnExt,
+            FnReturnRef,
+            FnUnOpRef,
+            FnVoidRef,
+        };
+
+        let st = stack.len();
+        let f_index = if let Some(name) = use_lookup.overloads.get(&self.info.source_range) {
+            module.find_function(name, relative)
+        } else if let Some(ref alias) = self.info.alias {
+            if let Some(&i) = use_lookup
+                .aliases
+                .get(alias)
+                .and_then(|map| map.get(&self.info.name))
+            {
+                match i {
+                    FnAlias::Loaded(i) => FnIndex::Loaded(i as isize - relative as isize),
+                    FnAlias::External(i) => {
+                        let f = &module.ext_prelude[i];
+                        match f.f {
+                            FnExt::Void(ff) => FnIndex::Void(FnVoidRef(ff)),
+                            FnExt::Return(ff) => FnIndex::Return(FnReturnRef(ff)),
+                            FnExt::BinOp(ff) => FnIndex::BinOp(FnBinOpRef(ff)),
+                            FnExt::UnOp(ff) => FnIndex::UnOp(FnUnOpRef(ff)),
+                        }
+                    }
+                }
+            } else {
+                FnIndex::None
+            }
         } else {
-            let op = self.ops.pop().expect("Expected a binary operation");
-            let last = self.items.pop().expect("Expected argument");
-            let source_range = self.source_range;
-            BinOpExpression {
-                op,
-                left: self.into_expression(),
-                right: last,
-                source_range,
+            module.find_function(&self.info.name, relative)
+        };
+        self.f_index = f_index;
+        match f_index {
+            FnIndex::Loaded(f_index) => {
+                let index = (f_index + relative as isize) as usize;
+                if module.functions[index].returns() {
+                    stack.push(None);
+                }
+            }
+            FnIndex::Void(_)
+            | FnIndex::Return(_)
+            | FnIndex::Lazy(_, _)
+            | FnIndex::BinOp(_)
+            | FnIndex::UnOp(_) => {
+                // Don't push return since last value in block
+                // is used as return value.
+            }
+            FnIndex::None => {}
+        }
+        for arg in &mut self.args {
+            let arg_st = stack.len();
+            arg.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(arg_st);
+            if let FnIndex::BinOp(_) = f_index {
+            } else {
+                match *arg {
+                    Expression::Swizzle(ref swizzle) => {
+                        for _ in 0..swizzle.len() {
+                            stack.push(None);
+                        }
+                    }
+                    _ => {
+                        stack.push(None);
+                    }
+                }
+            }
+        }
+        stack.truncate(st);
+    }
+
+    /// Computes number of arguments including swizzles.
+    pub fn arg_len(&self) -> usize {
+        let mut sum = 0;
+        for arg in &self.args {
+            match *arg {
+                Expression::Swizzle(ref swizzle) => {
+                    sum += swizzle.len();
+                }
+                _ => {
+                    sum += 1;
+                }
             }
-            .into_expression()
         }
+        sum
     }
 }
 
-/// Binary operator.
-#[derive(Debug, Copy, Clone)]
-pub enum BinOp {
-    /// Addition operator (`+`).
-    Add,
-    /// Subtraction operator (`-`).
-    Sub,
-    /// Multiply operator (`*`).
-    Mul,
-    /// Dot product operator (`*.`).
-    Dot,
-    /// Cross product operator (`x`).
-    Cross,
-    /// Division operator (`/`).
-    Div,
-    /// Remainder operator (`%`).
-    Rem,
-    /// Power operator (`^`).
-    Pow,
-    /// Lazy OR operator (`||`).
-    OrElse,
-    /// Lazy AND operator (`&&`).
-    AndAlso,
-    /// Less.
-    Less,
-    /// Less or equal.
-    LessOrEqual,
-    /// Greater.
-    Greater,
-    /// Greater or equal.
-    GreaterOrEqual,
-    /// Equal.
-    Equal,
-    /// Not equal.
-    NotEqual,
+/// Closure call.
+#[derive(Debug, Clone)]
+pub struct CallClosure {
+    /// The closure.
+    pub item: Item,
+    /// Closure argument expressions.
+    pub args: Vec<Expression>,
+    /// Whether the first argument is piped, e.g. `x |> \f`.
+    pub pipe: bool,
+    /// The range in source.
+    pub source_range: Range,
 }
 
-pub(crate) const BINOP_PREC_POW: u8 = 3;
-pub(crate) const BINOP_PREC_MUL: u8 = 2;
-pub(crate) const BINOP_PREC_ADD: u8 = 1;
-pub(crate) const BINOP_PREC_EQ: u8 = 0;
+impl CallClosure {
+    /// Creates closure call from meta data.
+    pub fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, CallClosure), ()> {
+        let start = convert;
+        let node = "call_closure";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
 
-impl BinOp {
-    /// Returns symbol of binary operator.
-    pub fn symbol(self) -> &'static str {
-        match self {
-            BinOp::Add => "+",
-            BinOp::Sub => "-",
-            BinOp::Mul => "*",
-            BinOp::Dot => "*.",
-            BinOp::Cross => "x",
-            BinOp::Div => "/",
-            BinOp::Rem => "%",
-            BinOp::Pow => "^",
-            BinOp::OrElse => "||",
-            BinOp::AndAlso => "&&",
-            BinOp::Less => "<",
-            BinOp::LessOrEqual => "<=",
-            BinOp::Greater => ">",
-            BinOp::GreaterOrEqual => ">=",
-            BinOp::Equal => "==",
-            BinOp::NotEqual => "!=",
+        let mut item: Option<Item> = None;
+        let mut pipe = false;
+        let mut args = vec![];
+        let mut mutable: Vec<bool> = vec![];
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) = Item::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                item = Some(val);
+            } else if let Ok((range, val)) = convert.meta_bool("pipe") {
+                convert.update(range);
+                pipe = val;
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "call_arg", convert, ignored)
+            {
+                let mut peek = convert;
+                mutable.push(match peek.start_node("call_arg") {
+                    Ok(r) => {
+                        peek.update(r);
+                        peek.meta_bool("mut").is_ok()
+                    }
+                    _ => unreachable!(),
+                });
+                convert.update(range);
+                args.push(val);
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
         }
-    }
 
-    /// Returns symbol of binary operator in boolean variant.
-    pub fn symbol_bool(self) -> &'static str {
-        match self {
-            BinOp::Add => "or",
-            BinOp::Mul => "and",
-            _ => self.symbol(),
-        }
+        let item = item.ok_or(())?;
+        Ok((
+            convert.subtract(start),
+            CallClosure {
+                item,
+                args,
+                pipe,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
     }
 
-    /// Returns the operator precedence level.
-    /// Used to put parentheses in right places when printing out closures.
-    pub fn precedence(self) -> u8 {
-        match self {
-            BinOp::Less
-            | BinOp::LessOrEqual
-            | BinOp::Greater
-            | BinOp::GreaterOrEqual
-            | BinOp::Equal
-            | BinOp::NotEqual => BINOP_PREC_EQ,
-            BinOp::OrElse => BINOP_PREC_ADD,
-            BinOp::AndAlso => BINOP_PREC_MUL,
-            BinOp::Add | BinOp::Sub => BINOP_PREC_ADD,
-            BinOp::Mul | BinOp::Dot | BinOp::Cross | BinOp::Div | BinOp::Rem => BINOP_PREC_MUL,
-            BinOp::Pow => BINOP_PREC_POW,
-        }
-    }
-}
+    /// Creates named argument closure call from meta data.
+    pub fn named_from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, CallClosure), ()> {
+        let start = convert;
+        let node = "named_call_closure";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
 
-/// An item id.
-///
-/// This is the thing that's inside the square brackets, e.g. `foo[i]`.
-#[derive(Debug, Clone)]
-pub enum Id {
-    /// A string.
-    String(Range, Arc<String>),
-    /// A number.
-    F64(Range, f64),
-    /// An expression.
-    Expression(Expression),
-}
+        let mut item: Option<Item> = None;
+        let mut name = String::new();
+        let mut args = vec![];
+        let mut mutable: Vec<bool> = vec![];
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) = Item::from_meta_data(file, source, convert, ignored) {
+                convert.update(range);
+                item = Some(val);
+            } else if let Ok((range, val)) = convert.meta_string("word") {
+                convert.update(range);
+                if !name.is_empty() {
+                    name.push('_');
+                }
+                name.push_str(&val);
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "call_arg", convert, ignored)
+            {
+                let mut peek = convert;
+                mutable.push(match peek.start_node("call_arg") {
+                    Ok(r) => {
+                        peek.update(r);
+                        peek.meta_bool("mut").is_ok()
+                    }
+                    _ => unreachable!(),
+                });
+                convert.update(range);
+                args.push(val);
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
 
-impl Id {
-    /// Gets the range in source.
-    pub fn source_range(&self) -> Range {
-        match *self {
-            Id::String(range, _) => range,
-            Id::F64(range, _) => range,
-            Id::Expression(ref expr) => expr.source_range(),
+        let mut item = item.ok_or(())?;
+        {
+            if item.ids.is_empty() {
+                // Append name to item.
+                let n = Arc::make_mut(&mut item.name);
+                n.push_str("__");
+                n.push_str(&name);
+            } else {
+                let last = item.ids.len() - 1;
+                if let Id::String(_, ref mut n) = item.ids[last] {
+                    // Append name to last id.
+                    let n = Arc::make_mut(n);
+                    n.push_str("__");
+                    n.push_str(&name);
+                }
+            }
         }
+        Ok((
+            convert.subtract(start),
+            CallClosure {
+                item,
+                args,
+                pipe: false,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
     }
 
     fn get_locals(
@@ -2295,136 +4321,79 @@ impl Id {
         closure_stack: &mut Vec<usize>,
         module: &Module,
         use_lookup: &UseLookup,
-    ) -> bool {
-        match *self {
-            Id::String(_, _) => false,
-            Id::F64(_, _) => false,
-            Id::Expression(ref mut expr) => {
-                let st = stack.len();
-                expr.get_locals(relative, stack, closure_stack, module, use_lookup);
-                stack.truncate(st);
-                true
+    ) {
+        let st = stack.len();
+        self.item.get_locals(relative, stack, closure_stack, module, use_lookup);
+        // All closures must return a value.
+        // Use return type because it has the same name.
+        stack.push(Some(crate::runtime::RETURN_TYPE.clone()));
+        for arg in &mut self.args {
+            let arg_st = stack.len();
+            arg.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(arg_st);
+            match *arg {
+                Expression::Swizzle(ref swizzle) => {
+                    for _ in 0..swizzle.len() {
+                        stack.push(None);
+                    }
+                }
+                _ => {
+                    stack.push(None);
+                }
             }
         }
-    }
-}
-
-/// Item.
-#[derive(Debug, Clone)]
-pub struct Item {
-    /// The name of item.
-    pub name: Arc<String>,
-    /// Dynamical looked up stack id.
-    ///
-    /// This is checked against the static stack id when
-    /// the Cargo feature "debug_lookup" is enabled.
-    pub stack_id: Cell<Option<usize>>,
-    /// Statical looked up stack id.
-    ///
-    /// This is used when the Cargo feature "debug_lookup" is disabled.
-    pub static_stack_id: Cell<Option<usize>>,
-    /// Whether the item is a current object.
-    pub current: bool,
-    /// Whether there is a `?` after the item.
-    pub try_flag: bool,
-    /// Item ids.
-    pub ids: Vec<Id>,
-    /// Stores indices of ids that should propagate errors.
-    pub try_ids: Vec<usize>,
-    /// The range in source.
-    pub source_range: Range,
-}
-
-impl Item {
-    /// Creates item from variable.
-    pub fn from_variable(name: Arc<String>, source_range: Range) -> Item {
-        Item {
-            name,
-            current: false,
-            stack_id: Cell::new(None),
-            static_stack_id: Cell::new(None),
-            try_flag: false,
-            ids: vec![],
-            try_ids: vec![],
-            source_range,
-        }
+        stack.truncate(st);
     }
 
-    /// Truncates item extra to a given length.
-    fn trunc(&self, n: usize) -> Item {
-        Item {
-            name: self.name.clone(),
-            current: self.current,
-            stack_id: Cell::new(None),
-            static_stack_id: Cell::new(None),
-            try_flag: self.try_flag,
-            ids: self.ids.iter().take(n).cloned().collect(),
-            try_ids: {
-                let mut try_ids = vec![];
-                for &ind in &self.try_ids {
-                    if ind >= n {
-                        break;
-                    }
-                    try_ids.push(ind);
+    /// Computes number of arguments including swizzles.
+    pub fn arg_len(&self) -> usize {
+        let mut sum = 0;
+        for arg in &self.args {
+            match *arg {
+                Expression::Swizzle(ref swizzle) => {
+                    sum += swizzle.len();
                 }
-                try_ids
-            },
-            source_range: self.source_range,
+                _ => {
+                    sum += 1;
+                }
+            }
         }
+        sum
     }
+}
+
+/// 4D vector norm.
+#[derive(Debug, Clone)]
+pub struct Norm {
+    /// Expression argument.
+    pub expr: Expression,
+    /// The range in source.
+    pub source_range: Range,
+}
 
-    /// Creates item from meta data.
+impl Norm {
+    /// Creates 4D vector norm from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Item), ()> {
+    ) -> Result<(Range, Norm), ()> {
         let start = convert;
-        let node = "item";
+        let node = "norm";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut name: Option<Arc<String>> = None;
-        let mut current = false;
-        let mut ids = vec![];
-        let mut try_ids = vec![];
-        let mut try_flag = false;
+        let mut expr: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = convert.meta_string("name") {
-                convert.update(range);
-                name = Some(val);
-            } else if let Ok((range, _)) = convert.meta_bool("current") {
-                convert.update(range);
-                current = true;
-            } else if let Ok((range, _)) = convert.meta_bool("try_item") {
-                convert.update(range);
-                try_flag = true;
-                // Ignore item extra node, which is there to help the type checker.
-            } else if let Ok(range) = convert.start_node("item_extra") {
-                convert.update(range);
-            } else if let Ok(range) = convert.end_node("item_extra") {
-                convert.update(range);
-            } else if let Ok((range, val)) = convert.meta_string("id") {
-                let start_id = convert;
-                convert.update(range);
-                ids.push(Id::String(convert.source(start_id).unwrap(), val));
-            } else if let Ok((range, val)) = convert.meta_f64("id") {
-                let start_id = convert;
-                convert.update(range);
-                ids.push(Id::F64(convert.source(start_id).unwrap(), val));
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "id", convert, ignored)
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
             {
                 convert.update(range);
-                ids.push(Id::Expression(val));
-            } else if let Ok((range, _)) = convert.meta_bool("try_id") {
-                convert.update(range);
-                // id is pushed before the `?` operator, therefore subtract 1.
-                try_ids.push(ids.len() - 1);
+                expr = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -2432,90 +4401,70 @@ impl Item {
             }
         }
 
-        let name = name.ok_or(())?;
+        let expr = expr.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Item {
-                name,
-                stack_id: Cell::new(None),
-                static_stack_id: Cell::new(None),
-                current,
-                try_flag,
-                ids,
-                try_ids,
+            Norm {
+                expr,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
-    fn get_locals(
-        &mut self,
-        relative: usize,
-        stack: &mut Vec<Option<Arc<String>>>,
-        closure_stack: &mut Vec<usize>,
-        module: &Module,
-        use_lookup: &UseLookup,
-    ) {
-        let st = stack.len();
-        for (i, n) in stack.iter().rev().enumerate() {
-            if let Some(ref n) = *n {
-                if **n == **self.name {
-                    self.static_stack_id.set(Some(i + 1));
-                    break;
-                }
-            }
-        }
-        for id in &mut self.ids {
-            if id.get_locals(relative, stack, closure_stack, module, use_lookup) {
-                stack.push(None);
-            }
-        }
-        stack.truncate(st);
+    fn into_call_expr(self) -> Expression {
+        Expression::Call(Box::new(Call {
+            args: vec![self.expr],
+            custom_source: None,
+            f_index: FnIndex::None,
+            info: Box::new(CallInfo {
+                alias: None,
+                name: crate::NORM.clone(),
+                pipe: false,
+                source_range: self.source_range,
+            }),
+        }))
     }
 }
 
-/// Go call.
-#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+/// Default value operator, e.g. `x ?? 0`.
 #[derive(Debug, Clone)]
-pub struct Go {
-    /// Function call.
-    pub call: Call,
+pub struct Coalesce {
+    /// Option or result.
+    pub expr: Expression,
+    /// Default value.
+    pub default: Expression,
     /// The range in source.
     pub source_range: Range,
 }
 
-/// Go call.
-#[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-#[derive(Debug, Clone)]
-pub enum Go {}
-
-impl Go {
-    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
-    /// Creates go call from meta data.
-    pub fn from_meta_data(
+impl Coalesce {
+    /// Creates default value operator from meta data.
+    pub(crate) fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Go), ()> {
+    ) -> Result<(Range, Coalesce), ()> {
         let start = convert;
-        let node = "go";
+        let node = "coalesce";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut call: Option<Call> = None;
+        let mut expr: Option<Expression> = None;
+        let mut default: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = Call::from_meta_data(file, source, convert, ignored) {
-                convert.update(range);
-                call = Some(val);
             } else if let Ok((range, val)) =
-                Call::named_from_meta_data(file, source, convert, ignored)
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
             {
                 convert.update(range);
-                call = Some(val);
+                if expr.is_none() {
+                    expr = Some(val);
+                } else {
+                    default = Some(val);
+                }
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -2523,192 +4472,187 @@ impl Go {
             }
         }
 
-        let call = call.ok_or(())?;
+        let expr = expr.ok_or(())?;
+        let default = default.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Go {
-                call,
+            Coalesce {
+                expr,
+                default,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
-    #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-    /// Creates go call from meta data.
-    pub fn from_meta_data(
-        _file: &Arc<String>,
-        _source: &Arc<String>,
-        _convert: Convert,
-        _ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Go), ()> {
-        Err(())
-    }
-
-    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
-    fn get_locals(
-        &mut self,
-        relative: usize,
-        stack: &mut Vec<Option<Arc<String>>>,
-        closure_stack: &mut Vec<usize>,
-        module: &Module,
-        use_lookup: &UseLookup,
-    ) {
-        let st = stack.len();
-        for arg in &mut self.call.args {
-            let st = stack.len();
-            arg.get_locals(relative, stack, closure_stack, module, use_lookup);
-            stack.truncate(st);
-        }
-        stack.truncate(st);
+    /// Uses `unwrap_or`, which evaluates the default value only when needed.
+    fn into_call_expr(self) -> Expression {
+        Expression::Call(Box::new(Call {
+            args: vec![self.expr, self.default],
+            custom_source: None,
+            f_index: FnIndex::None,
+            info: Box::new(CallInfo {
+                alias: None,
+                name: crate::UNWRAP_OR.clone(),
+                pipe: false,
+                source_range: self.source_range,
+            }),
+        }))
     }
-
-    #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-    fn get_locals(
-        &mut self,
-        _relative: usize,
-        _stack: &mut Vec<Option<Arc<String>>>,
-        _closure_stack: &mut Vec<usize>,
-        _module: &Module,
-        _use_lookup: &UseLookup,
-    ) {}
 }
 
-/// Call info.
+/// Range, e.g. `0..n`.
 #[derive(Debug, Clone)]
-pub struct CallInfo {
-    /// Name of function.
-    pub name: Arc<String>,
-    /// Alias.
-    pub alias: Option<Arc<String>>,
+pub struct RangeExpr {
+    /// Start of range.
+    pub start: Expression,
+    /// End of range, which is not included.
+    pub end: Expression,
     /// The range in source.
     pub source_range: Range,
 }
 
-/// Loaded function call.
-#[derive(Debug, Clone)]
-pub struct CallLoaded {
-    /// Arguments.
-    pub args: Vec<Expression>,
-    /// Relative to function you call from.
-    pub fun: isize,
-    /// Info about the call.
-    pub info: Box<CallInfo>,
-    /// A custom source, such as when calling a function inside a loaded module.
-    pub custom_source: Option<Arc<String>>,
-}
+impl RangeExpr {
+    /// Creates range from meta data.
+    pub(crate) fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, RangeExpr), ()> {
+        let start = convert;
+        let node = "range";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
 
-/// External function call.
-#[derive(Debug, Clone)]
-pub struct CallLazy {
-    /// Arguments.
-    pub args: Vec<Expression>,
-    /// Function pointer.
-    pub fun: crate::FnReturnRef,
-    /// Lazy invariant.
-    pub lazy_inv: crate::LazyInvariant,
-    /// Info about the call.
-    pub info: Box<CallInfo>,
-}
+        let mut range_start: Option<Expression> = None;
+        let mut range_end: Option<Expression> = None;
+        loop {
+            if let Ok(range) = convert.end_node(node) {
+                convert.update(range);
+                break;
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
+            {
+                convert.update(range);
+                if range_start.is_none() {
+                    range_start = Some(val);
+                } else {
+                    range_end = Some(val);
+                }
+            } else {
+                let range = convert.ignore();
+                convert.update(range);
+                ignored.push(range);
+            }
+        }
 
-/// External function call.
-#[derive(Debug, Clone)]
-pub struct CallBinOp {
-    /// Left argument.
-    pub left: Expression,
-    /// Right argument.
-    pub right: Expression,
-    /// Function pointer.
-    pub fun: crate::FnBinOpRef,
-    /// Info about the call.
-    pub info: Box<CallInfo>,
-}
+        let range_start = range_start.ok_or(())?;
+        let range_end = range_end.ok_or(())?;
+        Ok((
+            convert.subtract(start),
+            RangeExpr {
+                start: range_start,
+                end: range_end,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
 
-/// External function call.
-#[derive(Debug, Clone)]
-pub struct CallUnOp {
-    /// Argument.
-    pub arg: Expression,
-    /// Function pointer.
-    pub fun: crate::FnUnOpRef,
-    /// Info about the call.
-    pub info: Box<CallInfo>,
+    fn into_call_expr(self) -> Expression {
+        Expression::Call(Box::new(Call {
+            args: vec![self.start, self.end],
+            custom_source: None,
+            f_index: FnIndex::None,
+            info: Box::new(CallInfo {
+                alias: None,
+                name: crate::RANGE.clone(),
+                pipe: false,
+                source_range: self.source_range,
+            }),
+        }))
+    }
 }
 
-/// External function call.
+/// Binary operator expression.
 #[derive(Debug, Clone)]
-pub struct CallReturn {
-    /// Arguments.
-    pub args: Vec<Expression>,
-    /// Function pointer.
-    pub fun: crate::FnReturnRef,
-    /// Info about the call.
-    pub info: Box<CallInfo>,
+pub struct BinOpExpression {
+    /// Binary operator.
+    pub op: BinOp,
+    /// Left side expression.
+    pub left: Expression,
+    /// Right side expression.
+    pub right: Expression,
+    /// The range in source.
+    pub source_range: Range,
+    /// The range of the operator in source.
+    ///
+    /// The call to the operator uses this range to look up operator overloads.
+    pub op_range: Range,
 }
 
-/// External function call.
-#[derive(Debug, Clone)]
-pub struct CallVoid {
-    /// Arguments.
-    pub args: Vec<Expression>,
-    /// Function pointer.
-    pub fun: crate::FnVoidRef,
-    /// Info about the call.
-    pub info: Box<CallInfo>,
-}
+impl BinOpExpression {
+    fn into_expression(self) -> Expression {
+        use self::BinOp::*;
 
-/// Function call.
-#[derive(Debug, Clone)]
-pub struct Call {
-    /// Arguments.
-    pub args: Vec<Expression>,
-    /// Function index.
-    pub f_index: FnIndex,
-    /// Info about the call.
-    pub info: Box<CallInfo>,
-    /// A custom source, such as when calling a function inside a loaded module.
-    pub custom_source: Option<Arc<String>>,
+        Expression::Call(Box::new(Call {
+            args: vec![self.left, self.right],
+            custom_source: None,
+            f_index: FnIndex::None,
+            info: Box::new(CallInfo {
+                alias: None,
+                name: match self.op {
+                    Add => crate::ADD.clone(),
+                    Sub => crate::SUB.clone(),
+                    Mul => crate::MUL.clone(),
+                    Div => crate::DIV.clone(),
+                    Rem => crate::REM.clone(),
+                    Pow => crate::POW.clone(),
+                    Dot => crate::DOT.clone(),
+                    Cross => crate::CROSS.clone(),
+                    AndAlso => crate::AND_ALSO.clone(),
+                    OrElse => crate::OR_ELSE.clone(),
+                    Less => crate::LESS.clone(),
+                    LessOrEqual => crate::LESS_OR_EQUAL.clone(),
+                    Greater => crate::GREATER.clone(),
+                    GreaterOrEqual => crate::GREATER_OR_EQUAL.clone(),
+                    Equal => crate::EQUAL.clone(),
+                    NotEqual => crate::NOT_EQUAL.clone(),
+                    // `|>` is rewritten into a call when parsing.
+                    Pipe => unreachable!(),
+                },
+                pipe: false,
+                source_range: self.op_range,
+            }),
+        }))
+    }
 }
 
-impl Call {
-    /// Creates call from meta data.
+/// Unary operator expression.
+pub struct UnOpExpression;
+
+impl UnOpExpression {
+    /// Creates unary operator expression from meta data.
     pub fn from_meta_data(
+        node: &str,
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Call), ()> {
+    ) -> Result<(Range, Expression), ()> {
         let start = convert;
-        let node = "call";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut alias: Option<Arc<String>> = None;
-        let mut name: Option<Arc<String>> = None;
-        let mut args = vec![];
-        let mut mutable: Vec<bool> = vec![];
+        let mut expr: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = convert.meta_string("alias") {
-                convert.update(range);
-                alias = Some(val);
-            } else if let Ok((range, val)) = convert.meta_string("name") {
-                convert.update(range);
-                name = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "call_arg", convert, ignored)
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
             {
-                let mut peek = convert;
-                mutable.push(match peek.start_node("call_arg") {
-                    Ok(r) => {
-                        peek.update(r);
-                        peek.meta_bool("mut").is_ok()
-                    }
-                    _ => unreachable!(),
-                });
                 convert.update(range);
-                args.push(val);
+                expr = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -2716,84 +4660,95 @@ impl Call {
             }
         }
 
-        let mut name = name.ok_or(())?;
-
-        // Append mutability information to function name.
-        if mutable.iter().any(|&arg| arg) {
-            let mut name_plus_args = String::from(&**name);
-            name_plus_args.push('(');
-            let mut first = true;
-            for &arg in &mutable {
-                if !first {
-                    name_plus_args.push(',');
-                }
-                name_plus_args.push_str(if arg { "mut" } else { "_" });
-                first = false;
-            }
-            name_plus_args.push(')');
-            name = Arc::new(name_plus_args);
-        }
-
-        Ok((
-            convert.subtract(start),
-            Call {
-                args,
-                f_index: FnIndex::None,
+        let expr = expr.ok_or(())?;
+        Ok((convert.subtract(start), {
+            let name = match node {
+                "not" => crate::NOT.clone(),
+                "neg" => crate::NEG.clone(),
+                _ => return Err(()),
+            };
+            Expression::Call(Box::new(Call {
+                args: vec![expr],
                 custom_source: None,
+                f_index: FnIndex::None,
                 info: Box::new(CallInfo {
-                    alias,
+                    alias: None,
                     name,
+                    pipe: false,
                     source_range: convert.source(start).unwrap(),
                 }),
-            },
-        ))
+            }))
+        }))
     }
+}
 
-    /// Creates named argument call from meta data.
-    pub fn named_from_meta_data(
+/// Assignment expression.
+#[derive(Debug, Clone)]
+pub struct Assign {
+    /// Assignment operator.
+    pub op: AssignOp,
+    /// Left side expression.
+    pub left: Expression,
+    /// Right side expression.
+    pub right: Expression,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl Assign {
+    /// Creates assignment expression from meta data.
+    pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Call), ()> {
+    ) -> Result<(Range, Assign), ()> {
         let start = convert;
-        let node = "named_call";
+        let node = "assign";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut alias: Option<Arc<String>> = None;
-        let mut name = String::new();
-        let mut args = vec![];
-        let mut mutable: Vec<bool> = vec![];
+        let mut op: Option<AssignOp> = None;
+        let mut left: Option<Expression> = None;
+        let mut right: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = convert.meta_string("alias") {
+            } else if let Ok((range, _)) = convert.meta_bool(":=") {
                 convert.update(range);
-                alias = Some(val);
-            } else if let Ok((range, val)) = convert.meta_string("word") {
+                op = Some(AssignOp::Assign);
+            } else if let Ok((range, _)) = convert.meta_bool("=") {
                 convert.update(range);
-                if !name.is_empty() {
-                    name.push('_');
-                    name.push_str(&val);
-                } else {
-                    name.push_str(&val);
-                    name.push('_');
-                }
+                op = Some(AssignOp::Set);
+            } else if let Ok((range, _)) = convert.meta_bool("+=") {
+                convert.update(range);
+                op = Some(AssignOp::Add);
+            } else if let Ok((range, _)) = convert.meta_bool("-=") {
+                convert.update(range);
+                op = Some(AssignOp::Sub);
+            } else if let Ok((range, _)) = convert.meta_bool("*=") {
+                convert.update(range);
+                op = Some(AssignOp::Mul);
+            } else if let Ok((range, _)) = convert.meta_bool("/=") {
+                convert.update(range);
+                op = Some(AssignOp::Div);
+            } else if let Ok((range, _)) = convert.meta_bool("%=") {
+                convert.update(range);
+                op = Some(AssignOp::Rem);
+            } else if let Ok((range, _)) = convert.meta_bool("^=") {
+                convert.update(range);
+                op = Some(AssignOp::Pow);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "call_arg", convert, ignored)
+                Expression::from_meta_data(file, source, "left", convert, ignored)
             {
-                let mut peek = convert;
-                mutable.push(match peek.start_node("call_arg") {
-                    Ok(r) => {
-                        peek.update(r);
-                        peek.meta_bool("mut").is_ok()
-                    }
-                    _ => unreachable!(),
-                });
                 convert.update(range);
-                args.push(val);
+                left = Some(val);
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "right", convert, ignored)
+            {
+                convert.update(range);
+                right = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -2801,31 +4756,16 @@ impl Call {
             }
         }
 
-        // Append mutability information to function name.
-        if mutable.iter().any(|&arg| arg) {
-            name.push('(');
-            let mut first = true;
-            for &arg in &mutable {
-                if !first {
-                    name.push(',');
-                }
-                name.push_str(if arg { "mut" } else { "_" });
-                first = false;
-            }
-            name.push(')');
-        }
-
+        let op = op.ok_or(())?;
+        let left = left.ok_or(())?;
+        let right = right.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Call {
-                args,
-                f_index: FnIndex::None,
-                custom_source: None,
-                info: Box::new(CallInfo {
-                    alias,
-                    name: Arc::new(name),
-                    source_range: convert.source(start).unwrap(),
-                }),
+            Assign {
+                op,
+                left,
+                right,
+                source_range: convert.source(start).unwrap(),
             },
         ))
     }
@@ -2838,142 +4778,148 @@ impl Call {
         module: &Module,
         use_lookup: &UseLookup,
     ) {
-        use crate::{
-            FnBinOpRef,
-            FnExt,
-            FnReturnRef,
-            FnUnOpRef,
-            FnVoidRef,
-        };
-
-        let st = stack.len();
-        let f_index = if let Some(ref alias) = self.info.alias {
-            if let Some(&i) = use_lookup
-                .aliases
-                .get(alias)
-                .and_then(|map| map.get(&self.info.name))
-            {
-                match i {
-                    FnAlias::Loaded(i) => FnIndex::Loaded(i as isize - relative as isize),
-                    FnAlias::External(i) => {
-                        let f = &module.ext_prelude[i];
-                        match f.f {
-                            FnExt::Void(ff) => FnIndex::Void(FnVoidRef(ff)),
-                            FnExt::Return(ff) => FnIndex::Return(FnReturnRef(ff)),
-                            FnExt::BinOp(ff) => FnIndex::BinOp(FnBinOpRef(ff)),
-                            FnExt::UnOp(ff) => FnIndex::UnOp(FnUnOpRef(ff)),
-                        }
-                    }
-                }
-            } else {
-                FnIndex::None
+        // Compound assignment with an operator overload sets the result of calling it,
+        // e.g. `a += b` becomes `a = add(a, b)`.
+        match self.op.binop_name() {
+            Some(name) if use_lookup.overloads.contains_key(&self.source_range) => {
+                self.right = Expression::Call(Box::new(Call {
+                    args: vec![self.left.clone(), self.right.clone()],
+                    custom_source: None,
+                    f_index: FnIndex::None,
+                    info: Box::new(CallInfo {
+                        alias: None,
+                        name,
+                        pipe: false,
+                        source_range: self.source_range,
+                    }),
+                }));
+                self.op = AssignOp::Set;
             }
-        } else {
-            module.find_function(&self.info.name, relative)
-        };
-        self.f_index = f_index;
-        match f_index {
-            FnIndex::Loaded(f_index) => {
-                let index = (f_index + relative as isize) as usize;
-                if module.functions[index].returns() {
-                    stack.push(None);
-                }
-            }
-            FnIndex::Void(_)
-            | FnIndex::Return(_)
-            | FnIndex::Lazy(_, _)
-            | FnIndex::BinOp(_)
-            | FnIndex::UnOp(_) => {
-                // Don't push return since last value in block
-                // is used as return value.
-            }
-            FnIndex::None => {}
+            _ => {}
         }
-        for arg in &mut self.args {
-            let arg_st = stack.len();
-            arg.get_locals(relative, stack, closure_stack, module, use_lookup);
-            stack.truncate(arg_st);
-            if let FnIndex::BinOp(_) = f_index {
-            } else {
-                match *arg {
-                    Expression::Swizzle(ref swizzle) => {
-                        for _ in 0..swizzle.len() {
-                            stack.push(None);
-                        }
-                    }
-                    _ => {
-                        stack.push(None);
-                    }
-                }
+
+        // Declared locals in right expressions are popped from the stack.
+        let st = stack.len();
+        self.right.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(st);
+
+        // Declare new local when there is an item with no extra.
+        if let Expression::Item(ref item) = self.left {
+            if item.ids.is_empty() && self.op == AssignOp::Assign {
+                stack.push(Some(item.name.clone()));
+                return;
             }
         }
+
+        self.left.get_locals(relative, stack, closure_stack, module, use_lookup);
         stack.truncate(st);
     }
+}
 
-    /// Computes number of arguments including swizzles.
-    pub fn arg_len(&self) -> usize {
-        let mut sum = 0;
-        for arg in &self.args {
-            match *arg {
-                Expression::Swizzle(ref swizzle) => {
-                    sum += swizzle.len();
-                }
-                _ => {
-                    sum += 1;
-                }
-            }
+/// Assignment operator.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AssignOp {
+    /// :=
+    Assign,
+    /// =
+    Set,
+    /// +=
+    Add,
+    /// -=
+    Sub,
+    /// *=
+    Mul,
+    /// /=
+    Div,
+    /// %=
+    Rem,
+    /// ^=
+    Pow,
+}
+
+impl AssignOp {
+    /// Returns symbol of assignment operator.
+    pub fn symbol(self) -> &'static str {
+        use self::AssignOp::*;
+
+        match self {
+            Assign => ":=",
+            Set => "=",
+            Add => "+=",
+            Sub => "-=",
+            Mul => "*=",
+            Div => "/=",
+            Rem => "%=",
+            Pow => "^=",
+        }
+    }
+
+    /// Returns the name of the binary operator used by compound assignment, e.g. `add` for `+=`.
+    pub(crate) fn binop_name(self) -> Option<Arc<String>> {
+        use self::AssignOp::*;
+
+        match self {
+            Assign | Set => None,
+            Add => Some(crate::ADD.clone()),
+            Sub => Some(crate::SUB.clone()),
+            Mul => Some(crate::MUL.clone()),
+            Div => Some(crate::DIV.clone()),
+            Rem => Some(crate::REM.clone()),
+            Pow => Some(crate::POW.clone()),
         }
-        sum
     }
 }
 
-/// Closure call.
+/// 4D matrix expression.
 #[derive(Debug, Clone)]
-pub struct CallClosure {
-    /// The closure.
-    pub item: Item,
-    /// Closure argument expressions.
+pub struct Mat4 {
+    /// Row vector argument expressions.
     pub args: Vec<Expression>,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl CallClosure {
-    /// Creates closure call from meta data.
+impl Mat4 {
+    /// Creates 4D matrix from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, CallClosure), ()> {
+    ) -> Result<(Range, Mat4), ()> {
         let start = convert;
-        let node = "call_closure";
+        let node = "mat4";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut item: Option<Item> = None;
-        let mut args = vec![];
-        let mut mutable: Vec<bool> = vec![];
+        let mut x: Option<Expression> = None;
+        let mut y: Option<Expression> = None;
+        let mut z: Option<Expression> = None;
+        let mut w: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = Item::from_meta_data(file, source, convert, ignored) {
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "ex", convert, ignored)
+            {
                 convert.update(range);
-                item = Some(val);
+                x = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "call_arg", convert, ignored)
+                Expression::from_meta_data(file, source, "ey", convert, ignored)
             {
-                let mut peek = convert;
-                mutable.push(match peek.start_node("call_arg") {
-                    Ok(r) => {
-                        peek.update(r);
-                        peek.meta_bool("mut").is_ok()
-                    }
-                    _ => unreachable!(),
-                });
                 convert.update(range);
-                args.push(val);
+                y = Some(val);
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "ez", convert, ignored)
+            {
+                convert.update(range);
+                z = Some(val);
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "ew", convert, ignored)
+            {
+                convert.update(range);
+                w = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -2981,59 +4927,103 @@ impl CallClosure {
             }
         }
 
-        let item = item.ok_or(())?;
+        let x = x.ok_or(())?;
+        let y = y.unwrap_or_else(|| {
+            Expression::Variable(Box::new((
+                Range::empty(0),
+                Variable::Vec4([0.0, 1.0, 0.0, 0.0]),
+            )))
+        });
+        let z = z.unwrap_or_else(|| {
+            Expression::Variable(Box::new((
+                Range::empty(0),
+                Variable::Vec4([0.0, 0.0, 1.0, 0.0]),
+            )))
+        });
+        let w = w.unwrap_or_else(|| {
+            Expression::Variable(Box::new((
+                Range::empty(0),
+                Variable::Vec4([0.0, 0.0, 0.0, 1.0]),
+            )))
+        });
         Ok((
             convert.subtract(start),
-            CallClosure {
-                item,
-                args,
+            Mat4 {
+                args: vec![x, y, z, w],
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
-    /// Creates named argument closure call from meta data.
-    pub fn named_from_meta_data(
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        let st = stack.len();
+        for arg in &mut self.args {
+            let arg_st = stack.len();
+            arg.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(arg_st);
+            stack.push(None);
+        }
+        stack.truncate(st);
+    }
+}
+
+/// 4D vector expression.
+#[derive(Debug, Clone)]
+pub struct Vec4 {
+    /// Component expressions.
+    pub args: Vec<Expression>,
+    /// The range in source.
+    pub source_range: Range,
+}
+
+impl Vec4 {
+    /// Creates 4D vector from meta data.
+    pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, CallClosure), ()> {
+    ) -> Result<(Range, Vec4), ()> {
         let start = convert;
-        let node = "named_call_closure";
-        let start_range = convert.start_node(node)?;
-        convert.update(start_range);
-
-        let mut item: Option<Item> = None;
-        let mut name = String::new();
-        let mut args = vec![];
-        let mut mutable: Vec<bool> = vec![];
+        let node = "vec4";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut x: Option<Expression> = None;
+        let mut y: Option<Expression> = None;
+        let mut z: Option<Expression> = None;
+        let mut w: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = Item::from_meta_data(file, source, convert, ignored) {
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "x", convert, ignored)
+            {
                 convert.update(range);
-                item = Some(val);
-            } else if let Ok((range, val)) = convert.meta_string("word") {
+                x = Some(val);
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "y", convert, ignored)
+            {
                 convert.update(range);
-                if !name.is_empty() {
-                    name.push('_');
-                }
-                name.push_str(&val);
+                y = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "call_arg", convert, ignored)
+                Expression::from_meta_data(file, source, "z", convert, ignored)
             {
-                let mut peek = convert;
-                mutable.push(match peek.start_node("call_arg") {
-                    Ok(r) => {
-                        peek.update(r);
-                        peek.meta_bool("mut").is_ok()
-                    }
-                    _ => unreachable!(),
-                });
                 convert.update(range);
-                args.push(val);
+                z = Some(val);
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "w", convert, ignored)
+            {
+                convert.update(range);
+                w = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -3041,33 +5031,41 @@ impl CallClosure {
             }
         }
 
-        let mut item = item.ok_or(())?;
-        {
-            if item.ids.is_empty() {
-                // Append name to item.
-                let n = Arc::make_mut(&mut item.name);
-                n.push_str("__");
-                n.push_str(&name);
-            } else {
-                let last = item.ids.len() - 1;
-                if let Id::String(_, ref mut n) = item.ids[last] {
-                    // Append name to last id.
-                    let n = Arc::make_mut(n);
-                    n.push_str("__");
-                    n.push_str(&name);
-                }
-            }
-        }
+        let x = x.ok_or(())?;
+        let y = y.unwrap_or_else(|| {
+            Expression::Variable(Box::new((Range::empty(0), Variable::f64(0.0))))
+        });
+        let z = z.unwrap_or_else(|| {
+            Expression::Variable(Box::new((Range::empty(0), Variable::f64(0.0))))
+        });
+        let w = w.unwrap_or_else(|| {
+            Expression::Variable(Box::new((Range::empty(0), Variable::f64(0.0))))
+        });
         Ok((
             convert.subtract(start),
-            CallClosure {
-                item,
-                args,
+            Vec4 {
+                args: vec![x, y, z, w],
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
+    fn precompute(&self) -> Option<Variable> {
+        let mut v: [f32; 4] = [0.0; 4];
+        for i in 0..self.args.len().min(4) {
+            if let Some(val) = self.args[i].precompute() {
+                if let Variable::F64(val, _) = val {
+                    v[i] = val as f32;
+                } else {
+                    return None;
+                }
+            } else {
+                return None;
+            }
+        }
+        Some(Variable::Vec4(v))
+    }
+
     fn get_locals(
         &mut self,
         relative: usize,
@@ -3077,10 +5075,6 @@ impl CallClosure {
         use_lookup: &UseLookup,
     ) {
         let st = stack.len();
-        self.item.get_locals(relative, stack, closure_stack, module, use_lookup);
-        // All closures must return a value.
-        // Use return type because it has the same name.
-        stack.push(Some(crate::runtime::RETURN_TYPE.clone()));
         for arg in &mut self.args {
             let arg_st = stack.len();
             arg.get_locals(relative, stack, closure_stack, module, use_lookup);
@@ -3098,51 +5092,53 @@ impl CallClosure {
         }
         stack.truncate(st);
     }
-
-    /// Computes number of arguments including swizzles.
-    pub fn arg_len(&self) -> usize {
-        let mut sum = 0;
-        for arg in &self.args {
-            match *arg {
-                Expression::Swizzle(ref swizzle) => {
-                    sum += swizzle.len();
-                }
-                _ => {
-                    sum += 1;
-                }
-            }
-        }
-        sum
-    }
 }
 
-/// 4D vector norm.
+/// 4D vector unloop expression.
 #[derive(Debug, Clone)]
-pub struct Norm {
-    /// Expression argument.
+pub struct Vec4UnLoop {
+    /// Name of the variable.
+    pub name: Arc<String>,
+    /// Expression of the loop.
     pub expr: Expression,
+    /// The length of the loop.
+    pub len: u8,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Norm {
-    /// Creates 4D vector norm from meta data.
+impl Vec4UnLoop {
+    /// Creates 4D vector-unloop from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Norm), ()> {
+    ) -> Result<(Range, Vec4UnLoop), ()> {
         let start = convert;
-        let node = "norm";
+        let node = "vec4_un_loop";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
+        let mut name: Option<Arc<String>> = None;
         let mut expr: Option<Expression> = None;
+        let mut len: u8 = 4;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
+            } else if let Ok((range, _)) = convert.meta_bool("4") {
+                convert.update(range);
+                len = 4;
+            } else if let Ok((range, _)) = convert.meta_bool("3") {
+                convert.update(range);
+                len = 3;
+            } else if let Ok((range, _)) = convert.meta_bool("2") {
+                convert.update(range);
+                len = 2;
+            } else if let Ok((range, val)) = convert.meta_string("name") {
+                convert.update(range);
+                name = Some(val);
             } else if let Ok((range, val)) =
                 Expression::from_meta_data(file, source, "expr", convert, ignored)
             {
@@ -3155,98 +5151,95 @@ impl Norm {
             }
         }
 
+        let name = name.ok_or(())?;
         let expr = expr.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Norm {
+            Vec4UnLoop {
+                name,
                 expr,
+                len,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
-    fn into_call_expr(self) -> Expression {
-        Expression::Call(Box::new(Call {
-            args: vec![self.expr],
-            custom_source: None,
-            f_index: FnIndex::None,
-            info: Box::new(CallInfo {
-                alias: None,
-                name: crate::NORM.clone(),
-                source_range: self.source_range,
-            }),
+    fn into_expression(self) -> Expression {
+        let source_range = self.source_range;
+
+        let zero = || Expression::Variable(Box::new((source_range, Variable::f64(0.0))));
+
+        let replace_0 = replace::number(&self.expr, &self.name, 0.0);
+        let replace_1 = replace::number(&self.expr, &self.name, 1.0);
+        let replace_2 = if self.len > 2 {
+            replace::number(&self.expr, &self.name, 2.0)
+        } else {
+            zero()
+        };
+        let replace_3 = if self.len > 3 {
+            replace::number(&self.expr, &self.name, 3.0)
+        } else {
+            zero()
+        };
+
+        Expression::Vec4(Box::new(Vec4 {
+            args: vec![replace_0, replace_1, replace_2, replace_3],
+            source_range,
         }))
     }
 }
 
-/// Binary operator expression.
+/// Swizzle expression.
 #[derive(Debug, Clone)]
-pub struct BinOpExpression {
-    /// Binary operator.
-    pub op: BinOp,
-    /// Left side expression.
-    pub left: Expression,
-    /// Right side expression.
-    pub right: Expression,
+pub struct Swizzle {
+    /// First component swizzle.
+    pub sw0: usize,
+    /// Second component swizzle.
+    pub sw1: usize,
+    /// Third component swizzle.
+    pub sw2: Option<usize>,
+    /// Fourth component swizzle.
+    pub sw3: Option<usize>,
+    /// 4D vector expression.
+    pub expr: Expression,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl BinOpExpression {
-    fn into_expression(self) -> Expression {
-        use self::BinOp::*;
-
-        Expression::Call(Box::new(Call {
-            args: vec![self.left, self.right],
-            custom_source: None,
-            f_index: FnIndex::None,
-            info: Box::new(CallInfo {
-                alias: None,
-                name: match self.op {
-                    Add => crate::ADD.clone(),
-                    Sub => crate::SUB.clone(),
-                    Mul => crate::MUL.clone(),
-                    Div => crate::DIV.clone(),
-                    Rem => crate::REM.clone(),
-                    Pow => crate::POW.clone(),
-                    Dot => crate::DOT.clone(),
-                    Cross => crate::CROSS.clone(),
-                    AndAlso => crate::AND_ALSO.clone(),
-                    OrElse => crate::OR_ELSE.clone(),
-                    Less => crate::LESS.clone(),
-                    LessOrEqual => crate::LESS_OR_EQUAL.clone(),
-                    Greater => crate::GREATER.clone(),
-                    GreaterOrEqual => crate::GREATER_OR_EQUAL.clone(),
-                    Equal => crate::EQUAL.clone(),
-                    NotEqual => crate::NOT_EQUAL.clone(),
-                },
-                source_range: self.source_range,
-            }),
-        }))
-    }
-}
-
-/// Unary operator expression.
-pub struct UnOpExpression;
-
-impl UnOpExpression {
-    /// Creates unary operator expression from meta data.
+impl Swizzle {
+    /// Create swizzle from meta data.
     pub fn from_meta_data(
-        node: &str,
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Expression), ()> {
+    ) -> Result<(Range, Swizzle), ()> {
         let start = convert;
+        let node = "swizzle";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
+        let mut sw0: Option<usize> = None;
+        let mut sw1: Option<usize> = None;
+        let mut sw2: Option<usize> = None;
+        let mut sw3: Option<usize> = None;
         let mut expr: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
-                break;
+                break;
+            } else if let Ok((range, val)) = Sw::from_meta_data("sw0", convert, ignored) {
+                convert.update(range);
+                sw0 = Some(val.ind);
+            } else if let Ok((range, val)) = Sw::from_meta_data("sw1", convert, ignored) {
+                convert.update(range);
+                sw1 = Some(val.ind);
+            } else if let Ok((range, val)) = Sw::from_meta_data("sw2", convert, ignored) {
+                convert.update(range);
+                sw2 = Some(val.ind);
+            } else if let Ok((range, val)) = Sw::from_meta_data("sw3", convert, ignored) {
+                convert.update(range);
+                sw3 = Some(val.ind);
             } else if let Ok((range, val)) =
                 Expression::from_meta_data(file, source, "expr", convert, ignored)
             {
@@ -3259,94 +5252,64 @@ impl UnOpExpression {
             }
         }
 
+        let sw0 = sw0.ok_or(())?;
+        let sw1 = sw1.ok_or(())?;
         let expr = expr.ok_or(())?;
-        Ok((convert.subtract(start), {
-            let name = match node {
-                "not" => crate::NOT.clone(),
-                "neg" => crate::NEG.clone(),
-                _ => return Err(()),
-            };
-            Expression::Call(Box::new(Call {
-                args: vec![expr],
-                custom_source: None,
-                f_index: FnIndex::None,
-                info: Box::new(CallInfo {
-                    alias: None,
-                    name,
-                    source_range: convert.source(start).unwrap(),
-                }),
-            }))
-        }))
+        Ok((
+            convert.subtract(start),
+            Swizzle {
+                sw0,
+                sw1,
+                sw2,
+                sw3,
+                expr,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
+
+    fn len(&self) -> usize {
+        2 + if self.sw2.is_some() { 1 } else { 0 } + if self.sw3.is_some() { 1 } else { 0 }
     }
 }
 
-/// Assignment expression.
+/// Component swizzle expression.
 #[derive(Debug, Clone)]
-pub struct Assign {
-    /// Assignment operator.
-    pub op: AssignOp,
-    /// Left side expression.
-    pub left: Expression,
-    /// Right side expression.
-    pub right: Expression,
+pub struct Sw {
+    /// The component index of the swizzle.
+    pub ind: usize,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Assign {
-    /// Creates assignment expression from meta data.
+impl Sw {
+    /// Creates component swizzle from meta data.
     pub fn from_meta_data(
-        file: &Arc<String>,
-        source: &Arc<String>,
+        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Assign), ()> {
+    ) -> Result<(Range, Sw), ()> {
         let start = convert;
-        let node = "assign";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut op: Option<AssignOp> = None;
-        let mut left: Option<Expression> = None;
-        let mut right: Option<Expression> = None;
+        let mut ind: Option<usize> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, _)) = convert.meta_bool(":=") {
-                convert.update(range);
-                op = Some(AssignOp::Assign);
-            } else if let Ok((range, _)) = convert.meta_bool("=") {
-                convert.update(range);
-                op = Some(AssignOp::Set);
-            } else if let Ok((range, _)) = convert.meta_bool("+=") {
-                convert.update(range);
-                op = Some(AssignOp::Add);
-            } else if let Ok((range, _)) = convert.meta_bool("-=") {
-                convert.update(range);
-                op = Some(AssignOp::Sub);
-            } else if let Ok((range, _)) = convert.meta_bool("*=") {
-                convert.update(range);
-                op = Some(AssignOp::Mul);
-            } else if let Ok((range, _)) = convert.meta_bool("/=") {
-                convert.update(range);
-                op = Some(AssignOp::Div);
-            } else if let Ok((range, _)) = convert.meta_bool("%=") {
+            } else if let Ok((range, _)) = convert.meta_bool("x") {
                 convert.update(range);
-                op = Some(AssignOp::Rem);
-            } else if let Ok((range, _)) = convert.meta_bool("^=") {
+                ind = Some(0);
+            } else if let Ok((range, _)) = convert.meta_bool("y") {
                 convert.update(range);
-                op = Some(AssignOp::Pow);
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "left", convert, ignored)
-            {
+                ind = Some(1);
+            } else if let Ok((range, _)) = convert.meta_bool("z") {
                 convert.update(range);
-                left = Some(val);
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "right", convert, ignored)
-            {
+                ind = Some(2);
+            } else if let Ok((range, _)) = convert.meta_bool("w") {
                 convert.update(range);
-                right = Some(val);
+                ind = Some(3);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -3354,135 +5317,79 @@ impl Assign {
             }
         }
 
-        let op = op.ok_or(())?;
-        let left = left.ok_or(())?;
-        let right = right.ok_or(())?;
+        let ind = ind.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Assign {
-                op,
-                left,
-                right,
+            Sw {
+                ind,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
-
-    fn get_locals(
-        &mut self,
-        relative: usize,
-        stack: &mut Vec<Option<Arc<String>>>,
-        closure_stack: &mut Vec<usize>,
-        module: &Module,
-        use_lookup: &UseLookup,
-    ) {
-        // Declared locals in right expressions are popped from the stack.
-        let st = stack.len();
-        self.right.get_locals(relative, stack, closure_stack, module, use_lookup);
-        stack.truncate(st);
-
-        // Declare new local when there is an item with no extra.
-        if let Expression::Item(ref item) = self.left {
-            if item.ids.is_empty() && self.op == AssignOp::Assign {
-                stack.push(Some(item.name.clone()));
-                return;
-            }
-        }
-
-        self.left.get_locals(relative, stack, closure_stack, module, use_lookup);
-        stack.truncate(st);
-    }
-}
-
-/// Assignment operator.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-pub enum AssignOp {
-    /// :=
-    Assign,
-    /// =
-    Set,
-    /// +=
-    Add,
-    /// -=
-    Sub,
-    /// *=
-    Mul,
-    /// /=
-    Div,
-    /// %=
-    Rem,
-    /// ^=
-    Pow,
-}
-
-impl AssignOp {
-    /// Returns symbol of assignment operator.
-    pub fn symbol(self) -> &'static str {
-        use self::AssignOp::*;
-
-        match self {
-            Assign => ":=",
-            Set => "=",
-            Add => "+=",
-            Sub => "-=",
-            Mul => "*=",
-            Div => "/=",
-            Rem => "%=",
-            Pow => "^=",
-        }
-    }
 }
 
-/// 4D matrix expression.
+/// For-expression.
 #[derive(Debug, Clone)]
-pub struct Mat4 {
-    /// Row vector argument expressions.
-    pub args: Vec<Expression>,
+pub struct For {
+    /// The initial expression.
+    pub init: Expression,
+    /// Expression evaluated for determining whether to continue or not.
+    pub cond: Expression,
+    /// Expression evaluated at each step.
+    pub step: Expression,
+    /// Block expression.
+    pub block: Block,
+    /// Loop label.
+    pub label: Option<Arc<String>>,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Mat4 {
-    /// Creates 4D matrix from meta data.
+impl For {
+    /// Creates For-expression from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Mat4), ()> {
+    ) -> Result<(Range, For), ()> {
         let start = convert;
-        let node = "mat4";
+        let node = "for";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut x: Option<Expression> = None;
-        let mut y: Option<Expression> = None;
-        let mut z: Option<Expression> = None;
-        let mut w: Option<Expression> = None;
+        let mut init: Option<Expression> = None;
+        let mut cond: Option<Expression> = None;
+        let mut step: Option<Expression> = None;
+        let mut block: Option<Block> = None;
+        let mut label: Option<Arc<String>> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "ex", convert, ignored)
+                Expression::from_meta_data(file, source, "init", convert, ignored)
             {
                 convert.update(range);
-                x = Some(val);
+                init = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "ey", convert, ignored)
+                Expression::from_meta_data(file, source, "cond", convert, ignored)
             {
                 convert.update(range);
-                y = Some(val);
+                cond = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "ez", convert, ignored)
+                Expression::from_meta_data(file, source, "step", convert, ignored)
             {
                 convert.update(range);
-                z = Some(val);
+                step = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "ew", convert, ignored)
+                Block::from_meta_data(file, source, "block", convert, ignored)
             {
                 convert.update(range);
-                w = Some(val);
+                block = Some(val);
+            } else if let Ok((range, val)) = convert.meta_string("label") {
+                convert.update(range);
+                label = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -3490,29 +5397,18 @@ impl Mat4 {
             }
         }
 
-        let x = x.ok_or(())?;
-        let y = y.unwrap_or_else(|| {
-            Expression::Variable(Box::new((
-                Range::empty(0),
-                Variable::Vec4([0.0, 1.0, 0.0, 0.0]),
-            )))
-        });
-        let z = z.unwrap_or_else(|| {
-            Expression::Variable(Box::new((
-                Range::empty(0),
-                Variable::Vec4([0.0, 0.0, 1.0, 0.0]),
-            )))
-        });
-        let w = w.unwrap_or_else(|| {
-            Expression::Variable(Box::new((
-                Range::empty(0),
-                Variable::Vec4([0.0, 0.0, 0.0, 1.0]),
-            )))
-        });
+        let init = init.ok_or(())?;
+        let cond = cond.ok_or(())?;
+        let step = step.ok_or(())?;
+        let block = block.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Mat4 {
-                args: vec![x, y, z, w],
+            For {
+                init,
+                cond,
+                step,
+                block,
+                label,
                 source_range: convert.source(start).unwrap(),
             },
         ))
@@ -3527,66 +5423,75 @@ impl Mat4 {
         use_lookup: &UseLookup,
     ) {
         let st = stack.len();
-        for arg in &mut self.args {
-            let arg_st = stack.len();
-            arg.get_locals(relative, stack, closure_stack, module, use_lookup);
-            stack.truncate(arg_st);
-            stack.push(None);
-        }
+        self.init.get_locals(relative, stack, closure_stack, module, use_lookup);
+        let after_init = stack.len();
+        self.cond.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(after_init);
+        self.step.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(after_init);
+        self.block.get_locals(relative, stack, closure_stack, module, use_lookup);
         stack.truncate(st);
     }
 }
 
-/// 4D vector expression.
+/// For-In expression.
 #[derive(Debug, Clone)]
-pub struct Vec4 {
-    /// Component expressions.
-    pub args: Vec<Expression>,
+pub struct ForIn {
+    /// Name of the loop variable, or key/index when a value is bound too.
+    pub name: Arc<String>,
+    /// Name of the value variable, e.g. `val` in `for key, val in obj`.
+    pub value: Option<Arc<String>>,
+    /// The in-type, array, object, link or string expression to read from.
+    pub iter: Expression,
+    /// Block expression.
+    pub block: Block,
+    /// Loop label.
+    pub label: Option<Arc<String>>,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Vec4 {
-    /// Creates 4D vector from meta data.
+impl ForIn {
+    /// Creates For-In expression from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
+        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Vec4), ()> {
+    ) -> Result<(Range, ForIn), ()> {
         let start = convert;
-        let node = "vec4";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut x: Option<Expression> = None;
-        let mut y: Option<Expression> = None;
-        let mut z: Option<Expression> = None;
-        let mut w: Option<Expression> = None;
+        let mut name: Option<Arc<String>> = None;
+        let mut value: Option<Arc<String>> = None;
+        let mut iter: Option<Expression> = None;
+        let mut block: Option<Block> = None;
+        let mut label: Option<Arc<String>> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "x", convert, ignored)
-            {
+            } else if let Ok((range, val)) = convert.meta_string("name") {
                 convert.update(range);
-                x = Some(val);
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "y", convert, ignored)
-            {
+                name = Some(val);
+            } else if let Ok((range, val)) = convert.meta_string("value") {
                 convert.update(range);
-                y = Some(val);
+                value = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "z", convert, ignored)
+                Expression::from_meta_data(file, source, "iter", convert, ignored)
             {
                 convert.update(range);
-                z = Some(val);
+                iter = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "w", convert, ignored)
+                Block::from_meta_data(file, source, "block", convert, ignored)
             {
                 convert.update(range);
-                w = Some(val);
+                block = Some(val);
+            } else if let Ok((range, val)) = convert.meta_string("label") {
+                convert.update(range);
+                label = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -3594,41 +5499,22 @@ impl Vec4 {
             }
         }
 
-        let x = x.ok_or(())?;
-        let y = y.unwrap_or_else(|| {
-            Expression::Variable(Box::new((Range::empty(0), Variable::f64(0.0))))
-        });
-        let z = z.unwrap_or_else(|| {
-            Expression::Variable(Box::new((Range::empty(0), Variable::f64(0.0))))
-        });
-        let w = w.unwrap_or_else(|| {
-            Expression::Variable(Box::new((Range::empty(0), Variable::f64(0.0))))
-        });
+        let name = name.ok_or(())?;
+        let iter = iter.ok_or(())?;
+        let block = block.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Vec4 {
-                args: vec![x, y, z, w],
+            ForIn {
+                name,
+                value,
+                iter,
+                block,
+                label,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
-    fn precompute(&self) -> Option<Variable> {
-        let mut v: [f32; 4] = [0.0; 4];
-        for i in 0..self.args.len().min(4) {
-            if let Some(val) = self.args[i].precompute() {
-                if let Variable::F64(val, _) = val {
-                    v[i] = val as f32;
-                } else {
-                    return None;
-                }
-            } else {
-                return None;
-            }
-        }
-        Some(Variable::Vec4(v))
-    }
-
     fn get_locals(
         &mut self,
         relative: usize,
@@ -3638,75 +5524,83 @@ impl Vec4 {
         use_lookup: &UseLookup,
     ) {
         let st = stack.len();
-        for arg in &mut self.args {
-            let arg_st = stack.len();
-            arg.get_locals(relative, stack, closure_stack, module, use_lookup);
-            stack.truncate(arg_st);
-            match *arg {
-                Expression::Swizzle(ref swizzle) => {
-                    for _ in 0..swizzle.len() {
-                        stack.push(None);
-                    }
-                }
-                _ => {
-                    stack.push(None);
-                }
-            }
+        self.iter.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(st);
+        stack.push(Some(self.name.clone()));
+        if let Some(ref value) = self.value {
+            stack.push(Some(value.clone()));
         }
+        self.block.get_locals(relative, stack, closure_stack, module, use_lookup);
         stack.truncate(st);
     }
 }
 
-/// 4D vector unloop expression.
+/// For-N expression.
 #[derive(Debug, Clone)]
-pub struct Vec4UnLoop {
-    /// Name of the variable.
+pub struct ForN {
+    /// Name of index variable.
     pub name: Arc<String>,
-    /// Expression of the loop.
-    pub expr: Expression,
-    /// The length of the loop.
-    pub len: u8,
+    /// Start expression.
+    ///
+    /// This is evaluated before starting the loop.
+    pub start: Option<Expression>,
+    /// End expression.
+    ///
+    /// This is evaluated before starting the loop.
+    pub end: Expression,
+    /// Bloc expression.
+    pub block: Block,
+    /// Loop label.
+    pub label: Option<Arc<String>>,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Vec4UnLoop {
-    /// Creates 4D vector-unloop from meta data.
+impl ForN {
+    /// Creates For-N expression from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
+        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Vec4UnLoop), ()> {
+    ) -> Result<(Range, ForN), ()> {
         let start = convert;
-        let node = "vec4_un_loop";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut name: Option<Arc<String>> = None;
-        let mut expr: Option<Expression> = None;
-        let mut len: u8 = 4;
+        let mut indices: Vec<(Arc<String>, Option<Expression>, Option<Expression>)> = vec![];
+        let mut block: Option<Block> = None;
+        let mut label: Option<Arc<String>> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, _)) = convert.meta_bool("4") {
-                convert.update(range);
-                len = 4;
-            } else if let Ok((range, _)) = convert.meta_bool("3") {
+            } else if let Ok((range, val)) =
+                Block::from_meta_data(file, source, "block", convert, ignored)
+            {
                 convert.update(range);
-                len = 3;
-            } else if let Ok((range, _)) = convert.meta_bool("2") {
+                block = Some(val);
+            } else if let Ok((range, val)) = convert.meta_string("label") {
                 convert.update(range);
-                len = 2;
+                label = Some(val);
             } else if let Ok((range, val)) = convert.meta_string("name") {
                 convert.update(range);
-                name = Some(val);
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "expr", convert, ignored)
-            {
-                convert.update(range);
-                expr = Some(val);
+                let mut start_expr: Option<Expression> = None;
+                let mut end_expr: Option<Expression> = None;
+                if let Ok((range, val)) =
+                    Expression::from_meta_data(file, source, "start", convert, ignored)
+                {
+                    convert.update(range);
+                    start_expr = Some(val);
+                }
+                if let Ok((range, val)) =
+                    Expression::from_meta_data(file, source, "end", convert, ignored)
+                {
+                    convert.update(range);
+                    end_expr = Some(val);
+                }
+                indices.push((val, start_expr, end_expr));
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -3714,100 +5608,134 @@ impl Vec4UnLoop {
             }
         }
 
-        let name = name.ok_or(())?;
-        let expr = expr.ok_or(())?;
-        Ok((
+        ForN::create(
+            node,
             convert.subtract(start),
-            Vec4UnLoop {
+            convert.source(start).unwrap(),
+            label,
+            &indices,
+            block,
+        )
+    }
+
+    fn create(
+        node: &str,
+        range: Range,
+        source_range: Range,
+        label: Option<Arc<String>>,
+        indices: &[(Arc<String>, Option<Expression>, Option<Expression>)],
+        mut block: Option<Block>,
+    ) -> Result<(Range, ForN), ()> {
+        if indices.is_empty() {
+            return Err(());
+        }
+
+        let name: Arc<String> = indices[0].0.clone();
+        let start_expr = indices[0].1.clone();
+        let mut end_expr = indices[0].2.clone();
+
+        if indices.len() > 1 {
+            let (_, new_for_n) =
+                ForN::create(node, range, source_range, None, &indices[1..], block)?;
+            block = Some(Block {
+                source_range,
+                expressions: vec![match node {
+                    "for_n" => Expression::ForN(Box::new(new_for_n)),
+                    "sum" => Expression::Sum(Box::new(new_for_n)),
+                    "sum_vec4" => Expression::SumVec4(Box::new(new_for_n)),
+                    "prod" => Expression::Prod(Box::new(new_for_n)),
+                    "prod_vec4" => Expression::ProdVec4(Box::new(new_for_n)),
+                    "any" => Expression::Any(Box::new(new_for_n)),
+                    "all" => Expression::All(Box::new(new_for_n)),
+                    "min" => Expression::Min(Box::new(new_for_n)),
+                    "max" => Expression::Max(Box::new(new_for_n)),
+                    "sift" => Expression::Sift(Box::new(new_for_n)),
+                    "link_for" => Expression::LinkFor(Box::new(new_for_n)),
+                    _ => return Err(()),
+                }],
+            });
+        }
+
+        let block = block.ok_or(())?;
+
+        // Infer list length from index.
+        if end_expr.is_none() {
+            end_expr = infer_len::infer(&block, &name);
+        }
+
+        let end_expr = end_expr.ok_or(())?;
+        Ok((
+            range,
+            ForN {
                 name,
-                expr,
-                len,
-                source_range: convert.source(start).unwrap(),
+                start: start_expr,
+                end: end_expr,
+                block,
+                label,
+                source_range,
             },
         ))
     }
 
-    fn into_expression(self) -> Expression {
-        let source_range = self.source_range;
-
-        let zero = || Expression::Variable(Box::new((source_range, Variable::f64(0.0))));
-
-        let replace_0 = replace::number(&self.expr, &self.name, 0.0);
-        let replace_1 = replace::number(&self.expr, &self.name, 1.0);
-        let replace_2 = if self.len > 2 {
-            replace::number(&self.expr, &self.name, 2.0)
-        } else {
-            zero()
-        };
-        let replace_3 = if self.len > 3 {
-            replace::number(&self.expr, &self.name, 3.0)
-        } else {
-            zero()
-        };
-
-        Expression::Vec4(Box::new(Vec4 {
-            args: vec![replace_0, replace_1, replace_2, replace_3],
-            source_range,
-        }))
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        let st = stack.len();
+        if let Some(ref mut start) = self.start {
+            start.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(st);
+        }
+        self.end.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(st);
+        stack.push(Some(self.name.clone()));
+        self.block.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(st);
     }
 }
 
-/// Swizzle expression.
+/// Loop expression.
 #[derive(Debug, Clone)]
-pub struct Swizzle {
-    /// First component swizzle.
-    pub sw0: usize,
-    /// Second component swizzle.
-    pub sw1: usize,
-    /// Third component swizzle.
-    pub sw2: Option<usize>,
-    /// Fourth component swizzle.
-    pub sw3: Option<usize>,
-    /// 4D vector expression.
-    pub expr: Expression,
+pub struct Loop {
+    /// Block expression.
+    pub block: Block,
+    /// Loop label.
+    pub label: Option<Arc<String>>,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Swizzle {
-    /// Create swizzle from meta data.
+impl Loop {
+    /// Creates loop expression from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Swizzle), ()> {
+    ) -> Result<(Range, Loop), ()> {
         let start = convert;
-        let node = "swizzle";
+        let node = "loop";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut sw0: Option<usize> = None;
-        let mut sw1: Option<usize> = None;
-        let mut sw2: Option<usize> = None;
-        let mut sw3: Option<usize> = None;
-        let mut expr: Option<Expression> = None;
+        let mut block: Option<Block> = None;
+        let mut label: Option<Arc<String>> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = Sw::from_meta_data("sw0", convert, ignored) {
-                convert.update(range);
-                sw0 = Some(val.ind);
-            } else if let Ok((range, val)) = Sw::from_meta_data("sw1", convert, ignored) {
-                convert.update(range);
-                sw1 = Some(val.ind);
-            } else if let Ok((range, val)) = Sw::from_meta_data("sw2", convert, ignored) {
-                convert.update(range);
-                sw2 = Some(val.ind);
-            } else if let Ok((range, val)) = Sw::from_meta_data("sw3", convert, ignored) {
-                convert.update(range);
-                sw3 = Some(val.ind);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "expr", convert, ignored)
+                Block::from_meta_data(file, source, "block", convert, ignored)
             {
                 convert.update(range);
-                expr = Some(val);
+                block = Some(val);
+            } else if let Ok((range, val)) = convert.meta_string("label") {
+                convert.update(range);
+                label = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -3815,64 +5743,64 @@ impl Swizzle {
             }
         }
 
-        let sw0 = sw0.ok_or(())?;
-        let sw1 = sw1.ok_or(())?;
-        let expr = expr.ok_or(())?;
+        let block = block.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Swizzle {
-                sw0,
-                sw1,
-                sw2,
-                sw3,
-                expr,
+            Loop {
+                block,
+                label,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
-    fn len(&self) -> usize {
-        2 + if self.sw2.is_some() { 1 } else { 0 } + if self.sw3.is_some() { 1 } else { 0 }
+    fn into_expression(self) -> Expression {
+        let source_range = self.source_range;
+        Expression::For(Box::new(For {
+            block: self.block,
+            label: self.label,
+            init: Expression::Block(Box::new(Block {
+                expressions: vec![],
+                source_range,
+            })),
+            step: Expression::Block(Box::new(Block {
+                expressions: vec![],
+                source_range,
+            })),
+            cond: Expression::Variable(Box::new((source_range, Variable::bool(true)))),
+            source_range,
+        }))
     }
 }
 
-/// Component swizzle expression.
+/// Break expression.
 #[derive(Debug, Clone)]
-pub struct Sw {
-    /// The component index of the swizzle.
-    pub ind: usize,
+pub struct Break {
+    /// Loop label.
+    pub label: Option<Arc<String>>,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Sw {
-    /// Creates component swizzle from meta data.
+impl Break {
+    /// Creates break expression from meta data.
     pub fn from_meta_data(
-        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Sw), ()> {
+    ) -> Result<(Range, Break), ()> {
         let start = convert;
+        let node = "break";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut ind: Option<usize> = None;
+        let mut label: Option<Arc<String>> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, _)) = convert.meta_bool("x") {
-                convert.update(range);
-                ind = Some(0);
-            } else if let Ok((range, _)) = convert.meta_bool("y") {
-                convert.update(range);
-                ind = Some(1);
-            } else if let Ok((range, _)) = convert.meta_bool("z") {
-                convert.update(range);
-                ind = Some(2);
-            } else if let Ok((range, _)) = convert.meta_bool("w") {
+            } else if let Ok((range, val)) = convert.meta_string("label") {
                 convert.update(range);
-                ind = Some(3);
+                label = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -3880,76 +5808,41 @@ impl Sw {
             }
         }
 
-        let ind = ind.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Sw {
-                ind,
+            Break {
+                label,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 }
 
-/// For-expression.
+/// Continue expression.
 #[derive(Debug, Clone)]
-pub struct For {
-    /// The initial expression.
-    pub init: Expression,
-    /// Expression evaluated for determining whether to continue or not.
-    pub cond: Expression,
-    /// Expression evaluated at each step.
-    pub step: Expression,
-    /// Block expression.
-    pub block: Block,
-    /// Loop label.
+pub struct Continue {
+    /// The loop label.
     pub label: Option<Arc<String>>,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl For {
-    /// Creates For-expression from meta data.
+impl Continue {
+    /// Creates continue expression from meta data.
     pub fn from_meta_data(
-        file: &Arc<String>,
-        source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, For), ()> {
+    ) -> Result<(Range, Continue), ()> {
         let start = convert;
-        let node = "for";
+        let node = "continue";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut init: Option<Expression> = None;
-        let mut cond: Option<Expression> = None;
-        let mut step: Option<Expression> = None;
-        let mut block: Option<Block> = None;
         let mut label: Option<Arc<String>> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "init", convert, ignored)
-            {
-                convert.update(range);
-                init = Some(val);
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "cond", convert, ignored)
-            {
-                convert.update(range);
-                cond = Some(val);
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "step", convert, ignored)
-            {
-                convert.update(range);
-                step = Some(val);
-            } else if let Ok((range, val)) =
-                Block::from_meta_data(file, source, "block", convert, ignored)
-            {
-                convert.update(range);
-                block = Some(val);
             } else if let Ok((range, val)) = convert.meta_string("label") {
                 convert.update(range);
                 label = Some(val);
@@ -3960,102 +5853,80 @@ impl For {
             }
         }
 
-        let init = init.ok_or(())?;
-        let cond = cond.ok_or(())?;
-        let step = step.ok_or(())?;
-        let block = block.ok_or(())?;
         Ok((
             convert.subtract(start),
-            For {
-                init,
-                cond,
-                step,
-                block,
+            Continue {
                 label,
-                source_range: convert.source(start).unwrap(),
-            },
-        ))
-    }
-
-    fn get_locals(
-        &mut self,
-        relative: usize,
-        stack: &mut Vec<Option<Arc<String>>>,
-        closure_stack: &mut Vec<usize>,
-        module: &Module,
-        use_lookup: &UseLookup,
-    ) {
-        let st = stack.len();
-        self.init.get_locals(relative, stack, closure_stack, module, use_lookup);
-        let after_init = stack.len();
-        self.cond.get_locals(relative, stack, closure_stack, module, use_lookup);
-        stack.truncate(after_init);
-        self.step.get_locals(relative, stack, closure_stack, module, use_lookup);
-        stack.truncate(after_init);
-        self.block.get_locals(relative, stack, closure_stack, module, use_lookup);
-        stack.truncate(st);
-    }
-}
-
-/// For-In expression.
-#[derive(Debug, Clone)]
-#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
-pub struct ForIn {
-    /// Name of the loop variable.
-    pub name: Arc<String>,
-    /// The in-type expression to read from.
-    pub iter: Expression,
-    /// Block expression.
-    pub block: Block,
-    /// Loop label.
-    pub label: Option<Arc<String>>,
-    /// The range in source.
-    pub source_range: Range,
+                source_range: convert.source(start).unwrap(),
+            },
+        ))
+    }
 }
 
-/// For-In expression.
+/// If-expression.
 #[derive(Debug, Clone)]
-#[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-pub enum ForIn {}
+pub struct If {
+    /// Condition.
+    pub cond: Expression,
+    /// True block.
+    pub true_block: Block,
+    /// Else-if conditions.
+    pub else_if_conds: Vec<Expression>,
+    /// Else-if blocks.
+    pub else_if_blocks: Vec<Block>,
+    /// Else block.
+    pub else_block: Option<Block>,
+    /// The range in source.
+    pub source_range: Range,
+}
 
-impl ForIn {
-    /// Creates For-In expression from meta data.
-    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+impl If {
+    /// Creates if-expression from meta data.
     pub fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
-        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, ForIn), ()> {
+    ) -> Result<(Range, If), ()> {
         let start = convert;
+        let node = "if";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut name: Option<Arc<String>> = None;
-        let mut iter: Option<Expression> = None;
-        let mut block: Option<Block> = None;
-        let mut label: Option<Arc<String>> = None;
+        let mut cond: Option<Expression> = None;
+        let mut true_block: Option<Block> = None;
+        let mut else_if_conds: Vec<Expression> = vec![];
+        let mut else_if_blocks: Vec<Block> = vec![];
+        let mut else_block: Option<Block> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = convert.meta_string("name") {
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "cond", convert, ignored)
+            {
                 convert.update(range);
-                name = Some(val);
+                cond = Some(val);
             } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "iter", convert, ignored)
+                Block::from_meta_data(file, source, "true_block", convert, ignored)
             {
                 convert.update(range);
-                iter = Some(val);
+                true_block = Some(val);
             } else if let Ok((range, val)) =
-                Block::from_meta_data(file, source, "block", convert, ignored)
+                Expression::from_meta_data(file, source, "else_if_cond", convert, ignored)
             {
                 convert.update(range);
-                block = Some(val);
-            } else if let Ok((range, val)) = convert.meta_string("label") {
+                else_if_conds.push(val);
+            } else if let Ok((range, val)) =
+                Block::from_meta_data(file, source, "else_if_block", convert, ignored)
+            {
                 convert.update(range);
-                label = Some(val);
+                else_if_blocks.push(val);
+            } else if let Ok((range, val)) =
+                Block::from_meta_data(file, source, "else_block", convert, ignored)
+            {
+                convert.update(range);
+                else_block = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -4063,34 +5934,21 @@ impl ForIn {
             }
         }
 
-        let name = name.ok_or(())?;
-        let iter = iter.ok_or(())?;
-        let block = block.ok_or(())?;
+        let cond = cond.ok_or(())?;
+        let true_block = true_block.ok_or(())?;
         Ok((
             convert.subtract(start),
-            ForIn {
-                name,
-                iter,
-                block,
-                label,
+            If {
+                cond,
+                true_block,
+                else_if_conds,
+                else_if_blocks,
+                else_block,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
 
-    /// Creates For-In expression from meta data.
-    #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-    pub fn from_meta_data(
-        _file: &Arc<String>,
-        _source: &Arc<String>,
-        _node: &str,
-        _convert: Convert,
-        _ignored: &mut Vec<Range>,
-    ) -> Result<(Range, ForIn), ()> {
-        Err(())
-    }
-
-    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
     fn get_locals(
         &mut self,
         relative: usize,
@@ -4100,90 +5958,66 @@ impl ForIn {
         use_lookup: &UseLookup,
     ) {
         let st = stack.len();
-        self.iter.get_locals(relative, stack, closure_stack, module, use_lookup);
+        self.cond.get_locals(relative, stack, closure_stack, module, use_lookup);
         stack.truncate(st);
-        stack.push(Some(self.name.clone()));
-        self.block.get_locals(relative, stack, closure_stack, module, use_lookup);
+        self.true_block.get_locals(relative, stack, closure_stack, module, use_lookup);
         stack.truncate(st);
+        // Does not matter that conditions are looked up before blocks,
+        // since the stack gets truncated anyway.
+        for else_if_cond in &mut self.else_if_conds {
+            else_if_cond.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(st);
+        }
+        for else_if_block in &mut self.else_if_blocks {
+            else_if_block.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(st);
+        }
+        if let Some(ref mut else_block) = self.else_block {
+            else_block.get_locals(relative, stack, closure_stack, module, use_lookup);
+            stack.truncate(st);
+        }
     }
-
-    #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-    fn get_locals(
-        &mut self,
-        _relative: usize,
-        _stack: &mut Vec<Option<Arc<String>>>,
-        _closure_stack: &mut Vec<usize>,
-        _module: &Module,
-        _use_lookup: &UseLookup,
-    ) {}
 }
 
-/// For-N expression.
+/// Match expression, e.g. `match x { some(a) => a, none() => 0 }`.
 #[derive(Debug, Clone)]
-pub struct ForN {
-    /// Name of index variable.
-    pub name: Arc<String>,
-    /// Start expression.
-    ///
-    /// This is evaluated before starting the loop.
-    pub start: Option<Expression>,
-    /// End expression.
-    ///
-    /// This is evaluated before starting the loop.
-    pub end: Expression,
-    /// Bloc expression.
-    pub block: Block,
-    /// Loop label.
-    pub label: Option<Arc<String>>,
+pub struct Match {
+    /// The expression to match against.
+    pub expr: Expression,
+    /// Match arms, tested in order.
+    pub arms: Vec<MatchArm>,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl ForN {
-    /// Creates For-N expression from meta data.
-    pub fn from_meta_data(
+impl Match {
+    /// Creates match expression from meta data.
+    pub(crate) fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
-        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, ForN), ()> {
+    ) -> Result<(Range, Match), ()> {
         let start = convert;
+        let node = "match";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut indices: Vec<(Arc<String>, Option<Expression>, Option<Expression>)> = vec![];
-        let mut block: Option<Block> = None;
-        let mut label: Option<Arc<String>> = None;
+        let mut expr: Option<Expression> = None;
+        let mut arms: Vec<MatchArm> = vec![];
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
             } else if let Ok((range, val)) =
-                Block::from_meta_data(file, source, "block", convert, ignored)
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
             {
                 convert.update(range);
-                block = Some(val);
-            } else if let Ok((range, val)) = convert.meta_string("label") {
-                convert.update(range);
-                label = Some(val);
-            } else if let Ok((range, val)) = convert.meta_string("name") {
+                expr = Some(val);
+            } else if let Ok((range, val)) = MatchArm::from_meta_data(file, source, convert, ignored)
+            {
                 convert.update(range);
-                let mut start_expr: Option<Expression> = None;
-                let mut end_expr: Option<Expression> = None;
-                if let Ok((range, val)) =
-                    Expression::from_meta_data(file, source, "start", convert, ignored)
-                {
-                    convert.update(range);
-                    start_expr = Some(val);
-                }
-                if let Ok((range, val)) =
-                    Expression::from_meta_data(file, source, "end", convert, ignored)
-                {
-                    convert.update(range);
-                    end_expr = Some(val);
-                }
-                indices.push((val, start_expr, end_expr));
+                arms.push(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -4191,71 +6025,13 @@ impl ForN {
             }
         }
 
-        ForN::create(
-            node,
-            convert.subtract(start),
-            convert.source(start).unwrap(),
-            label,
-            &indices,
-            block,
-        )
-    }
-
-    fn create(
-        node: &str,
-        range: Range,
-        source_range: Range,
-        label: Option<Arc<String>>,
-        indices: &[(Arc<String>, Option<Expression>, Option<Expression>)],
-        mut block: Option<Block>,
-    ) -> Result<(Range, ForN), ()> {
-        if indices.is_empty() {
-            return Err(());
-        }
-
-        let name: Arc<String> = indices[0].0.clone();
-        let start_expr = indices[0].1.clone();
-        let mut end_expr = indices[0].2.clone();
-
-        if indices.len() > 1 {
-            let (_, new_for_n) =
-                ForN::create(node, range, source_range, None, &indices[1..], block)?;
-            block = Some(Block {
-                source_range,
-                expressions: vec![match node {
-                    "for_n" => Expression::ForN(Box::new(new_for_n)),
-                    "sum" => Expression::Sum(Box::new(new_for_n)),
-                    "sum_vec4" => Expression::SumVec4(Box::new(new_for_n)),
-                    "prod" => Expression::Prod(Box::new(new_for_n)),
-                    "prod_vec4" => Expression::ProdVec4(Box::new(new_for_n)),
-                    "any" => Expression::Any(Box::new(new_for_n)),
-                    "all" => Expression::All(Box::new(new_for_n)),
-                    "min" => Expression::Min(Box::new(new_for_n)),
-                    "max" => Expression::Max(Box::new(new_for_n)),
-                    "sift" => Expression::Sift(Box::new(new_for_n)),
-                    "link_for" => Expression::LinkFor(Box::new(new_for_n)),
-                    _ => return Err(()),
-                }],
-            });
-        }
-
-        let block = block.ok_or(())?;
-
-        // Infer list length from index.
-        if end_expr.is_none() {
-            end_expr = infer_len::infer(&block, &name);
-        }
-
-        let end_expr = end_expr.ok_or(())?;
+        let expr = expr.ok_or(())?;
         Ok((
-            range,
-            ForN {
-                name,
-                start: start_expr,
-                end: end_expr,
-                block,
-                label,
-                source_range,
+            convert.subtract(start),
+            Match {
+                expr,
+                arms,
+                source_range: convert.source(start).unwrap(),
             },
         ))
     }
@@ -4269,56 +6045,55 @@ impl ForN {
         use_lookup: &UseLookup,
     ) {
         let st = stack.len();
-        if let Some(ref mut start) = self.start {
-            start.get_locals(relative, stack, closure_stack, module, use_lookup);
+        self.expr.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(st);
+        for arm in &mut self.arms {
+            // Bindings are pushed in the same order as the runtime pushes them.
+            arm.pattern.get_locals(stack);
+            arm.expr.get_locals(relative, stack, closure_stack, module, use_lookup);
             stack.truncate(st);
         }
-        self.end.get_locals(relative, stack, closure_stack, module, use_lookup);
-        stack.truncate(st);
-        stack.push(Some(self.name.clone()));
-        self.block.get_locals(relative, stack, closure_stack, module, use_lookup);
-        stack.truncate(st);
     }
 }
-
-/// Loop expression.
-#[derive(Debug, Clone)]
-pub struct Loop {
-    /// Block expression.
-    pub block: Block,
-    /// Loop label.
-    pub label: Option<Arc<String>>,
+
+/// Match arm, e.g. `some(a) => a`.
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    /// The pattern to test.
+    pub pattern: Pattern,
+    /// The expression to evaluate when the pattern matches.
+    pub expr: Expression,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Loop {
-    /// Creates loop expression from meta data.
-    pub fn from_meta_data(
+impl MatchArm {
+    /// Creates match arm from meta data.
+    pub(crate) fn from_meta_data(
         file: &Arc<String>,
         source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Loop), ()> {
+    ) -> Result<(Range, MatchArm), ()> {
         let start = convert;
-        let node = "loop";
+        let node = "arm";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut block: Option<Block> = None;
-        let mut label: Option<Arc<String>> = None;
+        let mut pattern: Option<Pattern> = None;
+        let mut expr: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
+            } else if let Ok((range, val)) = Pattern::from_meta_data("pat", convert, ignored) {
+                convert.update(range);
+                pattern = Some(val);
             } else if let Ok((range, val)) =
-                Block::from_meta_data(file, source, "block", convert, ignored)
+                Expression::from_meta_data(file, source, "expr", convert, ignored)
             {
                 convert.update(range);
-                block = Some(val);
-            } else if let Ok((range, val)) = convert.meta_string("label") {
-                convert.update(range);
-                label = Some(val);
+                expr = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -4326,64 +6101,57 @@ impl Loop {
             }
         }
 
-        let block = block.ok_or(())?;
+        let pattern = pattern.ok_or(())?;
+        let expr = expr.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Loop {
-                block,
-                label,
+            MatchArm {
+                pattern,
+                expr,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
-
-    fn into_expression(self) -> Expression {
-        let source_range = self.source_range;
-        Expression::For(Box::new(For {
-            block: self.block,
-            label: self.label,
-            init: Expression::Block(Box::new(Block {
-                expressions: vec![],
-                source_range,
-            })),
-            step: Expression::Block(Box::new(Block {
-                expressions: vec![],
-                source_range,
-            })),
-            cond: Expression::Variable(Box::new((source_range, Variable::bool(true)))),
-            source_range,
-        }))
-    }
 }
 
-/// Break expression.
+/// Destructuring declaration, e.g. `[a, b] := pair`.
 #[derive(Debug, Clone)]
-pub struct Break {
-    /// Loop label.
-    pub label: Option<Arc<String>>,
+pub struct Destructure {
+    /// The pattern declaring the new locals.
+    pub pattern: Pattern,
+    /// Right side expression.
+    pub right: Expression,
     /// The range in source.
     pub source_range: Range,
 }
 
-impl Break {
-    /// Creates break expression from meta data.
-    pub fn from_meta_data(
+impl Destructure {
+    /// Creates destructuring declaration from meta data.
+    pub(crate) fn from_meta_data(
+        file: &Arc<String>,
+        source: &Arc<String>,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Break), ()> {
+    ) -> Result<(Range, Destructure), ()> {
         let start = convert;
-        let node = "break";
+        let node = "destructure";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut label: Option<Arc<String>> = None;
+        let mut pattern: Option<Pattern> = None;
+        let mut right: Option<Expression> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = convert.meta_string("label") {
+            } else if let Ok((range, val)) = Pattern::from_meta_data("pat", convert, ignored) {
                 convert.update(range);
-                label = Some(val);
+                pattern = Some(val);
+            } else if let Ok((range, val)) =
+                Expression::from_meta_data(file, source, "right", convert, ignored)
+            {
+                convert.update(range);
+                right = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -4391,44 +6159,147 @@ impl Break {
             }
         }
 
+        let pattern = pattern.ok_or(())?;
+        let right = right.ok_or(())?;
         Ok((
             convert.subtract(start),
-            Break {
-                label,
+            Destructure {
+                pattern,
+                right,
                 source_range: convert.source(start).unwrap(),
             },
         ))
     }
+
+    fn get_locals(
+        &mut self,
+        relative: usize,
+        stack: &mut Vec<Option<Arc<String>>>,
+        closure_stack: &mut Vec<usize>,
+        module: &Module,
+        use_lookup: &UseLookup,
+    ) {
+        // Declared locals in right expressions are popped from the stack.
+        let st = stack.len();
+        self.right.get_locals(relative, stack, closure_stack, module, use_lookup);
+        stack.truncate(st);
+
+        self.pattern.get_locals(stack);
+    }
 }
 
-/// Continue expression.
+/// Pattern, used to test and destructure a value.
 #[derive(Debug, Clone)]
-pub struct Continue {
-    /// The loop label.
-    pub label: Option<Arc<String>>,
-    /// The range in source.
-    pub source_range: Range,
+pub enum Pattern {
+    /// Matches anything without binding, `_`.
+    Wildcard(Range),
+    /// Matches anything and binds the value to a name.
+    Bind(Range, Arc<String>),
+    /// Matches a literal number, string or bool.
+    Value(Range, Variable),
+    /// Matches `some(_)`.
+    Some(Range, Box<Pattern>),
+    /// Matches `none()`.
+    None(Range),
+    /// Matches `ok(_)`.
+    Ok(Range, Box<Pattern>),
+    /// Matches `err(_)`.
+    Err(Range, Box<Pattern>),
+    /// Matches an array of the same length.
+    Array(Range, Vec<Pattern>),
+    /// Matches an object with the keys.
+    Object(Range, Vec<(Arc<String>, Pattern)>),
+    /// Matches an enum variant.
+    Variant(Range, Arc<String>, Box<Pattern>),
+    /// Matches the first components of a 4D vector.
+    Vec4(Range, Vec<Pattern>),
 }
 
-impl Continue {
-    /// Creates continue expression from meta data.
-    pub fn from_meta_data(
+impl Pattern {
+    /// Creates pattern from meta data.
+    pub(crate) fn from_meta_data(
+        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, Continue), ()> {
+    ) -> Result<(Range, Pattern), ()> {
         let start = convert;
-        let node = "continue";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut label: Option<Arc<String>> = None;
+        let mut result: Option<Pattern> = None;
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) = convert.meta_string("label") {
+            } else if let Ok((range, val)) = Pattern::from_meta_data("pat_some", convert, ignored) {
                 convert.update(range);
-                label = Some(val);
+                result = Some(Pattern::Some(convert.source(start).unwrap(), Box::new(val)));
+            } else if let Ok((range, val)) = Pattern::from_meta_data("pat_ok", convert, ignored) {
+                convert.update(range);
+                result = Some(Pattern::Ok(convert.source(start).unwrap(), Box::new(val)));
+            } else if let Ok((range, val)) = Pattern::from_meta_data("pat_err", convert, ignored) {
+                convert.update(range);
+                result = Some(Pattern::Err(convert.source(start).unwrap(), Box::new(val)));
+            } else if let Ok(range) = convert.start_node("pat_none") {
+                convert.update(range);
+                let range = convert.end_node("pat_none")?;
+                convert.update(range);
+                result = Some(Pattern::None(convert.source(start).unwrap()));
+            } else if let Ok((range, items)) =
+                Pattern::items_from_meta_data("pat_arr", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Pattern::Array(convert.source(start).unwrap(), items));
+            } else if let Ok((range, items)) =
+                Pattern::items_from_meta_data("pat_vec4", convert, ignored)
+            {
+                convert.update(range);
+                result = Some(Pattern::Vec4(convert.source(start).unwrap(), items));
+            } else if let Ok(range) = convert.start_node("pat_obj") {
+                convert.update(range);
+                let mut key_values = vec![];
+                loop {
+                    if let Ok(range) = convert.end_node("pat_obj") {
+                        convert.update(range);
+                        break;
+                    } else if let Ok((range, val)) =
+                        Pattern::key_from_meta_data(convert, ignored)
+                    {
+                        convert.update(range);
+                        key_values.push(val);
+                    } else {
+                        let range = convert.ignore();
+                        convert.update(range);
+                        ignored.push(range);
+                    }
+                }
+                result = Some(Pattern::Object(convert.source(start).unwrap(), key_values));
+            } else if let Ok((range, val)) = convert.meta_f64("num") {
+                convert.update(range);
+                result = Some(Pattern::Value(convert.source(start).unwrap(), Variable::f64(val)));
+            } else if let Ok((range, val)) = convert.meta_string("text") {
+                convert.update(range);
+                result = Some(Pattern::Value(convert.source(start).unwrap(), Variable::Str(val)));
+            } else if let Ok((range, val)) = convert.meta_bool("bool") {
+                convert.update(range);
+                result = Some(Pattern::Value(convert.source(start).unwrap(), Variable::bool(val)));
+            } else if let Ok((range, tag)) = convert.meta_string("tag") {
+                convert.update(range);
+                let (range, val) = Pattern::from_meta_data("pat_variant", convert, ignored)?;
+                convert.update(range);
+                result = Some(Pattern::Variant(
+                    convert.source(start).unwrap(),
+                    tag,
+                    Box::new(val),
+                ));
+            } else if let Ok((range, val)) = convert.meta_string("bind") {
+                convert.update(range);
+                let source_range = convert.source(start).unwrap();
+                result = Some(if &**val == "_" {
+                    Pattern::Wildcard(source_range)
+                } else {
+                    Pattern::Bind(source_range, val)
+                });
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -4436,80 +6307,57 @@ impl Continue {
             }
         }
 
-        Ok((
-            convert.subtract(start),
-            Continue {
-                label,
-                source_range: convert.source(start).unwrap(),
-            },
-        ))
+        let result = result.ok_or(())?;
+        Ok((convert.subtract(start), result))
     }
-}
-
-/// If-expression.
-#[derive(Debug, Clone)]
-pub struct If {
-    /// Condition.
-    pub cond: Expression,
-    /// True block.
-    pub true_block: Block,
-    /// Else-if conditions.
-    pub else_if_conds: Vec<Expression>,
-    /// Else-if blocks.
-    pub else_if_blocks: Vec<Block>,
-    /// Else block.
-    pub else_block: Option<Block>,
-    /// The range in source.
-    pub source_range: Range,
-}
 
-impl If {
-    /// Creates if-expression from meta data.
-    pub fn from_meta_data(
-        file: &Arc<String>,
-        source: &Arc<String>,
+    fn items_from_meta_data(
+        node: &str,
         mut convert: Convert,
         ignored: &mut Vec<Range>,
-    ) -> Result<(Range, If), ()> {
+    ) -> Result<(Range, Vec<Pattern>), ()> {
         let start = convert;
-        let node = "if";
         let start_range = convert.start_node(node)?;
         convert.update(start_range);
 
-        let mut cond: Option<Expression> = None;
-        let mut true_block: Option<Block> = None;
-        let mut else_if_conds: Vec<Expression> = vec![];
-        let mut else_if_blocks: Vec<Block> = vec![];
-        let mut else_block: Option<Block> = None;
+        let mut items = vec![];
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "cond", convert, ignored)
-            {
+            } else if let Ok((range, val)) = Pattern::from_meta_data("pat", convert, ignored) {
                 convert.update(range);
-                cond = Some(val);
-            } else if let Ok((range, val)) =
-                Block::from_meta_data(file, source, "true_block", convert, ignored)
-            {
+                items.push(val);
+            } else {
+                let range = convert.ignore();
                 convert.update(range);
-                true_block = Some(val);
-            } else if let Ok((range, val)) =
-                Expression::from_meta_data(file, source, "else_if_cond", convert, ignored)
-            {
+                ignored.push(range);
+            }
+        }
+        Ok((convert.subtract(start), items))
+    }
+
+    fn key_from_meta_data(
+        mut convert: Convert,
+        ignored: &mut Vec<Range>,
+    ) -> Result<(Range, (Arc<String>, Pattern)), ()> {
+        let start = convert;
+        let node = "pat_key";
+        let start_range = convert.start_node(node)?;
+        convert.update(start_range);
+
+        let mut key: Option<Arc<String>> = None;
+        let mut pattern: Option<Pattern> = None;
+        loop {
+            if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
-                else_if_conds.push(val);
-            } else if let Ok((range, val)) =
-                Block::from_meta_data(file, source, "else_if_block", convert, ignored)
-            {
+                break;
+            } else if let Ok((range, val)) = convert.meta_string("key") {
                 convert.update(range);
-                else_if_blocks.push(val);
-            } else if let Ok((range, val)) =
-                Block::from_meta_data(file, source, "else_block", convert, ignored)
-            {
+                key = Some(val);
+            } else if let Ok((range, val)) = Pattern::from_meta_data("pat", convert, ignored) {
                 convert.update(range);
-                else_block = Some(val);
+                pattern = Some(val);
             } else {
                 let range = convert.ignore();
                 convert.update(range);
@@ -4517,47 +6365,52 @@ impl If {
             }
         }
 
-        let cond = cond.ok_or(())?;
-        let true_block = true_block.ok_or(())?;
-        Ok((
-            convert.subtract(start),
-            If {
-                cond,
-                true_block,
-                else_if_conds,
-                else_if_blocks,
-                else_block,
-                source_range: convert.source(start).unwrap(),
-            },
-        ))
+        let key = key.ok_or(())?;
+        // `{x}` is short for `{x: x}`.
+        let pattern =
+            pattern.unwrap_or_else(|| Pattern::Bind(convert.source(start).unwrap(), key.clone()));
+        Ok((convert.subtract(start), (key, pattern)))
     }
 
-    fn get_locals(
-        &mut self,
-        relative: usize,
-        stack: &mut Vec<Option<Arc<String>>>,
-        closure_stack: &mut Vec<usize>,
-        module: &Module,
-        use_lookup: &UseLookup,
-    ) {
-        let st = stack.len();
-        self.cond.get_locals(relative, stack, closure_stack, module, use_lookup);
-        stack.truncate(st);
-        self.true_block.get_locals(relative, stack, closure_stack, module, use_lookup);
-        stack.truncate(st);
-        // Does not matter that conditions are looked up before blocks,
-        // since the stack gets truncated anyway.
-        for else_if_cond in &mut self.else_if_conds {
-            else_if_cond.get_locals(relative, stack, closure_stack, module, use_lookup);
-            stack.truncate(st);
-        }
-        for else_if_block in &mut self.else_if_blocks {
-            else_if_block.get_locals(relative, stack, closure_stack, module, use_lookup);
-            stack.truncate(st);
+    /// Gets the range in source.
+    pub fn source_range(&self) -> Range {
+        use self::Pattern::*;
+
+        match *self {
+            Wildcard(range)
+            | Bind(range, _)
+            | Value(range, _)
+            | Some(range, _)
+            | None(range)
+            | Ok(range, _)
+            | Err(range, _)
+            | Array(range, _)
+            | Object(range, _)
+            | Variant(range, _, _)
+            | Vec4(range, _) => range,
         }
-        if let Some(ref mut else_block) = self.else_block {
-            else_block.get_locals(relative, stack, closure_stack, module, use_lookup);
-            stack.truncate(st);
+    }
+
+    /// Pushes bound names, from left to right.
+    pub(crate) fn get_locals(&self, stack: &mut Vec<Option<Arc<String>>>) {
+        use self::Pattern::*;
+
+        match *self {
+            Wildcard(_) | Value(_, _) | None(_) => {}
+            Bind(_, ref name) => stack.push(Option::Some(name.clone())),
+            Some(_, ref pat) | Ok(_, ref pat) | Err(_, ref pat) | Variant(_, _, ref pat) => {
+                pat.get_locals(stack)
+            }
+            Array(_, ref pats) | Vec4(_, ref pats) => {
+                for pat in pats {
+                    pat.get_locals(stack);
+                }
+            }
+            Object(_, ref key_values) => {
+                for (_, pat) in key_values {
+                    pat.get_locals(stack);
+                }
+            }
         }
     }
 }
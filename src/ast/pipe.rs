@@ -0,0 +1,98 @@
+//! Moves the left side of `|>` into the call as first argument.
+//!
+//! For example, `x |> f(a) |> \g` is parsed as `x` followed by two `pipe` nodes.
+//! This is rewritten into `\g(f(x, a))`, where each call is marked with `pipe`,
+//! such that the lifetime checker sees the piped value as first argument
+//! and the AST conversion remembers how to print the calls back.
+//! Method calls must be rewritten first, since they might contain `|>`.
+
+use piston_meta::MetaData;
+use range::Range;
+use std::sync::Arc;
+
+use super::method_call::end_of_node;
+
+lazy_static! {
+    static ref PIPE: Arc<String> = Arc::new("pipe".into());
+    static ref CALL_ARG: Arc<String> = Arc::new("call_arg".into());
+}
+
+/// Returns meta data where `pipe` nodes are rewritten into calls.
+pub(crate) fn desugar(data: &[Range<MetaData>]) -> Vec<Range<MetaData>> {
+    let mut res = Vec::with_capacity(data.len());
+    desugar_into(data, &mut res);
+    res
+}
+
+fn desugar_into(data: &[Range<MetaData>], res: &mut Vec<Range<MetaData>>) {
+    let mut i = 0;
+    while i < data.len() {
+        match data[i].data {
+            MetaData::StartNode(_) => {
+                let end = end_of_node(data, i);
+                let mut inner = vec![];
+                desugar_into(&data[i + 1..end], &mut inner);
+                res.push(data[i].clone());
+                pipe(inner, res);
+                res.push(data[end].clone());
+                i = end + 1;
+            }
+            _ => {
+                res.push(data[i].clone());
+                i += 1;
+            }
+        }
+    }
+}
+
+// Rewrites the content of a node containing `|>`.
+fn pipe(inner: Vec<Range<MetaData>>, res: &mut Vec<Range<MetaData>>) {
+    let is_pipe = |d: &Range<MetaData>| d.data == MetaData::StartNode(PIPE.clone());
+    let first = match inner.iter().position(is_pipe) {
+        Some(k) if k > 0 => k,
+        _ => {
+            res.extend(inner);
+            return;
+        }
+    };
+
+    let start = inner[0].offset;
+    let mut expr = inner[..first].to_vec();
+    let mut j = first;
+    while j < inner.len() && is_pipe(&inner[j]) {
+        let end = end_of_node(&inner, j);
+        let call_start = j + 1;
+        let call_end = end_of_node(&inner, call_start);
+        let call = &inner[call_start + 1..call_end];
+        // The piped value goes before the arguments,
+        // which follow the name of a call or the item of a closure call.
+        let mut k = 0;
+        while k < call.len() {
+            match call[k].data {
+                MetaData::StartNode(ref n) if &***n == "item" => {
+                    k = end_of_node(call, k) + 1;
+                    break;
+                }
+                MetaData::StartNode(_) => break,
+                _ => k += 1,
+            }
+        }
+
+        let pipe_range = inner[j].range();
+        let expr_end = expr.iter().map(|d| d.next_offset()).max().unwrap_or(start);
+        let mut piped = vec![inner[call_start].clone()];
+        piped.extend_from_slice(&call[..k]);
+        piped.push(pipe_range.wrap(MetaData::Bool(PIPE.clone(), true)));
+        piped.push(Range::empty(start).wrap(MetaData::StartNode(CALL_ARG.clone())));
+        piped.append(&mut expr);
+        piped.push(Range::new(start, expr_end - start).wrap(MetaData::EndNode(CALL_ARG.clone())));
+        piped.extend_from_slice(&call[k..]);
+        piped.push(inner[call_end].clone());
+        // Values such as `?` apply to the result of the call.
+        piped.extend_from_slice(&inner[call_end + 1..end]);
+        expr = piped;
+        j = end + 1;
+    }
+    res.append(&mut expr);
+    res.extend_from_slice(&inner[j..]);
+}
@@ -1,11 +1,12 @@
 use std::sync::Arc;
 
 use super::{
-    Array, ArrayFill, Assign, Block, Call, CallClosure, Expression, For, ForN, Grab,
-    Id, If, Item, Link, Mat4, Object, Swizzle, TryExpr, Vec4,
+    Array, ArrayFill, Assign, Block, Call, CallClosure, Defer, Destructure, Expression, For, ForIn,
+    ForN, Grab, Id, If, Interpolation, Item, Link, Map, Mat4, Match, MatchArm, Object, Set,
+    Swizzle, TryCatch, TryExpr, Variant, Vec4,
 };
 #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
-use super::{ForIn, Go};
+use super::{Go, Yield};
 use crate::Variable;
 
 /// Replaces an item with a number.
@@ -46,11 +47,23 @@ pub fn number(expr: &Expression, name: &Arc<String>, val: f64) -> Expression {
                     try_flag: item.try_flag,
                     ids: new_ids,
                     try_ids: item.try_ids.clone(),
+                    opt_ids: item.opt_ids.clone(),
+                    constant: item.constant.clone(),
                     source_range: item.source_range,
                 }))
             }
         }
         E::Block(ref block) => E::Block(Box::new(number_block(block, name, val))),
+        E::Defer(ref defer) => E::Defer(Box::new(Defer {
+            block: number_block(&defer.block, name, val),
+            source_range: defer.source_range,
+        })),
+        E::TryCatch(ref try_catch) => E::TryCatch(Box::new(TryCatch {
+            block: number_block(&try_catch.block, name, val),
+            name: try_catch.name.clone(),
+            catch_block: number_block(&try_catch.catch_block, name, val),
+            source_range: try_catch.source_range,
+        })),
         E::Assign(ref assign_expr) => E::Assign(Box::new(Assign {
             op: assign_expr.op,
             left: number(&assign_expr.left, name, val),
@@ -67,6 +80,19 @@ pub fn number(expr: &Expression, name: &Arc<String>, val: f64) -> Expression {
                 source_range: obj_expr.source_range,
             }))
         }
+        E::Map(ref map_expr) => {
+            let mut new_key_values: Vec<(Expression, Expression)> = vec![];
+            for key_value in &map_expr.key_values {
+                new_key_values.push((
+                    number(&key_value.0, name, val),
+                    number(&key_value.1, name, val),
+                ));
+            }
+            E::Map(Box::new(Map {
+                key_values: new_key_values,
+                source_range: map_expr.source_range,
+            }))
+        }
         E::Call(ref call_expr) => E::Call(Box::new(number_call(call_expr, name, val))),
         E::CallVoid(_) => unimplemented!("`CallVoid` is transformed from `Call` later"),
         E::CallReturn(_) => unimplemented!("`CallVoid` is transformed from `Call` later"),
@@ -84,6 +110,16 @@ pub fn number(expr: &Expression, name: &Arc<String>, val: f64) -> Expression {
                 source_range: array_expr.source_range,
             }))
         }
+        E::Set(ref set_expr) => {
+            let mut new_items: Vec<Expression> = vec![];
+            for item in &set_expr.items {
+                new_items.push(number(item, name, val));
+            }
+            E::Set(Box::new(Set {
+                items: new_items,
+                source_range: set_expr.source_range,
+            }))
+        }
         E::ArrayFill(ref array_fill_expr) => E::ArrayFill(Box::new(ArrayFill {
             fill: number(&array_fill_expr.fill, name, val),
             n: number(&array_fill_expr.n, name, val),
@@ -100,6 +136,13 @@ pub fn number(expr: &Expression, name: &Arc<String>, val: f64) -> Expression {
         })),
         #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
         E::Go(ref go) => match **go {},
+        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+        E::Yield(ref y) => E::Yield(Box::new(Yield {
+            expr: number(&y.expr, name, val),
+            source_range: y.source_range,
+        })),
+        #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+        E::Yield(ref y) => match **y {},
         E::Vec4(ref vec4_expr) => {
             let mut new_args: Vec<Expression> = vec![];
             for arg in &vec4_expr.args {
@@ -155,84 +198,74 @@ pub fn number(expr: &Expression, name: &Arc<String>, val: f64) -> Expression {
                 }))
             }
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::ForIn(ref for_in_expr) => E::ForIn(Box::new(ForIn {
             label: for_in_expr.label.clone(),
             name: for_in_expr.name.clone(),
+            value: for_in_expr.value.clone(),
             iter: number(&for_in_expr.iter, name, val),
             block: number_block(&for_in_expr.block, name, val),
             source_range: for_in_expr.source_range,
         })),
-        #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-        E::ForIn(ref for_in_expr) |
-        E::SumIn(ref for_in_expr) |
-        E::ProdIn(ref for_in_expr) |
-        E::MinIn(ref for_in_expr) |
-        E::MaxIn(ref for_in_expr) |
-        E::SiftIn(ref for_in_expr) |
-        E::AnyIn(ref for_in_expr) |
-        E::AllIn(ref for_in_expr) |
-        E::LinkIn(ref for_in_expr) => match **for_in_expr {},
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::SumIn(ref for_in_expr) => E::SumIn(Box::new(ForIn {
             label: for_in_expr.label.clone(),
             name: for_in_expr.name.clone(),
+            value: for_in_expr.value.clone(),
             iter: number(&for_in_expr.iter, name, val),
             block: number_block(&for_in_expr.block, name, val),
             source_range: for_in_expr.source_range,
         })),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::ProdIn(ref for_in_expr) => E::ProdIn(Box::new(ForIn {
             label: for_in_expr.label.clone(),
             name: for_in_expr.name.clone(),
+            value: for_in_expr.value.clone(),
             iter: number(&for_in_expr.iter, name, val),
             block: number_block(&for_in_expr.block, name, val),
             source_range: for_in_expr.source_range,
         })),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::MinIn(ref for_in_expr) => E::MinIn(Box::new(ForIn {
             label: for_in_expr.label.clone(),
             name: for_in_expr.name.clone(),
+            value: for_in_expr.value.clone(),
             iter: number(&for_in_expr.iter, name, val),
             block: number_block(&for_in_expr.block, name, val),
             source_range: for_in_expr.source_range,
         })),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::MaxIn(ref for_in_expr) => E::MaxIn(Box::new(ForIn {
             label: for_in_expr.label.clone(),
             name: for_in_expr.name.clone(),
+            value: for_in_expr.value.clone(),
             iter: number(&for_in_expr.iter, name, val),
             block: number_block(&for_in_expr.block, name, val),
             source_range: for_in_expr.source_range,
         })),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::AnyIn(ref for_in_expr) => E::AnyIn(Box::new(ForIn {
             label: for_in_expr.label.clone(),
             name: for_in_expr.name.clone(),
+            value: for_in_expr.value.clone(),
             iter: number(&for_in_expr.iter, name, val),
             block: number_block(&for_in_expr.block, name, val),
             source_range: for_in_expr.source_range,
         })),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::AllIn(ref for_in_expr) => E::AllIn(Box::new(ForIn {
             label: for_in_expr.label.clone(),
             name: for_in_expr.name.clone(),
+            value: for_in_expr.value.clone(),
             iter: number(&for_in_expr.iter, name, val),
             block: number_block(&for_in_expr.block, name, val),
             source_range: for_in_expr.source_range,
         })),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::SiftIn(ref for_in_expr) => E::SiftIn(Box::new(ForIn {
             label: for_in_expr.label.clone(),
             name: for_in_expr.name.clone(),
+            value: for_in_expr.value.clone(),
             iter: number(&for_in_expr.iter, name, val),
             block: number_block(&for_in_expr.block, name, val),
             source_range: for_in_expr.source_range,
         })),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::LinkIn(ref for_in_expr) => E::LinkIn(Box::new(ForIn {
             label: for_in_expr.label.clone(),
             name: for_in_expr.name.clone(),
+            value: for_in_expr.value.clone(),
             iter: number(&for_in_expr.iter, name, val),
             block: number_block(&for_in_expr.block, name, val),
             source_range: for_in_expr.source_range,
@@ -293,6 +326,50 @@ pub fn number(expr: &Expression, name: &Arc<String>, val: f64) -> Expression {
             source_range: try_expr.source_range,
         })),
         E::In(_) => expr.clone(),
+        E::Match(ref match_expr) => {
+            let mut new_arms: Vec<MatchArm> = vec![];
+            for arm in &match_expr.arms {
+                let mut binds = vec![];
+                arm.pattern.get_locals(&mut binds);
+                // The pattern shadows the name inside the arm.
+                let expr = if binds.iter().any(|n| n.as_ref() == Some(name)) {
+                    arm.expr.clone()
+                } else {
+                    number(&arm.expr, name, val)
+                };
+                new_arms.push(MatchArm {
+                    pattern: arm.pattern.clone(),
+                    expr,
+                    source_range: arm.source_range,
+                });
+            }
+            E::Match(Box::new(Match {
+                expr: number(&match_expr.expr, name, val),
+                arms: new_arms,
+                source_range: match_expr.source_range,
+            }))
+        }
+        E::Variant(ref variant) => E::Variant(Box::new(Variant {
+            name: variant.name.clone(),
+            expr: number(&variant.expr, name, val),
+            source_range: variant.source_range,
+        })),
+        E::Destructure(ref destructure) => E::Destructure(Box::new(Destructure {
+            pattern: destructure.pattern.clone(),
+            right: number(&destructure.right, name, val),
+            source_range: destructure.source_range,
+        })),
+        E::Interpolation(ref interp) => {
+            let mut new_exprs: Vec<Expression> = vec![];
+            for expr in &interp.exprs {
+                new_exprs.push(number(expr, name, val));
+            }
+            E::Interpolation(Box::new(Interpolation {
+                texts: interp.texts.clone(),
+                exprs: new_exprs,
+                source_range: interp.source_range,
+            }))
+        }
     }
 }
 
@@ -317,6 +394,7 @@ fn number_call_closure(call_expr: &CallClosure, name: &Arc<String>, val: f64) ->
     CallClosure {
         item: call_expr.item.clone(),
         args: new_args,
+        pipe: call_expr.pipe,
         source_range: call_expr.source_range,
     }
 }
@@ -342,6 +420,19 @@ fn number_block(block: &Block, name: &Arc<String>, val: f64) -> Block {
                         continue;
                     }
                 }
+            } else if let Expression::Destructure(ref destructure) = *expr {
+                // Check for declaration of same name.
+                let mut binds = vec![];
+                destructure.pattern.get_locals(&mut binds);
+                if binds.iter().any(|n| n.as_ref() == Some(name)) {
+                    new_expressions.push(Expression::Destructure(Box::new(Destructure {
+                        pattern: destructure.pattern.clone(),
+                        right: number(&destructure.right, name, val),
+                        source_range: destructure.source_range,
+                    })));
+                    just_clone = true;
+                    continue;
+                }
             }
             new_expressions.push(number(expr, name, val));
         }
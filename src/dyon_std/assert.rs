@@ -0,0 +1,60 @@
+//! Assertion functions.
+//!
+//! Asserts are skipped when the module strips them, see `Module::strip_asserts`.
+
+use super::equal;
+use crate::write::{write_variable, EscapeString};
+use crate::{Runtime, Variable, TINVOTS};
+
+// Writes a value for the error message.
+fn fmt(rt: &Runtime, v: &Variable) -> String {
+    let mut buf: Vec<u8> = vec![];
+    write_variable(&mut buf, rt, v, EscapeString::Json, 0).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+// Writes the derived information of a secret, e.g. from `all` or `min` loops.
+fn evidence(rt: &Runtime, label: &str, sec: &[Variable]) -> String {
+    let mut sec = sec.to_vec();
+    sec.reverse();
+    format!("\n{}: {}", label, fmt(rt, &Variable::Array(sec.into())))
+}
+
+pub(crate) fn assert(rt: &mut Runtime) -> Result<(), String> {
+    let v = rt.stack.pop().expect(TINVOTS);
+    match *rt.get(&v) {
+        Variable::Bool(true, _) => Ok(()),
+        Variable::Bool(false, ref sec) => {
+            let mut msg = format!("{}\nAssertion failed", rt.stack_trace());
+            if let Some(ref sec) = *sec {
+                msg.push_str(&evidence(rt, "why not", sec));
+            }
+            Err(msg)
+        }
+        ref x => Err(rt.expected_arg(0, x, "bool")),
+    }
+}
+
+pub(crate) fn assert_eq(rt: &mut Runtime) -> Result<(), String> {
+    let b = rt.stack.pop().expect(TINVOTS);
+    let a = rt.stack.pop().expect(TINVOTS);
+    let (a, b) = (rt.get(&a), rt.get(&b));
+    if let Ok(Variable::Bool(true, _)) = equal(a, b) {
+        return Ok(());
+    }
+    let mut msg = format!(
+        "{}\nAssertion failed\nleft: {}\nright: {}",
+        rt.stack_trace(),
+        fmt(rt, a),
+        fmt(rt, b)
+    );
+    for v in [a, b] {
+        match *v {
+            Variable::Bool(true, Some(ref sec)) => msg.push_str(&evidence(rt, "why", sec)),
+            Variable::Bool(false, Some(ref sec)) => msg.push_str(&evidence(rt, "why not", sec)),
+            Variable::F64(_, Some(ref sec)) => msg.push_str(&evidence(rt, "where", sec)),
+            _ => {}
+        }
+    }
+    Err(msg)
+}
@@ -0,0 +1,169 @@
+//! Byte buffer functions.
+//!
+//! Integers are read and written as `i64` and floats as `f64`.
+//! Functions ending with `_le` use little-endian byte order,
+//! and functions ending with `_be` use big-endian byte order.
+
+use std::sync::Arc;
+
+use crate::{Runtime, Variable, TINVOTS};
+
+pub(crate) fn bytes(rt: &mut Runtime) -> Result<Variable, String> {
+    let v = rt.stack.pop().expect(TINVOTS);
+    let arr = match *rt.get(&v) {
+        Variable::Array(ref arr) => arr.clone(),
+        ref x => return Err(rt.expected_arg(0, x, "[f64]")),
+    };
+    let mut res = Vec::with_capacity(arr.len());
+    for it in &*arr {
+        match *rt.get(it) {
+            Variable::F64(x, _) if (0.0..=255.0).contains(&x) && x.fract() == 0.0 => {
+                res.push(x as u8)
+            }
+            _ => {
+                return Err({
+                    rt.arg_err_index.set(Some(0));
+                    "Expected integers from `0` to `255`".into()
+                })
+            }
+        }
+    }
+    Ok(Variable::Bytes(Arc::new(res)))
+}
+
+// Gets a position or length in bytes.
+fn index(rt: &Runtime, arg: usize, v: &Variable) -> Result<usize, String> {
+    match *rt.get(v) {
+        Variable::F64(x, _) if x >= 0.0 && x.fract() == 0.0 && x < usize::MAX as f64 => {
+            Ok(x as usize)
+        }
+        Variable::F64(x, _) if x >= 0.0 && x.fract() == 0.0 => Err({
+            rt.arg_err_index.set(Some(arg));
+            format!("Integer `{}` is too large", x)
+        }),
+        Variable::F64(x, _) => Err({
+            rt.arg_err_index.set(Some(arg));
+            format!("Expected non-negative integer, found `{}`", x)
+        }),
+        ref x => Err(rt.expected_arg(arg, x, "f64")),
+    }
+}
+
+// Reads `N` bytes at position.
+fn read<const N: usize>(rt: &mut Runtime) -> Result<[u8; N], String> {
+    let pos = rt.stack.pop().expect(TINVOTS);
+    let v = rt.stack.pop().expect(TINVOTS);
+    let pos = index(rt, 1, &pos)?;
+    match *rt.get(&v) {
+        Variable::Bytes(ref bytes) => match bytes.get(pos..).and_then(|x| x.get(..N)) {
+            Some(x) => Ok(x.try_into().unwrap()),
+            None => Err({
+                rt.arg_err_index.set(Some(1));
+                format!(
+                    "Reading `{}` bytes at `{}` is out of bounds for length `{}`",
+                    N,
+                    pos,
+                    bytes.len()
+                )
+            }),
+        },
+        ref x => Err(rt.expected_arg(0, x, "bytes")),
+    }
+}
+
+// Writes `N` bytes at position, growing the buffer when writing past the end.
+//
+// The position can not be after the end, so bytes grow at most by `N`.
+fn write<const N: usize>(
+    rt: &mut Runtime,
+    f: impl FnOnce(&Variable) -> Result<[u8; N], String>,
+) -> Result<(), String> {
+    let x = rt.stack.pop().expect(TINVOTS);
+    let pos = rt.stack.pop().expect(TINVOTS);
+    let v = rt.stack.pop().expect(TINVOTS);
+    let pos = index(rt, 1, &pos)?;
+    let x = f(rt.get(&x)).inspect_err(|_| rt.arg_err_index.set(Some(2)))?;
+    let bytes = match v {
+        Variable::Ref(ind) => match rt.stack[ind] {
+            Variable::Bytes(ref mut bytes) => Arc::make_mut(bytes),
+            _ => return Err(rt.expected_arg(0, &rt.stack[ind], "bytes")),
+        },
+        _ => {
+            return Err({
+                rt.arg_err_index.set(Some(0));
+                "Expected reference to bytes".into()
+            })
+        }
+    };
+    if pos > bytes.len() {
+        return Err({
+            rt.arg_err_index.set(Some(1));
+            format!(
+                "Writing `{}` bytes at `{}` is out of bounds for length `{}`",
+                N,
+                pos,
+                bytes.len()
+            )
+        });
+    }
+    if bytes.len() < pos + N {
+        bytes.resize(pos + N, 0);
+    }
+    bytes[pos..pos + N].copy_from_slice(&x);
+    Ok(())
+}
+
+macro_rules! int {
+    ($read:ident, $write:ident, $ty:ident, $from:ident, $to:ident) => {
+        pub(crate) fn $read(rt: &mut Runtime) -> Result<Variable, String> {
+            let x = $ty::$from(read(rt)?);
+            Ok(Variable::I64(i64::try_from(x).map_err(|_| {
+                rt.arg_err_index.set(Some(1));
+                format!("Value `{}` does not fit in `i64`", x)
+            })?))
+        }
+
+        pub(crate) fn $write(rt: &mut Runtime) -> Result<(), String> {
+            write(rt, |x| match *x {
+                Variable::I64(x) => $ty::try_from(x).map(|x| x.$to()).map_err(|_| {
+                    format!("Value `{}` does not fit in `{}`", x, stringify!($ty))
+                }),
+                _ => Err(format!("Expected `i64`, found `{}`", x.typeof_var())),
+            })
+        }
+    };
+}
+
+macro_rules! float {
+    ($read:ident, $write:ident, $ty:ident, $from:ident, $to:ident) => {
+        pub(crate) fn $read(rt: &mut Runtime) -> Result<Variable, String> {
+            Ok(Variable::f64($ty::$from(read(rt)?).into()))
+        }
+
+        pub(crate) fn $write(rt: &mut Runtime) -> Result<(), String> {
+            write(rt, |x| match *x {
+                Variable::F64(x, _) => Ok((x as $ty).$to()),
+                _ => Err(format!("Expected `f64`, found `{}`", x.typeof_var())),
+            })
+        }
+    };
+}
+
+int!(read_u8, write_u8, u8, from_le_bytes, to_le_bytes);
+int!(read_i8, write_i8, i8, from_le_bytes, to_le_bytes);
+int!(read_u16_le, write_u16_le, u16, from_le_bytes, to_le_bytes);
+int!(read_u16_be, write_u16_be, u16, from_be_bytes, to_be_bytes);
+int!(read_i16_le, write_i16_le, i16, from_le_bytes, to_le_bytes);
+int!(read_i16_be, write_i16_be, i16, from_be_bytes, to_be_bytes);
+int!(read_u32_le, write_u32_le, u32, from_le_bytes, to_le_bytes);
+int!(read_u32_be, write_u32_be, u32, from_be_bytes, to_be_bytes);
+int!(read_i32_le, write_i32_le, i32, from_le_bytes, to_le_bytes);
+int!(read_i32_be, write_i32_be, i32, from_be_bytes, to_be_bytes);
+int!(read_u64_le, write_u64_le, u64, from_le_bytes, to_le_bytes);
+int!(read_u64_be, write_u64_be, u64, from_be_bytes, to_be_bytes);
+int!(read_i64_le, write_i64_le, i64, from_le_bytes, to_le_bytes);
+int!(read_i64_be, write_i64_be, i64, from_be_bytes, to_be_bytes);
+float!(read_f32_le, write_f32_le, f32, from_le_bytes, to_le_bytes);
+float!(read_f32_be, write_f32_be, f32, from_be_bytes, to_be_bytes);
+float!(read_f64_le, write_f64_le, f64, from_le_bytes, to_le_bytes);
+float!(read_f64_be, write_f64_be, f64, from_be_bytes, to_be_bytes);
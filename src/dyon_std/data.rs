@@ -84,6 +84,11 @@ fn expr(read: &mut ReadToken, strings: &mut Strings, data: &str) -> Result<Varia
             ));
         }
     }
+    if let Some(range) = read.tag("set") {
+        // Set.
+        *read = read.consume(range.length);
+        return set(read, strings, data);
+    }
     if let Some(range) = read.tag("link") {
         // Link.
         *read = read.consume(range.length);
@@ -108,6 +113,17 @@ fn expr(read: &mut ReadToken, strings: &mut Strings, data: &str) -> Result<Varia
     }
     // Number.
     if let Some(range) = read.number(&NUMBER_SETTINGS) {
+        // Integer, e.g. `42i`.
+        if read.consume(range.length).tag("i").is_some() {
+            let text = read.raw_string(range.length).replace('_', "");
+            return match text.parse::<i64>() {
+                Ok(val) => {
+                    *read = read.consume(range.length + 1);
+                    Ok(Variable::I64(val))
+                }
+                Err(err) => Err(error(range, &format!("{}", err), data)),
+            };
+        }
         match read.parse_number(&NUMBER_SETTINGS, range.length) {
             Ok(val) => {
                 *read = read.consume(range.length);
@@ -233,6 +249,39 @@ fn array(read: &mut ReadToken, strings: &mut Strings, data: &str) -> Result<Vari
     Ok(Variable::Array(Arc::new(res)))
 }
 
+fn set(read: &mut ReadToken, strings: &mut Strings, data: &str) -> Result<Variable, String> {
+    use crate::Key;
+
+    opt_w(read);
+
+    if let Some(range) = read.tag("{") {
+        *read = read.consume(range.length);
+    } else {
+        return Err(error(read.start(), "Expected `{`", data));
+    }
+
+    let mut res = HashSet::new();
+    let mut was_comma = false;
+    loop {
+        opt_w(read);
+
+        if let Some(range) = read.tag("}") {
+            *read = read.consume(range.length);
+            break;
+        }
+
+        if !res.is_empty() && !was_comma {
+            return Err(error(read.start(), "Expected `,`", data));
+        }
+
+        let start = read.start();
+        let v = expr(read, strings, data)?;
+        res.insert(Key::new(&v, &[]).map_err(|err| error(start, &err, data))?);
+        was_comma = comma(read);
+    }
+    Ok(Variable::Set(Arc::new(res)))
+}
+
 fn link(read: &mut ReadToken, strings: &mut Strings, data: &str) -> Result<Variable, String> {
     use crate::Link;
 
@@ -13,6 +13,7 @@ pub fn list_functions(module: &Module) -> Vec<Variable> {
     let lifetime: Arc<String> = Arc::new("lifetime".into());
     let ret_lifetime: Arc<String> = Arc::new("return".into());
     let ty: Arc<String> = Arc::new("type".into());
+    let default: Arc<String> = Arc::new("default".into());
     let external: Arc<String> = Arc::new("external".into());
     let loaded: Arc<String> = Arc::new("loaded".into());
     for f in &*module.ext_prelude {
@@ -43,6 +44,7 @@ pub fn list_functions(module: &Module) -> Vec<Variable> {
                 takes.clone(),
                 Variable::Str(Arc::new(f.p.tys[i].description())),
             );
+            obj_arg.insert(default.clone(), Variable::Option(None));
             args.push(Variable::Object(Arc::new(obj_arg)));
         }
         obj.insert(arguments.clone(), Variable::Array(Arc::new(args)));
@@ -68,6 +70,10 @@ pub fn list_functions(module: &Module) -> Vec<Variable> {
                 },
             );
             obj_arg.insert(takes.clone(), Variable::Str(Arc::new(arg.ty.description())));
+            obj_arg.insert(
+                default.clone(),
+                Variable::Option(arg.default.as_ref().map(|val| Box::new(val.clone()))),
+            );
             args.push(Variable::Object(Arc::new(obj_arg)));
         }
         obj.insert(arguments.clone(), Variable::Array(Arc::new(args)));
@@ -83,3 +89,27 @@ pub fn list_functions(module: &Module) -> Vec<Variable> {
     });
     functions
 }
+
+/// Lists all constants available in a module.
+pub fn list_constants(module: &Module) -> Vec<Variable> {
+    let mut constants = vec![];
+    let name: Arc<String> = Arc::new("name".into());
+    let ty: Arc<String> = Arc::new("type".into());
+    let value: Arc<String> = Arc::new("value".into());
+    for c in &module.constants {
+        let mut obj = HashMap::new();
+        obj.insert(name.clone(), Variable::Str(c.name.clone()));
+        obj.insert(ty.clone(), Variable::Str(Arc::new(c.ty.description())));
+        obj.insert(value.clone(), c.value.clone());
+        constants.push(Variable::Object(obj.into()));
+    }
+    // Sort by constant names.
+    constants.sort_by(|a, b| match (a, b) {
+        (Variable::Object(a), Variable::Object(b)) => match (&a[&name], &b[&name]) {
+            (Variable::Str(a), Variable::Str(b)) => a.cmp(b),
+            _ => panic!("Expected two strings"),
+        },
+        _ => panic!("Expected two objects"),
+    });
+    constants
+}
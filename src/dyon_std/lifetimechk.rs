@@ -64,14 +64,19 @@ fn min_ref(v: &Variable, min: &mut Option<usize>) {
         Return => {}
         Bool(_, _) => {}
         F64(_, _) => {}
+        I64(_) => {}
         Vec4(_) => {}
         Mat4(_) => {}
         Str(_) => {}
         Link(_) => {}
+        Bytes(_) => {}
+        Range(_, _) => {}
+        Set(_) => {}
         UnsafeRef(_) => {}
         RustObject(_) => {}
         Option(_) => {}
         Result(_) => {}
+        Variant(_, _) => {}
         #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         Thread(_) => {}
         Array(ref arr) => {
@@ -84,8 +89,15 @@ fn min_ref(v: &Variable, min: &mut Option<usize>) {
                 min_ref(v, min);
             }
         }
+        Map(ref map) => {
+            for v in map.values() {
+                min_ref(v, min);
+            }
+        }
         Closure(_, _) => {}
         #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         In(_) => {}
+        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+        Gen(_) => {}
     }
 }
@@ -2,17 +2,28 @@
 
 use crate::*;
 
+pub(crate) use self::assert::*;
+pub(crate) use self::bytes::*;
+pub(crate) use self::range::{range, reverse_range, slice, slice_of};
+pub(crate) use self::set::*;
+
+mod assert;
+mod bytes;
 mod data;
 mod functions;
 #[cfg(feature = "file")]
 mod io;
 mod lifetimechk;
 mod meta;
+mod range;
+mod set;
 
 #[cfg(not(all(not(target_family = "wasm"), feature = "http")))]
 const HTTP_SUPPORT_DISABLED: &'static str = "Http support is disabled";
 
 pub(crate) const FILE_SUPPORT_DISABLED: &'static str = "File support is disabled";
+const INTEGER_OVERFLOW: &str = "Integer overflow";
+const DIVISION_BY_ZERO: &str = "Division by zero";
 
 pub(crate) fn and_also(rt: &mut Runtime) -> Result<Variable, String> {
     use Variable::*;
@@ -41,8 +52,9 @@ pub(crate) fn less(a: &Variable, b: &Variable) -> Result<Variable, String> {
 
     Ok(match (a, b) {
         (&F64(a, ref sec), &F64(b, _)) => Bool(a < b, sec.clone()),
+        (&I64(a), &I64(b)) => Variable::bool(a < b),
         (&Str(ref a), &Str(ref b)) => Variable::bool(a < b),
-        _ => return Err("Expected `f64` or `str`".into()),
+        _ => return Err("Expected `f64`, `i64` or `str`".into()),
     })
 }
 
@@ -51,8 +63,9 @@ pub(crate) fn less_or_equal(a: &Variable, b: &Variable) -> Result<Variable, Stri
 
     Ok(match (a, b) {
         (&F64(a, ref sec), &F64(b, _)) => Bool(a <= b, sec.clone()),
+        (&I64(a), &I64(b)) => Variable::bool(a <= b),
         (&Str(ref a), &Str(ref b)) => Variable::bool(a <= b),
-        _ => return Err("Expected `f64` or `str`".into()),
+        _ => return Err("Expected `f64`, `i64` or `str`".into()),
     })
 }
 
@@ -81,9 +94,12 @@ pub(crate) fn equal(a: &Variable, b: &Variable) -> Result<Variable, String> {
 
     Ok(match (a, b) {
         (&F64(a, ref sec), &F64(b, _)) => Bool(a == b, sec.clone()),
+        (&I64(a), &I64(b)) => Variable::bool(a == b),
         (&Str(ref a), &Str(ref b)) => Variable::bool(a == b),
         (&Bool(a, ref sec), &Bool(b, _)) => Bool(a == b, sec.clone()),
         (&Vec4(a), &Vec4(b)) => Variable::bool(a == b),
+        (Bytes(a), Bytes(b)) => Variable::bool(a == b),
+        (Range(a, b), Range(c, d)) => Variable::bool(a == c && b == d),
         (&Object(ref a), &Object(ref b)) => Variable::bool(
             a.len() == b.len()
                 && a.iter().all(|a| {
@@ -94,6 +110,17 @@ pub(crate) fn equal(a: &Variable, b: &Variable) -> Result<Variable, String> {
                     }
                 }),
         ),
+        (Map(a), Map(b)) => Variable::bool(
+            a.len() == b.len()
+                && a.iter().all(|a| {
+                    if let Some(b_val) = b.get(a.0) {
+                        matches!(equal(a.1, b_val), Ok(Variable::Bool(true, _)))
+                    } else {
+                        false
+                    }
+                }),
+        ),
+        (Set(a), Set(b)) => Variable::bool(a == b),
         (&Array(ref a), &Array(ref b)) => Variable::bool(
             a.len() == b.len()
                 && a.iter()
@@ -104,7 +131,15 @@ pub(crate) fn equal(a: &Variable, b: &Variable) -> Result<Variable, String> {
         (&Option(None), &Option(_)) => Variable::bool(false),
         (&Option(_), &Option(None)) => Variable::bool(false),
         (&Option(Some(ref a)), &Option(Some(ref b))) => equal(a, b)?,
-        _ => return Err("Expected `f64`, `str`, `bool`, `vec4`, `{}`, `[]` or `opt`".into()),
+        (Variant(a_tag, a), Variant(b_tag, b)) => match (a, b) {
+            (Some(a), Some(b)) if a_tag == b_tag => equal(a, b)?,
+            _ => Variable::bool(a_tag == b_tag && a.is_none() && b.is_none()),
+        },
+        _ => {
+            return Err("Expected `f64`, `i64`, `str`, `bool`, `vec4`, `bytes`, `range`, \
+                `{}`, `map`, `set`, `[]`, `opt` or enum"
+                .into())
+        }
     })
 }
 
@@ -123,6 +158,7 @@ pub(crate) fn add(a: &Variable, b: &Variable) -> Result<Variable, String> {
 
     Ok(match (a, b) {
         (&F64(a, ref sec), &F64(b, _)) => F64(a + b, sec.clone()),
+        (&I64(a), &I64(b)) => I64(a.checked_add(b).ok_or(INTEGER_OVERFLOW)?),
         (&Vec4(a), &Vec4(b)) => Vec4(vecmath::vec4_add(a, b)),
         (&Vec4(a), &F64(b, _)) | (&F64(b, _), &Vec4(a)) => {
             let b = b as f32;
@@ -146,7 +182,17 @@ pub(crate) fn add(a: &Variable, b: &Variable) -> Result<Variable, String> {
             Str(Arc::new(res))
         }
         (&Link(ref a), &Link(ref b)) => Link(Box::new(a.add(b))),
-        _ => return Err("Expected `f64`, `vec4`, `mat4`, `bool`, `str` or `link`".into()),
+        (Bytes(a), Bytes(b)) => {
+            let mut res = Vec::with_capacity(a.len() + b.len());
+            res.extend_from_slice(a);
+            res.extend_from_slice(b);
+            Bytes(Arc::new(res))
+        }
+        _ => {
+            return Err(
+                "Expected `f64`, `i64`, `vec4`, `mat4`, `bool`, `str`, `link` or `bytes`".into(),
+            )
+        }
     })
 }
 
@@ -155,6 +201,7 @@ pub(crate) fn sub(a: &Variable, b: &Variable) -> Result<Variable, String> {
 
     Ok(match (a, b) {
         (&F64(a, ref sec), &F64(b, _)) => F64(a - b, sec.clone()),
+        (&I64(a), &I64(b)) => I64(a.checked_sub(b).ok_or(INTEGER_OVERFLOW)?),
         (&Vec4(a), &Vec4(b)) => Vec4(vecmath::vec4_sub(a, b)),
         (&Vec4(a), &F64(b, _)) => {
             let b = b as f32;
@@ -184,7 +231,7 @@ pub(crate) fn sub(a: &Variable, b: &Variable) -> Result<Variable, String> {
             ]))
         }
         (&Bool(a, ref sec), &Bool(b, _)) => Bool(a && !b, sec.clone()),
-        _ => return Err("Expected `f64`, `vec4`, `mat4` or `bool`".into()),
+        _ => return Err("Expected `f64`, `i64`, `vec4`, `mat4` or `bool`".into()),
     })
 }
 
@@ -193,6 +240,7 @@ pub(crate) fn mul(a: &Variable, b: &Variable) -> Result<Variable, String> {
 
     Ok(match (a, b) {
         (&F64(a, ref sec), &F64(b, _)) => F64(a * b, sec.clone()),
+        (&I64(a), &I64(b)) => I64(a.checked_mul(b).ok_or(INTEGER_OVERFLOW)?),
         (&Vec4(a), &Vec4(b)) => Vec4(vecmath::vec4_mul(a, b)),
         (&Vec4(a), &F64(b, _)) | (&F64(b, _), &Vec4(a)) => {
             let b = b as f32;
@@ -210,7 +258,7 @@ pub(crate) fn mul(a: &Variable, b: &Variable) -> Result<Variable, String> {
         }
         (&Mat4(ref a), &Vec4(b)) => Vec4(vecmath::col_mat4_transform(**a, b)),
         (&Bool(a, ref sec), &Bool(b, _)) => Bool(a && b, sec.clone()),
-        _ => return Err("Expected `f64`, `vec4`, `mat4` or `bool`".into()),
+        _ => return Err("Expected `f64`, `i64`, `vec4`, `mat4` or `bool`".into()),
     })
 }
 
@@ -219,6 +267,8 @@ pub(crate) fn div(a: &Variable, b: &Variable) -> Result<Variable, String> {
 
     Ok(match (a, b) {
         (&F64(a, ref sec), &F64(b, _)) => F64(a / b, sec.clone()),
+        (&I64(_), &I64(0)) => return Err(DIVISION_BY_ZERO.into()),
+        (&I64(a), &I64(b)) => I64(a.checked_div(b).ok_or(INTEGER_OVERFLOW)?),
         (&Vec4(a), &Vec4(b)) => Vec4([a[0] / b[0], a[1] / b[1], a[2] / b[2], a[3] / b[3]]),
         (&Vec4(a), &F64(b, _)) => {
             let b = b as f32;
@@ -228,7 +278,7 @@ pub(crate) fn div(a: &Variable, b: &Variable) -> Result<Variable, String> {
             let a = a as f32;
             Vec4([a / b[0], a / b[1], a / b[2], a / b[3]])
         }
-        _ => return Err("Expected `f64` or `vec4`".into()),
+        _ => return Err("Expected `f64`, `i64` or `vec4`".into()),
     })
 }
 
@@ -237,6 +287,8 @@ pub(crate) fn rem(a: &Variable, b: &Variable) -> Result<Variable, String> {
 
     Ok(match (a, b) {
         (&F64(a, ref sec), &F64(b, _)) => F64(a % b, sec.clone()),
+        (&I64(_), &I64(0)) => return Err(DIVISION_BY_ZERO.into()),
+        (&I64(a), &I64(b)) => I64(a.checked_rem(b).ok_or(INTEGER_OVERFLOW)?),
         (&Vec4(a), &Vec4(b)) => Vec4([a[0] % b[0], a[1] % b[1], a[2] % b[2], a[3] % b[3]]),
         (&Vec4(a), &F64(b, _)) => {
             let b = b as f32;
@@ -246,7 +298,7 @@ pub(crate) fn rem(a: &Variable, b: &Variable) -> Result<Variable, String> {
             let a = a as f32;
             Vec4([a % b[0], a % b[1], a % b[2], a % b[3]])
         }
-        _ => return Err("Expected `f64` or `vec4`".into()),
+        _ => return Err("Expected `f64`, `i64` or `vec4`".into()),
     })
 }
 
@@ -255,6 +307,10 @@ pub(crate) fn pow(a: &Variable, b: &Variable) -> Result<Variable, String> {
 
     Ok(match (a, b) {
         (&F64(a, ref sec), &F64(b, _)) => F64(a.powf(b), sec.clone()),
+        (&I64(a), &I64(b)) => {
+            let b = u32::try_from(b).map_err(|_| "Expected non-negative `i64` exponent")?;
+            I64(a.checked_pow(b).ok_or(INTEGER_OVERFLOW)?)
+        }
         (&Vec4(a), &Vec4(b)) => Vec4([
             a[0].powf(b[0]),
             a[1].powf(b[1]),
@@ -270,7 +326,7 @@ pub(crate) fn pow(a: &Variable, b: &Variable) -> Result<Variable, String> {
             Vec4([a.powf(b[0]), a.powf(b[1]), a.powf(b[2]), a.powf(b[3])])
         }
         (&Bool(a, ref sec), &Bool(ref b, _)) => Bool(a ^ b, sec.clone()),
-        _ => return Err("Expected `f64`, `vec4` or `bool`".into()),
+        _ => return Err("Expected `f64`, `i64`, `vec4` or `bool`".into()),
     })
 }
 
@@ -284,6 +340,7 @@ pub(crate) fn not(a: &Variable) -> Result<Variable, String> {
 pub(crate) fn neg(a: &Variable) -> Result<Variable, String> {
     Ok(match *a {
         Variable::F64(v, ref sec) => Variable::F64(-v, sec.clone()),
+        Variable::I64(v) => Variable::I64(v.checked_neg().ok_or(INTEGER_OVERFLOW)?),
         Variable::Vec4(v) => Variable::Vec4([-v[0], -v[1], -v[2], -v[3]]),
         Variable::Mat4(ref m) => Variable::Mat4(Box::new([
             [-m[0][0], -m[0][1], -m[0][2], -m[0][3]],
@@ -291,7 +348,7 @@ pub(crate) fn neg(a: &Variable) -> Result<Variable, String> {
             [-m[2][0], -m[2][1], -m[2][2], -m[2][3]],
             [-m[3][0], -m[3][1], -m[3][2], -m[3][3]],
         ])),
-        _ => return Err("Expected `f64`, `vec4` or `mat4`".into()),
+        _ => return Err("Expected `f64`, `i64`, `vec4` or `mat4`".into()),
     })
 }
 
@@ -577,6 +634,32 @@ dyon_fn! {fn round(a: f64) -> f64 {a.round()}}
 dyon_fn! {fn abs(a: f64) -> f64 {a.abs()}}
 dyon_fn! {fn floor(a: f64) -> f64 {a.floor()}}
 dyon_fn! {fn ceil(a: f64) -> f64 {a.ceil()}}
+
+pub(crate) fn _i64(rt: &mut Runtime) -> Result<Variable, String> {
+    // The limit is `2^63`.
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+
+    let v = rt.stack.pop().expect(TINVOTS);
+    Ok(Variable::I64(match *rt.get(&v) {
+        // Rounds toward zero.
+        Variable::F64(x, _) if (-LIMIT..LIMIT).contains(&x) => x as i64,
+        Variable::F64(x, _) => {
+            return Err({
+                rt.arg_err_index.set(Some(0));
+                format!("Can not convert `{}` to `i64`", x)
+            })
+        }
+        ref x => return Err(rt.expected_arg(0, x, "f64")),
+    }))
+}
+
+pub(crate) fn _f64(rt: &mut Runtime) -> Result<Variable, String> {
+    let v = rt.stack.pop().expect(TINVOTS);
+    Ok(Variable::f64(match *rt.get(&v) {
+        Variable::I64(x) => x as f64,
+        ref x => return Err(rt.expected_arg(0, x, "i64")),
+    }))
+}
 #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
 dyon_fn! {fn sleep(v: f64) {
     use std::thread::sleep;
@@ -640,7 +723,10 @@ dyon_fn! {fn tau() -> f64 {6.283_185_307_179_586}}
 pub(crate) fn len(a: &Variable) -> Result<Variable, String> {
     match a {
         Variable::Array(arr) => Ok(Variable::f64(arr.len() as f64)),
-        _ => Err("Expected array".into()),
+        Variable::Bytes(bytes) => Ok(Variable::f64(bytes.len() as f64)),
+        Variable::Map(map) => Ok(Variable::f64(map.len() as f64)),
+        Variable::Set(set) => Ok(Variable::f64(set.len() as f64)),
+        _ => Err("Expected array, bytes, map or set".into()),
     }
 }
 
@@ -808,11 +894,29 @@ pub(crate) fn pop(rt: &mut Runtime) -> Result<Variable, String> {
 
 pub(crate) fn remove(rt: &mut Runtime) -> Result<Variable, String> {
     let index = rt.stack.pop().expect(TINVOTS);
+    let arr = rt.stack.pop().expect(TINVOTS);
+    match arr {
+        Variable::Ref(ind) if matches!(rt.stack[ind], Variable::Map(_)) => {
+            let key = Key::new(&index, &rt.stack).inspect_err(|_| rt.arg_err_index.set(Some(1)))?;
+            if let Variable::Map(ref mut map) = rt.stack[ind] {
+                return Arc::make_mut(map).remove(&key).ok_or_else(|| {
+                    rt.arg_err_index.set(Some(1));
+                    format!("Map has no key `{}`", key)
+                });
+            }
+        }
+        Variable::Ref(ind) if matches!(rt.stack[ind], Variable::Set(_)) => {
+            let key = Key::new(&index, &rt.stack).inspect_err(|_| rt.arg_err_index.set(Some(1)))?;
+            if let Variable::Set(ref mut set) = rt.stack[ind] {
+                return Ok(Variable::bool(Arc::make_mut(set).remove(&key)));
+            }
+        }
+        _ => {}
+    }
     let index = match rt.get(&index) {
         &Variable::F64(index, _) => index,
         x => return Err(rt.expected_arg(1, x, "number")),
     };
-    let arr = rt.stack.pop().expect(TINVOTS);
     if let Variable::Ref(ind) = arr {
         if let Variable::Array(ref arr) = rt.stack[ind] {
             let index = index as usize;
@@ -980,11 +1084,29 @@ pub(crate) fn json_string(rt: &mut Runtime) -> Result<Variable, String> {
     use write::{write_variable, EscapeString};
 
     let v = rt.stack.pop().expect(TINVOTS);
+    // JSON numbers keep integers exact, so the `i` suffix is left out.
+    if let Variable::I64(x) = *rt.get(&v) {
+        return Ok(Variable::Str(Arc::new(x.to_string())));
+    }
     let mut buf: Vec<u8> = vec![];
     write_variable(&mut buf, rt, rt.get(&v), EscapeString::Json, 0).unwrap();
     Ok(Variable::Str(Arc::new(String::from_utf8(buf).unwrap())))
 }
 
+dyon_fn! {fn bytes__str(text: Arc<String>) -> Arc<Vec<u8>> {
+    Arc::new(text.as_bytes().to_vec())
+}}
+
+dyon_fn! {fn str__bytes(bytes: Arc<Vec<u8>>) -> Variable {
+    Variable::Result(match String::from_utf8(bytes.to_vec()) {
+        Ok(s) => Ok(Box::new(Variable::Str(Arc::new(s)))),
+        Err(err) => Err(Box::new(Error {
+            message: Variable::Str(Arc::new(err.to_string())),
+            trace: vec![]
+        }))
+    })
+}}
+
 dyon_fn! {fn str__color(v: Vec4) -> Arc<String> {
     let v = v.0;
     let mut buf: Vec<u8> = vec![];
@@ -1043,13 +1165,18 @@ pub(crate) fn _typeof(rt: &mut Runtime) -> Result<Variable, String> {
     Ok(Variable::Str(match *rt.get(&v) {
         Str(_) => TEXT_TYPE.clone(),
         F64(_, _) => F64_TYPE.clone(),
+        I64(_) => I64_TYPE.clone(),
         Vec4(_) => VEC4_TYPE.clone(),
         Mat4(_) => MAT4_TYPE.clone(),
         Return => RETURN_TYPE.clone(),
         Bool(_, _) => BOOL_TYPE.clone(),
         Object(_) => OBJECT_TYPE.clone(),
+        Map(_) => MAP_TYPE.clone(),
+        Set(_) => SET_TYPE.clone(),
         Array(_) => ARRAY_TYPE.clone(),
         Link(_) => LINK_TYPE.clone(),
+        Bytes(_) => BYTES_TYPE.clone(),
+        Range(_, _) => RANGE_TYPE.clone(),
         Ref(_) => REF_TYPE.clone(),
         UnsafeRef(_) => UNSAFE_REF_TYPE.clone(),
         RustObject(_) => RUST_OBJECT_TYPE.clone(),
@@ -1060,6 +1187,9 @@ pub(crate) fn _typeof(rt: &mut Runtime) -> Result<Variable, String> {
         Closure(_, _) => CLOSURE_TYPE.clone(),
         #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         In(_) => IN_TYPE.clone(),
+        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+        Gen(_) => IN_TYPE.clone(),
+        Variant(ref tag, _) => tag.clone(),
     }))
 }
 
@@ -1469,6 +1599,7 @@ pub(crate) fn _call(rt: &mut Runtime) -> Result<(), String> {
                 info: Box::new(ast::CallInfo {
                     alias: None,
                     name: fn_name,
+                    pipe: false,
                     source_range: call_range,
                 }),
             };
@@ -1540,6 +1671,7 @@ pub(crate) fn call_ret(rt: &mut Runtime) -> Result<Variable, String> {
                     .collect(),
                 custom_source: Some(source),
                 info: Box::new(ast::CallInfo {
+                    pipe: false,
                     source_range: call_range,
                     alias: None,
                     name: fn_name.clone(),
@@ -1582,6 +1714,28 @@ pub(crate) fn functions__module(rt: &mut Runtime) -> Result<Variable, String> {
     Ok(Variable::Array(Arc::new(functions)))
 }
 
+pub(crate) fn constants(rt: &mut Runtime) -> Result<Variable, String> {
+    // List available constants in scope.
+    Ok(Variable::Array(functions::list_constants(&rt.module).into()))
+}
+
+pub(crate) fn constants__module(rt: &mut Runtime) -> Result<Variable, String> {
+    // List available constants in scope.
+    let m = rt.stack.pop().expect(TINVOTS);
+    let x = rt.get(&m);
+    let m = match x {
+        Variable::RustObject(obj) => obj.clone(),
+        x => return Err(rt.expected_arg(0, x, "Module")),
+    };
+
+    let constants = match m.lock().unwrap().downcast_ref::<Arc<Module>>() {
+        Some(m) => functions::list_constants(m),
+        None => return Err(rt.expected_arg(0, x, "Module")),
+    };
+
+    Ok(Variable::Array(constants.into()))
+}
+
 dyon_fn! {fn none() -> Variable {Variable::Option(None)}}
 
 pub(crate) fn some(rt: &mut Runtime) -> Result<Variable, String> {
@@ -1858,6 +2012,38 @@ pub(crate) fn load_string__file(_: &mut Runtime) -> Result<Variable, String> {
     Err(FILE_SUPPORT_DISABLED.into())
 }
 
+#[cfg(feature = "file")]
+dyon_fn! {fn save__bytes_file(bytes: Arc<Vec<u8>>, file: Arc<String>) -> Variable {
+    Variable::Result(match std::fs::write(&**file, &**bytes) {
+        Ok(()) => Ok(Box::new(Variable::Str(file))),
+        Err(err) => Err(Box::new(Error {
+            message: Variable::Str(Arc::new(err.to_string())),
+            trace: vec![]
+        }))
+    })
+}}
+
+#[cfg(not(feature = "file"))]
+pub(crate) fn save__bytes_file(_: &mut Runtime) -> Result<Variable, String> {
+    Err(FILE_SUPPORT_DISABLED.into())
+}
+
+#[cfg(feature = "file")]
+dyon_fn! {fn load_bytes__file(file: Arc<String>) -> Variable {
+    Variable::Result(match std::fs::read(&**file) {
+        Ok(bytes) => Ok(Box::new(Variable::Bytes(Arc::new(bytes)))),
+        Err(err) => Err(Box::new(Error {
+            message: Variable::Str(Arc::new(err.to_string())),
+            trace: vec![]
+        }))
+    })
+}}
+
+#[cfg(not(feature = "file"))]
+pub(crate) fn load_bytes__file(_: &mut Runtime) -> Result<Variable, String> {
+    Err(FILE_SUPPORT_DISABLED.into())
+}
+
 dyon_fn! {fn load_string__url(url: Arc<String>) -> Variable {
     Variable::Result(match meta::load_text_file_from_url(&**url) {
         Ok(s) => {
@@ -2030,14 +2216,22 @@ pub(crate) fn errstr__string_start_len_msg(rt: &mut Runtime) -> Result<Variable,
 
 pub(crate) fn has(rt: &mut Runtime) -> Result<Variable, String> {
     let key = rt.stack.pop().expect(TINVOTS);
+    let obj = rt.stack.pop().expect(TINVOTS);
+    if let Variable::Map(ref map) = *rt.get(&obj) {
+        let key = Key::new(&key, &rt.stack).inspect_err(|_| rt.arg_err_index.set(Some(1)))?;
+        return Ok(Variable::bool(map.contains_key(&key)));
+    }
+    if let Variable::Set(ref set) = *rt.get(&obj) {
+        let key = Key::new(&key, &rt.stack).inspect_err(|_| rt.arg_err_index.set(Some(1)))?;
+        return Ok(Variable::bool(set.contains(&key)));
+    }
     let key = match rt.get(&key) {
         &Variable::Str(ref t) => t.clone(),
         x => return Err(rt.expected_arg(1, x, "str")),
     };
-    let obj = rt.stack.pop().expect(TINVOTS);
     Ok(Variable::bool(match rt.get(&obj) {
         &Variable::Object(ref obj) => obj.contains_key(&key),
-        x => return Err(rt.expected_arg(0, x, "object")),
+        x => return Err(rt.expected_arg(0, x, "object, map or set")),
     }))
 }
 
@@ -2045,7 +2239,12 @@ pub(crate) fn keys(rt: &mut Runtime) -> Result<Variable, String> {
     let obj = rt.stack.pop().expect(TINVOTS);
     Ok(Variable::Array(Arc::new(match rt.get(&obj) {
         &Variable::Object(ref obj) => obj.keys().map(|k| Variable::Str(k.clone())).collect(),
-        x => return Err(rt.expected_arg(0, x, "object")),
+        Variable::Map(map) => {
+            let mut keys: Vec<&Key> = map.keys().collect();
+            keys.sort();
+            keys.into_iter().map(|k| k.to_variable()).collect()
+        }
+        x => return Err(rt.expected_arg(0, x, "object or map")),
     })))
 }
 
@@ -2082,6 +2281,17 @@ dyon_fn! {fn now() -> f64 {
 
 dyon_fn! {fn is_nan(v: f64) -> bool {v.is_nan()}}
 
+// Runs a generator until the next value.
+//
+// Generators produce values lazily, so this computes the value for both `next` and `wait_next`.
+#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+fn generator_next(mutex: &Mutex<crate::runtime::Generator>) -> Result<Variable, String> {
+    match mutex.lock() {
+        Ok(mut x) => Ok(Variable::Option(x.next()?.map(Box::new))),
+        Err(err) => Err(format!("Can not lock In mutex:\n{}", err)),
+    }
+}
+
 #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
 pub(crate) fn wait_next(rt: &mut Runtime) -> Result<Variable, String> {
     let v = rt.stack.pop().expect(TINVOTS);
@@ -2093,6 +2303,7 @@ pub(crate) fn wait_next(rt: &mut Runtime) -> Result<Variable, String> {
             },
             Err(err) => return Err(format!("Can not lock In mutex:\n{}", err.to_string())),
         },
+        Variable::Gen(mutex) => generator_next(mutex)?,
         x => return Err(rt.expected_arg(0, x, "in")),
     })
 }
@@ -2108,6 +2319,7 @@ pub(crate) fn next(rt: &mut Runtime) -> Result<Variable, String> {
             },
             Err(err) => return Err(format!("Can not lock In mutex:\n{}", err.to_string())),
         },
+        Variable::Gen(mutex) => generator_next(mutex)?,
         x => return Err(rt.expected_arg(0, x, "in")),
     })
 }
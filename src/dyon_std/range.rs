@@ -0,0 +1,89 @@
+//! Range functions.
+//!
+//! Ranges are half-open, such that `1..3` contains `1` and `2`.
+
+use std::sync::Arc;
+
+use crate::{Runtime, Variable, TINVOTS};
+
+pub(crate) fn range(rt: &mut Runtime) -> Result<Variable, String> {
+    let end = rt.stack.pop().expect(TINVOTS);
+    let start = rt.stack.pop().expect(TINVOTS);
+    let start = match *rt.get(&start) {
+        Variable::F64(x, _) => x,
+        ref x => return Err(rt.expected_arg(0, x, "f64")),
+    };
+    let end = match *rt.get(&end) {
+        Variable::F64(x, _) => x,
+        ref x => return Err(rt.expected_arg(1, x, "f64")),
+    };
+    Ok(Variable::Range(start, end))
+}
+
+/// Gets the indices of a range within a sequence of length `len`.
+pub(crate) fn bounds(start: f64, end: f64, len: usize) -> Result<(usize, usize), String> {
+    if start.fract() != 0.0 || end.fract() != 0.0 {
+        Err(format!("Expected integer range, found `{}..{}`", start, end))
+    } else if start < 0.0 || start > end || end > len as f64 {
+        Err(format!("Out of bounds `{}..{}`", start, end))
+    } else {
+        Ok((start as usize, end as usize))
+    }
+}
+
+/// Returns the items of an array, str or bytes within a range.
+pub(crate) fn slice_of(v: &Variable, start: f64, end: f64) -> Result<Variable, String> {
+    Ok(match *v {
+        Variable::Array(ref arr) => {
+            let (i, j) = bounds(start, end, arr.len())?;
+            Variable::Array(arr[i..j].to_vec().into())
+        }
+        Variable::Str(ref s) => {
+            let (i, j) = bounds(start, end, s.chars().count())?;
+            Variable::Str(Arc::new(s.chars().skip(i).take(j - i).collect()))
+        }
+        Variable::Bytes(ref bytes) => {
+            let (i, j) = bounds(start, end, bytes.len())?;
+            Variable::Bytes(Arc::new(bytes[i..j].to_vec()))
+        }
+        ref x => return Err(format!("Expected `[]`, `str` or `bytes`, found `{}`", x.typeof_var())),
+    })
+}
+
+pub(crate) fn slice(rt: &mut Runtime) -> Result<Variable, String> {
+    let r = rt.stack.pop().expect(TINVOTS);
+    let v = rt.stack.pop().expect(TINVOTS);
+    let (start, end) = match *rt.get(&r) {
+        Variable::Range(start, end) => (start, end),
+        ref x => return Err(rt.expected_arg(1, x, "range")),
+    };
+    match *rt.get(&v) {
+        Variable::Array(_) | Variable::Str(_) | Variable::Bytes(_) => {}
+        ref x => return Err(rt.expected_arg(0, x, "[], str or bytes")),
+    }
+    slice_of(rt.get(&v), start, end).inspect_err(|_| rt.arg_err_index.set(Some(1)))
+}
+
+pub(crate) fn reverse_range(rt: &mut Runtime) -> Result<(), String> {
+    let r = rt.stack.pop().expect(TINVOTS);
+    let v = rt.stack.pop().expect(TINVOTS);
+    let (start, end) = match *rt.get(&r) {
+        Variable::Range(start, end) => (start, end),
+        ref x => return Err(rt.expected_arg(1, x, "range")),
+    };
+    let arr = match v {
+        Variable::Ref(ind) => match rt.stack[ind] {
+            Variable::Array(ref mut arr) => arr,
+            _ => return Err(rt.expected_arg(0, &rt.stack[ind], "[]")),
+        },
+        _ => {
+            return Err({
+                rt.arg_err_index.set(Some(0));
+                "Expected reference to array".into()
+            })
+        }
+    };
+    let (i, j) = bounds(start, end, arr.len()).inspect_err(|_| rt.arg_err_index.set(Some(1)))?;
+    Arc::make_mut(arr)[i..j].reverse();
+    Ok(())
+}
@@ -0,0 +1,55 @@
+//! Set functions.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::{Key, Runtime, Set, Variable, TINVOTS};
+
+pub(crate) fn insert_set(rt: &mut Runtime) -> Result<(), String> {
+    let item = rt.stack.pop().expect(TINVOTS);
+    let v = rt.stack.pop().expect(TINVOTS);
+    let item = Key::new(&item, &rt.stack).inspect_err(|_| rt.arg_err_index.set(Some(1)))?;
+    let set = match v {
+        Variable::Ref(ind) => match rt.stack[ind] {
+            Variable::Set(ref mut set) => Arc::make_mut(set),
+            _ => return Err(rt.expected_arg(0, &rt.stack[ind], "set")),
+        },
+        _ => {
+            return Err({
+                rt.arg_err_index.set(Some(0));
+                "Expected reference to set".into()
+            })
+        }
+    };
+    set.insert(item);
+    Ok(())
+}
+
+// Pops two sets from the stack.
+fn sets(rt: &mut Runtime) -> Result<(Set, Set), String> {
+    let b = rt.stack.pop().expect(TINVOTS);
+    let a = rt.stack.pop().expect(TINVOTS);
+    let a = match *rt.get(&a) {
+        Variable::Set(ref a) => a.clone(),
+        ref x => return Err(rt.expected_arg(0, x, "set")),
+    };
+    let b = match *rt.get(&b) {
+        Variable::Set(ref b) => b.clone(),
+        ref x => return Err(rt.expected_arg(1, x, "set")),
+    };
+    Ok((a, b))
+}
+
+macro_rules! op {
+    ($name:ident) => {
+        pub(crate) fn $name(rt: &mut Runtime) -> Result<Variable, String> {
+            let (a, b) = sets(rt)?;
+            let res: HashSet<Key> = a.$name(&b).cloned().collect();
+            Ok(Variable::Set(Arc::new(res)))
+        }
+    };
+}
+
+op!(union);
+op!(intersection);
+op!(difference);
@@ -1,9 +1,12 @@
 //! Traits for Dyon interop.
 
+use std::collections::{BTreeSet, HashSet};
+use std::hash::Hash;
 use std::sync::Arc;
 
 use crate::{
     Error,
+    Key,
     Object,
     Runtime,
     RustObject,
@@ -99,6 +102,26 @@ impl PopVariable for Arc<String> {
     }
 }
 
+impl PopVariable for Arc<Vec<u8>> {
+    fn pop_var(rt: &Runtime, var: &Variable) -> Result<Self, String> {
+        if let Variable::Bytes(ref b) = *var {
+            Ok(b.clone())
+        } else {
+            Err(rt.expected(var, "bytes"))
+        }
+    }
+}
+
+impl PopVariable for std::ops::Range<f64> {
+    fn pop_var(rt: &Runtime, var: &Variable) -> Result<Self, String> {
+        if let Variable::Range(start, end) = *var {
+            Ok(start..end)
+        } else {
+            Err(rt.expected(var, "range"))
+        }
+    }
+}
+
 impl PopVariable for u32 {
     fn pop_var(rt: &Runtime, var: &Variable) -> Result<Self, String> {
         if let Variable::F64(n, _) = *var {
@@ -139,6 +162,32 @@ impl PopVariable for f64 {
     }
 }
 
+impl PopVariable for i64 {
+    fn pop_var(rt: &Runtime, var: &Variable) -> Result<Self, String> {
+        if let Variable::I64(n) = *var {
+            Ok(n)
+        } else {
+            Err(rt.expected(var, "i64"))
+        }
+    }
+}
+
+impl PopVariable for u64 {
+    fn pop_var(rt: &Runtime, var: &Variable) -> Result<Self, String> {
+        if let Variable::I64(n) = *var {
+            u64::try_from(n).map_err(|_| {
+                format!(
+                    "{}\nExpected non-negative `i64`, found `{}`",
+                    rt.stack_trace(),
+                    n
+                )
+            })
+        } else {
+            Err(rt.expected(var, "i64"))
+        }
+    }
+}
+
 impl<T: PopVariable> PopVariable for Option<T> {
     fn pop_var(rt: &Runtime, var: &Variable) -> Result<Self, String> {
         if let Variable::Option(ref s) = *var {
@@ -263,6 +312,26 @@ impl<T: PopVariable> PopVariable for Vec<T> {
     }
 }
 
+impl<T: PopVariable + Eq + Hash> PopVariable for HashSet<T> {
+    fn pop_var(rt: &Runtime, var: &Variable) -> Result<Self, String> {
+        if let Variable::Set(ref set) = *var {
+            set.iter().map(|it| PopVariable::pop_var(rt, &it.to_variable())).collect()
+        } else {
+            Err(rt.expected(var, "set"))
+        }
+    }
+}
+
+impl<T: PopVariable + Ord> PopVariable for BTreeSet<T> {
+    fn pop_var(rt: &Runtime, var: &Variable) -> Result<Self, String> {
+        if let Variable::Set(ref set) = *var {
+            set.iter().map(|it| PopVariable::pop_var(rt, &it.to_variable())).collect()
+        } else {
+            Err(rt.expected(var, "set"))
+        }
+    }
+}
+
 impl PushVariable for Variable {
     fn push_var(&self) -> Variable {
         self.clone()
@@ -305,6 +374,22 @@ impl PushVariable for f64 {
     }
 }
 
+impl PushVariable for i64 {
+    fn push_var(&self) -> Variable {
+        Variable::I64(*self)
+    }
+}
+
+/// Pushes `f64` when the value does not fit in `i64`.
+impl PushVariable for u64 {
+    fn push_var(&self) -> Variable {
+        match i64::try_from(*self) {
+            Ok(x) => Variable::I64(x),
+            Err(_) => Variable::f64(*self as f64),
+        }
+    }
+}
+
 impl PushVariable for str {
     fn push_var(&self) -> Variable {
         Variable::Str(Arc::new(self.into()))
@@ -323,6 +408,18 @@ impl PushVariable for Arc<String> {
     }
 }
 
+impl PushVariable for Arc<Vec<u8>> {
+    fn push_var(&self) -> Variable {
+        Variable::Bytes(self.clone())
+    }
+}
+
+impl PushVariable for std::ops::Range<f64> {
+    fn push_var(&self) -> Variable {
+        Variable::Range(self.start, self.end)
+    }
+}
+
 impl<T: PushVariable> PushVariable for Option<T> {
     fn push_var(&self) -> Variable {
         Variable::Option(self.as_ref().map(|v| Box::new(v.push_var())))
@@ -402,6 +499,31 @@ impl<T: PushVariable> PushVariable for Vec<T> {
     }
 }
 
+fn push_set<'a, T: PushVariable + 'a>(items: impl Iterator<Item = &'a T>) -> Variable {
+    Variable::Set(Arc::new(
+        items
+            .map(|it| {
+                Key::new(&it.push_var(), &[])
+                    .unwrap_or_else(|err| panic!("Could not push set item\n{}", err))
+            })
+            .collect(),
+    ))
+}
+
+/// Panics when an item can not be used in a set, e.g. `NaN`.
+impl<T: PushVariable> PushVariable for HashSet<T> {
+    fn push_var(&self) -> Variable {
+        push_set(self.iter())
+    }
+}
+
+/// Panics when an item can not be used in a set, e.g. `NaN`.
+impl<T: PushVariable> PushVariable for BTreeSet<T> {
+    fn push_var(&self) -> Variable {
+        push_set(self.iter())
+    }
+}
+
 impl ConvertVec4 for [f32; 2] {
     fn from(val: [f32; 4]) -> Self {
         [val[0], val[1]]
@@ -98,6 +98,31 @@ pub fn grab_expr(
             }
             x => x,
         },
+        E::Defer(ref defer) => match grab_block(level, rt, &defer.block, side) {
+            Ok((Grabbed::Block(x), Flow::Continue)) => Ok((
+                Grabbed::Expression(E::Defer(Box::new(ast::Defer {
+                    block: x,
+                    source_range: defer.source_range,
+                }))),
+                Flow::Continue,
+            )),
+            x => x,
+        },
+        E::TryCatch(ref try_catch) => Ok((
+            Grabbed::Expression(E::TryCatch(Box::new(ast::TryCatch {
+                block: match grab_block(level, rt, &try_catch.block, side) {
+                    Ok((Grabbed::Block(x), Flow::Continue)) => x,
+                    x => return x,
+                },
+                name: try_catch.name.clone(),
+                catch_block: match grab_block(level, rt, &try_catch.catch_block, side) {
+                    Ok((Grabbed::Block(x), Flow::Continue)) => x,
+                    x => return x,
+                },
+                source_range: try_catch.source_range,
+            }))),
+            Flow::Continue,
+        )),
         E::Assign(ref assign) => Ok((
             Grabbed::Expression(E::Assign(Box::new(ast::Assign {
                 op: assign.op,
@@ -113,6 +138,17 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
+        E::Destructure(ref destructure) => Ok((
+            Grabbed::Expression(E::Destructure(Box::new(ast::Destructure {
+                pattern: destructure.pattern.clone(),
+                right: match grab_expr(level, rt, &destructure.right, side) {
+                    Ok((Grabbed::Expression(x), Flow::Continue)) => x,
+                    x => return x,
+                },
+                source_range: destructure.source_range,
+            }))),
+            Flow::Continue,
+        )),
         E::If(ref if_expr) => Ok((
             Grabbed::Expression(E::If(Box::new(ast::If {
                 cond: match grab_expr(level, rt, &if_expr.cond, side) {
@@ -154,6 +190,58 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
+        E::Match(ref match_expr) => Ok((
+            Grabbed::Expression(E::Match(Box::new(ast::Match {
+                expr: match grab_expr(level, rt, &match_expr.expr, side) {
+                    Ok((Grabbed::Expression(x), Flow::Continue)) => x,
+                    x => return x,
+                },
+                arms: {
+                    let mut new_arms = vec![];
+                    for arm in &match_expr.arms {
+                        new_arms.push(ast::MatchArm {
+                            pattern: arm.pattern.clone(),
+                            expr: match grab_expr(level, rt, &arm.expr, side) {
+                                Ok((Grabbed::Expression(x), Flow::Continue)) => x,
+                                x => return x,
+                            },
+                            source_range: arm.source_range,
+                        });
+                    }
+                    new_arms
+                },
+                source_range: match_expr.source_range,
+            }))),
+            Flow::Continue,
+        )),
+        E::Variant(ref variant) => Ok((
+            Grabbed::Expression(E::Variant(Box::new(ast::Variant {
+                name: variant.name.clone(),
+                expr: match grab_expr(level, rt, &variant.expr, side) {
+                    Ok((Grabbed::Expression(x), Flow::Continue)) => x,
+                    x => return x,
+                },
+                source_range: variant.source_range,
+            }))),
+            Flow::Continue,
+        )),
+        E::Interpolation(ref interp) => Ok((
+            Grabbed::Expression(E::Interpolation(Box::new(ast::Interpolation {
+                texts: interp.texts.clone(),
+                exprs: {
+                    let mut new_exprs = vec![];
+                    for expr in &interp.exprs {
+                        new_exprs.push(match grab_expr(level, rt, expr, side) {
+                            Ok((Grabbed::Expression(x), Flow::Continue)) => x,
+                            x => return x,
+                        });
+                    }
+                    new_exprs
+                },
+                source_range: interp.source_range,
+            }))),
+            Flow::Continue,
+        )),
         #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::Go(ref go) => {
             let call = &go.call;
@@ -181,6 +269,19 @@ pub fn grab_expr(
         }
         #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
         E::Go(ref go) => match **go {},
+        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+        E::Yield(ref y) => Ok((
+            Grabbed::Expression(E::Yield(Box::new(ast::Yield {
+                expr: match grab_expr(level, rt, &y.expr, side) {
+                    Ok((Grabbed::Expression(x), Flow::Continue)) => x,
+                    x => return x,
+                },
+                source_range: y.source_range,
+            }))),
+            Flow::Continue,
+        )),
+        #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+        E::Yield(ref y) => match **y {},
         E::Call(ref call) => Ok((
             Grabbed::Expression(E::Call(Box::new(ast::Call {
                 args: {
@@ -311,6 +412,7 @@ pub fn grab_expr(
                     }
                     new_args
                 },
+                pipe: call_closure.pipe,
                 source_range: call_closure.source_range,
             }))),
             Flow::Continue,
@@ -449,6 +551,28 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
+        E::Map(ref map) => Ok((
+            Grabbed::Expression(E::Map(Box::new(ast::Map {
+                key_values: {
+                    let mut new_key_values = vec![];
+                    for key_value in &map.key_values {
+                        new_key_values.push((
+                            match grab_expr(level, rt, &key_value.0, side) {
+                                Ok((Grabbed::Expression(x), Flow::Continue)) => x,
+                                x => return x,
+                            },
+                            match grab_expr(level, rt, &key_value.1, side) {
+                                Ok((Grabbed::Expression(x), Flow::Continue)) => x,
+                                x => return x,
+                            },
+                        ));
+                    }
+                    new_key_values
+                },
+                source_range: map.source_range,
+            }))),
+            Flow::Continue,
+        )),
         E::Array(ref arr) => Ok((
             Grabbed::Expression(E::Array(Box::new(ast::Array {
                 items: {
@@ -465,6 +589,22 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
+        E::Set(ref set) => Ok((
+            Grabbed::Expression(E::Set(Box::new(ast::Set {
+                items: {
+                    let mut new_items = vec![];
+                    for item in &set.items {
+                        new_items.push(match grab_expr(level, rt, item, side) {
+                            Ok((Grabbed::Expression(x), Flow::Continue)) => x,
+                            x => return x,
+                        });
+                    }
+                    new_items
+                },
+                source_range: set.source_range,
+            }))),
+            Flow::Continue,
+        )),
         E::ArrayFill(ref arr_fill) => Ok((
             Grabbed::Expression(E::ArrayFill(Box::new(ast::ArrayFill {
                 fill: match grab_expr(level, rt, &arr_fill.fill, side) {
@@ -502,10 +642,10 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::ForIn(ref for_in_expr) => Ok((
             Grabbed::Expression(E::ForIn(Box::new(ast::ForIn {
                 name: for_in_expr.name.clone(),
+                value: for_in_expr.value.clone(),
                 iter: match grab_expr(level, rt, &for_in_expr.iter, side) {
                     Ok((Grabbed::Expression(x), Flow::Continue)) => x,
                     x => return x,
@@ -519,20 +659,10 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
-        #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-        E::ForIn(ref for_in_expr) |
-        E::SumIn(ref for_in_expr) |
-        E::ProdIn(ref for_in_expr) |
-        E::MinIn(ref for_in_expr) |
-        E::MaxIn(ref for_in_expr) |
-        E::SiftIn(ref for_in_expr) |
-        E::AnyIn(ref for_in_expr) |
-        E::AllIn(ref for_in_expr) |
-        E::LinkIn(ref for_in_expr) => match **for_in_expr {},
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::SumIn(ref for_in_expr) => Ok((
             Grabbed::Expression(E::SumIn(Box::new(ast::ForIn {
                 name: for_in_expr.name.clone(),
+                value: for_in_expr.value.clone(),
                 iter: match grab_expr(level, rt, &for_in_expr.iter, side) {
                     Ok((Grabbed::Expression(x), Flow::Continue)) => x,
                     x => return x,
@@ -546,10 +676,10 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::ProdIn(ref for_in_expr) => Ok((
             Grabbed::Expression(E::ProdIn(Box::new(ast::ForIn {
                 name: for_in_expr.name.clone(),
+                value: for_in_expr.value.clone(),
                 iter: match grab_expr(level, rt, &for_in_expr.iter, side) {
                     Ok((Grabbed::Expression(x), Flow::Continue)) => x,
                     x => return x,
@@ -563,10 +693,10 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::MinIn(ref for_in_expr) => Ok((
             Grabbed::Expression(E::MinIn(Box::new(ast::ForIn {
                 name: for_in_expr.name.clone(),
+                value: for_in_expr.value.clone(),
                 iter: match grab_expr(level, rt, &for_in_expr.iter, side) {
                     Ok((Grabbed::Expression(x), Flow::Continue)) => x,
                     x => return x,
@@ -580,10 +710,10 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::MaxIn(ref for_in_expr) => Ok((
             Grabbed::Expression(E::MaxIn(Box::new(ast::ForIn {
                 name: for_in_expr.name.clone(),
+                value: for_in_expr.value.clone(),
                 iter: match grab_expr(level, rt, &for_in_expr.iter, side) {
                     Ok((Grabbed::Expression(x), Flow::Continue)) => x,
                     x => return x,
@@ -597,10 +727,10 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::AnyIn(ref for_in_expr) => Ok((
             Grabbed::Expression(E::AnyIn(Box::new(ast::ForIn {
                 name: for_in_expr.name.clone(),
+                value: for_in_expr.value.clone(),
                 iter: match grab_expr(level, rt, &for_in_expr.iter, side) {
                     Ok((Grabbed::Expression(x), Flow::Continue)) => x,
                     x => return x,
@@ -614,10 +744,10 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::AllIn(ref for_in_expr) => Ok((
             Grabbed::Expression(E::AllIn(Box::new(ast::ForIn {
                 name: for_in_expr.name.clone(),
+                value: for_in_expr.value.clone(),
                 iter: match grab_expr(level, rt, &for_in_expr.iter, side) {
                     Ok((Grabbed::Expression(x), Flow::Continue)) => x,
                     x => return x,
@@ -631,10 +761,10 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::SiftIn(ref for_in_expr) => Ok((
             Grabbed::Expression(E::SiftIn(Box::new(ast::ForIn {
                 name: for_in_expr.name.clone(),
+                value: for_in_expr.value.clone(),
                 iter: match grab_expr(level, rt, &for_in_expr.iter, side) {
                     Ok((Grabbed::Expression(x), Flow::Continue)) => x,
                     x => return x,
@@ -648,10 +778,10 @@ pub fn grab_expr(
             }))),
             Flow::Continue,
         )),
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::LinkIn(ref for_in_expr) => Ok((
             Grabbed::Expression(E::LinkIn(Box::new(ast::ForIn {
                 name: for_in_expr.name.clone(),
+                value: for_in_expr.value.clone(),
                 iter: match grab_expr(level, rt, &for_in_expr.iter, side) {
                     Ok((Grabbed::Expression(x), Flow::Continue)) => x,
                     x => return x,
@@ -745,6 +875,8 @@ fn grab_item(
                 new_ids
             },
             try_ids: item.try_ids.clone(),
+            opt_ids: item.opt_ids.clone(),
+            constant: item.constant.clone(),
             source_range: item.source_range,
         }),
         Flow::Continue,
@@ -14,7 +14,7 @@ extern crate vecmath;
 use piston_meta::{parse_errstr, syntax_errstr, MetaData, Syntax};
 use range::Range;
 use std::any::Any;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::{Arc, Mutex};
 #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
@@ -24,6 +24,7 @@ pub mod ast;
 pub mod embed;
 mod lifetime;
 mod link;
+mod map;
 pub mod macros;
 mod mat4;
 mod module;
@@ -40,6 +41,7 @@ mod grab;
 
 pub use ast::Lazy;
 pub use link::Link;
+pub use map::Key;
 pub use mat4::Mat4;
 pub use module::Module;
 pub use prelude::{Dfn, Lt, Prelude};
@@ -72,6 +74,10 @@ lazy_static! {
     pub(crate) static ref NOT: Arc<String> = Arc::new("not".into());
     pub(crate) static ref NEG: Arc<String> = Arc::new("neg".into());
     pub(crate) static ref NORM: Arc<String> = Arc::new("norm".into());
+    pub(crate) static ref UNWRAP_OR: Arc<String> = Arc::new("unwrap_or".into());
+    pub(crate) static ref RANGE: Arc<String> = Arc::new("range".into());
+    pub(crate) static ref ASSERT: Arc<String> = Arc::new("assert".into());
+    pub(crate) static ref ASSERT_EQ: Arc<String> = Arc::new("assert_eq".into());
     pub(crate) static ref T: Arc<String> = Arc::new("T".into());
 }
 
@@ -91,6 +97,10 @@ pub static LAZY_NO: LazyInvariant = &[];
 pub type Array = Arc<Vec<Variable>>;
 /// Type alias for Dyon objects.
 pub type Object = Arc<HashMap<Arc<String>, Variable>>;
+/// Type alias for Dyon maps.
+pub type Map = Arc<HashMap<Key, Variable>>;
+/// Type alias for Dyon sets.
+pub type Set = Arc<HashSet<Key>>;
 /// Type alias for Rust objects.
 pub type RustObject = Arc<Mutex<dyn Any>>;
 
@@ -200,6 +210,8 @@ pub enum Variable {
     Bool(bool, Option<Box<Vec<Variable>>>),
     /// F64.
     F64(f64, Option<Box<Vec<Variable>>>),
+    /// 64-bit integer.
+    I64(i64),
     /// 4D vector.
     Vec4([f32; 4]),
     /// 4D matrix.
@@ -210,8 +222,16 @@ pub enum Variable {
     Array(Array),
     /// Object.
     Object(Object),
+    /// Map with arbitrary keys.
+    Map(Map),
+    /// Set of unique values.
+    Set(Set),
     /// Link.
     Link(Box<Link>),
+    /// Byte buffer.
+    Bytes(Arc<Vec<u8>>),
+    /// Half-open range of numbers, e.g. `1..3`.
+    Range(f64, f64),
     /// Unsafe reference.
     UnsafeRef(UnsafeRef),
     /// Rust object.
@@ -230,6 +250,16 @@ pub enum Variable {
     /// In-type.
     #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
     In(Arc<Mutex<::std::sync::mpsc::Receiver<Variable>>>),
+    /// In-type fed by a generator function.
+    ///
+    /// Values are produced lazily, one `yield` at a time,
+    /// by resuming the generator where it was suspended.
+    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+    Gen(Arc<Mutex<runtime::Generator>>),
+    /// Value tagged with an enum variant.
+    ///
+    /// Unit variants have no value.
+    Variant(Arc<String>, Option<Box<Variable>>),
 }
 
 /// This is requires because `UnsafeRef(*mut Variable)` can not be sent across threads.
@@ -256,13 +286,18 @@ impl Variable {
         match *self {
             Str(_) => TEXT_TYPE.clone(),
             F64(_, _) => F64_TYPE.clone(),
+            I64(_) => I64_TYPE.clone(),
             Vec4(_) => VEC4_TYPE.clone(),
             Mat4(_) => MAT4_TYPE.clone(),
             Return => RETURN_TYPE.clone(),
             Bool(_, _) => BOOL_TYPE.clone(),
             Object(_) => OBJECT_TYPE.clone(),
+            Map(_) => MAP_TYPE.clone(),
+            Set(_) => SET_TYPE.clone(),
             Array(_) => ARRAY_TYPE.clone(),
             Link(_) => LINK_TYPE.clone(),
+            Bytes(_) => BYTES_TYPE.clone(),
+            Range(_, _) => RANGE_TYPE.clone(),
             Ref(_) => REF_TYPE.clone(),
             UnsafeRef(_) => UNSAFE_REF_TYPE.clone(),
             RustObject(_) => RUST_OBJECT_TYPE.clone(),
@@ -273,6 +308,9 @@ impl Variable {
             Closure(_, _) => CLOSURE_TYPE.clone(),
             #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             In(_) => IN_TYPE.clone(),
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Gen(_) => IN_TYPE.clone(),
+            Variant(ref tag, _) => tag.clone(),
         }
     }
 
@@ -281,6 +319,7 @@ impl Variable {
 
         match *self {
             F64(_, _) => self.clone(),
+            I64(_) => self.clone(),
             Vec4(_) => self.clone(),
             Mat4(_) => self.clone(),
             Return => self.clone(),
@@ -293,6 +332,14 @@ impl Variable {
                 }
                 Object(res)
             }
+            Map(ref map) => {
+                let mut res = map.clone();
+                for val in Arc::make_mut(&mut res).values_mut() {
+                    *val = val.deep_clone(stack);
+                }
+                Map(res)
+            }
+            Set(_) => self.clone(),
             Array(ref arr) => {
                 let mut res = arr.clone();
                 for it in Arc::make_mut(&mut res) {
@@ -301,6 +348,8 @@ impl Variable {
                 Array(res)
             }
             Link(_) => self.clone(),
+            Bytes(_) => self.clone(),
+            Range(_, _) => self.clone(),
             Ref(ind) => stack[ind].deep_clone(stack),
             UnsafeRef(_) => panic!("Unsafe reference can not be cloned"),
             RustObject(_) => self.clone(),
@@ -316,6 +365,10 @@ impl Variable {
             Closure(_, _) => self.clone(),
             #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             In(_) => self.clone(),
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Gen(_) => self.clone(),
+            // Enum constructors always use deep clone, so it does not contain references.
+            Variant(_, _) => self.clone(),
         }
     }
 }
@@ -326,9 +379,15 @@ impl PartialEq for Variable {
             (&Variable::Return, _) => false,
             (&Variable::Bool(a, _), &Variable::Bool(b, _)) => a == b,
             (&Variable::F64(a, _), &Variable::F64(b, _)) => a == b,
+            (&Variable::I64(a), &Variable::I64(b)) => a == b,
             (&Variable::Str(ref a), &Variable::Str(ref b)) => a == b,
             (&Variable::Object(ref a), &Variable::Object(ref b)) => a == b,
+            (Variable::Map(a), Variable::Map(b)) => a == b,
+            (Variable::Set(a), Variable::Set(b)) => a == b,
             (&Variable::Array(ref a), &Variable::Array(ref b)) => a == b,
+            (Variable::Bytes(a), Variable::Bytes(b)) => a == b,
+            (Variable::Range(a, b), Variable::Range(c, d)) => a == c && b == d,
+            (Variable::Variant(a, x), Variable::Variant(b, y)) => a == b && x == y,
             (&Variable::Ref(_), _) => false,
             (&Variable::UnsafeRef(_), _) => false,
             (&Variable::RustObject(_), _) => false,
@@ -562,8 +621,32 @@ impl Call {
     }
 }
 
+/// Reports non-fatal problems found when loading a source.
+#[derive(Clone, Debug, Default)]
+pub struct LoadReport {
+    /// Warnings rendered with their location in the source.
+    pub warnings: Vec<String>,
+}
+
+impl LoadReport {
+    /// Prints warnings to standard error.
+    #[cfg(feature = "stdio")]
+    pub fn print_warnings(&self) {
+        for warning in &self.warnings {
+            eprintln!("{}", warning);
+        }
+    }
+}
+
 /// Loads source from file.
+///
+/// Warnings are ignored, use `load_report` to get them.
 pub fn load(source: &str, module: &mut Module) -> Result<(), String> {
+    load_report(source, module).map(|_| ())
+}
+
+/// Loads source from file and returns a report with warnings.
+pub fn load_report(source: &str, module: &mut Module) -> Result<LoadReport, String> {
     use std::fs::File;
     use std::io::Read;
 
@@ -572,7 +655,7 @@ pub fn load(source: &str, module: &mut Module) -> Result<(), String> {
     let mut data = Arc::new(String::new());
     data_file.read_to_string(Arc::make_mut(&mut data))
         .map_err(|err| format!("Could not open `{}`, {}", source, err))?;
-    load_str(source, data, module)
+    load_str_report(source, data, module)
 }
 
 lazy_static! {
@@ -598,12 +681,19 @@ pub(crate) fn check_str(
     let mut data = vec![];
     parse_errstr(syntax_rules, &d, &mut data)
         .map_err(|err| format!("In `{}:`\n{}", source, err))?;
+    let data = ast::desugar(&data);
 
     let check_data = data.clone();
     let prelude = Arc::new(Prelude::from_module(module));
 
     let mut nodes = vec![];
-    let _ = lifetime::check_core(&mut nodes, &check_data, &prelude);
+    let _ = lifetime::check_core(
+        &mut nodes,
+        &check_data,
+        &prelude,
+        &mut vec![],
+        &mut HashMap::new(),
+    );
     Ok(nodes)
 }
 
@@ -612,7 +702,22 @@ pub(crate) fn check_str(
 /// - source - The name of source file
 /// - d - The data of source file
 /// - module - The module to load the source
+///
+/// Warnings are ignored, use `load_str_report` to get them.
 pub fn load_str(source: &str, d: Arc<String>, module: &mut Module) -> Result<(), String> {
+    load_str_report(source, d, module).map(|_| ())
+}
+
+/// Loads a source from string and returns a report with warnings.
+///
+/// - source - The name of source file
+/// - d - The data of source file
+/// - module - The module to load the source
+pub fn load_str_report(
+    source: &str,
+    d: Arc<String>,
+    module: &mut Module,
+) -> Result<LoadReport, String> {
     #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
     struct MaybeThread<T>(std::thread::JoinHandle<T>);
 
@@ -650,6 +755,7 @@ pub fn load_str(source: &str, d: Arc<String>, module: &mut Module) -> Result<(),
     let mut data = vec![];
     parse_errstr(syntax_rules, &d, &mut data)
         .map_err(|err| format!("In `{}:`\n{}", source, err))?;
+    let data = ast::desugar(&data);
 
     let check_data = data.clone();
     let prelude = Arc::new(Prelude::from_module(module));
@@ -657,12 +763,15 @@ pub fn load_str(source: &str, d: Arc<String>, module: &mut Module) -> Result<(),
     // Do lifetime checking in parallel directly on meta data if possible.
     let handle = MaybeThread::spawn(move || {
         let check_data = check_data;
-        lifetime::check(&check_data, &prelude)
+        let mut warnings = vec![];
+        let mut overloads = HashMap::new();
+        lifetime::check(&check_data, &prelude, &mut warnings, &mut overloads)
+            .map(|rets| (rets, warnings, overloads))
     });
 
     // Convert to AST.
     let mut ignored = vec![];
-    let conv_res = ast::convert(
+    let conv_res = ast::parse(
         Arc::new(source.into()),
         d.clone(),
         &data,
@@ -671,14 +780,30 @@ pub fn load_str(source: &str, d: Arc<String>, module: &mut Module) -> Result<(),
     );
 
     // Check that lifetime checking succeeded.
-    match handle.join() {
-        Ok(refined_rets) => {
+    let mut report = LoadReport::default();
+    let overloads = match handle.join() {
+        Ok((refined_rets, warnings, overloads)) => {
             for (name, ty) in &refined_rets {
                 if let FnIndex::Loaded(f_index) = module.find_function(name, 0) {
                     let f = &mut module.functions[f_index as usize];
                     f.ret = ty.clone();
                 }
             }
+            for warning in warnings {
+                use piston_meta::ParseErrorHandler;
+
+                let (range, msg) = warning.decouple();
+                let mut buf: Vec<u8> = vec![];
+                ParseErrorHandler::new(&d)
+                    .write_msg(&mut buf, range, &format!("Warning: {}", msg))
+                    .unwrap();
+                report.warnings.push(format!(
+                    "In `{}`:\n{}",
+                    source,
+                    String::from_utf8(buf).unwrap()
+                ));
+            }
+            overloads
         }
         Err(err_msg) => {
             use piston_meta::ParseErrorHandler;
@@ -693,9 +818,16 @@ pub fn load_str(source: &str, d: Arc<String>, module: &mut Module) -> Result<(),
                 .unwrap();
             return Err(String::from_utf8(buf).unwrap());
         }
-    }
+    };
+
+    // Resolve calls using the operator overloads picked by the type checker.
+    let conv_res = conv_res.map(|mut use_lookup| {
+        use_lookup.overloads = overloads;
+        ast::resolve(&use_lookup, module);
+    });
 
-    check_ignored_meta_data(conv_res, source, &d, &data, &ignored)
+    check_ignored_meta_data(conv_res, source, &d, &data, &ignored)?;
+    Ok(report)
 }
 
 /// Loads a source from meta data.
@@ -706,6 +838,8 @@ pub fn load_meta(
     data: &[Range<MetaData>],
     module: &mut Module,
 ) -> Result<(), String> {
+    let data = &ast::desugar(data);
+
     // Convert to AST.
     let mut ignored = vec![];
     let conv_res = ast::convert(
@@ -7,6 +7,9 @@ pub enum Kind {
     Arg,
     Current,
     Block,
+    Defer,
+    TryCatch,
+    Catch,
     Expr,
     Add,
     Mul,
@@ -21,6 +24,11 @@ pub enum Kind {
     ItemExtra,
     Return,
     Object,
+    Map,
+    Set,
+    Key,
+    Coalesce,
+    Range,
     Array,
     ArrayItem,
     ArrayFill,
@@ -83,6 +91,7 @@ pub enum Kind {
     RetType,
     ReturnVoid,
     Go,
+    Yield,
     Swizzle,
     Sw0,
     Sw1,
@@ -100,6 +109,25 @@ pub enum Kind {
     Grab,
     TryExpr,
     In,
+    Match,
+    Arm,
+    Pat,
+    PatSome,
+    PatNone,
+    PatOk,
+    PatErr,
+    PatArr,
+    PatObj,
+    PatKey,
+    PatVariant,
+    Enum,
+    Variant,
+    Interp,
+    Destructure,
+    PatVec4,
+    Default,
+    Const,
+    TypeAlias,
     Ty,
     TyArg,
     TyRet,
@@ -115,6 +143,9 @@ impl Kind {
             "arg" => Kind::Arg,
             "current" => Kind::Current,
             "block" => Kind::Block,
+            "defer" => Kind::Defer,
+            "try_catch" => Kind::TryCatch,
+            "catch" => Kind::Catch,
             "expr" => Kind::Expr,
             "add" => Kind::Add,
             "mul" => Kind::Mul,
@@ -130,6 +161,11 @@ impl Kind {
             "item_extra" => Kind::ItemExtra,
             "return" => Kind::Return,
             "object" => Kind::Object,
+            "map" => Kind::Map,
+            "set" => Kind::Set,
+            "coalesce" => Kind::Coalesce,
+            "range" => Kind::Range,
+            "key" => Kind::Key,
             "array" => Kind::Array,
             "array_item" => Kind::ArrayItem,
             "array_fill" => Kind::ArrayFill,
@@ -192,6 +228,7 @@ impl Kind {
             "ret_type" => Kind::RetType,
             "return_void" => Kind::ReturnVoid,
             "go" => Kind::Go,
+            "yield" => Kind::Yield,
             "swizzle" => Kind::Swizzle,
             "sw0" => Kind::Sw0,
             "sw1" => Kind::Sw1,
@@ -210,6 +247,25 @@ impl Kind {
             "grab" => Kind::Grab,
             "try_expr" => Kind::TryExpr,
             "in" => Kind::In,
+            "match" => Kind::Match,
+            "arm" => Kind::Arm,
+            "pat" => Kind::Pat,
+            "pat_some" => Kind::PatSome,
+            "pat_none" => Kind::PatNone,
+            "pat_ok" => Kind::PatOk,
+            "pat_err" => Kind::PatErr,
+            "pat_arr" => Kind::PatArr,
+            "pat_obj" => Kind::PatObj,
+            "pat_key" => Kind::PatKey,
+            "pat_variant" => Kind::PatVariant,
+            "enum" => Kind::Enum,
+            "variant" => Kind::Variant,
+            "interp" => Kind::Interp,
+            "destructure" => Kind::Destructure,
+            "pat_vec4" => Kind::PatVec4,
+            "default" => Kind::Default,
+            "const" => Kind::Const,
+            "type_alias" => Kind::TypeAlias,
             "ty" => Kind::Ty,
             "ty_arg" => Kind::TyArg,
             "ty_ret" => Kind::TyRet,
@@ -227,7 +283,7 @@ impl Kind {
         )
     }
 
-    /// An in-loop receives an object from a receiver channel.
+    /// An in-loop reads values from a collection or a receiver channel.
     pub fn is_in_loop(self) -> bool {
         use self::Kind::*;
 
@@ -242,6 +298,13 @@ impl Kind {
         matches!(self, Kind::Vec4UnLoop)
     }
 
+    /// A pattern node can declare a variable.
+    pub fn is_pattern(self) -> bool {
+        use self::Kind::*;
+
+        matches!(self, Pat | PatSome | PatOk | PatErr | PatKey | PatVariant)
+    }
+
     pub fn is_block(self) -> bool {
         use self::Kind::*;
 
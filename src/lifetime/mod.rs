@@ -3,14 +3,14 @@ extern crate range;
 
 use self::kind::Kind;
 use self::lt::{arg_lifetime, compare_lifetimes, Lifetime};
-use self::node::convert_meta_data;
+use self::node::{constant, convert_meta_data};
 pub(crate) use self::node::Node;
 use self::piston_meta::MetaData;
 use self::range::Range;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use crate::ast::{AssignOp, UseLookup};
+use crate::ast::{default_call_names, overload_name, AssignOp, TypeAlias, UseLookup, Uses};
 use crate::prelude::{Lt, Prelude};
 
 use crate::Type;
@@ -23,12 +23,16 @@ mod typecheck;
 
 /// Checks lifetime constraints and does type checking.
 /// Returns refined return types of functions to put in AST.
+///
+/// The operator overloads picked by the type checker are stored by range in source.
 pub fn check(
     data: &[Range<MetaData>],
     prelude: &Prelude,
+    warnings: &mut Vec<Range<String>>,
+    overloads: &mut HashMap<Range, Arc<String>>,
 ) -> Result<HashMap<Arc<String>, Type>, Range<String>> {
     let mut nodes: Vec<Node> = vec![];
-    check_core(&mut nodes, data, prelude)
+    check_core(&mut nodes, data, prelude, warnings, overloads)
 }
 
 // Core lifetime and type check.
@@ -36,6 +40,8 @@ pub(crate) fn check_core(
     nodes: &mut Vec<Node>,
     data: &[Range<MetaData>],
     prelude: &Prelude,
+    warnings: &mut Vec<Range<String>>,
+    overloads: &mut HashMap<Range, Arc<String>>,
 ) -> Result<HashMap<Arc<String>, Type>, Range<String>> {
     convert_meta_data(nodes, data)?;
 
@@ -67,6 +73,7 @@ pub(crate) fn check_core(
                 alias: None,
                 mutable: false,
                 try_flag: false,
+                current: false,
                 grab_level: 0,
                 source: nodes[i].source,
                 start: nodes[i].start,
@@ -114,7 +121,7 @@ pub(crate) fn check_core(
 
             Node::rewrite_binop(
                 i,
-                match nodes[i].binops[0] {
+                match nodes[i].binops[0].0 {
                     Add => crate::ADD.clone(),
                     Sub => crate::SUB.clone(),
                     Mul => crate::MUL.clone(),
@@ -131,6 +138,8 @@ pub(crate) fn check_core(
                     GreaterOrEqual => crate::GREATER_OR_EQUAL.clone(),
                     Equal => crate::EQUAL.clone(),
                     NotEqual => crate::NOT_EQUAL.clone(),
+                    // `|>` is rewritten into a call when parsing.
+                    Pipe => unreachable!(),
                 },
                 nodes,
             );
@@ -185,6 +194,16 @@ pub(crate) fn check_core(
             }
             name_plus_args.push(')');
             nodes[i].names = vec![Arc::new(name_plus_args)];
+        } else if nodes[i].kind == Kind::Fn {
+            let tys: Vec<Type> = nodes[i]
+                .children
+                .iter()
+                .filter(|&&n| nodes[n].kind == Kind::Arg)
+                .map(|&n| nodes[n].ty.clone().unwrap_or(Type::Any))
+                .collect();
+            if let Some(name) = overload_name(nodes[i].name().unwrap(), &tys) {
+                nodes[i].names = vec![name];
+            }
         }
     }
 
@@ -196,9 +215,36 @@ pub(crate) fn check_core(
         .map(|(i, _)| i)
         .collect();
 
+    // Check that constants are computed from constant expressions.
+    // Stores constant node.
+    let mut const_lookup: HashMap<Arc<String>, usize> = HashMap::new();
+    for i in 0..nodes.len() {
+        if nodes[i].kind != Kind::Const {
+            continue;
+        }
+        let name = nodes[i].name().expect("Expected name").clone();
+        if const_lookup.contains_key(&name) {
+            return Err(nodes[i]
+                .source
+                .wrap(format!("Duplicate constant `{}`", name)));
+        }
+        match nodes[i].find_child_by_kind(nodes, Kind::Expr) {
+            Some(expr) if !constant(expr, nodes) => {
+                return Err(nodes[expr].source.wrap(format!(
+                    "Expected constant expression for `{}`",
+                    name
+                )))
+            }
+            _ => {}
+        }
+        const_lookup.insert(name, i);
+    }
+
     // Stores number of functions arguments with same index as `functions`.
     // To look up number of arguments, use `.enumerate()` on the loop.
     let mut function_args: Vec<usize> = Vec::with_capacity(functions.len());
+    // Stores number of trailing arguments with default values, with same index as `functions`.
+    let mut function_defaults: Vec<usize> = Vec::with_capacity(functions.len());
 
     // Collect indices to call nodes.
     let calls: Vec<usize> = nodes
@@ -347,6 +393,8 @@ pub(crate) fn check_core(
         .collect();
 
     // Link items to their declaration.
+    // Stores items referring to constants.
+    let mut const_items: Vec<usize> = vec![];
     for &i in &items {
         // When `return` is used as variable one does not need to link.
         if nodes[i].name().map(|n| &**n == "return") == Some(true) {
@@ -364,6 +412,7 @@ pub(crate) fn check_core(
             if nodes[parent].kind.is_decl_loop()
                 || nodes[parent].kind.is_decl_un_loop()
                 || nodes[parent].kind.is_in_loop()
+                || nodes[parent].kind == Kind::Catch
             {
                 let my_name = nodes[i].name().unwrap();
                 for name in &nodes[parent].names {
@@ -374,6 +423,15 @@ pub(crate) fn check_core(
                 }
             }
 
+            if nodes[parent].kind == Kind::Arm {
+                let my_name = nodes[i].name().unwrap();
+                let pat = nodes[parent].children[0];
+                if let Some(decl) = find_pattern_declaration(nodes, pat, my_name) {
+                    it = Some(decl);
+                    break 'search;
+                }
+            }
+
             let me = nodes[parent]
                 .children
                 .binary_search(&child)
@@ -385,6 +443,22 @@ pub(crate) fn check_core(
                 }
                 // Assign is inside an expression.
                 let j = nodes[j].children[0];
+                if nodes[j].kind == Kind::Destructure {
+                    let my_name = nodes[i].name().unwrap();
+                    let pat = nodes[j].children[0];
+                    if let Some(decl) = find_pattern_declaration(nodes, pat, my_name) {
+                        if grab > 0 {
+                            return Err(nodes[i].source.wrap(format!(
+                                "Grabbed `{}` has same name as variable.\n\
+                                Perhaps the grab level is set too high?",
+                                my_name
+                            )));
+                        }
+                        it = Some(decl);
+                        break 'search;
+                    }
+                    continue;
+                }
                 if nodes[j].op != Some(AssignOp::Assign) {
                     continue;
                 }
@@ -467,10 +541,21 @@ pub(crate) fn check_core(
                         break;
                     }
                 }
+                let name = nodes[i].name().expect("Expected name").clone();
                 match found {
                     Some(j) => {
                         nodes[i].declaration = Some(j);
                     }
+                    None if const_lookup.contains_key(&name) => {
+                        nodes[i].declaration = Some(const_lookup[&name]);
+                        const_items.push(i);
+                    }
+                    None if prelude.constants.contains_key(&name) => {
+                        if !nodes[i].item_ids() {
+                            nodes[i].ty = Some(prelude.constants[&name].clone());
+                        }
+                        const_items.push(i);
+                    }
                     None => {
                         return Err(nodes[i].source.wrap(format!(
                             "Could not find declaration of `{}`",
@@ -482,6 +567,22 @@ pub(crate) fn check_core(
         }
     }
 
+    // Check that constants are not mutated.
+    for &i in &const_items {
+        let parent = nodes[i].parent.expect("Expected parent");
+        let mutated = match nodes[parent].kind {
+            Kind::Left => true,
+            Kind::CallArg => nodes[parent].mutable,
+            _ => false,
+        };
+        if mutated {
+            return Err(nodes[i].source.wrap(format!(
+                "Can not mutate constant `{}`",
+                nodes[i].name().expect("Expected name")
+            )));
+        }
+    }
+
     // Report ranges that can not be inferred.
     for &inf in &inferred {
         for name in &nodes[inf].names {
@@ -525,6 +626,7 @@ pub(crate) fn check_core(
     for &f in &functions {
         arg_names.clear();
         let mut n = 0;
+        let mut defaults = 0;
         for &i in nodes[f]
             .children
             .iter()
@@ -538,9 +640,33 @@ pub(crate) fn check_core(
             } else {
                 arg_names.insert(name.clone());
             }
+            if let Some(default) = nodes[i].find_child_by_kind(nodes, Kind::Default) {
+                // Default values are computed when loading.
+                if !constant(default, nodes) {
+                    return Err(nodes[default].source.wrap(format!(
+                        "Expected constant as default value of `{}`",
+                        name
+                    )));
+                }
+                defaults += 1;
+            } else if defaults > 0 {
+                return Err(nodes[i].source.wrap(format!(
+                    "Expected default value of `{}`, \
+                    because it comes after an argument with default value",
+                    name
+                )));
+            }
             n += 1;
         }
         function_args.push(n);
+        function_defaults.push(defaults);
+    }
+
+    // Check that `yield` is only used in generator functions.
+    for i in 0..nodes.len() {
+        if nodes[i].kind == Kind::Yield {
+            check_yield(i, nodes)?;
+        }
     }
 
     // Check extra type information.
@@ -618,20 +744,93 @@ pub(crate) fn check_core(
         }
     }
 
+    // Calls can leave out trailing arguments with default values.
+    // Stores function index and number of arguments.
+    let mut default_lookup: HashMap<Arc<String>, (usize, usize)> = HashMap::new();
+    for (i, &f) in functions.iter().enumerate() {
+        if function_defaults[i] == 0 {
+            continue;
+        }
+        let args: Vec<(&str, bool)> = nodes[f]
+            .children
+            .iter()
+            .filter(|&&a| nodes[a].kind == Kind::Arg)
+            .map(|&a| (&***nodes[a].name().expect("Expected name"), nodes[a].mutable))
+            .collect();
+        let name = nodes[f].name().expect("Expected name");
+        for (call_name, n) in default_call_names(name, &args, function_defaults[i]) {
+            default_lookup.entry(call_name).or_insert((i, n));
+        }
+    }
+
+    // Enum variants are constructors returning the ad-hoc type of the enum.
+    // Stores variant node and number of arguments.
+    let mut variant_lookup: HashMap<Arc<String>, (usize, usize)> = HashMap::new();
+    for i in 0..nodes.len() {
+        if nodes[i].kind != Kind::Variant {
+            continue;
+        }
+        let name = nodes[i].name().expect("Expected name").clone();
+        if function_lookup.contains_key(&name) || variant_lookup.contains_key(&name) {
+            return Err(nodes[i]
+                .source
+                .wrap(format!("Duplicate function `{}`", name)));
+        }
+        let enum_name = nodes[i]
+            .parent
+            .and_then(|p| nodes[p].name())
+            .expect("Expected enum name")
+            .clone();
+        nodes[i].ty = Some(Type::AdHoc(enum_name, Box::new(Type::Any)));
+        variant_lookup.insert(name, (i, nodes[i].children.len()));
+    }
+
     let mut use_lookup: UseLookup = UseLookup::new();
+    let mut uses = Uses { use_imports: vec![] };
     for node in nodes.iter() {
         if node.kind == Kind::Uses {
-            use crate::ast::Uses;
             use piston_meta::bootstrap::Convert;
 
             let convert = Convert::new(&data[node.start..node.end]);
             if let Ok((_, val)) = Uses::from_meta_data(convert, &mut vec![]) {
                 use_lookup = UseLookup::from_uses_prelude(&val, prelude);
+                uses = val;
             }
             break;
         }
     }
 
+    // Resolve type aliases, where each alias can refer to the ones declared before it.
+    let mut type_aliases = TypeAlias::lookup(&prelude.type_aliases, &uses);
+    let mut local_aliases: HashSet<Arc<String>> = HashSet::new();
+    for node in nodes.iter_mut() {
+        if node.kind != Kind::TypeAlias {
+            continue;
+        }
+        let name = node.name().expect("Expected name").clone();
+        if !local_aliases.insert(name.clone()) {
+            return Err(node
+                .source
+                .wrap(format!("Duplicate type alias `{}`", name)));
+        }
+        if let Some(ref mut ty) = node.ty {
+            ty.insert_aliases(&type_aliases);
+            type_aliases.insert(name, ty.clone());
+        }
+    }
+    for node in nodes.iter_mut() {
+        if let Some(ref mut ty) = node.ty {
+            if node.kind != Kind::TypeAlias {
+                ty.insert_aliases(&type_aliases);
+            }
+            if let Some(name) = ty.unresolved_alias() {
+                return Err(node
+                    .source
+                    .wrap(format!("Could not find type alias `{}`", name)));
+            }
+        }
+    }
+
     // Link call nodes to functions.
     for &c in &calls {
         let n = {
@@ -655,6 +854,15 @@ pub(crate) fn check_core(
             }
             sum
         };
+        // Method calls start with the first argument.
+        let receiver = nodes[c]
+            .children
+            .iter()
+            .find(|&&i| nodes[i].kind == Kind::CallArg)
+            .filter(|&&i| nodes[i].source.offset == nodes[c].source.offset)
+            .copied();
+        // A method call on an object might be meant as calling a closure field.
+        let closure_field = receiver.map(|i| may_be_object(i, nodes)).unwrap_or(false);
 
         let node = &mut nodes[c];
         let name = node.name().expect("Expected name").clone();
@@ -668,18 +876,44 @@ pub(crate) fn check_core(
                 node.lts = prelude.list[i].lts.clone();
                 continue;
             } else {
-                return Err(node
-                    .source
-                    .wrap(format!("Could not find function `{}::{}`", alias, name)));
+                return Err(node.source.wrap(format!(
+                    "Could not find function `{}::{}`{}",
+                    alias,
+                    name,
+                    method_hint(&name, closure_field)
+                )));
             }
         }
         let i = match function_lookup.get(&name) {
             Some(&i) => i,
             None => {
+                // Check whether arguments with default values are left out.
+                if let Some(&(i, args)) = default_lookup.get(&name) {
+                    if args != n {
+                        return Err(node.source.wrap(format!(
+                            "{}: Expected {} arguments, found {}",
+                            name, args, n
+                        )));
+                    }
+                    node.declaration = Some(functions[i]);
+                    continue;
+                }
+                // Check whether it is an enum variant.
+                if let Some(&(v, args)) = variant_lookup.get(&name) {
+                    if args != n {
+                        return Err(node.source.wrap(format!(
+                            "{}: Expected {} arguments, found {}",
+                            name, args, n
+                        )));
+                    }
+                    node.declaration = Some(v);
+                    continue;
+                }
                 // Check whether it is a prelude function.
                 if let Some(&pf) = prelude.functions.get(&name) {
                     node.lts = prelude.list[pf].lts.clone();
-                    if node.lts.len() != n {
+                    let defaults = prelude.defaults.get(&pf).copied().unwrap_or(0);
+                    if node.lts.len() < n || node.lts.len() > n + defaults {
                         return Err(node.source.wrap(format!(
                             "{}: Expected {} arguments, found {}",
                             name,
@@ -689,18 +923,39 @@ pub(crate) fn check_core(
                     }
                     continue;
                 }
+                // Method calls can not pass the receiver as mutable.
+                let mut_name = format!("{}(mut{})", name, ",_".repeat(n.saturating_sub(1)));
+                if receiver.is_some()
+                    && (function_lookup.contains_key(&mut_name)
+                        || prelude.functions.contains_key(&mut_name))
+                {
+                    return Err(node.source.wrap(format!(
+                        "`{0}` requires `mut` on the first argument, \
+                        which can not be used with method calls\n\
+                        Use `{0}(mut x, ...)` instead of `x.{0}(...)`",
+                        name
+                    )));
+                }
                 let suggestions = suggestions(&**name, &function_lookup, prelude);
-                return Err(node
-                    .source
-                    .wrap(format!("Could not find function `{}`{}", name, suggestions)));
+                return Err(node.source.wrap(format!(
+                    "Could not find function `{}`{}{}",
+                    name,
+                    suggestions,
+                    method_hint(&name, closure_field)
+                )));
             }
         };
-        // Check that number of arguments is the same as in declaration.
-        if function_args[i] != n {
+        // Check that number of arguments is the same as in declaration,
+        // except for trailing arguments with default values.
+        if function_args[i] < n || function_args[i] > n + function_defaults[i] {
             let suggestions = suggestions(&**name, &function_lookup, prelude);
             return Err(node.source.wrap(format!(
-                "{}: Expected {} arguments, found {}{}",
-                name, function_args[i], n, suggestions
+                "{}: Expected {} arguments, found {}{}{}",
+                name,
+                function_args[i],
+                n,
+                suggestions,
+                method_hint(&name, closure_field)
             )));
         }
         node.declaration = Some(functions[i]);
@@ -935,6 +1190,10 @@ pub(crate) fn check_core(
                 .enumerate()
                 .filter(|&(_, &i)| nodes[i].kind == Kind::Arg)
             {
+                if i >= call.children.len() {
+                    // Arguments with default values are left out.
+                    break;
+                }
                 let arg = &nodes[a];
                 if arg.lifetime.is_some() {
                     return Err(nodes[call.children[i]].source.wrap(
@@ -1023,6 +1282,10 @@ pub(crate) fn check_core(
                 .filter(|&(_, &i)| nodes[i].kind == Kind::Arg)
                 .map(|(i, a)| (map_arg_call_arg_index(i), a))
             {
+                if i >= call.children.len() {
+                    // Arguments with default values are left out.
+                    break;
+                }
                 let arg = &nodes[a];
                 if let Some(ref lt) = arg.lifetime {
                     // When arguments should outlive the return value,
@@ -1044,6 +1307,10 @@ pub(crate) fn check_core(
                         let (_, ind) = *arg_names
                             .get(&(declaration, lt.clone()))
                             .expect("Expected argument name");
+                        if ind >= call.children.len() {
+                            // Default values are constants.
+                            continue;
+                        }
                         let left = call.children[ind];
                         let right = call.children[i];
                         let lifetime_left = &nodes[left].lifetime(nodes, &arg_names);
@@ -1061,6 +1328,10 @@ pub(crate) fn check_core(
                 .enumerate()
                 .map(|(i, a)| (map_arg_call_arg_index(i), a))
             {
+                if i >= call.children.len() {
+                    // Arguments with default values are left out.
+                    break;
+                }
                 let arg = &nodes[call.children[i]];
                 match lt {
                     Lt::Default => {}
@@ -1077,6 +1348,10 @@ pub(crate) fn check_core(
                                 .source
                                 .wrap("Requires reference to variable".to_string()));
                         }
+                        if ind >= call.children.len() {
+                            // Default values are constants.
+                            continue;
+                        }
 
                         let left = call.children[ind];
                         let right = call.children[i];
@@ -1141,7 +1416,8 @@ pub(crate) fn check_core(
         }
     }
 
-    typecheck::run(nodes, prelude, &use_lookup)?;
+    typecheck::run(nodes, prelude, &use_lookup, warnings, overloads)?;
+    node::warnings(nodes, warnings);
 
     // Copy refined return types to use in AST.
     let mut refined_rets: HashMap<Arc<String>, Type> = HashMap::new();
@@ -1154,6 +1430,60 @@ pub(crate) fn check_core(
     Ok(refined_rets)
 }
 
+// Checks that `yield` is used in a generator function.
+//
+// Generators are suspended and resumed at `yield` through blocks, `if` and loops,
+// so `yield` must be a statement that is only inside these.
+#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+fn check_yield(i: usize, nodes: &[Node]) -> Result<(), Range<String>> {
+    let f = nodes[i].find_fn(nodes).expect("Expected function");
+    if nodes[f].kind == Kind::Closure {
+        return Err(nodes[i].source.wrap("Can not use `yield` in closure".to_string()));
+    }
+    if !matches!(nodes[f].ty, Some(Type::In(_)) | Some(Type::Any)) {
+        return Err(nodes[i].source.wrap(format!(
+            "Can not use `yield` outside generator, requires `-> in[T]` on `{}`",
+            nodes[f].name().expect("Expected name")
+        )));
+    }
+    let mut stmt = i;
+    loop {
+        let block = nodes[stmt]
+            .parent
+            .filter(|&expr| nodes[expr].kind == Kind::Expr)
+            .and_then(|expr| nodes[expr].parent)
+            .filter(|&block| {
+                matches!(
+                    nodes[block].kind,
+                    Kind::Block | Kind::TrueBlock | Kind::ElseIfBlock | Kind::ElseBlock
+                )
+            });
+        let parent = block.and_then(|block| nodes[block].parent);
+        stmt = match (block, parent.map(|p| nodes[p].kind)) {
+            (Some(_), Some(Kind::Fn)) => return Ok(()),
+            (Some(block), Some(Kind::Expr)) => block,
+            (Some(_), Some(Kind::If | Kind::For | Kind::ForN | Kind::ForIn | Kind::Loop)) => {
+                parent.unwrap()
+            }
+            _ => {
+                return Err(nodes[i].source.wrap(
+                    "Can not use `yield` here, \
+                    it must be a statement in blocks, `if` or loops"
+                        .to_string(),
+                ))
+            }
+        };
+    }
+}
+
+// Generators return in-types, so `yield` requires the `threading` feature.
+#[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+fn check_yield(i: usize, nodes: &[Node]) -> Result<(), Range<String>> {
+    Err(nodes[i]
+        .source
+        .wrap("Generators require the `threading` feature".to_string()))
+}
+
 // Search for suggestions using matching function signature.
 // Meant to be put last in error message.
 fn suggestions(
@@ -1192,6 +1522,55 @@ fn suggestions(
     }
 }
 
+// Returns `true` if the receiver of a method call might be an object.
+//
+// Looks through declarations of items to the assigned expression,
+// and treats unknown types as possible objects.
+fn may_be_object(mut i: usize, nodes: &[Node]) -> bool {
+    while matches!(nodes[i].kind, Kind::CallArg | Kind::Expr | Kind::Right)
+        && nodes[i].children.len() == 1
+    {
+        i = nodes[i].children[0];
+    }
+    match nodes[i].kind {
+        Kind::Item if nodes[i].children.is_empty() => match nodes[i].declaration {
+            Some(decl) if decl != i && nodes[decl].kind == Kind::Item => {
+                // Find the right side of the declaring assignment.
+                let right = nodes[decl]
+                    .parent
+                    .and_then(|left| nodes[left].parent)
+                    .filter(|&assign| nodes[assign].kind == Kind::Assign)
+                    .and_then(|assign| nodes[assign].find_child_by_kind(nodes, Kind::Right));
+                match right {
+                    Some(right) => may_be_object(right, nodes),
+                    None => true,
+                }
+            }
+            Some(decl) => may_be_object_type(nodes[decl].ty.as_ref()),
+            None => true,
+        },
+        _ => may_be_object_type(nodes[i].ty.as_ref()),
+    }
+}
+
+fn may_be_object_type(ty: Option<&Type>) -> bool {
+    ty.map(|ty| Type::Object.goes_with(ty)).unwrap_or(true)
+}
+
+// Explains that a method call could also be meant as calling a closure field.
+// Meant to be put last in error message.
+fn method_hint(name: &str, closure_field: bool) -> String {
+    if !closure_field {
+        return String::from("");
+    }
+    let name = name.split('(').next().unwrap_or(name);
+    format!(
+        "\n\n`x.{0}(...)` calls function `{0}` with `x` as first argument.\n\
+        To call a closure stored in the field `x.{0}`, use `\\x.{0}(...)`",
+        name
+    )
+}
+
 /// Maps (function, argument_name) => (argument, index)
 pub type ArgNames = HashMap<(usize, Arc<String>), (usize, usize)>;
 
@@ -1255,3 +1634,32 @@ pub(crate) fn to_array(nodes: &[Node]) -> Vec<crate::Variable> {
     }
     res
 }
+
+/// Searches a pattern for the node that binds a name.
+fn find_pattern_declaration(nodes: &[Node], pat: usize, name: &Arc<String>) -> Option<usize> {
+    let node = &nodes[pat];
+    if !node.kind.is_pattern() {
+        return None;
+    }
+    if node.children.is_empty() {
+        // Object keys without sub-pattern bind the key.
+        if node.names.iter().any(|n| n == name && &***n != "_") {
+            return Some(pat);
+        }
+        return None;
+    }
+    for &ch in &node.children {
+        if let Some(decl) = find_pattern_declaration(nodes, ch, name) {
+            return Some(decl);
+        }
+        // Arrays, objects and vectors contain patterns.
+        if let Kind::PatArr | Kind::PatObj | Kind::PatVec4 = nodes[ch].kind {
+            for &ch in &nodes[ch].children {
+                if let Some(decl) = find_pattern_declaration(nodes, ch, name) {
+                    return Some(decl);
+                }
+            }
+        }
+    }
+    None
+}
@@ -4,8 +4,9 @@ use super::piston_meta::bootstrap::Convert;
 use super::piston_meta::MetaData;
 use super::ArgNames;
 use range::Range;
+use std::collections::HashSet;
 use std::sync::Arc;
-use crate::ast::{AssignOp, BinOp};
+use crate::ast::{parse_int, parse_range_num, AssignOp, BinOp};
 use crate::{Lt, Type};
 
 #[derive(Debug)]
@@ -22,6 +23,8 @@ pub(crate) struct Node {
     pub mutable: bool,
     /// Whether there is a `?` operator used on the node.
     pub try_flag: bool,
+    /// Whether the item refers to a current object, e.g. `~ x`.
+    pub current: bool,
     /// The grab level.
     pub grab_level: u16,
     /// The range in source.
@@ -40,8 +43,10 @@ pub(crate) struct Node {
     pub declaration: Option<usize>,
     /// Operation.
     pub op: Option<AssignOp>,
-    /// Binary operators.
-    pub binops: Vec<BinOp>,
+    /// Binary operators, with the range of the operator in source.
+    ///
+    /// Calls rewritten from binary operators keep their operator.
+    pub binops: Vec<(BinOp, Range)>,
     /// The argument lifetime constraints, one for each argument to a function.
     /// Just using an empty vector for nodes that are not functions.
     pub lts: Vec<Lt>,
@@ -66,7 +71,6 @@ impl Node {
     pub fn rewrite_binop(i: usize, name: Arc<String>, nodes: &mut Vec<Node>) {
         nodes[i].kind = Kind::Call;
         nodes[i].names.push(name);
-        nodes[i].binops.clear();
 
         let old_left = nodes[i].children[0];
         let old_right = nodes[i].children[1];
@@ -80,6 +84,7 @@ impl Node {
             alias: None,
             mutable: false,
             try_flag: false,
+            current: false,
             grab_level: 0,
             source: nodes[old_left].source,
             start: nodes[old_left].start,
@@ -100,6 +105,7 @@ impl Node {
             alias: None,
             mutable: false,
             try_flag: false,
+            current: false,
             grab_level: 0,
             source: nodes[old_right].source,
             start: nodes[old_right].start,
@@ -169,16 +175,30 @@ impl Node {
         None
     }
 
+    /// Finds the function or closure that contains the node.
+    pub fn find_fn(&self, nodes: &[Node]) -> Option<usize> {
+        let mut parent = self.parent;
+        while let Some(p) = parent {
+            if nodes[p].kind == Kind::Fn || nodes[p].kind == Kind::Closure {
+                return Some(p);
+            }
+            parent = nodes[p].parent;
+        }
+        None
+    }
+
+    /// Returns `true` if the item looks up ids with unknown type.
+    ///
+    /// Items using `?.` get the type `opt` when converting from meta data.
     pub fn item_ids(&self) -> bool {
-        self.kind == Kind::Item && !self.children.is_empty()
+        self.kind == Kind::Item && !self.children.is_empty() && self.ty.is_none()
     }
 
     pub fn inner_type(&self, ty: &Type) -> Type {
         if self.try_flag {
-            match ty {
-                &Type::Option(ref ty) => (**ty).clone(),
-                &Type::Result(ref ty) => (**ty).clone(),
-                x => x.clone(),
+            match ty.unalias() {
+                Type::Option(ty) | Type::Result(ty) => *ty,
+                x => x,
             }
         } else {
             ty.clone()
@@ -192,7 +212,9 @@ impl Node {
             Pow | Sum | SumIn | Prod | ProdIn | SumVec4 | Min | MinIn | Max | MaxIn | Any
             | AnyIn | All | AllIn | LinkIn | Vec4 | Mat4 | Vec4UnLoop | Swizzle | Assign | For
             | ForN | ForIn | Link | LinkFor | Closure | CallClosure | Grab | TryExpr | Norm
-            | In => false,
+            | In | Pat | PatSome | PatNone | PatOk | PatErr | PatArr | PatObj | PatKey
+            | PatVariant | PatVec4 | Enum | Variant | Interp | Destructure | Default
+            | Const | TypeAlias | Yield | Coalesce | Defer | Range => false,
             Add | Mul | Compare => self.children.len() == 1,
             _ => true,
         }
@@ -207,6 +229,10 @@ impl Node {
         if !self.has_lifetime() {
             return Err(LifetimeError::None);
         }
+        if self.kind == Kind::Item && !self.children.is_empty() && !self.item_ids() {
+            // Items using `?.` are copied into `some(_)`.
+            return Err(LifetimeError::None);
+        }
         if let Some(declaration) = self.declaration {
             if self.kind == Kind::Item {
                 let arg = &nodes[declaration];
@@ -214,6 +240,19 @@ impl Node {
                     return arg_lifetime(declaration, arg, nodes, arg_names);
                 } else if arg.kind == Kind::Current {
                     return Ok(Lifetime::Current(declaration));
+                } else if arg.kind.is_pattern() || arg.kind == Kind::Const {
+                    // Values bound by patterns are copies.
+                    // Constants live as long as the module.
+                    return Err(LifetimeError::None);
+                } else if arg.kind.is_in_loop() {
+                    // Elements live as long as the collection.
+                    if let Some(iter) = arg.find_child_by_kind(nodes, Kind::Iter) {
+                        match nodes[iter].lifetime(nodes, arg_names) {
+                            Err(LifetimeError::FailedToUnify) => {}
+                            x => return x,
+                        }
+                    }
+                    return Ok(Lifetime::Local(declaration));
                 } else {
                     return Ok(Lifetime::Local(declaration));
                 }
@@ -247,6 +286,9 @@ impl Node {
             } else if self.kind == Kind::Item && self.name().map(|n| &**n == "return") == Some(true)
             {
                 return Ok(Lifetime::Return(vec![]));
+            } else if self.kind == Kind::Item {
+                // Constants from imported modules live as long as the module.
+                return Err(LifetimeError::None);
             }
         }
 
@@ -265,6 +307,8 @@ impl Node {
                 (_, Kind::Loop) => {}
                 #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
                 (_, Kind::Go) => {}
+                #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+                (_, Kind::Yield) => {}
                 (_, Kind::For) => {}
                 (_, Kind::ForN) => {}
                 (_, Kind::ForIn) => {}
@@ -290,11 +334,19 @@ impl Node {
                 (_, Kind::Vec4UnLoop) => {}
                 (_, Kind::Vec4) => {}
                 (_, Kind::Mat4) => {}
+                (_, Kind::Interp) => {}
                 (_, Kind::Start) => continue,
                 (_, Kind::End) => continue,
                 (_, Kind::Assign) => {}
+                (_, Kind::Destructure) => {}
                 (_, Kind::Object) => {}
                 (_, Kind::KeyValue) => {}
+                (_, Kind::Map) => {}
+                (_, Kind::Set) => {}
+                (_, Kind::Key) => {
+                    // Keys are copied into the map or set.
+                    continue;
+                }
                 (_, Kind::Val) => {}
                 (_, Kind::Add) => {}
                 (_, Kind::Mul) => {}
@@ -305,11 +357,21 @@ impl Node {
                 (_, Kind::CallClosure) => {}
                 (_, Kind::Grab) => {}
                 (_, Kind::TryExpr) => {}
+                (Kind::Match, Kind::Expr) => {
+                    // The matched value is copied when bound.
+                    continue;
+                }
+                (_, Kind::Match) => {}
+                (_, Kind::Arm) => {}
+                (Kind::Arm, Kind::Pat) => continue,
                 (_, Kind::Arg) => continue,
+                (_, Kind::Default) => continue,
                 (_, Kind::Current) => continue,
                 (Kind::CallClosure, Kind::Item) => continue,
                 (_, Kind::Item) => {}
                 (_, Kind::Norm) => {}
+                (_, Kind::Coalesce) => {}
+                (_, Kind::Range) => {}
                 (_, Kind::Compare) => {
                     // The result of all compare operators does not depend
                     // on the lifetime of the arguments.
@@ -324,6 +386,10 @@ impl Node {
                 (_, Kind::ArrayFill) => {}
                 (_, Kind::Pow) => {}
                 (_, Kind::Block) => {}
+                // A deferred block does not return a value.
+                (_, Kind::Defer) => continue,
+                (_, Kind::TryCatch) => {}
+                (_, Kind::Catch) => {}
                 (_, Kind::If) => {}
                 (_, Kind::TrueBlock) => {}
                 (_, Kind::ElseIfBlock) => {}
@@ -412,7 +478,16 @@ pub(crate) fn convert_meta_data(
                 // Parse type information and put it in parent node.
                 if kind == Kind::Type || kind == Kind::RetType {
                     let convert = Convert::new(&data[i..]);
-                    if let Ok((range, val)) = Type::from_meta_data(kind_name, convert, ignored) {
+                    if let Ok((range, mut val)) =
+                        Type::from_meta_data(kind_name, convert, ignored)
+                    {
+                        // Type parameters are stored after the name of generic functions.
+                        match parents.first() {
+                            Some(&f) if nodes[f].kind == Kind::Fn && nodes[f].names.len() > 1 => {
+                                val.insert_params(&nodes[f].names[1..]);
+                            }
+                            _ => {}
+                        }
                         let parent = *parents.last().unwrap();
                         nodes[parent].ty = Some(val);
                         skip = Some(range.next_offset() + i);
@@ -428,9 +503,18 @@ pub(crate) fn convert_meta_data(
                     #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
                     Kind::In => Some(Type::In(Box::new(Type::array()))),
                     Kind::Object => Some(Type::object()),
+                    Kind::Map => Some(Type::map()),
+                    Kind::Set => Some(Type::set()),
+                    Kind::Range => Some(Type::Range),
+                    // The error variable declared by `catch`.
+                    Kind::Catch => Some(Type::Result(Box::new(Type::Any))),
                     Kind::Sift | Kind::SiftIn => Some(Type::array()),
                     Kind::Sum | Kind::SumIn | Kind::Prod | Kind::ProdIn => Some(Type::F64),
                     Kind::Swizzle => Some(Type::F64),
+                    Kind::Interp => Some(Type::Str),
+                    Kind::Destructure | Kind::Defer => Some(Type::Void),
+                    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+                    Kind::Yield => Some(Type::Void),
                     Kind::Link | Kind::LinkFor => Some(Type::Link),
                     Kind::Any | Kind::AnyIn | Kind::All | Kind::AllIn => {
                         Some(Type::Secret(Box::new(Type::Bool)))
@@ -463,6 +547,7 @@ pub(crate) fn convert_meta_data(
                     ty,
                     mutable: false,
                     try_flag: false,
+                    current: false,
                     grab_level: 0,
                     source: Range::empty(0),
                     parent,
@@ -493,7 +578,7 @@ pub(crate) fn convert_meta_data(
                         let i = *parents.last().unwrap();
                         nodes[i].alias = Some(val.clone());
                     }
-                    "name" => {
+                    "name" | "value" => {
                         let i = *parents.last().unwrap();
                         nodes[i].names.push(val.clone());
                     }
@@ -519,16 +604,56 @@ pub(crate) fn convert_meta_data(
                     "text" => {
                         let i = *parents.last().unwrap();
                         nodes[i].ty = Some(Type::Str);
+                        // Use names as a way of storing text for narrowing by `typeof`.
+                        if nodes[i].kind == Kind::Expr {
+                            nodes[i].names.push(val.clone());
+                        }
                     }
                     "color" => {
                         let i = *parents.last().unwrap();
                         nodes[i].ty = Some(Type::Vec4);
                     }
+                    "int" => {
+                        if parse_int(val).is_none() {
+                            return Err(d.range().wrap(format!("Invalid `i64` literal `{}i`", val)));
+                        }
+                        let i = *parents.last().unwrap();
+                        nodes[i].ty = Some(Type::I64);
+                    }
+                    "range_num" => {
+                        if parse_range_num(val).is_none() {
+                            return Err(d.range().wrap(format!("Invalid number `{}`", val)));
+                        }
+                        let i = *parents.last().unwrap();
+                        nodes[i].ty = Some(Type::F64);
+                    }
+                    "ty_param" => {
+                        // Use names as a way of storing type parameters.
+                        let i = *parents.last().unwrap();
+                        nodes[i].names.push(val.clone());
+                    }
                     "ty_var" => {
                         // Use names as a way of storing type variables.
                         let i = *parents.last().unwrap();
                         nodes[i].names.push(val.clone());
                     }
+                    "bind" => {
+                        let i = *parents.last().unwrap();
+                        nodes[i].names.push(val.clone());
+                    }
+                    "key" => {
+                        let i = *parents.last().unwrap();
+                        if nodes[i].kind == Kind::PatKey || nodes[i].kind == Kind::KeyValue {
+                            nodes[i].names.push(val.clone());
+                        }
+                    }
+                    "id" => {
+                        // Store field names used to look up the type of records.
+                        let i = *parents.last().unwrap();
+                        if nodes[i].kind == Kind::ItemExtra {
+                            nodes[i].names.push(val.clone());
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -570,10 +695,21 @@ pub(crate) fn convert_meta_data(
                         let i = *parents.last().unwrap();
                         nodes[i].mutable = _val;
                     }
-                    "try" | "try_item" => {
+                    "current" => {
+                        let i = *parents.last().unwrap();
+                        nodes[i].current = _val;
+                    }
+                    "try" | "try_item" | "try_id" => {
                         let i = *parents.last().unwrap();
                         nodes[i].try_flag = _val;
                     }
+                    "opt_id" => {
+                        // The item extra belongs to an item.
+                        let i = *parents.last().unwrap();
+                        if let Some(item) = nodes[i].parent {
+                            nodes[item].ty = Some(Type::option());
+                        }
+                    }
                     "bool" => {
                         let i = *parents.last().unwrap();
                         nodes[i].ty = Some(Type::Bool);
@@ -596,67 +732,67 @@ pub(crate) fn convert_meta_data(
                     }
                     "*." => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::Dot);
+                        nodes[i].binops.push((BinOp::Dot, d.range()));
                     }
                     "x" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::Cross);
+                        nodes[i].binops.push((BinOp::Cross, d.range()));
                     }
                     "*" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::Mul);
+                        nodes[i].binops.push((BinOp::Mul, d.range()));
                     }
                     "/" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::Div);
+                        nodes[i].binops.push((BinOp::Div, d.range()));
                     }
                     "%" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::Rem);
+                        nodes[i].binops.push((BinOp::Rem, d.range()));
                     }
                     "^" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::Pow);
+                        nodes[i].binops.push((BinOp::Pow, d.range()));
                     }
                     "&&" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::AndAlso);
+                        nodes[i].binops.push((BinOp::AndAlso, d.range()));
                     }
                     "+" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::Add);
+                        nodes[i].binops.push((BinOp::Add, d.range()));
                     }
                     "-" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::Sub);
+                        nodes[i].binops.push((BinOp::Sub, d.range()));
                     }
                     "||" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::OrElse);
+                        nodes[i].binops.push((BinOp::OrElse, d.range()));
                     }
                     "<" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::Less);
+                        nodes[i].binops.push((BinOp::Less, d.range()));
                     }
                     "<=" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::LessOrEqual);
+                        nodes[i].binops.push((BinOp::LessOrEqual, d.range()));
                     }
                     ">" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::Greater);
+                        nodes[i].binops.push((BinOp::Greater, d.range()));
                     }
                     ">=" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::GreaterOrEqual);
+                        nodes[i].binops.push((BinOp::GreaterOrEqual, d.range()));
                     }
                     "==" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::Equal);
+                        nodes[i].binops.push((BinOp::Equal, d.range()));
                     }
                     "!=" => {
                         let i = *parents.last().unwrap();
-                        nodes[i].binops.push(BinOp::NotEqual);
+                        nodes[i].binops.push((BinOp::NotEqual, d.range()));
                     }
                     _ => {}
                 }
@@ -666,6 +802,13 @@ pub(crate) fn convert_meta_data(
                     let i = *parents.last().unwrap();
                     nodes[i].ty = Some(Type::F64);
                 }
+                "id" => {
+                    // Numeric ids index into arrays, so the field names are not complete.
+                    let i = *parents.last().unwrap();
+                    if nodes[i].kind == Kind::ItemExtra {
+                        nodes[i].ty = Some(Type::F64);
+                    }
+                }
                 "grab_level" => {
                     if val < 1.0 {
                         return Err(d
@@ -681,3 +824,129 @@ pub(crate) fn convert_meta_data(
     }
     Ok(())
 }
+
+/// Returns `true` if an expression can be computed when loading.
+///
+/// Used for default values of arguments, which are literals or collections of literals.
+pub(crate) fn constant(i: usize, nodes: &[Node]) -> bool {
+    let node = &nodes[i];
+    match node.kind {
+        Kind::Expr if node.children.is_empty() => node.ty.is_some(),
+        Kind::Add | Kind::Mul if node.children.len() != 1 => false,
+        Kind::Interp => node.children.is_empty(),
+        // Negative numbers are rewritten into calls to `neg`.
+        Kind::Call => {
+            node.name() == Some(&*crate::NEG) && node.children.iter().all(|&ch| constant(ch, nodes))
+        }
+        Kind::Expr | Kind::Add | Kind::Mul | Kind::CallArg | Kind::Default | Kind::Const
+        | Kind::Val | Kind::Array | Kind::ArrayItem | Kind::ArrayFill | Kind::Fill | Kind::N
+        | Kind::Object | Kind::KeyValue | Kind::Map | Kind::Set | Kind::Key | Kind::Vec4
+        | Kind::X | Kind::Y | Kind::Z | Kind::W | Kind::Link | Kind::LinkItem => {
+            node.children.iter().all(|&ch| constant(ch, nodes))
+        }
+        _ => false,
+    }
+}
+
+/// Collects warnings about unused variables, unreachable statements and shadowing.
+///
+/// Variables starting with `_` are not reported as unused.
+pub(crate) fn warnings(nodes: &[Node], warnings: &mut Vec<Range<String>>) {
+    let used: HashSet<usize> = nodes
+        .iter()
+        .filter(|n| n.kind == Kind::Item)
+        .filter_map(|n| n.declaration)
+        .collect();
+    let unused = |i: usize| {
+        !used.contains(&i) && nodes[i].name().map(|n| !n.starts_with('_')) == Some(true)
+    };
+
+    for (i, node) in nodes.iter().enumerate() {
+        match node.kind {
+            Kind::Arg if unused(i) => {
+                warnings.push(node.source.wrap(format!(
+                    "Unused argument `{}`",
+                    node.name().expect("Expected name")
+                )));
+            }
+            kind if kind.is_block() => {
+                for (j, &stmt) in node.children.iter().enumerate() {
+                    if let Some(item) = local_declaration(stmt, nodes) {
+                        if unused(item) {
+                            warnings.push(nodes[item].source.wrap(format!(
+                                "Unused variable `{}`",
+                                nodes[item].name().expect("Expected name")
+                            )));
+                        }
+                        if shadowed(i, j, nodes).is_some() {
+                            warnings.push(nodes[item].source.wrap(format!(
+                                "Declaration of `{}` shadows an earlier declaration",
+                                nodes[item].name().expect("Expected name")
+                            )));
+                        }
+                    }
+                }
+                // Report the first statement after one that always exits.
+                let exit = node.children.iter().position(|&stmt| {
+                    match nodes[stmt].children.first().map(|&ch| nodes[ch].kind) {
+                        Some(Kind::Return | Kind::ReturnVoid | Kind::Break | Kind::Continue) => {
+                            true
+                        }
+                        Some(Kind::Match) => nodes[stmt].ty == Some(Type::Unreachable),
+                        _ => false,
+                    }
+                });
+                if let Some(&next) = exit.and_then(|k| node.children.get(k + 1)) {
+                    warnings.push(nodes[next].source.wrap("Unreachable statement".into()));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the item declared by a statement, e.g. `x` in `x := 2`.
+fn local_declaration(stmt: usize, nodes: &[Node]) -> Option<usize> {
+    let assign = *nodes[stmt].children.first()?;
+    if nodes[assign].op != Some(AssignOp::Assign) {
+        return None;
+    }
+    let left = nodes[assign].find_child_by_kind(nodes, Kind::Left)?;
+    let item = *nodes[left].children.first()?;
+    if nodes[item].kind == Kind::Item && !nodes[item].current && !nodes[item].item_ids() {
+        Some(item)
+    } else {
+        None
+    }
+}
+
+/// Finds a declaration in an enclosing scope with the same name as the one
+/// declared by statement `j` of block `block`.
+fn shadowed(block: usize, j: usize, nodes: &[Node]) -> Option<usize> {
+    let item = local_declaration(nodes[block].children[j], nodes)?;
+    let name = nodes[item].name()?;
+    let mut parent = block;
+    let mut me = 0;
+    loop {
+        // Redeclaring a variable in the same block is not reported.
+        for &stmt in nodes[parent].children[..me].iter().rev() {
+            match local_declaration(stmt, nodes) {
+                Some(decl) if nodes[decl].name() == Some(name) => return Some(decl),
+                _ => {}
+            }
+        }
+        if nodes[parent].kind.is_decl_loop() && nodes[parent].names.contains(name) {
+            return Some(parent);
+        }
+        if matches!(nodes[parent].kind, Kind::Fn | Kind::Closure) {
+            return nodes[parent]
+                .children
+                .iter()
+                .copied()
+                .find(|&arg| nodes[arg].kind == Kind::Arg && nodes[arg].name() == Some(name));
+        }
+        let grand_parent = nodes[parent].parent?;
+        me = nodes[grand_parent].children.iter().position(|&ch| ch == parent)?;
+        parent = grand_parent;
+    }
+}
@@ -3,7 +3,10 @@ use super::node::Node;
 use range::Range;
 use crate::ast::UseLookup;
 use crate::{Prelude, Type};
+use std::collections::HashMap;
+use std::sync::Arc;
 
+mod overload;
 mod refine;
 
 /// Runs type checking.
@@ -47,8 +50,10 @@ pub(crate) fn run(
     nodes: &mut Vec<Node>,
     prelude: &Prelude,
     use_lookup: &UseLookup,
+    warnings: &mut Vec<Range<String>>,
+    overloads: &mut HashMap<Range, Arc<String>>,
 ) -> Result<(), Range<String>> {
-    use std::collections::HashMap;
+    let candidates = overload::candidates(nodes, prelude);
 
     // Keep an extra todo-list for nodes that are affected by type refinement.
     let mut todo: Vec<usize> = (0..nodes.len()).collect();
@@ -79,6 +84,16 @@ pub(crate) fn run(
                         }
                     }
                 }
+                Kind::Const => {
+                    if nodes[i].ty.is_some() {
+                        // No further work is required for constant with type.
+                        continue 'node;
+                    }
+                    // Infer type from value.
+                    if let Some(ch) = nodes[i].find_child_by_kind(nodes, Kind::Expr) {
+                        this_ty = nodes[ch].ty.clone();
+                    }
+                }
                 Kind::Fn => {
                     if nodes[i].ty.is_some() {
                         // No further work is required for function.
@@ -236,7 +251,19 @@ pub(crate) fn run(
                     this_ty = expr_type;
                 }
                 Kind::Call => {
-                    if let Some(decl) = nodes[i].declaration {
+                    // Pick operator overload from the ad-hoc types of arguments.
+                    let picked = match nodes[i].name().and_then(|n| candidates.get(&***n)) {
+                        Some(list) if nodes[i].alias.is_none() => overload::pick(i, list, nodes),
+                        _ => None,
+                    };
+                    if let Some(f) = picked {
+                        // Binary operators are looked up by the range of the operator.
+                        let range = nodes[i].binops.first().map(|op| op.1);
+                        overloads.insert(range.unwrap_or(nodes[i].source), f.name.clone());
+                        nodes[i].names[0] = f.name.clone();
+                        nodes[i].declaration = f.decl;
+                        this_ty = Some(f.ret.clone());
+                    } else if let Some(decl) = nodes[i].declaration {
                         refine::declaration(i, decl, nodes, &mut todo, &mut this_ty)?;
 
                         // If the type has not been refined, fall back to default type signature.
@@ -245,6 +272,22 @@ pub(crate) fn run(
                                 this_ty = Some(ty.clone());
                             }
                         }
+
+                        // Bind type parameters of generic function.
+                        let tys: Vec<Type> = nodes[decl]
+                            .children
+                            .iter()
+                            .filter(|&&arg| nodes[arg].kind == Kind::Arg)
+                            .map(|&arg| nodes[arg].ty.clone().unwrap_or(Type::Any))
+                            .collect();
+                        match nodes[decl].ty {
+                            Some(ref ret)
+                                if ret.has_params() || tys.iter().any(|ty| ty.has_params()) =>
+                            {
+                                refine::params(i, &tys, ret, nodes, &mut todo, &mut this_ty)?;
+                            }
+                            _ => {}
+                        }
                     } else if let Some(ref alias) = nodes[i].alias {
                         use crate::ast::FnAlias;
 
@@ -268,7 +311,10 @@ pub(crate) fn run(
                         }
                     } else if let Some(&f) = prelude.functions.get(nodes[i].name().unwrap()) {
                         let f = &prelude.list[f];
-                        if f.ext.is_empty() {
+                        if f.ret.has_params() || f.tys.iter().any(|ty| ty.has_params()) {
+                            // Bind type parameters of generic function.
+                            refine::params(i, &f.tys, &f.ret, nodes, &mut todo, &mut this_ty)?;
+                        } else if f.ext.is_empty() {
                             this_ty = Some(f.ret.clone());
                         } else {
                             refine::prelude(i, f, nodes, &mut todo, &mut this_ty)?;
@@ -360,10 +406,24 @@ pub(crate) fn run(
                 }
                 Kind::Item => {
                     if nodes[i].item_ids() {
-                        todo.push(i);
-                        continue 'node;
-                    }
-                    if let Some(decl) = nodes[i].declaration {
+                        match field_type(i, nodes)? {
+                            Some(ty) => this_ty = Some(ty),
+                            None => {
+                                todo.push(i);
+                                continue 'node;
+                            }
+                        }
+                    } else if !nodes[i].children.is_empty() {
+                        // Items using `?.` are `opt`.
+                        match opt_chain_type(i, nodes)? {
+                            Some(ty) if nodes[i].ty.as_ref() != Some(&ty) => this_ty = Some(ty),
+                            Some(_) => continue 'node,
+                            None => {
+                                todo.push(i);
+                                continue 'node;
+                            }
+                        }
+                    } else if let Some(decl) = nodes[i].declaration {
                         match nodes[decl].kind {
                             Kind::Sum
                             | Kind::Min
@@ -390,6 +450,33 @@ pub(crate) fn run(
                                         .inner_type(nodes[decl].ty.as_ref().unwrap_or(&Type::Any)),
                                 );
                             }
+                            kind if kind.is_in_loop() => {
+                                let iter = match nodes[decl].find_child_by_kind(nodes, Kind::Iter) {
+                                    Some(x) => x,
+                                    None => continue 'node,
+                                };
+                                let iter_ty = match nodes[iter].ty {
+                                    None => {
+                                        todo.push(i);
+                                        continue 'node;
+                                    }
+                                    Some(ref ty) => ty.clone(),
+                                };
+                                if let Some((key, val)) = in_loop_types(&iter_ty) {
+                                    // A single loop variable gets the key of object or map entries.
+                                    let names = &nodes[decl].names;
+                                    let use_key = if names.len() > 1 {
+                                        nodes[i].name() == Some(&names[0])
+                                    } else {
+                                        matches!(
+                                            iter_ty,
+                                            Type::Object | Type::Record(_) | Type::Map(..)
+                                        )
+                                    };
+                                    let ty = if use_key { key } else { val };
+                                    this_ty = Some(nodes[i].inner_type(&ty));
+                                }
+                            }
                             _ => {
                                 if let Some(ref ty) = nodes[decl].ty {
                                     this_ty = Some(nodes[i].inner_type(ty));
@@ -413,6 +500,9 @@ pub(crate) fn run(
                             }
                         }
                     }
+                    if nodes[i].children.is_empty() {
+                        this_ty = this_ty.map(|ty| narrow(i, ty, nodes));
+                    }
                 }
                 Kind::Return
                 | Kind::Val
@@ -422,6 +512,8 @@ pub(crate) fn run(
                 | Kind::Right
                 | Kind::ElseIfCond
                 | Kind::Grab
+                | Kind::Iter
+                | Kind::Default
                 | Kind::Add
                 | Kind::Mul
                 | Kind::Pow => {
@@ -479,6 +571,82 @@ pub(crate) fn run(
                         this_ty = Some(nodes[i].inner_type(&ty));
                     }
                 }
+                Kind::Coalesce => {
+                    if nodes[i].children.len() < 2 {
+                        continue 'node;
+                    }
+                    let (expr, default) = (nodes[i].children[0], nodes[i].children[1]);
+                    let inner = match nodes[expr].ty {
+                        None => {
+                            todo.push(i);
+                            continue 'node;
+                        }
+                        Some(Type::Option(ref ty)) | Some(Type::Result(ref ty)) => (**ty).clone(),
+                        Some(Type::Any) => Type::Any,
+                        Some(ref ty) => {
+                            return Err(nodes[expr].source.wrap(format!(
+                                "Type mismatch (#2400):\n\
+                                Expected `opt` or `res`, found `{}`",
+                                ty.description()
+                            )));
+                        }
+                    };
+                    let default_ty = match nodes[default].ty {
+                        None => {
+                            todo.push(i);
+                            continue 'node;
+                        }
+                        Some(ref ty) => ty.clone(),
+                    };
+                    if !inner.goes_with(&default_ty) {
+                        return Err(nodes[default].source.wrap(format!(
+                            "Type mismatch (#2500):\n\
+                            Expected `{}`, found `{}`",
+                            inner.description(),
+                            default_ty.description()
+                        )));
+                    }
+                    this_ty = Some(inner);
+                }
+                Kind::Range => {
+                    for &ch in &nodes[i].children {
+                        match nodes[ch].ty {
+                            None => {
+                                todo.push(i);
+                                continue 'node;
+                            }
+                            Some(ref ty) if !ty.goes_with(&Type::F64) => {
+                                return Err(nodes[ch].source.wrap(format!(
+                                    "Type mismatch (#2600):\n\
+                                    Expected `f64`, found `{}`",
+                                    ty.description()
+                                )));
+                            }
+                            Some(_) => {}
+                        }
+                    }
+                }
+                Kind::Object => {
+                    // Infer record type from the fields.
+                    let mut fields = vec![];
+                    for &ch in &nodes[i].children {
+                        let val = match nodes[ch].find_child_by_kind(nodes, Kind::Val) {
+                            None => continue,
+                            Some(val) => val,
+                        };
+                        match (nodes[ch].name(), &nodes[val].ty) {
+                            (Some(name), Some(ty)) => fields.push((name.clone(), ty.clone())),
+                            _ => {
+                                todo.push(i);
+                                continue 'node;
+                            }
+                        }
+                    }
+                    if fields.is_empty() {
+                        continue 'node;
+                    }
+                    this_ty = Some(Type::Record(fields));
+                }
                 Kind::Compare => {
                     let left = match nodes[i].find_child_by_kind(nodes, Kind::Left) {
                         None => {
@@ -570,6 +738,77 @@ pub(crate) fn run(
 
                     this_ty = Some(true_type);
                 }
+                Kind::TryCatch => {
+                    let ch = match nodes[i].find_child_by_kind(nodes, Kind::Block) {
+                        None => {
+                            todo.push(i);
+                            continue 'node;
+                        }
+                        Some(ch) => ch,
+                    };
+                    match nodes[ch].ty {
+                        None => {
+                            todo.push(i);
+                            continue 'node;
+                        }
+                        Some(ref ty) => this_ty = Some(nodes[i].inner_type(ty)),
+                    }
+                }
+                Kind::Match => {
+                    let mut match_ty = None;
+                    for &ch in &nodes[i].children {
+                        if nodes[ch].kind != Kind::Arm {
+                            continue;
+                        }
+                        match nodes[ch].ty {
+                            None => {
+                                todo.push(i);
+                                continue 'node;
+                            }
+                            Some(Type::Unreachable) => {}
+                            Some(ref ty) => {
+                                if match_ty.is_none() {
+                                    match_ty = Some(ty.clone());
+                                }
+                            }
+                        }
+                    }
+                    this_ty = Some(match_ty.unwrap_or(Type::Unreachable));
+                }
+                Kind::Arm => {
+                    this_ty = nodes[i]
+                        .find_child_by_kind(nodes, Kind::Expr)
+                        .and_then(|ch| nodes[ch].ty.clone());
+                }
+                Kind::Pat | Kind::PatSome | Kind::PatOk | Kind::PatErr | Kind::PatVariant => {
+                    if nodes[i].ty.is_some() {
+                        // Literal values have known type.
+                        continue 'node;
+                    }
+                    let ty = match pattern_input_type(i, nodes) {
+                        None => {
+                            todo.push(i);
+                            continue 'node;
+                        }
+                        Some(ty) => ty,
+                    };
+                    this_ty = Some(match (kind, ty) {
+                        (Kind::Pat, ty) => ty,
+                        (Kind::PatErr, _) | (Kind::PatVariant, _) | (_, Type::Any) => Type::Any,
+                        (Kind::PatSome, Type::Option(ty)) => *ty,
+                        (Kind::PatOk, Type::Result(ty)) => *ty,
+                        (_, ty) => {
+                            return Err(nodes[i].source.wrap(format!(
+                                "Type mismatch (#1800):\nExpected `{}`, found `{}`",
+                                if kind == Kind::PatSome { "opt" } else { "res" },
+                                ty.description()
+                            )));
+                        }
+                    });
+                }
+                Kind::PatKey => {
+                    this_ty = Some(Type::Any);
+                }
                 Kind::Arg => {
                     if nodes[i].ty.is_none() {
                         this_ty = Some(Type::Any);
@@ -613,6 +852,8 @@ pub(crate) fn run(
                 }
                 _ => {}
             }
+            // Inferred types do not keep the names of type aliases.
+            let this_ty = this_ty.map(|ty| ty.unalias());
             if this_ty.is_some() {
                 if let (&Some(ref old_ty), &Some(ref new_ty)) = (&nodes[i].ty, &this_ty) {
                     if old_ty != new_ty {
@@ -655,6 +896,13 @@ pub(crate) fn run(
         return Err(delay_errs.values().next().unwrap().clone());
     }
 
+    // Generator functions end without returning a value.
+    let generators: Vec<usize> = nodes
+        .iter()
+        .filter(|n| n.kind == Kind::Yield)
+        .filter_map(|n| n.find_fn(nodes))
+        .collect();
+
     // After type propagation.
     for i in 0..nodes.len() {
         let kind = nodes[i].kind;
@@ -677,6 +925,7 @@ pub(crate) fn run(
 
                     // Check all return statements.
                     let mut found_return = false;
+                    let ty = if generators.contains(&i) { &Type::Void } else { ty };
                     check_fn(i, nodes, ty, &mut found_return)?;
                     // Report if there is no return statement.
                     if !found_return
@@ -711,10 +960,38 @@ pub(crate) fn run(
                     }
                 }
             }
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Kind::Yield => {
+                let f = nodes[i].find_fn(nodes).expect("Expected function");
+                let ch = nodes[i].find_child_by_kind(nodes, Kind::Expr).expect("Expected expr");
+                match (&nodes[f].ty, &nodes[ch].ty) {
+                    (Some(Type::In(in_ty)), Some(ty)) if !in_ty.goes_with(ty) => {
+                        return Err(nodes[ch].source.wrap(format!(
+                            "Type mismatch (#2300):\nExpected `{}`, found `{}`",
+                            in_ty.description(),
+                            ty.description()
+                        )));
+                    }
+                    _ => {}
+                }
+            }
             Kind::If => check_if(i, nodes)?,
+            Kind::TryCatch => check_try_catch(i, nodes)?,
+            Kind::Match => check_match(i, nodes, warnings)?,
             Kind::Assign => {
                 use crate::ast::AssignOp;
 
+                // Pick operator overload for compound assignment from the ad-hoc types.
+                let picked = match nodes[i].op.and_then(|op| op.binop_name()) {
+                    Some(name) => candidates
+                        .get(&**name)
+                        .and_then(|list| overload::pick_assign(i, list, nodes)),
+                    None => None,
+                };
+                if let Some(f) = picked {
+                    overloads.insert(nodes[i].source, f.name.clone());
+                    continue;
+                }
                 match nodes[i].op {
                     Some(AssignOp::Add) | Some(AssignOp::Sub) => {
                         let left = nodes[i].find_child_by_kind(nodes, Kind::Left).unwrap();
@@ -732,6 +1009,28 @@ pub(crate) fn run(
                             }
                         }
                     }
+                    Some(AssignOp::Set) => {
+                        let left = nodes[i].find_child_by_kind(nodes, Kind::Left).unwrap();
+                        let right = nodes[i].find_child_by_kind(nodes, Kind::Right).unwrap();
+                        let item = nodes[left].find_child_by_kind(nodes, Kind::Item);
+                        let field_ty = match item {
+                            Some(item) if !nodes[item].children.is_empty() => {
+                                field_type(item, nodes)?
+                            }
+                            _ => None,
+                        };
+                        match (field_ty, &nodes[right].ty) {
+                            (Some(field_ty), Some(right_ty)) if !field_ty.goes_with(right_ty) => {
+                                return Err(nodes[right].source.wrap(format!(
+                                    "Type mismatch (#2900):\n\
+                                    Expected `{}`, found `{}`",
+                                    field_ty.description(),
+                                    right_ty.description()
+                                )));
+                            }
+                            _ => {}
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -782,6 +1081,43 @@ pub(crate) fn run(
                     }
                 }
             }
+            Kind::Default => {
+                let arg = nodes[i].parent.expect("Expected parent");
+                match (&nodes[arg].ty, &nodes[i].ty) {
+                    (Some(arg_ty), Some(ty)) if !arg_ty.goes_with(ty) => {
+                        return Err(nodes[i].source.wrap(format!(
+                            "Type mismatch (#2100):\nExpected `{}`, found `{}`",
+                            arg_ty.description(),
+                            ty.description()
+                        )));
+                    }
+                    _ => {}
+                }
+            }
+            Kind::Const => {
+                if let Some(ch) = nodes[i].find_child_by_kind(nodes, Kind::Expr) {
+                    match (&nodes[i].ty, &nodes[ch].ty) {
+                        (Some(const_ty), Some(ty)) if !const_ty.goes_with(ty) => {
+                            return Err(nodes[ch].source.wrap(format!(
+                                "Type mismatch (#2200):\nExpected `{}`, found `{}`",
+                                const_ty.description(),
+                                ty.description()
+                            )));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Kind::Iter => match nodes[i].ty {
+                Some(ref ty) if in_loop_types(ty).is_none() => {
+                    return Err(nodes[i].source.wrap(format!(
+                        "Type mismatch (#2000):\n\
+                        Expected `in`, `[]`, `{{}}`, `link` or `str`, found `{}`",
+                        ty.description()
+                    )));
+                }
+                _ => {}
+            },
             _ => {}
         }
     }
@@ -851,6 +1187,330 @@ fn check_fn(
     Ok(())
 }
 
+/// Gets the type of value tested by a pattern node.
+fn pattern_input_type(n: usize, nodes: &[Node]) -> Option<Type> {
+    let parent = nodes[n].parent?;
+    match nodes[parent].kind {
+        Kind::Arm => {
+            let match_node = nodes[parent].parent?;
+            let expr = nodes[match_node].find_child_by_kind(nodes, Kind::Expr)?;
+            nodes[expr].ty.clone()
+        }
+        Kind::Pat | Kind::PatSome | Kind::PatOk | Kind::PatErr | Kind::PatVariant => {
+            nodes[parent].ty.clone()
+        }
+        Kind::Destructure => {
+            let right = nodes[parent].find_child_by_kind(nodes, Kind::Right)?;
+            nodes[right].ty.clone()
+        }
+        Kind::PatVec4 => Some(Type::F64),
+        Kind::PatArr => {
+            let arr = nodes[parent].parent?;
+            match nodes[arr].ty {
+                Some(Type::Array(ref ty)) => Some((**ty).clone()),
+                Some(_) => Some(Type::Any),
+                None => None,
+            }
+        }
+        _ => Some(Type::Any),
+    }
+}
+
+/// Gets the type of a field lookup, e.g. `p.x`, when the argument `p` is a record.
+///
+/// Returns `None` if the type is unknown.
+fn field_type(i: usize, nodes: &[Node]) -> Result<Option<Type>, Range<String>> {
+    let extra = match nodes[i].find_child_by_kind(nodes, Kind::ItemExtra) {
+        Some(extra) if !nodes[i].try_flag => extra,
+        _ => return Ok(None),
+    };
+    // Numeric, expression and `?` ids are not field names.
+    if nodes[extra].ty.is_some() || nodes[extra].try_flag || !nodes[extra].children.is_empty() {
+        return Ok(None);
+    }
+    // Declarations such as `p.y := 2` insert new fields.
+    let op = nodes[i]
+        .parent
+        .filter(|&left| nodes[left].kind == Kind::Left)
+        .and_then(|left| nodes[left].parent)
+        .and_then(|assign| nodes[assign].op);
+    if op == Some(crate::ast::AssignOp::Assign) {
+        return Ok(None);
+    }
+    let mut ty = match nodes[i].declaration.map(|decl| &nodes[decl]) {
+        Some(&Node { kind: Kind::Arg, ty: Some(ref ty), .. }) => ty,
+        _ => return Ok(None),
+    };
+    for name in &nodes[extra].names {
+        let mut record = ty;
+        while let Type::Alias(_, ref inner) = *record {
+            record = inner;
+        }
+        ty = match *record {
+            Type::Record(ref fields) => match fields.iter().find(|(n, _)| n == name) {
+                Some((_, field_ty)) => field_ty,
+                None => {
+                    return Err(nodes[i].source.wrap(format!(
+                        "Type mismatch (#2800):\nNo field `{}` in `{}`",
+                        name,
+                        ty.description()
+                    )))
+                }
+            },
+            _ => return Ok(None),
+        };
+    }
+    Ok(Some(ty.clone()))
+}
+
+/// Infers `opt[T]` for an item using `?.`, where `T` is the type of the last field.
+///
+/// Options are unwrapped before looking up fields in records.
+/// Returns `None` when the type of the declaration is not known yet.
+fn opt_chain_type(i: usize, nodes: &[Node]) -> Result<Option<Type>, Range<String>> {
+    let extra = match nodes[i].find_child_by_kind(nodes, Kind::ItemExtra) {
+        Some(extra) => extra,
+        None => return Ok(None),
+    };
+    let mut ty = match nodes[i].declaration.and_then(|decl| nodes[decl].ty.as_ref()) {
+        Some(ty) => ty.unalias(),
+        None => return Ok(None),
+    };
+    // Numeric and expression ids are not field names.
+    if nodes[extra].ty.is_some() || !nodes[extra].children.is_empty() {
+        return Ok(Some(Type::option()));
+    }
+    for name in &nodes[extra].names {
+        if let Type::Option(inner) = ty {
+            ty = *inner;
+        }
+        ty = match ty {
+            Type::Record(ref fields) => match fields.iter().find(|(n, _)| n == name) {
+                Some((_, field_ty)) => field_ty.clone(),
+                None => {
+                    return Err(nodes[i].source.wrap(format!(
+                        "Type mismatch (#2800):\nNo field `{}` in `{}`",
+                        name,
+                        ty.description()
+                    )))
+                }
+            },
+            _ => return Ok(Some(Type::option())),
+        };
+    }
+    Ok(Some(Type::Option(Box::new(ty))))
+}
+
+/// Narrows a union type of an item inside blocks checking `typeof`.
+///
+/// For example, `x` has type `f64` in the true block of `if typeof(x) == "number" { ... }`.
+fn narrow(i: usize, ty: Type, nodes: &[Node]) -> Type {
+    let (decl, mut members) = match (nodes[i].declaration, ty.unalias()) {
+        (Some(decl), Type::Union(members)) => (decl, members),
+        _ => return ty,
+    };
+    let mut checks = vec![];
+    let mut child = i;
+    while let Some(parent) = nodes[child].parent {
+        match nodes[child].kind {
+            Kind::TrueBlock | Kind::ElseIfBlock | Kind::ElseBlock => {
+                let cond = nodes[parent].find_child_by_kind(nodes, Kind::Cond);
+                if let Some((name, equal)) = cond.and_then(|cond| typeof_check(cond, decl, nodes)) {
+                    // Else blocks are reached when the condition is false.
+                    checks.push((name, equal == (nodes[child].kind == Kind::TrueBlock)));
+                }
+            }
+            Kind::Expr if nodes[parent].kind.is_block() => {
+                // Statements after `if` blocks that always exit are reached
+                // when the condition is false.
+                for &stmt in nodes[parent].children.iter().take_while(|&&n| n != child) {
+                    if let Some((name, equal)) = exit_check(stmt, decl, nodes) {
+                        checks.push((name, !equal));
+                    }
+                }
+            }
+            _ => {}
+        }
+        child = parent;
+    }
+    for (name, equal) in checks {
+        let narrowed: Vec<Type> = members
+            .iter()
+            .filter(|ty| (ty.typeof_name() == Some(&***name)) == equal)
+            .cloned()
+            .collect();
+        if !narrowed.is_empty() {
+            members = narrowed;
+        }
+    }
+    if members.len() == 1 {
+        members.pop().unwrap()
+    } else {
+        Type::Union(members)
+    }
+}
+
+/// Gets the `typeof` check of an `if` statement without else blocks
+/// where the true block always exits, e.g. `if typeof(x) != "number" { return }`.
+fn exit_check(stmt: usize, decl: usize, nodes: &[Node]) -> Option<(&Arc<String>, bool)> {
+    let node = nodes[stmt].children.first().map(|&n| &nodes[n])?;
+    if node.kind != Kind::If || node.children.len() != 2 {
+        return None;
+    }
+    let block = node.find_child_by_kind(nodes, Kind::TrueBlock)?;
+    let mut last = *nodes[block].children.last()?;
+    while nodes[last].kind == Kind::Expr && nodes[last].children.len() == 1 {
+        last = nodes[last].children[0];
+    }
+    match nodes[last].kind {
+        Kind::Return | Kind::ReturnVoid | Kind::Break | Kind::Continue => {
+            typeof_check(node.find_child_by_kind(nodes, Kind::Cond)?, decl, nodes)
+        }
+        _ => None,
+    }
+}
+
+/// Gets the type name from a condition `typeof(x) == "<name>"`, or `!=`,
+/// and whether it checks for equality.
+fn typeof_check(cond: usize, decl: usize, nodes: &[Node]) -> Option<(&Arc<String>, bool)> {
+    // Skip expression wrappers.
+    let unwrap = |mut n: usize| {
+        while nodes[n].kind == Kind::Expr && nodes[n].children.len() == 1 {
+            n = nodes[n].children[0];
+        }
+        n
+    };
+    let call = unwrap(*nodes[cond].children.first()?);
+    let equal = match nodes[call].name().map(|name| &***name) {
+        Some("equal") if nodes[call].kind == Kind::Call => true,
+        Some("not_equal") if nodes[call].kind == Kind::Call => false,
+        _ => return None,
+    };
+    let args: Vec<usize> = nodes[call]
+        .children
+        .iter()
+        .filter(|&&arg| nodes[arg].kind == Kind::CallArg && nodes[arg].children.len() == 1)
+        .map(|&arg| unwrap(nodes[arg].children[0]))
+        .collect();
+    if args.len() != 2 {
+        return None;
+    }
+    let (typeof_call, text) = match (nodes[args[0]].kind, nodes[args[1]].kind) {
+        (Kind::Call, Kind::Expr) => (args[0], args[1]),
+        (Kind::Expr, Kind::Call) => (args[1], args[0]),
+        _ => return None,
+    };
+    if nodes[typeof_call].name().map(|name| &***name) != Some("typeof") {
+        return None;
+    }
+    let item = nodes[typeof_call]
+        .children
+        .iter()
+        .find(|&&arg| nodes[arg].kind == Kind::CallArg && nodes[arg].children.len() == 1)
+        .map(|&arg| unwrap(nodes[arg].children[0]))?;
+    match nodes[item] {
+        Node { kind: Kind::Item, declaration: Some(d), ref children, .. }
+            if d == decl && children.is_empty() =>
+        {
+            nodes[text].name().map(|name| (name, equal))
+        }
+        _ => None,
+    }
+}
+
+/// Gets the key and value types of a for-in loop, or `None` if the type can not be iterated.
+fn in_loop_types(ty: &Type) -> Option<(Type, Type)> {
+    match *ty {
+        Type::Any | Type::Unreachable => Some((Type::Any, Type::Any)),
+        Type::Array(ref ty) => Some((Type::F64, (**ty).clone())),
+        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+        Type::In(ref ty) => Some((Type::F64, (**ty).clone())),
+        Type::Object | Type::Record(_) => Some((Type::Str, Type::Any)),
+        Type::Map(ref key, ref val) => Some(((**key).clone(), (**val).clone())),
+        Type::Set(ref ty) => Some((Type::F64, (**ty).clone())),
+        Type::Link => Some((Type::F64, Type::Any)),
+        Type::Str => Some((Type::F64, Type::Str)),
+        Type::AdHoc(_, ref ty) => in_loop_types(ty),
+        _ => None,
+    }
+}
+
+/// Returns `true` if a pattern matches any value.
+fn is_irrefutable(n: usize, nodes: &[Node]) -> bool {
+    nodes[n].children.is_empty() && !nodes[n].names.is_empty()
+}
+
+fn check_match(
+    n: usize,
+    nodes: &[Node],
+    warnings: &mut Vec<Range<String>>,
+) -> Result<(), Range<String>> {
+    let match_type = match nodes[n].ty {
+        None => return Ok(()),
+        Some(ref ty) => ty,
+    };
+
+    let mut any = false;
+    let mut some = false;
+    let mut none = false;
+    let mut ok = false;
+    let mut err = false;
+    for &ch in &nodes[n].children {
+        if nodes[ch].kind != Kind::Arm {
+            continue;
+        }
+        match nodes[ch].ty {
+            Some(ref arm_type) if !arm_type.goes_with(match_type) => {
+                return Err(nodes[ch].source.wrap(format!(
+                    "Type mismatch (#1900):\nExpected `{}`, found `{}`",
+                    match_type.description(),
+                    arm_type.description()
+                )));
+            }
+            _ => {}
+        }
+        let pat = match nodes[ch].find_child_by_kind(nodes, Kind::Pat) {
+            None => continue,
+            Some(pat) => pat,
+        };
+        if is_irrefutable(pat, nodes) {
+            any = true;
+            continue;
+        }
+        if let Some(&inner) = nodes[pat].children.first() {
+            let inner_any = is_irrefutable(inner, nodes);
+            match nodes[inner].kind {
+                Kind::PatSome => some |= inner_any,
+                Kind::PatNone => none = true,
+                Kind::PatOk => ok |= inner_any,
+                Kind::PatErr => err |= inner_any,
+                _ => {}
+            }
+        }
+    }
+
+    if !any {
+        let expr_type = nodes[n]
+            .find_child_by_kind(nodes, Kind::Expr)
+            .and_then(|expr| nodes[expr].ty.as_ref());
+        let missing = match expr_type {
+            Some(Type::Option(_)) if !(some && none) => {
+                Some(if some { "none()" } else { "some(_)" })
+            }
+            Some(Type::Result(_)) if !(ok && err) => Some(if ok { "err(_)" } else { "ok(_)" }),
+            _ => None,
+        };
+        if let Some(missing) = missing {
+            warnings.push(nodes[n].source.wrap(format!(
+                "Non-exhaustive `match`:\nPattern `{}` is not covered",
+                missing
+            )));
+        }
+    }
+
+    Ok(())
+}
+
 fn check_if(n: usize, nodes: &[Node]) -> Result<(), Range<String>> {
     if let Some(ch) = nodes[n].find_child_by_kind(nodes, Kind::Cond) {
         if let Some(ref cond_ty) = nodes[ch].ty {
@@ -908,3 +1568,30 @@ fn check_if(n: usize, nodes: &[Node]) -> Result<(), Range<String>> {
 
     Ok(())
 }
+
+fn check_try_catch(n: usize, nodes: &[Node]) -> Result<(), Range<String>> {
+    let try_type = match nodes[n].find_child_by_kind(nodes, Kind::Block) {
+        None => return Ok(()),
+        Some(tb) => match nodes[tb].ty {
+            None => return Ok(()),
+            Some(ref ty) => ty,
+        },
+    };
+    let cb = match nodes[n]
+        .find_child_by_kind(nodes, Kind::Catch)
+        .and_then(|ch| nodes[ch].find_child_by_kind(nodes, Kind::Block))
+    {
+        None => return Ok(()),
+        Some(cb) => cb,
+    };
+    match nodes[cb].ty {
+        Some(ref catch_type) if !catch_type.goes_with(try_type) => {
+            Err(nodes[cb].source.wrap(format!(
+                "Type mismatch (#2700):\nExpected `{}`, found `{}`",
+                try_type.description(),
+                catch_type.description()
+            )))
+        }
+        _ => Ok(()),
+    }
+}
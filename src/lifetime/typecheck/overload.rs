@@ -0,0 +1,107 @@
+use super::*;
+use crate::ast::overload_name;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Binary operator overload.
+pub(crate) struct Overload {
+    /// The name of the overload function.
+    pub name: Arc<String>,
+    /// The argument types.
+    pub tys: Vec<Type>,
+    /// The return type.
+    pub ret: Type,
+    /// The function node, when declared in the same source.
+    pub decl: Option<usize>,
+}
+
+// Returns the operator name when the function is an overload.
+fn operator<'a>(name: &'a Arc<String>, tys: &[Type]) -> Option<&'a str> {
+    let op = name.split('(').next()?;
+    if overload_name(op, tys).as_ref() == Some(name) {
+        Some(op)
+    } else {
+        None
+    }
+}
+
+/// Collects overloads by the name of operator.
+///
+/// Overloads declared in the same source are picked first.
+pub(crate) fn candidates(nodes: &[Node], prelude: &Prelude) -> HashMap<String, Vec<Overload>> {
+    let mut res: HashMap<String, Vec<Overload>> = HashMap::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if node.kind != Kind::Fn {
+            continue;
+        }
+        let tys: Vec<Type> = node
+            .children
+            .iter()
+            .filter(|&&n| nodes[n].kind == Kind::Arg)
+            .map(|&n| nodes[n].ty.clone().unwrap_or(Type::Any))
+            .collect();
+        let name = node.name().unwrap();
+        if let Some(op) = operator(name, &tys) {
+            res.entry(op.into()).or_default().push(Overload {
+                name: name.clone(),
+                tys,
+                ret: node.ty.clone().unwrap_or(Type::Any),
+                decl: Some(i),
+            });
+        }
+    }
+    for (f, (_, name)) in prelude.list.iter().zip(&prelude.namespaces) {
+        if let Some(op) = operator(name, &f.tys) {
+            res.entry(op.into()).or_default().push(Overload {
+                name: name.clone(),
+                tys: f.tys.clone(),
+                ret: f.ret.clone(),
+                decl: None,
+            });
+        }
+    }
+    res
+}
+
+/// Picks the first overload matching the ad-hoc types of arguments.
+///
+/// Returns `None` when some argument type is unknown or no overload matches,
+/// which falls back to the standard operator.
+pub(crate) fn pick<'a>(
+    i: usize,
+    overloads: &'a [Overload],
+    nodes: &[Node],
+) -> Option<&'a Overload> {
+    let mut tys = vec![];
+    for &arg in nodes[i]
+        .children
+        .iter()
+        .filter(|&&arg| nodes[arg].kind == Kind::CallArg && !nodes[arg].children.is_empty())
+    {
+        tys.push(nodes[nodes[arg].children[0]].ty.as_ref()?);
+    }
+    find(overloads, &tys)
+}
+
+/// Picks the first overload matching the ad-hoc types of compound assignment, e.g. `a += b`.
+pub(crate) fn pick_assign<'a>(
+    i: usize,
+    overloads: &'a [Overload],
+    nodes: &[Node],
+) -> Option<&'a Overload> {
+    let left = nodes[i].find_child_by_kind(nodes, Kind::Left)?;
+    let right = nodes[i].find_child_by_kind(nodes, Kind::Right)?;
+    find(overloads, &[nodes[left].ty.as_ref()?, nodes[right].ty.as_ref()?])
+}
+
+fn find<'a>(overloads: &'a [Overload], tys: &[&Type]) -> Option<&'a Overload> {
+    overloads.iter().find(|f| {
+        f.tys.len() == tys.len()
+            && f.tys.iter().zip(tys).all(|(b, &a)| {
+                // Ad-hoc types must match by name, not just by inner type.
+                (!matches!(b, Type::AdHoc(_, _)) || matches!(a, Type::AdHoc(_, _)))
+                    && b.goes_with(a)
+            })
+    })
+}
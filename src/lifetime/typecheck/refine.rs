@@ -192,3 +192,45 @@ pub(crate) fn prelude(
 
     report(i, found, ambiguous, f.ext.len(), nodes, todo)
 }
+
+/// Binds type parameters of a generic function to the argument types of a call.
+pub(crate) fn params(
+    i: usize,
+    tys: &[Type],
+    ret: &Type,
+    nodes: &[Node],
+    todo: &mut Vec<usize>,
+    this_ty: &mut Option<Type>,
+) -> Result<(), Range<String>> {
+    let mut bound = vec![];
+    let mut unknown = false;
+    for (arg_expr, ty) in nodes[i]
+        .children
+        .iter()
+        .filter(|&&arg| nodes[arg].kind == Kind::CallArg && !nodes[arg].children.is_empty())
+        .map(|&arg| nodes[arg].children[0])
+        .zip(tys)
+    {
+        match nodes[arg_expr].ty {
+            None => unknown = true,
+            Some(ref found) => ty
+                .bind_params(found, &mut bound)
+                .map_err(|err| nodes[arg_expr].source.wrap(err))?,
+        }
+    }
+    if unknown {
+        // Wait for argument types, but use unbound type parameters meanwhile.
+        *this_ty = None;
+        if nodes[i].ty.is_none() {
+            let mut ret = ret.clone();
+            ret.insert_bound_params(&[]);
+            *this_ty = Some(ret);
+            todo.push(i);
+        }
+        return Ok(());
+    }
+    let mut ret = ret.clone();
+    ret.insert_bound_params(&bound);
+    *this_ty = Some(ret);
+    Ok(())
+}
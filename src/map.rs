@@ -0,0 +1,156 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::Variable;
+
+/// Stores a map key or set item.
+///
+/// Numbers are never `NaN` and `-0` is stored as `0`,
+/// such that equal keys have equal hashes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Key {
+    /// Boolean.
+    Bool(bool),
+    /// F64.
+    F64(f64),
+    /// 64-bit integer.
+    I64(i64),
+    /// Text.
+    Str(Arc<String>),
+    /// 4D vector.
+    Vec4([f32; 4]),
+    /// Array of keys.
+    Array(Vec<Key>),
+}
+
+impl Key {
+    /// Creates a key from a variable, resolving references on the stack.
+    pub fn new(var: &Variable, stack: &[Variable]) -> Result<Key, String> {
+        match *var {
+            Variable::Ref(ind) => Key::new(&stack[ind], stack),
+            Variable::Bool(x, _) => Ok(Key::Bool(x)),
+            Variable::F64(x, _) => {
+                if x.is_nan() {
+                    Err("Can not use `NaN` as map key or set item".into())
+                } else {
+                    Ok(Key::F64(if x == 0.0 { 0.0 } else { x }))
+                }
+            }
+            Variable::I64(x) => Ok(Key::I64(x)),
+            Variable::Str(ref x) => Ok(Key::Str(x.clone())),
+            Variable::Vec4(mut x) => {
+                for x in &mut x {
+                    if x.is_nan() {
+                        return Err("Can not use `NaN` as map key or set item".into());
+                    } else if *x == 0.0 {
+                        *x = 0.0;
+                    }
+                }
+                Ok(Key::Vec4(x))
+            }
+            Variable::Array(ref arr) => Ok(Key::Array(
+                arr.iter()
+                    .map(|x| Key::new(x, stack))
+                    .collect::<Result<_, _>>()?,
+            )),
+            ref x => Err(format!("Can not use `{}` as map key or set item", x.typeof_var())),
+        }
+    }
+
+    /// Converts key into a variable.
+    pub fn to_variable(&self) -> Variable {
+        match *self {
+            Key::Bool(x) => Variable::bool(x),
+            Key::F64(x) => Variable::f64(x),
+            Key::I64(x) => Variable::I64(x),
+            Key::Str(ref x) => Variable::Str(x.clone()),
+            Key::Vec4(x) => Variable::Vec4(x),
+            Key::Array(ref arr) => {
+                let arr: Vec<Variable> = arr.iter().map(|x| x.to_variable()).collect();
+                Variable::Array(arr.into())
+            }
+        }
+    }
+
+    // Orders keys of different types.
+    fn rank(&self) -> u8 {
+        match *self {
+            Key::Bool(_) => 0,
+            Key::F64(_) => 1,
+            Key::I64(_) => 2,
+            Key::Str(_) => 3,
+            Key::Vec4(_) => 4,
+            Key::Array(_) => 5,
+        }
+    }
+}
+
+// Numbers are never `NaN`, so equality is reflexive.
+impl Eq for Key {}
+
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match *self {
+            Key::Bool(x) => x.hash(state),
+            Key::F64(x) => x.to_bits().hash(state),
+            Key::I64(x) => x.hash(state),
+            Key::Str(ref x) => x.hash(state),
+            Key::Vec4(x) => {
+                for x in x {
+                    x.to_bits().hash(state)
+                }
+            }
+            Key::Array(ref arr) => arr.hash(state),
+        }
+    }
+}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Key) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Key) -> Ordering {
+        match (self, other) {
+            (Key::Bool(a), Key::Bool(b)) => a.cmp(b),
+            (Key::F64(a), Key::F64(b)) => a.total_cmp(b),
+            (Key::I64(a), Key::I64(b)) => a.cmp(b),
+            (Key::Str(a), Key::Str(b)) => a.cmp(b),
+            (Key::Vec4(a), Key::Vec4(b)) => a
+                .iter()
+                .zip(b.iter())
+                .map(|(a, b)| a.total_cmp(b))
+                .find(|x| x.is_ne())
+                .unwrap_or(Ordering::Equal),
+            (Key::Array(a), Key::Array(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Key::Bool(x) => write!(f, "{}", x),
+            Key::F64(x) => write!(f, "{}", x),
+            Key::I64(x) => write!(f, "{}i", x),
+            Key::Str(ref x) => write!(f, "{:?}", x),
+            Key::Vec4(x) => write!(f, "({}, {}, {}, {})", x[0], x[1], x[2], x[3]),
+            Key::Array(ref arr) => {
+                write!(f, "[")?;
+                for (i, x) in arr.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", x)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
@@ -7,7 +7,10 @@ pub struct Module {
     // The length where lower indices are made transitive.
     pub(crate) transitive_functions_len: usize,
     pub(crate) ext_prelude: Vec<FnExternal>,
+    pub(crate) constants: Vec<ast::Const>,
+    pub(crate) type_aliases: Vec<ast::TypeAlias>,
     pub(crate) register_namespace: Arc<Vec<Arc<String>>>,
+    pub(crate) strip_asserts: bool,
 }
 
 impl Default for Module {
@@ -23,7 +26,10 @@ impl Module {
             functions: vec![],
             transitive_functions_len: 0,
             ext_prelude: vec![],
+            constants: vec![],
+            type_aliases: vec![],
             register_namespace: Arc::new(vec![]),
+            strip_asserts: false,
         }
     }
 
@@ -48,7 +54,7 @@ impl Module {
         self.transitive_functions_len = self.functions.len();
     }
 
-    /// Import external prelude and loaded functions from module.
+    /// Import external prelude, loaded functions, constants and type aliases from module.
     pub fn import(&mut self, other: &Module) {
         // Add external functions from imports.
         for f in &other.ext_prelude {
@@ -64,6 +70,14 @@ impl Module {
         for f in &other.functions {
             self.functions.push(f.clone())
         }
+        // Register constants from imports.
+        for c in &other.constants {
+            self.constants.push(c.clone())
+        }
+        // Register type aliases from imports.
+        for alias in &other.type_aliases {
+            self.type_aliases.push(alias.clone())
+        }
     }
 
     /// Creates a new module with standard library.
@@ -87,6 +101,7 @@ impl Module {
                         Secret(Box::new(Bool)),
                     ),
                     (vec![], vec![F64; 2], Bool),
+                    (vec![], vec![I64; 2], Bool),
                     (vec![], vec![Str; 2], Bool),
                 ],
                 lazy: LAZY_NO,
@@ -106,6 +121,7 @@ impl Module {
                         Secret(Box::new(Bool)),
                     ),
                     (vec![], vec![F64; 2], Bool),
+                    (vec![], vec![I64; 2], Bool),
                     (vec![], vec![Str; 2], Bool),
                 ],
                 lazy: LAZY_NO,
@@ -125,6 +141,7 @@ impl Module {
                         Secret(Box::new(Bool)),
                     ),
                     (vec![], vec![F64; 2], Bool),
+                    (vec![], vec![I64; 2], Bool),
                     (vec![], vec![Str; 2], Bool),
                 ],
                 lazy: LAZY_NO,
@@ -144,6 +161,7 @@ impl Module {
                         Secret(Box::new(Bool)),
                     ),
                     (vec![], vec![F64; 2], Bool),
+                    (vec![], vec![I64; 2], Bool),
                     (vec![], vec![Str; 2], Bool),
                 ],
                 lazy: LAZY_NO,
@@ -163,6 +181,7 @@ impl Module {
                         Secret(Box::new(Bool)),
                     ),
                     (vec![], vec![F64; 2], Bool),
+                    (vec![], vec![I64; 2], Bool),
                     (vec![], vec![Str; 2], Bool),
                     (
                         vec![],
@@ -171,7 +190,11 @@ impl Module {
                     ),
                     (vec![], vec![Bool; 2], Bool),
                     (vec![], vec![Vec4; 2], Bool),
+                    (vec![], vec![Bytes; 2], Bool),
+                    (vec![], vec![Type::Range; 2], Bool),
                     (vec![], vec![Type::object(), Type::object()], Bool),
+                    (vec![], vec![Type::map(), Type::map()], Bool),
+                    (vec![], vec![Type::set(), Type::set()], Bool),
                     (vec![], vec![Type::array(), Type::array()], Bool),
                     (vec![], vec![Type::option(), Type::option()], Bool),
                 ],
@@ -192,6 +215,7 @@ impl Module {
                         Secret(Box::new(Bool)),
                     ),
                     (vec![], vec![F64; 2], Bool),
+                    (vec![], vec![I64; 2], Bool),
                     (vec![], vec![Str; 2], Bool),
                     (
                         vec![],
@@ -200,7 +224,11 @@ impl Module {
                     ),
                     (vec![], vec![Bool; 2], Bool),
                     (vec![], vec![Vec4; 2], Bool),
+                    (vec![], vec![Bytes; 2], Bool),
+                    (vec![], vec![Type::Range; 2], Bool),
                     (vec![], vec![Type::object(), Type::object()], Bool),
+                    (vec![], vec![Type::map(), Type::map()], Bool),
+                    (vec![], vec![Type::set(), Type::set()], Bool),
                     (vec![], vec![Type::array(), Type::array()], Bool),
                     (vec![], vec![Type::option(), Type::option()], Bool),
                 ],
@@ -252,6 +280,7 @@ impl Module {
                 ret: Any,
                 ext: vec![
                     Type::all_ext(vec![F64, F64], F64),
+                    Type::all_ext(vec![I64, I64], I64),
                     Type::all_ext(vec![Vec4, Vec4], Vec4),
                     Type::all_ext(vec![Vec4, F64], Vec4),
                     Type::all_ext(vec![F64, Vec4], Vec4),
@@ -261,6 +290,7 @@ impl Module {
                     Type::all_ext(vec![Bool, Bool], Bool),
                     Type::all_ext(vec![Str, Str], Str),
                     Type::all_ext(vec![Link, Link], Link),
+                    Type::all_ext(vec![Bytes, Bytes], Bytes),
                 ],
                 lazy: LAZY_NO,
             },
@@ -274,6 +304,7 @@ impl Module {
                 ret: Any,
                 ext: vec![
                     Type::all_ext(vec![F64, F64], F64),
+                    Type::all_ext(vec![I64, I64], I64),
                     Type::all_ext(vec![Vec4, Vec4], Vec4),
                     Type::all_ext(vec![Vec4, F64], Vec4),
                     Type::all_ext(vec![F64, Vec4], Vec4),
@@ -294,6 +325,7 @@ impl Module {
                 ret: Any,
                 ext: vec![
                     (vec![], vec![F64, F64], F64),
+                    (vec![], vec![I64, I64], I64),
                     (vec![], vec![Vec4, Vec4], Vec4),
                     (vec![], vec![Vec4, F64], Vec4),
                     (vec![], vec![F64, Vec4], Vec4),
@@ -315,6 +347,7 @@ impl Module {
                 ret: Any,
                 ext: vec![
                     (vec![], vec![F64, F64], F64),
+                    (vec![], vec![I64, I64], I64),
                     (vec![], vec![Vec4, Vec4], Vec4),
                     (vec![], vec![Vec4, F64], Vec4),
                     (vec![], vec![F64, Vec4], Vec4),
@@ -331,6 +364,7 @@ impl Module {
                 ret: Any,
                 ext: vec![
                     (vec![], vec![F64, F64], F64),
+                    (vec![], vec![I64, I64], I64),
                     (vec![], vec![Vec4, Vec4], Vec4),
                     (vec![], vec![Vec4, F64], Vec4),
                     (vec![], vec![F64, Vec4], Vec4),
@@ -347,6 +381,7 @@ impl Module {
                 ret: Any,
                 ext: vec![
                     (vec![], vec![F64, F64], F64),
+                    (vec![], vec![I64, I64], I64),
                     (vec![], vec![Vec4, Vec4], Vec4),
                     (vec![], vec![Vec4, F64], Vec4),
                     (vec![], vec![F64, Vec4], Vec4),
@@ -382,6 +417,7 @@ impl Module {
                 ret: Any,
                 ext: vec![
                     (vec![], vec![F64], F64),
+                    (vec![], vec![I64], I64),
                     (vec![], vec![Vec4], Vec4),
                     (vec![], vec![Mat4], Mat4),
                 ],
@@ -469,6 +505,8 @@ impl Module {
         m.add_str("abs", abs, Dfn::nl(vec![F64], F64));
         m.add_str("floor", floor, Dfn::nl(vec![F64], F64));
         m.add_str("ceil", ceil, Dfn::nl(vec![F64], F64));
+        m.add_str("i64", _i64, Dfn::nl(vec![F64], I64));
+        m.add_str("f64", _f64, Dfn::nl(vec![I64], F64));
         #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         m.add_str("sleep", sleep, Dfn::nl(vec![F64], Void));
         #[cfg(feature = "rand")]
@@ -489,7 +527,17 @@ impl Module {
         m.add_str("trim_right", trim_right, Dfn::nl(vec![Str], Str));
         m.add_str("str", _str, Dfn::nl(vec![Any], Str));
         m.add_str("fmt__tab_string", fmt__tab_string, Dfn::nl(vec![F64, Str], Str));
-        m.add_str("json_string", json_string, Dfn::nl(vec![Str], Str));
+        m.add_str(
+            "json_string",
+            json_string,
+            Dfn {
+                lts: vec![Lt::Default],
+                tys: vec![Any],
+                ret: Str,
+                ext: vec![(vec![], vec![Str], Str), (vec![], vec![I64], Str)],
+                lazy: LAZY_NO,
+            },
+        );
         m.add_str("str__color", str__color, Dfn::nl(vec![Vec4], Str));
         m.add_str(
             "srgb_to_linear__color",
@@ -545,6 +593,16 @@ impl Module {
             load_string__file,
             Dfn::nl(vec![Str], Type::Result(Box::new(Str))),
         );
+        m.add_str(
+            "save__bytes_file",
+            save__bytes_file,
+            Dfn::nl(vec![Bytes, Str], Type::Result(Box::new(Str))),
+        );
+        m.add_str(
+            "load_bytes__file",
+            load_bytes__file,
+            Dfn::nl(vec![Str], Type::Result(Box::new(Bytes))),
+        );
         m.add_str(
             "load_string__url",
             load_string__url,
@@ -625,6 +683,12 @@ impl Module {
             functions__module,
             Dfn::nl(vec![Any], Any),
         );
+        m.add_str("constants", constants, Dfn::nl(vec![], Any));
+        m.add_str(
+            "constants__module",
+            constants__module,
+            Dfn::nl(vec![Any], Any),
+        );
         m.add_str("is_err", is_err, Dfn::nl(vec![Type::result()], Bool));
         m.add_str("is_ok", is_ok, Dfn::nl(vec![Type::result()], Bool));
         m.add_str("min", min, Dfn::nl(vec![Type::Array(Box::new(F64))], F64));
@@ -650,12 +714,84 @@ impl Module {
             explain_where,
             Dfn::nl(vec![F64, Any], Type::Secret(Box::new(F64))),
         );
+        m.add_str("assert", assert, Dfn::nl(vec![Bool], Void));
+        m.add_str("assert_eq", assert_eq, Dfn::nl(vec![Any; 2], Void));
         m.add_str("head", head, Dfn::nl(vec![Link], Any));
         m.add_str("tip", tip, Dfn::nl(vec![Link], Type::Option(Box::new(Any))));
         m.add_str("tail", tail, Dfn::nl(vec![Link], Link));
         m.add_str("neck", neck, Dfn::nl(vec![Link], Link));
         m.add_str("is_empty", is_empty, Dfn::nl(vec![Link], Bool));
-        m.add_unop_str("len", len, Dfn::nl(vec![Type::array()], F64));
+        m.add_unop_str(
+            "len",
+            len,
+            Dfn {
+                lts: vec![Lt::Default],
+                tys: vec![Union(vec![Type::array(), Bytes, Type::map(), Type::set()])],
+                ret: F64,
+                ext: vec![
+                    (vec![], vec![Type::array()], F64),
+                    (vec![], vec![Bytes], F64),
+                    (vec![], vec![Type::map()], F64),
+                    (vec![], vec![Type::set()], F64),
+                ],
+                lazy: LAZY_NO,
+            },
+        );
+        m.add_str("bytes", bytes, Dfn::nl(vec![Type::Array(Box::new(F64))], Bytes));
+        m.add_str("bytes__str", bytes__str, Dfn::nl(vec![Str], Bytes));
+        m.add_str("str__bytes", str__bytes, Dfn::nl(vec![Bytes], Type::Result(Box::new(Str))));
+        m.add_str("range", range, Dfn::nl(vec![F64, F64], Type::Range));
+        m.add_str(
+            "slice",
+            slice,
+            Dfn {
+                lts: vec![Lt::Default; 2],
+                tys: vec![Union(vec![Type::array(), Str, Bytes]), Type::Range],
+                ret: Any,
+                ext: vec![
+                    (vec![], vec![Type::array(), Type::Range], Type::array()),
+                    (vec![], vec![Str, Type::Range], Str),
+                    (vec![], vec![Bytes, Type::Range], Bytes),
+                ],
+                lazy: LAZY_NO,
+            },
+        );
+        m.add_str("read_u8", read_u8, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_i8", read_i8, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_u16_le", read_u16_le, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_u16_be", read_u16_be, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_i16_le", read_i16_le, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_i16_be", read_i16_be, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_u32_le", read_u32_le, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_u32_be", read_u32_be, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_i32_le", read_i32_le, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_i32_be", read_i32_be, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_u64_le", read_u64_le, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_u64_be", read_u64_be, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_i64_le", read_i64_le, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_i64_be", read_i64_be, Dfn::nl(vec![Bytes, F64], I64));
+        m.add_str("read_f32_le", read_f32_le, Dfn::nl(vec![Bytes, F64], F64));
+        m.add_str("read_f32_be", read_f32_be, Dfn::nl(vec![Bytes, F64], F64));
+        m.add_str("read_f64_le", read_f64_le, Dfn::nl(vec![Bytes, F64], F64));
+        m.add_str("read_f64_be", read_f64_be, Dfn::nl(vec![Bytes, F64], F64));
+        m.add_str("write_u8(mut,_,_)", write_u8, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_i8(mut,_,_)", write_i8, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_u16_le(mut,_,_)", write_u16_le, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_u16_be(mut,_,_)", write_u16_be, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_i16_le(mut,_,_)", write_i16_le, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_i16_be(mut,_,_)", write_i16_be, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_u32_le(mut,_,_)", write_u32_le, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_u32_be(mut,_,_)", write_u32_be, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_i32_le(mut,_,_)", write_i32_le, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_i32_be(mut,_,_)", write_i32_be, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_u64_le(mut,_,_)", write_u64_le, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_u64_be(mut,_,_)", write_u64_be, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_i64_le(mut,_,_)", write_i64_le, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_i64_be(mut,_,_)", write_i64_be, Dfn::nl(vec![Bytes, F64, I64], Void));
+        m.add_str("write_f32_le(mut,_,_)", write_f32_le, Dfn::nl(vec![Bytes, F64, F64], Void));
+        m.add_str("write_f32_be(mut,_,_)", write_f32_be, Dfn::nl(vec![Bytes, F64, F64], Void));
+        m.add_str("write_f64_le(mut,_,_)", write_f64_le, Dfn::nl(vec![Bytes, F64, F64], Void));
+        m.add_str("write_f64_be(mut,_,_)", write_f64_be, Dfn::nl(vec![Bytes, F64, F64], Void));
         m.add_str(
             "push_ref(mut,_)",
             push_ref,
@@ -690,6 +826,22 @@ impl Module {
                 lazy: LAZY_NO,
             },
         );
+        m.add_str("insert(mut,_)", insert_set, Dfn::nl(vec![Type::set(), Any], Void));
+        m.add_str(
+            "union",
+            union,
+            Dfn::nl(vec![Type::set(), Type::set()], Type::set()),
+        );
+        m.add_str(
+            "intersection",
+            intersection,
+            Dfn::nl(vec![Type::set(), Type::set()], Type::set()),
+        );
+        m.add_str(
+            "difference",
+            difference,
+            Dfn::nl(vec![Type::set(), Type::set()], Type::set()),
+        );
         m.add_str(
             "pop(mut)",
             pop,
@@ -706,13 +858,22 @@ impl Module {
             remove,
             Dfn {
                 lts: vec![Lt::Return, Lt::Default],
-                tys: vec![Type::array(), F64],
+                tys: vec![Any; 2],
                 ret: Any,
-                ext: vec![],
+                ext: vec![
+                    (vec![], vec![Type::array(), F64], Any),
+                    (vec![], vec![Type::map(), Any], Any),
+                    (vec![], vec![Type::set(), Any], Bool),
+                ],
                 lazy: LAZY_NO,
             },
         );
         m.add_str("reverse(mut)", reverse, Dfn::nl(vec![Type::array()], Void));
+        m.add_str(
+            "reverse(mut,_)",
+            reverse_range,
+            Dfn::nl(vec![Type::array(), Type::Range], Void),
+        );
         m.add_str("clear(mut)", clear, Dfn::nl(vec![Type::array()], Void));
         m.add_str(
             "swap(mut,_,_)",
@@ -754,11 +915,34 @@ impl Module {
             errstr__string_start_len_msg,
             Dfn::nl(vec![Str, F64, F64, Str], Str),
         );
-        m.add_str("has", has, Dfn::nl(vec![Object, Str], Bool));
+        m.add_str(
+            "has",
+            has,
+            Dfn {
+                lts: vec![Lt::Default; 2],
+                tys: vec![Any; 2],
+                ret: Bool,
+                ext: vec![
+                    (vec![], vec![Object, Str], Bool),
+                    (vec![], vec![Type::map(), Any], Bool),
+                    (vec![], vec![Type::set(), Any], Bool),
+                ],
+                lazy: LAZY_NO,
+            },
+        );
         m.add_str(
             "keys",
             keys,
-            Dfn::nl(vec![Object], Type::Array(Box::new(Str))),
+            Dfn {
+                lts: vec![Lt::Default],
+                tys: vec![Any],
+                ret: Type::array(),
+                ext: vec![
+                    (vec![], vec![Object], Type::Array(Box::new(Str))),
+                    (vec![], vec![Type::map()], Type::array()),
+                ],
+                lazy: LAZY_NO,
+            },
         );
         m.add_str(
             "chars",
@@ -784,10 +968,22 @@ impl Module {
         self.register_namespace = Arc::new(vec![]);
     }
 
+    /// Sets whether to skip calls to `assert` and `assert_eq`, e.g. for release runs.
+    ///
+    /// The arguments of stripped asserts are not evaluated.
+    pub fn strip_asserts(&mut self, val: bool) {
+        self.strip_asserts = val;
+    }
+
     pub(crate) fn register(&mut self, function: ast::Function) {
         self.functions.push(function);
     }
 
+    /// Find constant by name.
+    pub(crate) fn find_constant(&self, name: &Arc<String>) -> Option<&ast::Const> {
+        self.constants.iter().rev().find(|c| &c.name == name)
+    }
+
     /// Find function relative another function index.
     pub fn find_function(&self, name: &Arc<String>, relative: usize) -> FnIndex {
         for (i, f) in self.functions.iter().enumerate().rev() {
@@ -795,6 +991,12 @@ impl Module {
                 return FnIndex::Loaded(i as isize - relative as isize);
             }
         }
+        // Look for calls leaving out arguments with default values.
+        for (i, f) in self.functions.iter().enumerate().rev() {
+            if f.default_calls.iter().any(|(n, _)| n == name) {
+                return FnIndex::Loaded(i as isize - relative as isize);
+            }
+        }
         for f in self.ext_prelude.iter().rev() {
             if &f.name == name {
                 return match f.f {
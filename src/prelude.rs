@@ -96,7 +96,11 @@ impl Dfn {
 pub struct Prelude {
     pub(crate) functions: HashMap<Arc<String>, usize>,
     pub(crate) list: Vec<Dfn>,
+    /// Stores number of trailing arguments with default values, by index in `list`.
+    pub(crate) defaults: HashMap<usize, usize>,
     pub(crate) namespaces: Vec<(Arc<Vec<Arc<String>>>, Arc<String>)>,
+    pub(crate) constants: HashMap<Arc<String>, Type>,
+    pub(crate) type_aliases: Vec<ast::TypeAlias>,
 }
 
 impl Default for Prelude {
@@ -119,7 +123,10 @@ impl Prelude {
         Prelude {
             functions: HashMap::new(),
             list: vec![],
+            defaults: HashMap::new(),
             namespaces: vec![],
+            constants: HashMap::new(),
+            type_aliases: vec![],
         }
     }
 
@@ -131,7 +138,20 @@ impl Prelude {
         }
         for f in &module.functions {
             prelude.insert(f.namespace.clone(), f.name.clone(), Dfn::new(f));
+            // Calls leaving out arguments with default values use the same signature.
+            let defaults = f.args.iter().rev().take_while(|arg| arg.default.is_some()).count();
+            if defaults > 0 {
+                let ind = prelude.list.len() - 1;
+                prelude.defaults.insert(ind, defaults);
+                for (name, _) in &f.default_calls {
+                    prelude.functions.entry(name.clone()).or_insert(ind);
+                }
+            }
+        }
+        for c in &module.constants {
+            prelude.constants.insert(c.name.clone(), c.ty.clone());
         }
+        prelude.type_aliases = module.type_aliases.clone();
         prelude
     }
 }
@@ -1,4 +1,156 @@
 use super::*;
+use crate::{Link, Map, Object};
+
+/// Iterates over the values of a for-in loop.
+pub(crate) enum Iter {
+    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+    In(Arc<::std::sync::Mutex<::std::sync::mpsc::Receiver<Variable>>>, usize),
+    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+    Gen(Arc<::std::sync::Mutex<Generator>>, usize),
+    Array(Arc<Vec<Variable>>, usize),
+    Object(Object, Vec<Arc<String>>, usize),
+    Map(Map, Vec<Key>, usize),
+    Set(Vec<Key>, usize),
+    /// Stores the link, the current slice, the offset within slice and the index.
+    Link(Link, usize, usize, usize),
+    Str(Arc<String>, usize, usize),
+}
+
+impl Iter {
+    /// Creates an iterator, or returns `None` if the value is not iterable.
+    fn new(var: &Variable) -> Option<Iter> {
+        match *var {
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Variable::In(ref val) => Some(Iter::In(val.clone(), 0)),
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Variable::Gen(ref val) => Some(Iter::Gen(val.clone(), 0)),
+            Variable::Array(ref arr) => Some(Iter::Array(arr.clone(), 0)),
+            Variable::Object(ref obj) => {
+                // Sort keys to visit entries in a predictable order.
+                let mut keys: Vec<Arc<String>> = obj.keys().cloned().collect();
+                keys.sort();
+                Some(Iter::Object(obj.clone(), keys, 0))
+            }
+            Variable::Map(ref map) => {
+                let mut keys: Vec<Key> = map.keys().cloned().collect();
+                keys.sort();
+                Some(Iter::Map(map.clone(), keys, 0))
+            }
+            Variable::Set(ref set) => {
+                let mut items: Vec<Key> = set.iter().cloned().collect();
+                items.sort();
+                Some(Iter::Set(items, 0))
+            }
+            Variable::Link(ref link) => Some(Iter::Link((**link).clone(), 0, 0, 0)),
+            Variable::Str(ref s) => Some(Iter::Str(s.clone(), 0, 0)),
+            _ => None,
+        }
+    }
+
+    /// Gets the next key and value.
+    ///
+    /// The key is the index, except for objects and maps where it is the key of the entry.
+    fn next(&mut self) -> Result<Option<(Variable, Variable)>, String> {
+        match *self {
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Iter::In(ref iter, ref mut i) => match iter.lock() {
+                Ok(x) => match x.try_recv() {
+                    Ok(x) => {
+                        *i += 1;
+                        Ok(Some((Variable::f64((*i - 1) as f64), x)))
+                    }
+                    Err(_) => Ok(None),
+                },
+                Err(err) => Err(format!("Can not lock In mutex:\n{}", err)),
+            },
+            // Run the generator, which ends when it returns.
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Iter::Gen(ref iter, ref mut i) => match iter.lock() {
+                Ok(mut x) => match x.next()? {
+                    Some(x) => {
+                        *i += 1;
+                        Ok(Some((Variable::f64((*i - 1) as f64), x)))
+                    }
+                    None => Ok(None),
+                },
+                Err(err) => Err(format!("Can not lock In mutex:\n{}", err)),
+            },
+            Iter::Array(ref arr, ref mut i) => {
+                if *i < arr.len() {
+                    *i += 1;
+                    Ok(Some((Variable::f64((*i - 1) as f64), arr[*i - 1].clone())))
+                } else {
+                    Ok(None)
+                }
+            }
+            Iter::Object(ref obj, ref keys, ref mut i) => {
+                if *i < keys.len() {
+                    let key = keys[*i].clone();
+                    *i += 1;
+                    let val = obj[&key].clone();
+                    Ok(Some((Variable::Str(key), val)))
+                } else {
+                    Ok(None)
+                }
+            }
+            Iter::Map(ref map, ref keys, ref mut i) => {
+                if *i < keys.len() {
+                    let key = &keys[*i];
+                    *i += 1;
+                    Ok(Some((key.to_variable(), map[key].clone())))
+                } else {
+                    Ok(None)
+                }
+            }
+            Iter::Set(ref items, ref mut i) => {
+                if *i < items.len() {
+                    *i += 1;
+                    Ok(Some((Variable::f64((*i - 1) as f64), items[*i - 1].to_variable())))
+                } else {
+                    Ok(None)
+                }
+            }
+            Iter::Link(ref link, ref mut slice, ref mut offset, ref mut i) => {
+                while let Some(s) = link.slices.get(*slice) {
+                    let k = s.start as usize + *offset;
+                    if k < s.end as usize {
+                        *offset += 1;
+                        *i += 1;
+                        return Ok(Some((Variable::f64((*i - 1) as f64), s.block.var(k as u8))));
+                    }
+                    *slice += 1;
+                    *offset = 0;
+                }
+                Ok(None)
+            }
+            Iter::Str(ref s, ref mut offset, ref mut i) => match s[*offset..].chars().next() {
+                Some(ch) => {
+                    *offset += ch.len_utf8();
+                    *i += 1;
+                    Ok(Some((Variable::f64((*i - 1) as f64), Variable::Str(Arc::new(ch.into())))))
+                }
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// Returns `true` if nothing was received from an `in` receiver.
+    ///
+    /// Reductions over an empty `in` return no value, as before collections were supported.
+    /// Other empty collections give the neutral value, e.g. `0` for `sum in`.
+    fn is_empty_in(&self) -> bool {
+        match *self {
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Iter::In(_, 0) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if a single loop variable gets the key instead of the value.
+    fn single_is_key(&self) -> bool {
+        matches!(*self, Iter::Object(..) | Iter::Map(..))
+    }
+}
 
 macro_rules! iter(
     ($rt:ident, $for_in_expr:ident) => {{
@@ -6,27 +158,35 @@ macro_rules! iter(
             (x, Flow::Return) => { return Ok((x, Flow::Return)); }
             (Some(x), Flow::Continue) => x,
             _ => return Err($rt.module.error($for_in_expr.iter.source_range(),
-                &format!("{}\nExpected in-type from for iter",
+                &format!("{}\nExpected collection from for iter",
                     $rt.stack_trace()), $rt))
         };
-        match $rt.get(&iter) {
-            &Variable::In(ref val) => val.clone(),
-            x => return Err($rt.module.error($for_in_expr.iter.source_range(),
-                            &$rt.expected(x, "in"), $rt))
+        match Iter::new($rt.get(&iter)) {
+            Some(x) => x,
+            None => return Err($rt.module.error($for_in_expr.iter.source_range(),
+                &$rt.expected($rt.get(&iter), "in, array, object, map, set, link or str"), $rt))
         }
     }};
 );
 
-macro_rules! iter_val(
+macro_rules! next(
     ($iter:ident, $rt:ident, $for_in_expr:ident) => {
-        match $iter.lock() {
-            Ok(x) => match x.try_recv() {
-                Ok(x) => x,
-                Err(_) => return Ok((None, Flow::Continue)),
-            },
+        match $iter.next() {
+            Ok(Some((key, val))) => {
+                $rt.local_stack.push(($for_in_expr.name.clone(), $rt.stack.len()));
+                if let Some(ref value) = $for_in_expr.value {
+                    $rt.stack.push(key);
+                    $rt.local_stack.push((value.clone(), $rt.stack.len()));
+                    $rt.stack.push(val);
+                } else if $iter.single_is_key() {
+                    $rt.stack.push(key);
+                } else {
+                    $rt.stack.push(val);
+                }
+            }
+            Ok(None) => break,
             Err(err) => {
-                return Err($rt.module.error($for_in_expr.source_range,
-                &format!("Can not lock In mutex:\n{}", err.to_string()), $rt));
+                return Err($rt.module.error($for_in_expr.source_range, &err, $rt));
             }
         }
     };
@@ -62,42 +222,31 @@ macro_rules! continue_(
     };
 );
 
-macro_rules! iter_val_inc(
-    ($iter:ident, $rt:ident, $for_in_expr:ident) => {
-        match $iter.lock() {
-            Ok(x) => match x.try_recv() {
-                Ok(x) => x,
-                Err(_) => break,
-            },
-            Err(err) => {
-                return Err($rt.module.error($for_in_expr.source_range,
-                &format!("Can not lock In mutex:\n{}", err.to_string()), $rt));
-            }
-        }
-    };
-);
-
 impl Runtime {
     pub(crate) fn for_in_expr(
         &mut self,
         for_in_expr: &ast::ForIn,
     ) -> Result<(Option<Variable>, Flow), String> {
-        let prev_st = self.stack.len();
-        let prev_lc = self.local_stack.len();
-
-        let iter = iter!(self, for_in_expr);
-        let iter_val = iter_val!(iter, self, for_in_expr);
-
-        // Initialize counter.
-        self.local_stack
-            .push((for_in_expr.name.clone(), self.stack.len()));
-        self.stack.push(iter_val);
+        // The next value was taken before a generator was suspended in the loop.
+        let (mut iter, (prev_st, prev_lc), mut resuming) = match self.resume() {
+            Some(generator::Frame::ForIn { iter, prev }) => (iter, prev, true),
+            _ => {
+                let prev = (self.stack.len(), self.local_stack.len());
+                (iter!(self, for_in_expr), prev, false)
+            }
+        };
 
-        let st = self.stack.len();
-        let lc = self.local_stack.len();
         let mut flow = Flow::Continue;
         loop {
+            if !resuming {
+                next!(iter, self, for_in_expr);
+            }
+            resuming = false;
             match self.block(&for_in_expr.block)? {
+                (_, Flow::Return) if self.yielding() => {
+                    let prev = (prev_st, prev_lc);
+                    return Ok(self.suspend(generator::Frame::ForIn { iter, prev }));
+                }
                 (x, Flow::Return) => {
                     return Ok((x, Flow::Return));
                 }
@@ -106,9 +255,8 @@ impl Runtime {
                 (_, Flow::ContinueLoop(x)) => continue_!(x, for_in_expr, flow),
             }
 
-            self.stack[st - 1] = iter_val_inc!(iter, self, for_in_expr);
-            self.stack.truncate(st);
-            self.local_stack.truncate(lc);
+            self.stack.truncate(prev_st);
+            self.local_stack.truncate(prev_lc);
         }
         self.stack.truncate(prev_st);
         self.local_stack.truncate(prev_lc);
@@ -122,20 +270,13 @@ impl Runtime {
         let prev_st = self.stack.len();
         let prev_lc = self.local_stack.len();
 
-        let iter = iter!(self, for_in_expr);
-        let iter_val = iter_val!(iter, self, for_in_expr);
+        let mut iter = iter!(self, for_in_expr);
 
         let mut sum = 0.0;
 
-        // Initialize counter.
-        self.local_stack
-            .push((for_in_expr.name.clone(), self.stack.len()));
-        self.stack.push(iter_val);
-
-        let st = self.stack.len();
-        let lc = self.local_stack.len();
         let mut flow = Flow::Continue;
         loop {
+            next!(iter, self, for_in_expr);
             match self.block(&for_in_expr.block)? {
                 (Some(x), Flow::Continue) => {
                     match self.get(&x) {
@@ -157,12 +298,14 @@ impl Runtime {
                 (_, Flow::ContinueLoop(x)) => continue_!(x, for_in_expr, flow),
             }
 
-            self.stack[st - 1] = iter_val_inc!(iter, self, for_in_expr);
-            self.stack.truncate(st);
-            self.local_stack.truncate(lc);
+            self.stack.truncate(prev_st);
+            self.local_stack.truncate(prev_lc);
         }
         self.stack.truncate(prev_st);
         self.local_stack.truncate(prev_lc);
+        if iter.is_empty_in() {
+            return Ok((None, flow));
+        }
         Ok((Some(Variable::f64(sum)), flow))
     }
 
@@ -173,20 +316,13 @@ impl Runtime {
         let prev_st = self.stack.len();
         let prev_lc = self.local_stack.len();
 
-        let iter = iter!(self, for_in_expr);
-        let iter_val = iter_val!(iter, self, for_in_expr);
+        let mut iter = iter!(self, for_in_expr);
 
         let mut prod = 1.0;
 
-        // Initialize counter.
-        self.local_stack
-            .push((for_in_expr.name.clone(), self.stack.len()));
-        self.stack.push(iter_val);
-
-        let st = self.stack.len();
-        let lc = self.local_stack.len();
         let mut flow = Flow::Continue;
         loop {
+            next!(iter, self, for_in_expr);
             match self.block(&for_in_expr.block)? {
                 (Some(x), Flow::Continue) => {
                     match self.get(&x) {
@@ -208,12 +344,14 @@ impl Runtime {
                 (_, Flow::ContinueLoop(x)) => continue_!(x, for_in_expr, flow),
             }
 
-            self.stack[st - 1] = iter_val_inc!(iter, self, for_in_expr);
-            self.stack.truncate(st);
-            self.local_stack.truncate(lc);
+            self.stack.truncate(prev_st);
+            self.local_stack.truncate(prev_lc);
         }
         self.stack.truncate(prev_st);
         self.local_stack.truncate(prev_lc);
+        if iter.is_empty_in() {
+            return Ok((None, flow));
+        }
         Ok((Some(Variable::f64(prod)), flow))
     }
 
@@ -224,19 +362,13 @@ impl Runtime {
         let prev_st = self.stack.len();
         let prev_lc = self.local_stack.len();
 
-        let iter = iter!(self, for_in_expr);
-        let iter_val = iter_val!(iter, self, for_in_expr);
+        let mut iter = iter!(self, for_in_expr);
 
         let mut min = ::std::f64::NAN;
         let mut sec = None;
-        // Initialize counter.
-        self.local_stack
-            .push((for_in_expr.name.clone(), self.stack.len()));
-        self.stack.push(iter_val);
-        let st = self.stack.len();
-        let lc = self.local_stack.len();
         let mut flow = Flow::Continue;
         loop {
+            next!(iter, self, for_in_expr);
             match self.block(&for_in_expr.block)? {
                 (Some(x), Flow::Continue) => {
                     match self.get(&x) {
@@ -244,10 +376,10 @@ impl Runtime {
                             if min.is_nan() || min > val {
                                 min = val;
                                 sec = match *val_sec {
-                                    None => Some(Box::new(vec![self.stack[st - 1].clone()])),
+                                    None => Some(Box::new(vec![self.stack[prev_st].clone()])),
                                     Some(ref arr) => {
                                         let mut arr = arr.clone();
-                                        arr.push(self.stack[st - 1].clone());
+                                        arr.push(self.stack[prev_st].clone());
                                         Some(arr)
                                     }
                                 };
@@ -276,12 +408,14 @@ impl Runtime {
                 (_, Flow::ContinueLoop(x)) => continue_!(x, for_in_expr, flow),
             }
 
-            self.stack[st - 1] = iter_val_inc!(iter, self, for_in_expr);
-            self.stack.truncate(st);
-            self.local_stack.truncate(lc);
+            self.stack.truncate(prev_st);
+            self.local_stack.truncate(prev_lc);
         }
         self.stack.truncate(prev_st);
         self.local_stack.truncate(prev_lc);
+        if iter.is_empty_in() {
+            return Ok((None, flow));
+        }
         Ok((Some(Variable::F64(min, sec)), flow))
     }
 
@@ -292,19 +426,13 @@ impl Runtime {
         let prev_st = self.stack.len();
         let prev_lc = self.local_stack.len();
 
-        let iter = iter!(self, for_in_expr);
-        let iter_val = iter_val!(iter, self, for_in_expr);
+        let mut iter = iter!(self, for_in_expr);
 
         let mut max = ::std::f64::NAN;
         let mut sec = None;
-        // Initialize counter.
-        self.local_stack
-            .push((for_in_expr.name.clone(), self.stack.len()));
-        self.stack.push(iter_val);
-        let st = self.stack.len();
-        let lc = self.local_stack.len();
         let mut flow = Flow::Continue;
         loop {
+            next!(iter, self, for_in_expr);
             match self.block(&for_in_expr.block)? {
                 (Some(x), Flow::Continue) => {
                     match self.get(&x) {
@@ -312,10 +440,10 @@ impl Runtime {
                             if max.is_nan() || max < val {
                                 max = val;
                                 sec = match *val_sec {
-                                    None => Some(Box::new(vec![self.stack[st - 1].clone()])),
+                                    None => Some(Box::new(vec![self.stack[prev_st].clone()])),
                                     Some(ref arr) => {
                                         let mut arr = arr.clone();
-                                        arr.push(self.stack[st - 1].clone());
+                                        arr.push(self.stack[prev_st].clone());
                                         Some(arr)
                                     }
                                 };
@@ -344,12 +472,14 @@ impl Runtime {
                 (_, Flow::ContinueLoop(x)) => continue_!(x, for_in_expr, flow),
             }
 
-            self.stack[st - 1] = iter_val_inc!(iter, self, for_in_expr);
-            self.stack.truncate(st);
-            self.local_stack.truncate(lc);
+            self.stack.truncate(prev_st);
+            self.local_stack.truncate(prev_lc);
         }
         self.stack.truncate(prev_st);
         self.local_stack.truncate(prev_lc);
+        if iter.is_empty_in() {
+            return Ok((None, flow));
+        }
         Ok((Some(Variable::F64(max, sec)), flow))
     }
 
@@ -360,20 +490,13 @@ impl Runtime {
         let prev_st = self.stack.len();
         let prev_lc = self.local_stack.len();
 
-        let iter = iter!(self, for_in_expr);
-        let iter_val = iter_val!(iter, self, for_in_expr);
+        let mut iter = iter!(self, for_in_expr);
 
         let mut any = false;
         let mut sec = None;
-        // Initialize counter.
-        self.local_stack
-            .push((for_in_expr.name.clone(), self.stack.len()));
-        self.stack.push(iter_val);
-
-        let st = self.stack.len();
-        let lc = self.local_stack.len();
         let mut flow = Flow::Continue;
         loop {
+            next!(iter, self, for_in_expr);
             match self.block(&for_in_expr.block)? {
                 (Some(x), Flow::Continue) => {
                     match self.get(&x) {
@@ -381,10 +504,10 @@ impl Runtime {
                             if val {
                                 any = true;
                                 sec = match *val_sec {
-                                    None => Some(Box::new(vec![self.stack[st - 1].clone()])),
+                                    None => Some(Box::new(vec![self.stack[prev_st].clone()])),
                                     Some(ref arr) => {
                                         let mut arr = arr.clone();
-                                        arr.push(self.stack[st - 1].clone());
+                                        arr.push(self.stack[prev_st].clone());
                                         Some(arr)
                                     }
                                 };
@@ -414,12 +537,14 @@ impl Runtime {
                 (_, Flow::ContinueLoop(x)) => continue_!(x, for_in_expr, flow),
             }
 
-            self.stack[st - 1] = iter_val_inc!(iter, self, for_in_expr);
-            self.stack.truncate(st);
-            self.local_stack.truncate(lc);
+            self.stack.truncate(prev_st);
+            self.local_stack.truncate(prev_lc);
         }
         self.stack.truncate(prev_st);
         self.local_stack.truncate(prev_lc);
+        if iter.is_empty_in() {
+            return Ok((None, flow));
+        }
         Ok((Some(Variable::Bool(any, sec)), flow))
     }
 
@@ -430,20 +555,13 @@ impl Runtime {
         let prev_st = self.stack.len();
         let prev_lc = self.local_stack.len();
 
-        let iter = iter!(self, for_in_expr);
-        let iter_val = iter_val!(iter, self, for_in_expr);
+        let mut iter = iter!(self, for_in_expr);
 
         let mut all = true;
         let mut sec = None;
-        // Initialize counter.
-        self.local_stack
-            .push((for_in_expr.name.clone(), self.stack.len()));
-        self.stack.push(iter_val);
-
-        let st = self.stack.len();
-        let lc = self.local_stack.len();
         let mut flow = Flow::Continue;
         loop {
+            next!(iter, self, for_in_expr);
             match self.block(&for_in_expr.block)? {
                 (Some(x), Flow::Continue) => {
                     match self.get(&x) {
@@ -451,10 +569,10 @@ impl Runtime {
                             if !val {
                                 all = false;
                                 sec = match *val_sec {
-                                    None => Some(Box::new(vec![self.stack[st - 1].clone()])),
+                                    None => Some(Box::new(vec![self.stack[prev_st].clone()])),
                                     Some(ref arr) => {
                                         let mut arr = arr.clone();
-                                        arr.push(self.stack[st - 1].clone());
+                                        arr.push(self.stack[prev_st].clone());
                                         Some(arr)
                                     }
                                 };
@@ -484,12 +602,14 @@ impl Runtime {
                 (_, Flow::ContinueLoop(x)) => continue_!(x, for_in_expr, flow),
             }
 
-            self.stack[st - 1] = iter_val_inc!(iter, self, for_in_expr);
-            self.stack.truncate(st);
-            self.local_stack.truncate(lc);
+            self.stack.truncate(prev_st);
+            self.local_stack.truncate(prev_lc);
         }
         self.stack.truncate(prev_st);
         self.local_stack.truncate(prev_lc);
+        if iter.is_empty_in() {
+            return Ok((None, flow));
+        }
         Ok((Some(Variable::Bool(all, sec)), flow))
     }
 
@@ -497,8 +617,6 @@ impl Runtime {
         &mut self,
         for_in_expr: &ast::ForIn,
     ) -> Result<(Option<Variable>, Flow), String> {
-        use crate::Link;
-
         fn sub_link_for_in_expr(
             res: &mut Link,
             rt: &mut Runtime,
@@ -507,19 +625,12 @@ impl Runtime {
             let prev_st = rt.stack.len();
             let prev_lc = rt.local_stack.len();
 
-            let iter = iter!(rt, for_in_expr);
-            let iter_val = iter_val!(iter, rt, for_in_expr);
-
-            // Initialize counter.
-            rt.local_stack
-                .push((for_in_expr.name.clone(), rt.stack.len()));
-            rt.stack.push(iter_val);
+            let mut iter = iter!(rt, for_in_expr);
 
-            let st = rt.stack.len();
-            let lc = rt.local_stack.len();
             let mut flow = Flow::Continue;
 
             'outer: loop {
+                next!(iter, rt, for_in_expr);
                 match for_in_expr.block.expressions[0] {
                     ast::Expression::Link(ref link) => {
                         // Evaluate link items directly.
@@ -611,9 +722,8 @@ impl Runtime {
                     }
                 }
 
-                rt.stack[st - 1] = iter_val_inc!(iter, rt, for_in_expr);
-                rt.stack.truncate(st);
-                rt.local_stack.truncate(lc);
+                rt.stack.truncate(prev_st);
+                rt.local_stack.truncate(prev_lc);
             }
             rt.stack.truncate(prev_st);
             rt.local_stack.truncate(prev_lc);
@@ -635,20 +745,13 @@ impl Runtime {
         let prev_lc = self.local_stack.len();
         let mut res: Vec<Variable> = vec![];
 
-        let iter = iter!(self, for_in_expr);
-        let iter_val = iter_val!(iter, self, for_in_expr);
+        let mut iter = iter!(self, for_in_expr);
 
-        // Initialize counter.
-        self.local_stack
-            .push((for_in_expr.name.clone(), self.stack.len()));
-        self.stack.push(iter_val);
-
-        let st = self.stack.len();
-        let lc = self.local_stack.len();
         let mut flow = Flow::Continue;
         loop {
+            next!(iter, self, for_in_expr);
             match self.block(&for_in_expr.block)? {
-                (Some(x), Flow::Continue) => res.push(x),
+                (Some(x), Flow::Continue) => res.push(self.get(&x).clone()),
                 (x, Flow::Return) => {
                     return Ok((x, Flow::Return));
                 }
@@ -663,12 +766,14 @@ impl Runtime {
                 (_, Flow::ContinueLoop(x)) => continue_!(x, for_in_expr, flow),
             }
 
-            self.stack[st - 1] = iter_val_inc!(iter, self, for_in_expr);
-            self.stack.truncate(st);
-            self.local_stack.truncate(lc);
+            self.stack.truncate(prev_st);
+            self.local_stack.truncate(prev_lc);
         }
         self.stack.truncate(prev_st);
         self.local_stack.truncate(prev_lc);
+        if iter.is_empty_in() {
+            return Ok((None, flow));
+        }
         Ok((Some(Variable::Array(Arc::new(res))), flow))
     }
 }
@@ -20,8 +20,10 @@ macro_rules! start(
     };
 );
 
-macro_rules! end(
+// Evaluates start and end, where a range gives both, e.g. `for i 0..n { ... }`.
+macro_rules! bounds(
     ($rt:ident, $for_n_expr:ident) => {{
+        let start = start!($rt, $for_n_expr);
         let end = match $rt.expression(&$for_n_expr.end, Side::Right)? {
             (x, Flow::Return) => { return Ok((x, Flow::Return)); }
             (Some(x), Flow::Continue) => x,
@@ -29,9 +31,12 @@ macro_rules! end(
                 &format!("{}\nExpected number from for end",
                     $rt.stack_trace()), $rt))
         };
-        match $rt.get(&end) {
-            &Variable::F64(val, _) => val,
-            x => return Err($rt.module.error($for_n_expr.end.source_range(),
+        match ($rt.get(&end), &$for_n_expr.start) {
+            (&Variable::F64(val, _), _) => (start, val),
+            (&Variable::Range(start, end), None) => (start, end),
+            (x, None) => return Err($rt.module.error($for_n_expr.end.source_range(),
+                            &$rt.expected(x, "number or range"), $rt)),
+            (x, Some(_)) => return Err($rt.module.error($for_n_expr.end.source_range(),
                             &$rt.expected(x, "number"), $rt))
         }
     }};
@@ -111,23 +116,33 @@ impl Runtime {
         &mut self,
         for_n_expr: &ast::ForN,
     ) -> Result<(Option<Variable>, Flow), String> {
-        let prev_st = self.stack.len();
-        let prev_lc = self.local_stack.len();
+        // The counter was checked before a generator was suspended in the loop.
+        let (end, (prev_st, prev_lc), (st, lc), mut resuming) = match self.resume() {
+            Some(generator::Frame::ForN { end, prev, lens }) => (end, prev, lens, true),
+            _ => {
+                let prev = (self.stack.len(), self.local_stack.len());
 
-        let start = start!(self, for_n_expr);
-        let end = end!(self, for_n_expr);
+                let (start, end) = bounds!(self, for_n_expr);
 
-        // Initialize counter.
-        self.local_stack
-            .push((for_n_expr.name.clone(), self.stack.len()));
-        self.stack.push(Variable::f64(start));
+                // Initialize counter.
+                self.local_stack
+                    .push((for_n_expr.name.clone(), self.stack.len()));
+                self.stack.push(Variable::f64(start));
 
-        let st = self.stack.len();
-        let lc = self.local_stack.len();
+                (end, prev, (self.stack.len(), self.local_stack.len()), false)
+            }
+        };
         let mut flow = Flow::Continue;
         loop {
-            cond!(self, for_n_expr, st, end);
+            if !resuming {
+                cond!(self, for_n_expr, st, end);
+            }
+            resuming = false;
             match self.block(&for_n_expr.block)? {
+                (_, Flow::Return) if self.yielding() => {
+                    let (prev, lens) = ((prev_st, prev_lc), (st, lc));
+                    return Ok(self.suspend(generator::Frame::ForN { end, prev, lens }));
+                }
                 (x, Flow::Return) => {
                     return Ok((x, Flow::Return));
                 }
@@ -152,8 +167,7 @@ impl Runtime {
         let prev_lc = self.local_stack.len();
         let mut sum = 0.0;
 
-        let start = start!(self, for_n_expr);
-        let end = end!(self, for_n_expr);
+        let (start, end) = bounds!(self, for_n_expr);
 
         // Initialize counter.
         self.local_stack
@@ -208,8 +222,7 @@ impl Runtime {
         let prev_lc = self.local_stack.len();
         let mut prod = 1.0;
 
-        let start = start!(self, for_n_expr);
-        let end = end!(self, for_n_expr);
+        let (start, end) = bounds!(self, for_n_expr);
 
         // Initialize counter.
         self.local_stack
@@ -263,8 +276,7 @@ impl Runtime {
         let prev_st = self.stack.len();
         let prev_lc = self.local_stack.len();
 
-        let start = start!(self, for_n_expr);
-        let end = end!(self, for_n_expr);
+        let (start, end) = bounds!(self, for_n_expr);
 
         let mut min = ::std::f64::NAN;
         let mut sec = None;
@@ -331,8 +343,7 @@ impl Runtime {
         let prev_st = self.stack.len();
         let prev_lc = self.local_stack.len();
 
-        let start = start!(self, for_n_expr);
-        let end = end!(self, for_n_expr);
+        let (start, end) = bounds!(self, for_n_expr);
 
         let mut max = ::std::f64::NAN;
         let mut sec = None;
@@ -400,8 +411,7 @@ impl Runtime {
         let prev_st = self.stack.len();
         let prev_lc = self.local_stack.len();
 
-        let start = start!(self, for_n_expr);
-        let end = end!(self, for_n_expr);
+        let (start, end) = bounds!(self, for_n_expr);
 
         let mut any = false;
         let mut sec = None;
@@ -470,8 +480,7 @@ impl Runtime {
         let prev_st = self.stack.len();
         let prev_lc = self.local_stack.len();
 
-        let start = start!(self, for_n_expr);
-        let end = end!(self, for_n_expr);
+        let (start, end) = bounds!(self, for_n_expr);
 
         let mut all = true;
         let mut sec = None;
@@ -547,8 +556,7 @@ impl Runtime {
             let prev_st = rt.stack.len();
             let prev_lc = rt.local_stack.len();
 
-            let start = start!(rt, for_n_expr);
-            let end = end!(rt, for_n_expr);
+            let (start, end) = bounds!(rt, for_n_expr);
 
             // Initialize counter.
             rt.local_stack
@@ -653,8 +661,7 @@ impl Runtime {
         let prev_lc = self.local_stack.len();
         let mut res: Vec<Variable> = vec![];
 
-        let start = start!(self, for_n_expr);
-        let end = end!(self, for_n_expr);
+        let (start, end) = bounds!(self, for_n_expr);
 
         // Initialize counter.
         self.local_stack
@@ -698,8 +705,7 @@ impl Runtime {
         let prev_lc = self.local_stack.len();
         let mut sum: [f32; 4] = [0.0; 4];
 
-        let start = start!(self, for_n_expr);
-        let end = end!(self, for_n_expr);
+        let (start, end) = bounds!(self, for_n_expr);
 
         // Initialize counter.
         self.local_stack
@@ -758,8 +764,7 @@ impl Runtime {
         let prev_lc = self.local_stack.len();
         let mut prod: [f32; 4] = [1.0; 4];
 
-        let start = start!(self, for_n_expr);
-        let end = end!(self, for_n_expr);
+        let (start, end) = bounds!(self, for_n_expr);
 
         // Initialize counter.
         self.local_stack
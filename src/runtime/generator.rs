@@ -0,0 +1,169 @@
+//! Generator functions are suspended at `yield` and resumed on the same thread.
+//!
+//! When a value is yielded, each block, `if` and loop on the way out of the function
+//! pushes a frame with its local state, leaving the stack of the generator as it is.
+//! Resuming runs the function body again, where each of these pops its frame
+//! and continues where it left off.
+//! The lifetime checker makes sure that `yield` is only used as a statement
+//! in blocks, `if` and loops, such that nothing else is between the function and `yield`.
+
+use super::for_in::Iter;
+use super::*;
+
+/// Local state of a block, `if` or loop where a generator function is suspended.
+pub(crate) enum Frame {
+    /// Index of the statement, stack lengths at start of block
+    /// and indices of deferred blocks with stack lengths where they were reached.
+    Block {
+        pos: usize,
+        lens: (usize, usize, usize),
+        deferred: Vec<(usize, (usize, usize, usize))>,
+    },
+    /// Index of the branch, where `0` is the true block and the else block is last.
+    If(usize),
+    /// Stack lengths before the loop and after the init expression.
+    For {
+        prev: (usize, usize),
+        lens: (usize, usize),
+    },
+    /// End of the counter, stack lengths before the loop and after the counter.
+    ForN {
+        end: f64,
+        prev: (usize, usize),
+        lens: (usize, usize),
+    },
+    /// Iterator and stack lengths before the loop.
+    ForIn { iter: Iter, prev: (usize, usize) },
+}
+
+// Frames only contain values that `Variable` contains, which can be sent between threads.
+unsafe impl Send for Frame {}
+
+/// Stores a generator function that is suspended at `yield`.
+#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+pub struct Generator {
+    /// Runs the generator function with its own stack.
+    rt: Runtime,
+    /// The index of the generator function.
+    index: usize,
+    /// Whether the generator function has returned.
+    done: bool,
+}
+
+#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+impl Generator {
+    /// Runs the generator function until the next `yield`.
+    ///
+    /// Returns `None` when the function has returned.
+    pub(crate) fn next(&mut self) -> Result<Option<Variable>, String> {
+        if self.done {
+            return Ok(None);
+        }
+        let module = self.rt.module.clone();
+        let res = self.rt.block(&module.functions[self.index].block);
+        if let Some(x) = self.rt.yielded.take() {
+            return Ok(Some(x));
+        }
+        self.done = true;
+        res.map(|_| None)
+    }
+}
+
+#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+impl std::fmt::Debug for Generator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "generator")
+    }
+}
+
+impl Runtime {
+    /// Returns `true` when a generator function is being suspended by `yield`.
+    pub(crate) fn yielding(&self) -> bool {
+        self.yielded.is_some()
+    }
+
+    /// Stores the local state of a block, `if` or loop while being suspended.
+    pub(crate) fn suspend(&mut self, frame: Frame) -> (Option<Variable>, Flow) {
+        self.frames.push(frame);
+        (None, Flow::Return)
+    }
+
+    /// Takes the local state to continue from when resuming a generator function.
+    pub(crate) fn resume(&mut self) -> Option<Frame> {
+        if self.yielded.is_none() {
+            self.frames.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Creates a generator that runs the function when asked for a value.
+    ///
+    /// The arguments and current objects are moved from the stack, starting at `st`.
+    /// The names of current objects are moved from the local stack, starting at `lc`.
+    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+    pub(crate) fn generator(&mut self, new_index: usize, st: usize, lc: usize) -> Variable {
+        use std::sync::Mutex;
+
+        let f = &self.module.functions[new_index];
+        let stack: Vec<Variable> =
+            self.stack[st..].iter().map(|v| v.deep_clone(&self.stack)).collect();
+        let mut local_stack: Vec<(Arc<String>, usize)> =
+            self.local_stack[lc..].iter().map(|(name, i)| (name.clone(), i - st)).collect();
+        let mut offset = 0;
+        if f.returns() {
+            local_stack.push((RETURN_TYPE.clone(), 0));
+            offset = 1;
+        }
+        for (i, arg) in f.args.iter().enumerate() {
+            local_stack.push((arg.name.clone(), offset + i));
+        }
+        self.stack.truncate(st);
+        self.local_stack.truncate(lc);
+
+        let rt = Runtime {
+            module: self.module.clone(),
+            stack,
+            local_stack,
+            current_stack: vec![],
+            module_lookup: self.module_lookup,
+            call_stack: vec![Call {
+                fn_name: f.name.clone(),
+                index: new_index,
+                file: Some(f.file.clone()),
+                stack_len: 0,
+                local_len: 0,
+                current_len: 0,
+            }],
+            rng: self.rng.clone(),
+            arg_err_index: Cell::new(None),
+            #[cfg(feature = "async")]
+            tokio_runtime: self.tokio_runtime.clone(),
+            yielded: None,
+            frames: vec![],
+        };
+        Variable::Gen(Arc::new(Mutex::new(Generator {
+            rt,
+            index: new_index,
+            done: false,
+        })))
+    }
+
+    /// Suspends a generator function with a value.
+    #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+    pub(crate) fn yield_expr(&mut self, yield_expr: &ast::Yield) -> FlowResult {
+        let x = match self.expression(&yield_expr.expr, Side::Right)? {
+            (Some(x), Flow::Continue) => x.deep_clone(&self.stack),
+            (x, Flow::Return) => return Ok((x, Flow::Return)),
+            _ => {
+                return self.err(
+                    yield_expr.expr.source_range(),
+                    "Expected something. \
+                            Expression did not return a value.",
+                )
+            }
+        };
+        self.yielded = Some(x);
+        Ok((None, Flow::Return))
+    }
+}
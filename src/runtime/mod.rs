@@ -5,23 +5,29 @@
 use rand;
 use range::Range;
 use std::cell::Cell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use crate::{
     ast,
     embed,
     FnIndex,
+    Key,
     Module,
     UnsafeRef,
     Variable,
+    ASSERT,
+    ASSERT_EQ,
     TINVOTS,
     CSIE,
 };
 
-#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
 mod for_in;
 mod for_n;
+mod generator;
+
+#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+pub use self::generator::Generator;
 
 type FlowResult = Result<(Option<Variable>, Flow), String>;
 
@@ -65,12 +71,17 @@ pub struct Call {
 lazy_static! {
     pub(crate) static ref TEXT_TYPE: Arc<String> = Arc::new("string".into());
     pub(crate) static ref F64_TYPE: Arc<String> = Arc::new("number".into());
+    pub(crate) static ref I64_TYPE: Arc<String> = Arc::new("i64".into());
     pub(crate) static ref VEC4_TYPE: Arc<String> = Arc::new("vec4".into());
     pub(crate) static ref MAT4_TYPE: Arc<String> = Arc::new("mat4".into());
     pub(crate) static ref RETURN_TYPE: Arc<String> = Arc::new("return".into());
     pub(crate) static ref BOOL_TYPE: Arc<String> = Arc::new("boolean".into());
     pub(crate) static ref OBJECT_TYPE: Arc<String> = Arc::new("object".into());
+    pub(crate) static ref MAP_TYPE: Arc<String> = Arc::new("map".into());
+    pub(crate) static ref SET_TYPE: Arc<String> = Arc::new("set".into());
     pub(crate) static ref LINK_TYPE: Arc<String> = Arc::new("link".into());
+    pub(crate) static ref BYTES_TYPE: Arc<String> = Arc::new("bytes".into());
+    pub(crate) static ref RANGE_TYPE: Arc<String> = Arc::new("range".into());
     pub(crate) static ref ARRAY_TYPE: Arc<String> = Arc::new("array".into());
     pub(crate) static ref UNSAFE_REF_TYPE: Arc<String> = Arc::new("unsafe_ref".into());
     pub(crate) static ref REF_TYPE: Arc<String> = Arc::new("ref".into());
@@ -126,6 +137,12 @@ pub struct Runtime {
     /// Tokio runtime handle.
     #[cfg(feature = "async")]
     pub tokio_runtime: Arc<tokio::runtime::Runtime>,
+    /// Value from `yield` while a generator function is being suspended.
+    pub(crate) yielded: Option<Variable>,
+    /// Local state of blocks and loops where a generator function is suspended.
+    ///
+    /// The innermost frame is pushed first, such that the outermost is popped first.
+    pub(crate) frames: Vec<generator::Frame>,
 }
 
 impl Default for Runtime {
@@ -135,6 +152,37 @@ impl Default for Runtime {
 }
 
 // Looks up an item from a variable property.
+// Unwraps `some(_)` before `?.`, returning `None` for `none()`.
+fn opt_lookup(
+    module: &Module,
+    var: *mut Variable,
+    call_stack: &[Call],
+    source_range: Range,
+) -> Result<Option<*mut Variable>, String> {
+    match unsafe { &mut *var } {
+        Variable::Option(Some(x)) => Ok(Some(&mut **x)),
+        Variable::Option(None) => Ok(None),
+        x => Err(module.error_fnindex(
+            source_range,
+            &format!(
+                "{}\nExpected `opt` before `?.`, found `{}`",
+                stack_trace(call_stack),
+                x.typeof_var()
+            ),
+            call_stack.last().expect(CSIE).index,
+        )),
+    }
+}
+
+// Wraps the value of an item using `?.` in `some(_)`.
+fn opt_wrap(item: &ast::Item, v: Variable) -> Variable {
+    if item.opt_ids.is_empty() {
+        v
+    } else {
+        Variable::Option(Some(Box::new(v)))
+    }
+}
+
 fn item_lookup(
     module: &Module,
     var: *mut Variable,
@@ -212,6 +260,58 @@ fn item_lookup(
                 Ok(v)
             }
         }
+        Map(map) => {
+            let key = match *prop {
+                Id::String(_, ref id) => Ok(Key::Str(id.clone())),
+                Id::F64(_, id) => Key::new(&F64(id, None), stack),
+                Id::Expression(_) => {
+                    let id = start_stack_len + *expr_j;
+                    *expr_j += 1;
+                    Key::new(&stack[id], stack)
+                }
+            };
+            let key = match key {
+                Ok(x) => x,
+                Err(err) => {
+                    return Err(module.error_fnindex(
+                        prop.source_range(),
+                        &format!("{}\n{}", stack_trace(call_stack), err),
+                        call_stack.last().expect(CSIE).index,
+                    ))
+                }
+            };
+            let v = match Arc::make_mut(map).entry(key) {
+                Entry::Vacant(vac) => {
+                    if insert && last {
+                        // Insert a key to overwrite with new value.
+                        vac.insert(Return)
+                    } else {
+                        return Err(module.error_fnindex(
+                            prop.source_range(),
+                            &format!(
+                                "{}\nMap has no key `{}`",
+                                stack_trace(call_stack),
+                                vac.key()
+                            ),
+                            call_stack.last().expect(CSIE).index,
+                        ));
+                    }
+                }
+                Entry::Occupied(v) => v.into_mut(),
+            };
+            // Resolve reference.
+            if let Ref(id) = *v {
+                // Do not look up if last, because references should be
+                // copy-on-write.
+                if last {
+                    Ok(v)
+                } else {
+                    Ok(&mut stack[id])
+                }
+            } else {
+                Ok(v)
+            }
+        }
         Array(arr) => {
             let id = match *prop {
                 Id::F64(_, id) => id,
@@ -349,10 +449,25 @@ fn item_lookup(
                 Ok(v)
             }
         }
+        Variant(_, Some(inner)) => {
+            // Look up in the tagged value.
+            let inner: *mut Variable = &mut **inner;
+            item_lookup(
+                module,
+                inner,
+                stack,
+                call_stack,
+                prop,
+                start_stack_len,
+                expr_j,
+                insert,
+                last,
+            )
+        }
         _ => Err(module.error_fnindex(
             prop.source_range(),
             &format!(
-                "{}\nLook up requires object or array",
+                "{}\nLook up requires object, map or array",
                 stack_trace(call_stack)
             ),
             call_stack.last().expect(CSIE).index,
@@ -360,6 +475,100 @@ fn item_lookup(
     }
 }
 
+// Looks up a byte, which is read as a number.
+fn bytes_lookup(
+    module: &Module,
+    bytes: &[u8],
+    stack: &[Variable],
+    call_stack: &[Call],
+    prop: &ast::Id,
+    start_stack_len: usize,
+    expr_j: usize,
+    side: Side,
+) -> Result<Variable, String> {
+    use ast::Id;
+
+    let err = |range, msg: &str| {
+        Err(module.error_fnindex(
+            range,
+            &format!("{}\n{}", stack_trace(call_stack), msg),
+            call_stack.last().expect(CSIE).index,
+        ))
+    };
+    if let Side::LeftInsert(_) = side {
+        return err(prop.source_range(), "Can not assign to bytes, use `write_u8`");
+    }
+    let id = match *prop {
+        Id::F64(_, id) => id,
+        Id::Expression(_) => {
+            let id = start_stack_len + expr_j;
+            let id = if let Variable::Ref(ref_id) = stack[id] {
+                ref_id
+            } else {
+                id
+            };
+            match stack[id] {
+                Variable::F64(id, _) => id,
+                _ => return err(prop.source_range(), "Expected number"),
+            }
+        }
+        Id::String(range, _) => return err(range, "Expected number"),
+    };
+    match bytes.get(id as usize) {
+        Some(&b) if id >= 0.0 => Ok(Variable::f64(b.into())),
+        _ => err(prop.source_range(), &format!("Out of bounds `{}`", id)),
+    }
+}
+
+// Gets the range of an id, e.g. `1..3` in `arr[1..3]`.
+fn range_id(
+    stack: &[Variable],
+    prop: &ast::Id,
+    start_stack_len: usize,
+    expr_j: usize,
+) -> Option<(f64, f64)> {
+    if let ast::Id::Expression(_) = *prop {
+        let id = start_stack_len + expr_j;
+        let id = if let Variable::Ref(ref_id) = stack[id] {
+            ref_id
+        } else {
+            id
+        };
+        if let Variable::Range(start, end) = stack[id] {
+            return Some((start, end));
+        }
+    }
+    None
+}
+
+// Looks up a slice of an array, str or bytes.
+fn slice_lookup(
+    module: &Module,
+    var: &Variable,
+    stack: &[Variable],
+    call_stack: &[Call],
+    prop: &ast::Id,
+    (start, end): (f64, f64),
+    side: Side,
+) -> Result<Variable, String> {
+    let err = |msg: &str| {
+        Err(module.error_fnindex(
+            prop.source_range(),
+            &format!("{}\n{}", stack_trace(call_stack), msg),
+            call_stack.last().expect(CSIE).index,
+        ))
+    };
+    if let Side::LeftInsert(_) = side {
+        return err("Can not assign to slice");
+    }
+    let var = if let Variable::Ref(ind) = *var {
+        &stack[ind]
+    } else {
+        var
+    };
+    crate::dyon_std::slice_of(var, start, end).or_else(|msg| err(&msg))
+}
+
 impl Runtime {
     /// Creates a new Runtime.
     pub fn new() -> Runtime {
@@ -381,6 +590,8 @@ impl Runtime {
             arg_err_index: Cell::new(None),
             #[cfg(feature = "async")]
             tokio_runtime: Arc::new(tokio::runtime::Runtime::new().unwrap()),
+            yielded: None,
+            frames: vec![],
         }
     }
 
@@ -574,9 +785,13 @@ impl Runtime {
         match *expr {
             Link(ref link) => self.link(link),
             Object(ref obj) => self.object(obj),
+            Map(ref map) => self.map(map),
+            Set(ref set) => self.set(set),
             Array(ref arr) => self.array(arr),
             ArrayFill(ref array_fill) => self.array_fill(array_fill),
             Block(ref block) => self.block(block),
+            Defer(ref defer) => self.err(defer.source_range, "Expected `defer` in block"),
+            TryCatch(ref try_catch) => self.try_catch(try_catch),
             Return(ref ret) => {
                 let x = match self.expression(ret, Side::Right)? {
                     (Some(x), Flow::Continue) => x,
@@ -594,6 +809,10 @@ impl Runtime {
             Go(ref go) => self.go(go),
             #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
             Go(ref go) => match **go {},
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Yield(ref y) => self.yield_expr(y),
+            #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+            Yield(ref y) => match **y {},
             Call(ref call) => {
                 let loader = false;
                 self.call_internal(call, loader)
@@ -615,47 +834,29 @@ impl Runtime {
             }
             Item(ref item) => self.item(item, side),
             Assign(ref assign) => self.assign(assign.op, &assign.left, &assign.right),
+            Destructure(ref destructure) => self.destructure(destructure),
             Vec4(ref vec4) => self.vec4(vec4, side),
             Mat4(ref mat4) => self.mat4(mat4, side),
             For(ref for_expr) => self.for_expr(for_expr),
             ForN(ref for_n_expr) => self.for_n_expr(for_n_expr),
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             ForIn(ref for_in_expr) => self.for_in_expr(for_in_expr),
-            #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-            ForIn(ref for_in_expr) |
-            SumIn(ref for_in_expr) |
-            ProdIn(ref for_in_expr) |
-            MinIn(ref for_in_expr) |
-            MaxIn(ref for_in_expr) |
-            SiftIn(ref for_in_expr) |
-            AnyIn(ref for_in_expr) |
-            AllIn(ref for_in_expr) |
-            LinkIn(ref for_in_expr) => match **for_in_expr {},
             Sum(ref for_n_expr) => self.sum_n_expr(for_n_expr),
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             SumIn(ref sum_in_expr) => self.sum_in_expr(sum_in_expr),
             SumVec4(ref for_n_expr) => self.sum_vec4_n_expr(for_n_expr),
             Prod(ref for_n_expr) => self.prod_n_expr(for_n_expr),
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             ProdIn(ref for_in_expr) => self.prod_in_expr(for_in_expr),
             ProdVec4(ref for_n_expr) => self.prod_vec4_n_expr(for_n_expr),
             Min(ref for_n_expr) => self.min_n_expr(for_n_expr),
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             MinIn(ref for_in_expr) => self.min_in_expr(for_in_expr),
             Max(ref for_n_expr) => self.max_n_expr(for_n_expr),
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             MaxIn(ref for_in_expr) => self.max_in_expr(for_in_expr),
             Sift(ref for_n_expr) => self.sift_n_expr(for_n_expr),
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             SiftIn(ref for_in_expr) => self.sift_in_expr(for_in_expr),
             Any(ref for_n_expr) => self.any_n_expr(for_n_expr),
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             AnyIn(ref for_in_expr) => self.any_in_expr(for_in_expr),
             All(ref for_n_expr) => self.all_n_expr(for_n_expr),
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             AllIn(ref for_in_expr) => self.all_in_expr(for_in_expr),
             LinkFor(ref for_n_expr) => self.link_for_n_expr(for_n_expr),
-            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
             LinkIn(ref for_in_expr) => self.link_for_in_expr(for_in_expr),
             If(ref if_expr) => self.if_expr(if_expr),
             Variable(ref range_var) => Ok((Some(range_var.1.clone()), Flow::Continue)),
@@ -675,6 +876,9 @@ impl Runtime {
             In(ref in_expr) => self.in_expr(in_expr),
             #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
             In(ref in_expr) => match **in_expr {},
+            Match(ref match_expr) => self.match_expr(match_expr),
+            Variant(ref variant) => self.variant(variant),
+            Interpolation(ref interp) => self.interpolation(interp),
         }
     }
 
@@ -733,6 +937,49 @@ impl Runtime {
         }
     }
 
+    fn try_catch(&mut self, try_catch: &ast::TryCatch) -> FlowResult {
+        use crate::Error;
+
+        let cs = self.call_stack.len();
+        let st = self.stack.len();
+        let lc = self.local_stack.len();
+        let cu = self.current_stack.len();
+        let err = match self.block(&try_catch.block) {
+            Err(err) => err,
+            x => return x,
+        };
+        // Separate the message from the call stack and the location in source.
+        let (message, location) = split_error(&err, &self.stack_trace());
+        // Functions that were running when the error happened, innermost first,
+        // starting with the function containing `try`.
+        let trace = self.call_stack[cs.saturating_sub(1)..]
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, call)| {
+                let file = match call.file {
+                    None => "".into(),
+                    Some(ref f) => format!(" ({})", f),
+                };
+                let location = if i == 0 { location } else { "" };
+                format!("In function `{}`{}\n{}", call.fn_name, file, location)
+                    .trim_end()
+                    .into()
+            })
+            .collect();
+        let message = Variable::Str(Arc::new(message.into()));
+        self.call_stack.truncate(cs);
+        self.stack.truncate(st);
+        self.local_stack.truncate(lc);
+        self.current_stack.truncate(cu);
+        self.local_stack.push((try_catch.name.clone(), self.stack.len()));
+        self.stack.push(Variable::Result(Err(Box::new(Error { message, trace }))));
+        let res = self.block(&try_catch.catch_block);
+        self.stack.truncate(st);
+        self.local_stack.truncate(lc);
+        res
+    }
+
     fn closure(&mut self, closure: &ast::Closure) -> FlowResult {
         use crate::grab::{self, Grabbed};
         use crate::ClosureEnvironment;
@@ -891,6 +1138,7 @@ impl Runtime {
             info: Box::new(ast::CallInfo {
                 alias: None,
                 name: name.clone(),
+                pipe: false,
                 source_range: Range::empty(0),
             }),
         };
@@ -930,13 +1178,46 @@ impl Runtime {
 
     fn block(&mut self, block: &ast::Block) -> FlowResult {
         let mut expect = None;
-        let st = self.stack.len();
-        let lc = self.local_stack.len();
-        let cu = self.current_stack.len();
-        for e in &block.expressions {
-            expect = match self.expression(e, Side::Right)? {
-                (x, Flow::Continue) => x,
+        let cs = self.call_stack.len();
+        let mut st = self.stack.len();
+        let mut lc = self.local_stack.len();
+        let mut cu = self.current_stack.len();
+        // Deferred blocks with their index and the stack lengths where they were reached.
+        let mut deferred = vec![];
+        let mut start = 0;
+        if let Some(generator::Frame::Block { pos, lens, deferred: d }) = self.resume() {
+            (st, lc, cu) = lens;
+            deferred = d
+                .into_iter()
+                .filter_map(|(i, lens)| match block.expressions[i] {
+                    ast::Expression::Defer(ref defer) => Some((i, &**defer, lens)),
+                    _ => None,
+                })
+                .collect();
+            // Continue after `yield` when there are no inner frames.
+            start = if self.frames.is_empty() { pos + 1 } else { pos };
+        }
+        for (i, e) in block.expressions.iter().enumerate().skip(start) {
+            if let ast::Expression::Defer(ref defer) = *e {
+                let lens = (self.stack.len(), self.local_stack.len(), self.current_stack.len());
+                deferred.push((i, &**defer, lens));
+                expect = None;
+                continue;
+            }
+            expect = match self.expression(e, Side::Right) {
+                Ok((x, Flow::Continue)) => x,
+                Ok(_) if self.yielding() => {
+                    let deferred = deferred.iter().map(|&(i, _, lens)| (i, lens)).collect();
+                    let lens = (st, lc, cu);
+                    return Ok(self.suspend(generator::Frame::Block { pos: i, lens, deferred }));
+                }
                 x => {
+                    let x = if deferred.is_empty() {
+                        x?
+                    } else {
+                        self.call_stack.truncate(cs);
+                        self.run_deferred(deferred, x)?
+                    };
                     self.stack.truncate(st);
                     self.local_stack.truncate(lc);
                     self.current_stack.truncate(cu);
@@ -945,10 +1226,75 @@ impl Runtime {
             }
         }
 
+        let res = if deferred.is_empty() {
+            (expect, Flow::Continue)
+        } else {
+            self.run_deferred(deferred, Ok((expect, Flow::Continue)))?
+        };
         self.stack.truncate(st);
         self.local_stack.truncate(lc);
         self.current_stack.truncate(cu);
-        Ok((expect, Flow::Continue))
+        Ok(res)
+    }
+
+    // Runs deferred blocks in reverse order when exiting a block with result `res`.
+    //
+    // An error returned from a deferred block is added to the trace of an error
+    // that is already being returned, or else becomes the returned value.
+    fn run_deferred(
+        &mut self,
+        deferred: Vec<(usize, &ast::Defer, (usize, usize, usize))>,
+        mut res: FlowResult,
+    ) -> FlowResult {
+        use crate::write::{write_variable, EscapeString};
+
+        for (_, defer, (st, lc, cu)) in deferred.into_iter().rev() {
+            self.stack.truncate(st);
+            self.local_stack.truncate(lc);
+            self.current_stack.truncate(cu);
+            let err = match self.block(&defer.block) {
+                Ok((_, Flow::Continue)) => continue,
+                Ok((Some(Variable::Result(Err(err))), Flow::Return)) => err,
+                Ok(_) => {
+                    return self.err(
+                        defer.source_range,
+                        "Can not use `return`, `break` or `continue` in `defer`",
+                    )
+                }
+                Err(msg) => {
+                    return Err(match res {
+                        Err(prev) => format!("{}\n{}", prev, msg),
+                        Ok(_) => msg,
+                    })
+                }
+            };
+            let mut w: Vec<u8> = vec![];
+            write_variable(&mut w, self, &err.message, EscapeString::None, 0).unwrap();
+            let msg = format!("In `defer`: {}", String::from_utf8(w).unwrap());
+            let msg = self.module.error(defer.source_range, &msg, self);
+            let prev = match res {
+                Ok((Some(ref x), Flow::Return)) => match *self.get(x) {
+                    Variable::Result(Err(ref prev)) => Some(prev.clone()),
+                    _ => None,
+                },
+                Err(ref mut prev) => {
+                    prev.push('\n');
+                    prev.push_str(&msg);
+                    continue;
+                }
+                Ok(_) => None,
+            };
+            let err = match prev {
+                Some(mut prev) => {
+                    prev.trace.push(msg);
+                    prev.trace.extend(err.trace);
+                    prev
+                }
+                None => err,
+            };
+            res = Ok((Some(Variable::Result(Err(err))), Flow::Return));
+        }
+        res
     }
 
     /// Start a new thread and return the handle.
@@ -1012,6 +1358,8 @@ impl Runtime {
             arg_err_index: Cell::new(None),
             #[cfg(feature = "async")]
             tokio_runtime: self.tokio_runtime.clone(),
+            yielded: None,
+            frames: vec![],
         };
         let handle: JoinHandle<Result<Variable, String>> = spawn!(self.tokio_runtime,
             let mut new_rt = new_rt;
@@ -1233,6 +1581,9 @@ impl Runtime {
         fun: crate::FnVoidRef,
         info: &ast::CallInfo,
     ) -> FlowResult {
+        if self.module.strip_asserts && (info.name == *ASSERT || info.name == *ASSERT_EQ) {
+            return Ok((None, Flow::Continue));
+        }
         for arg in args {
             match self.expression(arg, Side::Right)? {
                 (Some(x), Flow::Continue) => self.stack.push(x),
@@ -1532,6 +1883,13 @@ impl Runtime {
             };
         }
 
+        // Use default values for arguments left out.
+        for arg in f.args.iter().skip(self.stack.len() - st) {
+            if let Some(ref val) = arg.default {
+                self.stack.push(val.clone());
+            }
+        }
+
         // Look for variable in current stack.
         if !f.currents.is_empty() {
             for current in &f.currents {
@@ -1588,6 +1946,13 @@ impl Runtime {
             drop(channels);
         }
 
+        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+        if f.generator {
+            // Move the return value together with the arguments.
+            let st = if f.returns() { st - 1 } else { st };
+            return Ok((Some(self.generator(new_index, st, lc)), Flow::Continue));
+        }
+
         self.push_fn(
             info.name.clone(),
             new_index,
@@ -1751,6 +2116,7 @@ impl Runtime {
                     info: Box::new(ast::CallInfo {
                         alias: None,
                         name: name.clone(),
+                        pipe: false,
                         source_range: Range::empty(0),
                     }),
                 };
@@ -1784,6 +2150,7 @@ impl Runtime {
             info: Box::new(ast::CallInfo {
                 alias: None,
                 name,
+                pipe: false,
                 source_range: Range::empty(0),
             }),
         };
@@ -1894,6 +2261,55 @@ impl Runtime {
         Ok((Some(Variable::Object(Arc::new(object))), Flow::Continue))
     }
 
+    fn map(&mut self, map: &ast::Map) -> FlowResult {
+        let mut res: HashMap<_, _> = HashMap::new();
+        for (key, expr) in &map.key_values {
+            let key = match self.expression(key, Side::Right)? {
+                (Some(x), Flow::Continue) => match Key::new(&x, &self.stack) {
+                    Ok(x) => x,
+                    Err(err) => return self.err(key.source_range(), &err),
+                },
+                (x, Flow::Return) => {
+                    return Ok((x, Flow::Return));
+                }
+                _ => return self.err(key.source_range(), "Expected something"),
+            };
+            let x = match self.expression(expr, Side::Right)? {
+                (Some(x), Flow::Continue) => x,
+                (x, Flow::Return) => {
+                    return Ok((x, Flow::Return));
+                }
+                _ => return self.err(expr.source_range(), "Expected something"),
+            };
+            if res.contains_key(&key) {
+                return Err(self.module.error(
+                    expr.source_range(),
+                    &format!("{}\nDuplicate key in map `{}`", self.stack_trace(), key),
+                    self,
+                ));
+            }
+            res.insert(key, x);
+        }
+        Ok((Some(Variable::Map(res.into())), Flow::Continue))
+    }
+
+    fn set(&mut self, set: &ast::Set) -> FlowResult {
+        let mut res: HashSet<_> = HashSet::new();
+        for item in &set.items {
+            match self.expression(item, Side::Right)? {
+                (Some(x), Flow::Continue) => match Key::new(&x, &self.stack) {
+                    Ok(x) => res.insert(x),
+                    Err(err) => return self.err(item.source_range(), &err),
+                },
+                (x, Flow::Return) => {
+                    return Ok((x, Flow::Return));
+                }
+                _ => return self.err(item.source_range(), "Expected something"),
+            };
+        }
+        Ok((Some(Variable::Set(Arc::new(res))), Flow::Continue))
+    }
+
     fn array(&mut self, arr: &ast::Array) -> FlowResult {
         let mut array: Vec<Variable> = Vec::new();
         for item in &arr.items {
@@ -2028,6 +2444,37 @@ impl Runtime {
                         }
                     };
                 },
+                Variable::I64(b) => unsafe {
+                    match *r.0 {
+                        Variable::I64(ref mut n) => {
+                            use crate::dyon_std::{add, div, mul, pow, rem, sub};
+
+                            let (a, b) = (Variable::I64(*n), Variable::I64(b));
+                            let res = match op {
+                                Set | Assign => Ok(b),
+                                Add => add(&a, &b),
+                                Sub => sub(&a, &b),
+                                Mul => mul(&a, &b),
+                                Div => div(&a, &b),
+                                Rem => rem(&a, &b),
+                                Pow => pow(&a, &b),
+                            };
+                            match res {
+                                Ok(Variable::I64(res)) => *n = res,
+                                Ok(_) => unreachable!(),
+                                Err(err) => return self.err(left.source_range(), &err),
+                            }
+                        }
+                        Variable::Return => {
+                            if let Set = op {
+                                *r.0 = Variable::I64(b)
+                            } else {
+                                return self.err(left.source_range(), "Return has no value");
+                            }
+                        }
+                        _ => return self.err(left.source_range(), "Expected assigning to an i64"),
+                    };
+                },
                 Variable::Vec4(b) => unsafe {
                     match *r.0 {
                         Variable::Vec4(ref mut n) => match op {
@@ -2349,6 +2796,36 @@ impl Runtime {
             }
         }
     }
+    fn destructure(&mut self, destructure: &ast::Destructure) -> FlowResult {
+        let x = match self.expression(&destructure.right, Side::Right)? {
+            (x, Flow::Return) => return Ok((x, Flow::Return)),
+            (Some(x), Flow::Continue) => x,
+            _ => {
+                return self.err(
+                    destructure.right.source_range(),
+                    "Expected something from the right side",
+                )
+            }
+        };
+        let v = self.get(&x).clone();
+        let st = self.stack.len();
+        let lc = self.local_stack.len();
+        if !self.match_pattern(&destructure.pattern, &v) {
+            self.stack.truncate(st);
+            self.local_stack.truncate(lc);
+            return Err(self.module.error(
+                destructure.pattern.source_range(),
+                &format!(
+                    "{}\nPattern does not match value of type `{}`",
+                    self.stack_trace(),
+                    v.typeof_var()
+                ),
+                self,
+            ));
+        }
+        Ok((None, Flow::Continue))
+    }
+
     // `insert` is true for `:=` and false for `=`.
     // This works only on objects, but does not have to check since it is
     // ignored for arrays.
@@ -2406,6 +2883,10 @@ impl Runtime {
 
         use ast::Id;
 
+        if let Some(ref c) = item.constant {
+            return self.constant_item(item, c);
+        }
+
         let locals = self.local_stack.len() - self.call_stack.last().expect(CSIE).local_len;
         let stack_id = {
             if cfg!(not(feature = "debug_lookup")) {
@@ -2511,6 +2992,10 @@ impl Runtime {
             }
         }
 
+        if let (false, Side::LeftInsert(_)) = (item.opt_ids.is_empty(), side) {
+            return self.err(item.source_range, "Can not use `?.` on the left side");
+        }
+
         // Pre-evaluate expressions for identity.
         let start_stack_len = self.stack.len();
         for id in &item.ids {
@@ -2535,10 +3020,50 @@ impl Runtime {
 
         let v = {
             let item_len = item.ids.len();
+            let mut opt_ind = 0;
+            let mut root: *mut Variable = &mut stack[stack_id];
+            if item.opt_ids.first() == Some(&0) {
+                root = match opt_lookup(&self.module, root, call_stack, item.source_range)? {
+                    Some(x) => x,
+                    None => {
+                        stack.truncate(start_stack_len);
+                        return Ok((Some(Variable::Option(None)), Flow::Continue));
+                    }
+                };
+                opt_ind += 1;
+            }
+            if let (Some(r), 1) = (range_id(stack, &item.ids[0], start_stack_len, expr_j), item_len)
+            {
+                let v = slice_lookup(
+                    &self.module,
+                    unsafe { &*root },
+                    stack,
+                    call_stack,
+                    &item.ids[0],
+                    r,
+                    side,
+                )?;
+                stack.truncate(start_stack_len);
+                return Ok((Some(opt_wrap(item, v)), Flow::Continue));
+            }
+            if let (Variable::Bytes(bytes), 1) = (unsafe { &*root }, item_len) {
+                let v = bytes_lookup(
+                    &self.module,
+                    bytes,
+                    stack,
+                    call_stack,
+                    &item.ids[0],
+                    start_stack_len,
+                    expr_j,
+                    side,
+                )?;
+                stack.truncate(start_stack_len);
+                return Ok((Some(opt_wrap(item, v)), Flow::Continue));
+            }
             // Get the first variable (a.x).y
             let mut var: *mut Variable = item_lookup(
                 &self.module,
-                &mut stack[stack_id],
+                root,
                 stack,
                 call_stack,
                 &item.ids[0],
@@ -2609,8 +3134,48 @@ impl Runtime {
                     }
                 }
             }
+            if item.opt_ids.get(opt_ind) == Some(&1) {
+                let range = item.ids[0].source_range();
+                var = match opt_lookup(&self.module, var, call_stack, range)? {
+                    Some(x) => x,
+                    None => {
+                        stack.truncate(start_stack_len);
+                        return Ok((Some(Variable::Option(None)), Flow::Continue));
+                    }
+                };
+                opt_ind += 1;
+            }
             // Get the rest of the variables.
             for (i, prop) in item.ids[1..].iter().enumerate() {
+                if let (Some(r), true) =
+                    (range_id(stack, prop, start_stack_len, expr_j), i + 2 == item_len)
+                {
+                    let v = slice_lookup(
+                        &self.module,
+                        unsafe { &*var },
+                        stack,
+                        call_stack,
+                        prop,
+                        r,
+                        side,
+                    )?;
+                    stack.truncate(start_stack_len);
+                    return Ok((Some(opt_wrap(item, v)), Flow::Continue));
+                }
+                if let (Variable::Bytes(bytes), true) = (unsafe { &*var }, i + 2 == item_len) {
+                    let v = bytes_lookup(
+                        &self.module,
+                        bytes,
+                        stack,
+                        call_stack,
+                        prop,
+                        start_stack_len,
+                        expr_j,
+                        side,
+                    )?;
+                    stack.truncate(start_stack_len);
+                    return Ok((Some(opt_wrap(item, v)), Flow::Continue));
+                }
                 var = item_lookup(
                     &self.module,
                     unsafe { &mut *var },
@@ -2685,17 +3250,85 @@ impl Runtime {
                         }
                     }
                 }
+                if item.opt_ids.get(opt_ind) == Some(&(i + 2)) {
+                    var = match opt_lookup(&self.module, var, call_stack, prop.source_range())? {
+                        Some(x) => x,
+                        None => {
+                            stack.truncate(start_stack_len);
+                            return Ok((Some(Variable::Option(None)), Flow::Continue));
+                        }
+                    };
+                    opt_ind += 1;
+                }
             }
 
             match side {
-                Side::Right => unsafe { &*var }.clone(),
+                Side::Right => opt_wrap(item, unsafe { &*var }.clone()),
                 Side::LeftInsert(_) => Variable::UnsafeRef(UnsafeRef(var)),
             }
         };
         stack.truncate(start_stack_len);
         Ok((Some(v), Flow::Continue))
     }
+
+    // Looks up item in a constant without copying the constant.
+    fn constant_item(&mut self, item: &ast::Item, constant: &Variable) -> FlowResult {
+        use ast::Id;
+
+        if item.try_flag || !item.try_ids.is_empty() || !item.opt_ids.is_empty() {
+            return self.err(item.source_range, "Can not use `?` on a constant");
+        }
+        let mut var = constant;
+        for id in &item.ids {
+            let key = match *id {
+                Id::String(_, ref key) => Variable::Str(key.clone()),
+                Id::F64(_, ind) => Variable::f64(ind),
+                Id::Expression(ref expr) => match self.expression(expr, Side::Right)? {
+                    (x, Flow::Return) => return Ok((x, Flow::Return)),
+                    (Some(x), Flow::Continue) => self.get(&x).clone(),
+                    _ => return self.err(expr.source_range(), "Expected something for index"),
+                },
+            };
+            while let Variable::Variant(_, Some(ref inner)) = *var {
+                var = inner;
+            }
+            var = match (var, &key) {
+                (Variable::Object(obj), Variable::Str(key)) => match obj.get(key) {
+                    Some(x) => x,
+                    None => {
+                        return self.err(id.source_range(), &format!("Object has no key `{}`", key))
+                    }
+                },
+                (Variable::Object(_), _) => return self.err(id.source_range(), "Expected string"),
+                (Variable::Map(map), key) => {
+                    let key = match Key::new(key, &self.stack) {
+                        Ok(x) => x,
+                        Err(err) => return self.err(id.source_range(), &err),
+                    };
+                    match map.get(&key) {
+                        Some(x) => x,
+                        None => {
+                            return self.err(id.source_range(), &format!("Map has no key `{}`", key))
+                        }
+                    }
+                }
+                (Variable::Array(arr), &Variable::F64(ind, _)) => match arr.get(ind as usize) {
+                    Some(x) => x,
+                    None => {
+                        return self.err(id.source_range(), &format!("Out of bounds `{}`", ind))
+                    }
+                },
+                (Variable::Array(_), _) => return self.err(id.source_range(), "Expected number"),
+                _ => return self.err(id.source_range(), "Look up requires object, map or array"),
+            };
+        }
+        Ok((Some(var.clone()), Flow::Continue))
+    }
+
     fn if_expr(&mut self, if_expr: &ast::If) -> FlowResult {
+        if let Some(generator::Frame::If(branch)) = self.resume() {
+            return self.if_branch(if_expr, branch);
+        }
         let cond = match self.expression(&if_expr.cond, Side::Right)? {
             (Some(x), Flow::Continue) => x,
             (x, Flow::Return) => {
@@ -2718,13 +3351,9 @@ impl Runtime {
             }
         };
         if val {
-            return self.block(&if_expr.true_block);
+            return self.if_branch(if_expr, 0);
         }
-        for (cond, body) in if_expr
-            .else_if_conds
-            .iter()
-            .zip(if_expr.else_if_blocks.iter())
-        {
+        for (i, cond) in if_expr.else_if_conds.iter().enumerate() {
             let else_if_cond = match self.expression(cond, Side::Right)? {
                 (Some(x), Flow::Continue) => x,
                 (x, Flow::Return) => {
@@ -2735,54 +3364,196 @@ impl Runtime {
             match *self.get(&else_if_cond) {
                 Variable::Bool(false, _) => {}
                 Variable::Bool(true, _) => {
-                    return self.block(body);
+                    return self.if_branch(if_expr, i + 1);
                 }
                 _ => return self.err(cond.source_range(), "Expected bool from else if condition"),
             }
         }
-        if let Some(ref block) = if_expr.else_block {
-            self.block(block)
+        if if_expr.else_block.is_some() {
+            self.if_branch(if_expr, if_expr.else_if_blocks.len() + 1)
         } else {
             Ok((None, Flow::Continue))
         }
     }
-    fn for_expr(&mut self, for_expr: &ast::For) -> FlowResult {
-        let prev_st = self.stack.len();
-        let prev_lc = self.local_stack.len();
-        match self.expression(&for_expr.init, Side::Right)? {
-            (None, Flow::Continue) => {}
+
+    // Runs a block of `if`, where `0` is the true block and the else block is last.
+    fn if_branch(&mut self, if_expr: &ast::If, branch: usize) -> FlowResult {
+        let block = match branch {
+            0 => &if_expr.true_block,
+            i => match if_expr.else_if_blocks.get(i - 1) {
+                Some(block) => block,
+                None => if_expr.else_block.as_ref().expect("Expected else block"),
+            },
+        };
+        let res = self.block(block)?;
+        if self.yielding() {
+            return Ok(self.suspend(generator::Frame::If(branch)));
+        }
+        Ok(res)
+    }
+
+    fn match_expr(&mut self, match_expr: &ast::Match) -> FlowResult {
+        let v = match self.expression(&match_expr.expr, Side::Right)? {
+            (Some(x), Flow::Continue) => x,
             (x, Flow::Return) => {
                 return Ok((x, Flow::Return));
             }
-            _ => {
-                return self.err(
-                    for_expr.init.source_range(),
-                    "Expected nothing from for init",
-                )
-            }
+            _ => return self.err(match_expr.expr.source_range(), "Expected something"),
         };
+        let v = self.get(&v).clone();
         let st = self.stack.len();
         let lc = self.local_stack.len();
-        let mut flow = Flow::Continue;
-        loop {
-            let val = match self.expression(&for_expr.cond, Side::Right)? {
+        for arm in &match_expr.arms {
+            if self.match_pattern(&arm.pattern, &v) {
+                let (x, flow) = self.expression(&arm.expr, Side::Right)?;
+                // Resolve references to bound values before they are removed.
+                let x = if self.stack.len() > st {
+                    x.map(|x| x.deep_clone(&self.stack))
+                } else {
+                    x
+                };
+                self.stack.truncate(st);
+                self.local_stack.truncate(lc);
+                return Ok((x, flow));
+            }
+            self.stack.truncate(st);
+            self.local_stack.truncate(lc);
+        }
+        self.err(match_expr.source_range, "No `match` arm matched the value")
+    }
+
+    fn variant(&mut self, variant: &ast::Variant) -> FlowResult {
+        let x = match self.expression(&variant.expr, Side::Right)? {
+            (Some(x), Flow::Continue) => x,
+            (x, Flow::Return) => {
+                return Ok((x, Flow::Return));
+            }
+            _ => return self.err(variant.expr.source_range(), "Expected something"),
+        };
+        let x = x.deep_clone(&self.stack);
+        Ok((
+            Some(Variable::Variant(variant.name.clone(), Some(Box::new(x)))),
+            Flow::Continue,
+        ))
+    }
+
+    fn interpolation(&mut self, interp: &ast::Interpolation) -> FlowResult {
+        use crate::write::{write_variable, EscapeString};
+
+        let mut buf: Vec<u8> = vec![];
+        for (text, expr) in interp.texts.iter().zip(interp.exprs.iter()) {
+            buf.extend_from_slice(text.as_bytes());
+            let x = match self.expression(expr, Side::Right)? {
                 (Some(x), Flow::Continue) => x,
                 (x, Flow::Return) => return Ok((x, Flow::Return)),
-                _ => {
-                    return self.err(
-                        for_expr.cond.source_range(),
-                        "Expected bool from for condition",
-                    )
-                }
-            };
-            let val = match val {
-                Variable::Bool(val, _) => val,
-                _ => return self.err(for_expr.cond.source_range(), "Expected bool"),
+                _ => return self.err(expr.source_range(), "Expected something"),
             };
-            if !val {
-                break;
+            write_variable(&mut buf, self, self.get(&x), EscapeString::None, 0).unwrap();
+        }
+        if let Some(text) = interp.texts.last() {
+            buf.extend_from_slice(text.as_bytes());
+        }
+        Ok((
+            Some(Variable::Str(Arc::new(String::from_utf8(buf).unwrap()))),
+            Flow::Continue,
+        ))
+    }
+
+    /// Tests a value against a pattern, pushing bound values on the stack.
+    fn match_pattern(&mut self, pattern: &ast::Pattern, v: &Variable) -> bool {
+        use crate::ast::Pattern as P;
+
+        if let Variable::Ref(ind) = *v {
+            let v = self.stack[ind].clone();
+            return self.match_pattern(pattern, &v);
+        }
+        match (pattern, v) {
+            (P::Wildcard(_), _) => true,
+            (P::Bind(_, name), _) => {
+                let v = v.deep_clone(&self.stack);
+                self.local_stack.push((name.clone(), self.stack.len()));
+                self.stack.push(v);
+                true
+            }
+            (P::Value(_, a), b) => a == b,
+            (P::Some(_, pat), Variable::Option(Some(x))) => self.match_pattern(pat, x),
+            (P::None(_), Variable::Option(None)) => true,
+            (P::Ok(_, pat), Variable::Result(Ok(x))) => self.match_pattern(pat, x),
+            (P::Err(_, pat), Variable::Result(Err(err))) => {
+                self.match_pattern(pat, &err.message)
+            }
+            (P::Array(_, pats), Variable::Array(arr)) => {
+                pats.len() == arr.len()
+                    && pats
+                        .iter()
+                        .zip(arr.iter())
+                        .all(|(pat, x)| self.match_pattern(pat, x))
+            }
+            (P::Vec4(_, pats), Variable::Vec4(v)) => pats
+                .iter()
+                .zip(v.iter())
+                .all(|(pat, &x)| self.match_pattern(pat, &Variable::f64(f64::from(x)))),
+            (P::Variant(_, name, pat), Variable::Variant(tag, x)) if name == tag => match *x {
+                Some(ref x) => self.match_pattern(pat, x),
+                None => matches!(**pat, P::Wildcard(_)),
+            },
+            (P::Object(_, key_values), Variable::Object(obj)) => {
+                key_values.iter().all(|(key, pat)| match obj.get(key) {
+                    Some(x) => self.match_pattern(pat, x),
+                    None => false,
+                })
+            }
+            _ => false,
+        }
+    }
+    fn for_expr(&mut self, for_expr: &ast::For) -> FlowResult {
+        // The condition was checked before a generator was suspended in the loop.
+        let ((prev_st, prev_lc), (st, lc), mut resuming) = match self.resume() {
+            Some(generator::Frame::For { prev, lens }) => (prev, lens, true),
+            _ => {
+                let prev = (self.stack.len(), self.local_stack.len());
+                match self.expression(&for_expr.init, Side::Right)? {
+                    (None, Flow::Continue) => {}
+                    (x, Flow::Return) => {
+                        return Ok((x, Flow::Return));
+                    }
+                    _ => {
+                        return self.err(
+                            for_expr.init.source_range(),
+                            "Expected nothing from for init",
+                        )
+                    }
+                };
+                (prev, (self.stack.len(), self.local_stack.len()), false)
             }
+        };
+        let mut flow = Flow::Continue;
+        loop {
+            if !resuming {
+                let val = match self.expression(&for_expr.cond, Side::Right)? {
+                    (Some(x), Flow::Continue) => x,
+                    (x, Flow::Return) => return Ok((x, Flow::Return)),
+                    _ => {
+                        return self.err(
+                            for_expr.cond.source_range(),
+                            "Expected bool from for condition",
+                        )
+                    }
+                };
+                let val = match val {
+                    Variable::Bool(val, _) => val,
+                    _ => return self.err(for_expr.cond.source_range(), "Expected bool"),
+                };
+                if !val {
+                    break;
+                }
+            }
+            resuming = false;
             match self.block(&for_expr.block)? {
+                (_, Flow::Return) if self.yielding() => {
+                    let (prev, lens) = ((prev_st, prev_lc), (st, lc));
+                    return Ok(self.suspend(generator::Frame::For { prev, lens }));
+                }
                 (x, Flow::Return) => return Ok((x, Flow::Return)),
                 (_, Flow::Continue) => {}
                 (_, Flow::Break(x)) => {
@@ -2928,6 +3699,31 @@ impl Runtime {
     }
 }
 
+// Splits a runtime error into the message and the location in source.
+//
+// Runtime errors start with the call stack, followed by the message and lines of source,
+// where each line of source starts with `line,column: `.
+fn split_error<'a>(err: &'a str, stack_trace: &str) -> (&'a str, &'a str) {
+    let err = match err.strip_prefix(stack_trace) {
+        Some(rest) => rest.strip_prefix('\n').unwrap_or(rest),
+        None => err,
+    };
+    let is_location = |line: &str| {
+        line.split_once(": ")
+            .and_then(|(pos, _)| pos.split_once(','))
+            .map(|(a, b)| a.parse::<usize>().is_ok() && b.parse::<usize>().is_ok())
+            .unwrap_or(false)
+    };
+    let mut end = 0;
+    for line in err.split_inclusive('\n') {
+        if is_location(line) {
+            break;
+        }
+        end += line.len();
+    }
+    (err[..end].trim_end(), &err[end..])
+}
+
 fn stack_trace(call_stack: &[Call]) -> String {
     let mut s = String::new();
     for call in call_stack.iter() {
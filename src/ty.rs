@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use piston_meta::bootstrap::Convert;
@@ -17,6 +18,8 @@ pub enum Type {
     Bool,
     /// F64 type.
     F64,
+    /// I64 type.
+    I64,
     /// 4D vector type.
     Vec4,
     /// 4D matrix type.
@@ -25,10 +28,20 @@ pub enum Type {
     Str,
     /// Link type.
     Link,
+    /// Byte buffer type.
+    Bytes,
+    /// Range type.
+    Range,
     /// Array type.
     Array(Box<Type>),
     /// Object type.
     Object,
+    /// Object type with named fields, e.g. `{x: f64, name: str}`.
+    Record(Vec<(Arc<String>, Type)>),
+    /// Map type with key and value types.
+    Map(Box<Type>, Box<Type>),
+    /// Set type.
+    Set(Box<Type>),
     /// Option type.
     Option(Box<Type>),
     /// Result type.
@@ -45,6 +58,12 @@ pub enum Type {
     AdHoc(Arc<String>, Box<Type>),
     /// Closure type.
     Closure(Box<Dfn>),
+    /// Type parameter of a generic function, e.g. `T` in `fn first[T](xs: [T]) -> opt[T]`.
+    Var(Arc<String>),
+    /// Type alias, e.g. `Point` in `type Point = {x: f64, y: f64}`.
+    Alias(Arc<String>, Box<Type>),
+    /// Union type, e.g. `f64 | str`.
+    Union(Vec<Type>),
 }
 
 impl Type {
@@ -74,10 +93,13 @@ impl Type {
             Any => "any".into(),
             Bool => "bool".into(),
             F64 => "f64".into(),
+            I64 => "i64".into(),
             Vec4 => "vec4".into(),
             Mat4 => "mat4".into(),
             Str => "str".into(),
             Link => "link".into(),
+            Bytes => "bytes".into(),
+            Range => "range".into(),
             Array(ref ty) => {
                 if let Any = **ty {
                     "[]".into()
@@ -89,6 +111,33 @@ impl Type {
                 }
             }
             Object => "{}".into(),
+            Record(ref fields) => {
+                let mut res = String::from("{");
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        res.push_str(", ");
+                    }
+                    res.push_str(name);
+                    res.push_str(": ");
+                    res.push_str(&ty.description());
+                }
+                res.push('}');
+                res
+            }
+            Map(ref key, ref val) => {
+                if let (Any, Any) = (&**key, &**val) {
+                    "map".into()
+                } else {
+                    format!("map[{}, {}]", key.description(), val.description())
+                }
+            }
+            Set(ref ty) => {
+                if let Any = **ty {
+                    "set".into()
+                } else {
+                    format!("set[{}]", ty.description())
+                }
+            }
             Option(ref ty) => {
                 if let Any = **ty {
                     "opt".into()
@@ -150,6 +199,11 @@ impl Type {
                 s.push_str(&closure.ret.description());
                 s
             }
+            Var(ref name) | Alias(ref name, _) => (**name).clone(),
+            Union(ref members) => {
+                let members: Vec<String> = members.iter().map(|ty| ty.description()).collect();
+                members.join(" | ")
+            }
         }
     }
 
@@ -163,6 +217,16 @@ impl Type {
         Type::Object
     }
 
+    /// Returns a map type with `any` as key and value types.
+    pub fn map() -> Type {
+        Type::Map(Box::new(Type::Any), Box::new(Type::Any))
+    }
+
+    /// Returns a set type with an `any` as inner type.
+    pub fn set() -> Type {
+        Type::Set(Box::new(Type::Any))
+    }
+
     /// Returns an Option type with an `any` as inner type.
     pub fn option() -> Type {
         Type::Option(Box::new(Type::Any))
@@ -277,6 +341,202 @@ impl Type {
         }
     }
 
+    /// Returns the types contained in this type.
+    fn inner_types(&self) -> Vec<&Type> {
+        use self::Type::*;
+
+        match *self {
+            Array(ref ty) | Set(ref ty) | Option(ref ty) | Result(ref ty) | Secret(ref ty)
+            | AdHoc(_, ref ty) | Alias(_, ref ty) => vec![ty],
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Thread(ref ty) | In(ref ty) => vec![ty],
+            Map(ref key, ref val) => vec![key, val],
+            Record(ref fields) => fields.iter().map(|(_, ty)| ty).collect(),
+            Union(ref members) => members.iter().collect(),
+            Closure(ref cl) => cl.tys.iter().chain(Some(&cl.ret)).collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns the types contained in this type, for mutation.
+    fn inner_types_mut(&mut self) -> Vec<&mut Type> {
+        use self::Type::*;
+
+        match *self {
+            Array(ref mut ty) | Set(ref mut ty) | Option(ref mut ty) | Result(ref mut ty)
+            | Secret(ref mut ty) | AdHoc(_, ref mut ty) | Alias(_, ref mut ty) => vec![ty],
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Thread(ref mut ty) | In(ref mut ty) => vec![ty],
+            Map(ref mut key, ref mut val) => vec![key, val],
+            Record(ref mut fields) => fields.iter_mut().map(|(_, ty)| ty).collect(),
+            Union(ref mut members) => members.iter_mut().collect(),
+            Closure(ref mut cl) => {
+                let cl = &mut **cl;
+                cl.tys.iter_mut().chain(Some(&mut cl.ret)).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Returns `true` if the type uses type parameters.
+    pub fn has_params(&self) -> bool {
+        matches!(*self, Type::Var(_)) || self.inner_types().iter().any(|ty| ty.has_params())
+    }
+
+    /// Replaces ad-hoc types without inner type by type parameters of a generic function.
+    pub fn insert_params(&mut self, params: &[Arc<String>]) {
+        match *self {
+            Type::AdHoc(ref name, ref inner)
+                if **inner == Type::Object && params.contains(name) =>
+            {
+                *self = Type::Var(name.clone());
+                return;
+            }
+            _ => {}
+        }
+        for ty in self.inner_types_mut() {
+            ty.insert_params(params);
+        }
+    }
+
+    /// Binds type parameters to the types found in a call.
+    ///
+    /// Returns an error if a type parameter is bound to conflicting types.
+    pub fn bind_params(
+        &self,
+        found: &Type,
+        bound: &mut Vec<(Arc<String>, Type)>,
+    ) -> Result<(), String> {
+        use self::Type::*;
+
+        match (self, found) {
+            (Var(name), _) => match bound.iter_mut().find(|(n, _)| n == name) {
+                None => bound.push((name.clone(), found.clone())),
+                Some((_, ty)) => {
+                    if *ty == Any || (found.goes_with(ty) && !ty.goes_with(found)) {
+                        *ty = found.clone();
+                    } else if !ty.goes_with(found) {
+                        return Err(format!(
+                            "Type mismatch (#3000): Expected `{}`, found `{}` for `{}`",
+                            ty.description(),
+                            found.description(),
+                            name
+                        ));
+                    }
+                }
+            },
+            (Alias(_, ty), _) => ty.bind_params(found, bound)?,
+            (_, Alias(_, found_ty)) => self.bind_params(found_ty, bound)?,
+            (Record(fields), Record(found_fields)) => {
+                for (name, ty) in fields {
+                    if let Some((_, found_ty)) = found_fields.iter().find(|(n, _)| n == name) {
+                        ty.bind_params(found_ty, bound)?;
+                    }
+                }
+            }
+            (Closure(cl), Closure(found_cl)) if cl.tys.len() == found_cl.tys.len() => {
+                for (ty, found_ty) in self.inner_types().into_iter().zip(found.inner_types()) {
+                    ty.bind_params(found_ty, bound)?;
+                }
+            }
+            (Array(_), Array(_))
+            | (Set(_), Set(_))
+            | (Option(_), Option(_))
+            | (Result(_), Result(_))
+            | (Map(_, _), Map(_, _)) => {
+                for (ty, found_ty) in self.inner_types().into_iter().zip(found.inner_types()) {
+                    ty.bind_params(found_ty, bound)?;
+                }
+            }
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            (Thread(ty), Thread(found_ty)) | (In(ty), In(found_ty)) => {
+                ty.bind_params(found_ty, bound)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Replaces type parameters by bound types, or `any` when not bound.
+    pub fn insert_bound_params(&mut self, bound: &[(Arc<String>, Type)]) {
+        if let Type::Var(ref name) = *self {
+            *self = match bound.iter().find(|(n, _)| n == name) {
+                Some((_, ty)) => ty.clone(),
+                None => Type::Any,
+            };
+            return;
+        }
+        for ty in self.inner_types_mut() {
+            ty.insert_bound_params(bound);
+        }
+    }
+
+    /// Replaces ad-hoc types without inner type by type aliases.
+    pub fn insert_aliases(&mut self, aliases: &HashMap<Arc<String>, Type>) {
+        match *self {
+            Type::AdHoc(ref name, ref inner)
+                if **inner == Type::Object && aliases.contains_key(name) =>
+            {
+                *self = Type::Alias(name.clone(), Box::new(aliases[name].clone()));
+                return;
+            }
+            _ => {}
+        }
+        for ty in self.inner_types_mut() {
+            ty.insert_aliases(aliases);
+        }
+    }
+
+    /// Returns the name of an alias from another namespace that could not be found.
+    pub fn unresolved_alias(&self) -> Option<&Arc<String>> {
+        match *self {
+            Type::AdHoc(ref name, _) if name.contains("::") => Some(name),
+            _ => self.inner_types().into_iter().find_map(|ty| ty.unresolved_alias()),
+        }
+    }
+
+    /// Returns the type with aliases replaced by the types they refer to.
+    pub fn unalias(&self) -> Type {
+        let mut ty = match *self {
+            Type::Alias(_, ref ty) => return ty.unalias(),
+            _ => self.clone(),
+        };
+        for ty in ty.inner_types_mut() {
+            *ty = ty.unalias();
+        }
+        ty
+    }
+
+    /// Returns the name used by `typeof` for values of this type.
+    pub fn typeof_name(&self) -> Option<&'static str> {
+        use self::Type::*;
+
+        Some(match *self {
+            Bool => "boolean",
+            F64 => "number",
+            I64 => "i64",
+            Str => "string",
+            Vec4 => "vec4",
+            Mat4 => "mat4",
+            Link => "link",
+            Bytes => "bytes",
+            Range => "range",
+            Array(_) => "array",
+            Object | Record(_) => "object",
+            Map(_, _) => "map",
+            Set(_) => "set",
+            Option(_) => "option",
+            Result(_) => "result",
+            Closure(_) => "closure",
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            Thread(_) => "thread",
+            #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+            In(_) => "in",
+            Secret(ref ty) | AdHoc(_, ref ty) | Alias(_, ref ty) => return ty.typeof_name(),
+            _ => return None,
+        })
+    }
+
     /// Returns `true` if a type to be refined is ambiguous relative to this type (directional check).
     ///
     /// For example, the type ad-hoc type `Foo str` is ambiguous with type `str`.
@@ -286,9 +546,14 @@ impl Type {
         use self::Type::*;
 
         match (self, refine) {
+            (Alias(_, x), y) => x.ambiguous(y),
+            (x, Alias(_, y)) => x.ambiguous(y),
             (&AdHoc(ref xa, ref xb), &AdHoc(ref ya, ref yb)) if xa == ya => xb.ambiguous(yb),
             (&AdHoc(_, ref x), y) if x.goes_with(y) => true,
             (&Array(ref x), &Array(ref y)) if x.ambiguous(y) => true,
+            (Map(x, _), Map(y, _)) if x.ambiguous(y) => true,
+            (Map(_, x), Map(_, y)) if x.ambiguous(y) => true,
+            (Set(x), Set(y)) if x.ambiguous(y) => true,
             (&Option(ref x), &Option(ref y)) if x.ambiguous(y) => true,
             (&Result(ref x), &Result(ref y)) if x.ambiguous(y) => true,
             #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
@@ -297,11 +562,16 @@ impl Type {
             (&In(ref x), &In(ref y)) if x.ambiguous(y) => true,
             (&Bool, &Any) => true,
             (&F64, &Any) => true,
+            (&I64, &Any) => true,
             (&Str, &Any) => true,
             (&Vec4, &Any) => true,
             (&Mat4, &Any) => true,
             (&Link, &Any) => true,
+            (&Bytes, &Any) => true,
+            (&Range, &Any) => true,
             (&Array(_), &Any) => true,
+            (&Map(_, _), &Any) => true,
+            (&Set(_), &Any) => true,
             (&Option(_), &Any) => true,
             (&Result(_), &Any) => true,
             #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
@@ -319,7 +589,7 @@ impl Type {
 
         match *self {
             Closure(ref ty) => Some(ty.ret.clone()),
-            AdHoc(_, ref x) => x.closure_ret_ty(),
+            AdHoc(_, ref x) | Alias(_, ref x) => x.closure_ret_ty(),
             Any => Some(Type::Any),
             _ => None,
         }
@@ -334,12 +604,23 @@ impl Type {
     pub fn goes_with(&self, other: &Type) -> bool {
         use self::Type::*;
 
+        // Aliases go with the types they refer to.
+        if let Alias(_, ref other_ty) = *other {
+            return self.goes_with(other_ty);
+        }
+        // Every member of a union must go with the type.
+        match *other {
+            Union(ref others) if !matches!(*self, Any | Var(_) | Alias(_, _) | Union(_)) => {
+                return others.iter().all(|ty| self.goes_with(ty));
+            }
+            _ => {}
+        }
         // Invert the order because of complex ad-hoc logic.
-        if let AdHoc(_, _) = *other {
-            if let AdHoc(_, _) = *self {
-            } else {
+        match *other {
+            AdHoc(_, _) if !matches!(*self, AdHoc(_, _) | Union(_)) => {
                 return other.goes_with(self);
             }
+            _ => {}
         }
         if let Secret(ref other_ty) = *other {
             return if let Secret(ref this_ty) = *self {
@@ -353,6 +634,16 @@ impl Type {
             &Unreachable => true,
             _ if *other == Unreachable => true,
             &Any => *other != Void,
+            Alias(_, ty) => ty.goes_with(other),
+            // Some member of the union must go with every member of the other type.
+            Union(members) => match *other {
+                Union(ref others) => {
+                    others.iter().all(|other| members.iter().any(|ty| ty.goes_with(other)))
+                }
+                _ => members.iter().any(|ty| ty.goes_with(other)),
+            },
+            // Type parameters are bound per call.
+            &Var(_) => *other != Void,
             // Void only goes with void.
             &Void => *other == Void,
             &Array(ref arr) => {
@@ -362,9 +653,28 @@ impl Type {
                     matches!(*other, Any)
                 }
             }
-            &Object => {
-                if let Object = *other {
-                    true
+            // Objects without field information go with records.
+            &Object => matches!(*other, Object | Record(_) | Any),
+            // Other records must have the same fields, but might have more.
+            Record(fields) => match *other {
+                Record(ref other_fields) => fields.iter().all(|(name, ty)| {
+                    other_fields
+                        .iter()
+                        .any(|(other_name, other_ty)| name == other_name && ty.goes_with(other_ty))
+                }),
+                Object | Any => true,
+                _ => false,
+            },
+            Map(key, val) => {
+                if let Map(ref other_key, ref other_val) = *other {
+                    key.goes_with(other_key) && val.goes_with(other_val)
+                } else {
+                    matches!(*other, Any)
+                }
+            }
+            Set(ty) => {
+                if let Set(ref other_ty) = *other {
+                    ty.goes_with(other_ty)
                 } else {
                     matches!(*other, Any)
                 }
@@ -449,6 +759,8 @@ impl Type {
         use self::Type::*;
 
         match (self, other) {
+            (Alias(_, ty), _) => ty.add_assign(other),
+            (_, Alias(_, other_ty)) => self.add_assign(other_ty),
             (&AdHoc(ref name, ref ty), &AdHoc(ref other_name, ref other_ty)) => {
                 if name != other_name {
                     return false;
@@ -475,10 +787,14 @@ impl Type {
         convert.update(start_range);
 
         let mut ty: Option<Type> = None;
+        let mut union = vec![];
         loop {
             if let Ok(range) = convert.end_node(node) {
                 convert.update(range);
                 break;
+            } else if let Ok((range, val)) = Type::from_meta_data("union", convert, ignored) {
+                convert.update(range);
+                union.push(val);
             } else if let Ok((range, _)) = convert.meta_bool("any") {
                 convert.update(range);
                 ty = Some(Type::Any);
@@ -491,6 +807,9 @@ impl Type {
             } else if let Ok((range, _)) = convert.meta_bool("f64") {
                 convert.update(range);
                 ty = Some(Type::F64);
+            } else if let Ok((range, _)) = convert.meta_bool("i64") {
+                convert.update(range);
+                ty = Some(Type::I64);
             } else if let Ok((range, _)) = convert.meta_bool("sec_f64") {
                 convert.update(range);
                 ty = Some(Type::Secret(Box::new(Type::F64)));
@@ -506,6 +825,12 @@ impl Type {
             } else if let Ok((range, _)) = convert.meta_bool("link") {
                 convert.update(range);
                 ty = Some(Type::Link);
+            } else if let Ok((range, _)) = convert.meta_bool("bytes") {
+                convert.update(range);
+                ty = Some(Type::Bytes);
+            } else if let Ok((range, _)) = convert.meta_bool("range") {
+                convert.update(range);
+                ty = Some(Type::Range);
             } else if let Ok((range, _)) = convert.meta_bool("opt_any") {
                 convert.update(range);
                 ty = Some(Type::Option(Box::new(Type::Any)));
@@ -518,6 +843,32 @@ impl Type {
             } else if let Ok((range, _)) = convert.meta_bool("obj_any") {
                 convert.update(range);
                 ty = Some(Type::Object);
+            } else if let Ok(range) = convert.start_node("record") {
+                convert.update(range);
+                let mut fields = vec![];
+                while let Ok((range, name)) = convert.meta_string("field") {
+                    convert.update(range);
+                    let (range, ty) = Type::from_meta_data("field_ty", convert, ignored)?;
+                    convert.update(range);
+                    fields.push((name, ty));
+                }
+                let range = convert.end_node("record")?;
+                convert.update(range);
+                ty = Some(Type::Record(fields));
+            } else if let Ok((range, _)) = convert.meta_bool("map_any") {
+                convert.update(range);
+                ty = Some(Type::map());
+            } else if let Ok((range, key)) = Type::from_meta_data("map_key", convert, ignored) {
+                convert.update(range);
+                let (range, val) = Type::from_meta_data("map_val", convert, ignored)?;
+                convert.update(range);
+                ty = Some(Type::Map(Box::new(key), Box::new(val)));
+            } else if let Ok((range, _)) = convert.meta_bool("set_any") {
+                convert.update(range);
+                ty = Some(Type::set());
+            } else if let Ok((range, val)) = Type::from_meta_data("set", convert, ignored) {
+                convert.update(range);
+                ty = Some(Type::Set(Box::new(val)));
             } else if let Ok((range, val)) = Type::from_meta_data("opt", convert, ignored) {
                 convert.update(range);
                 ty = Some(Type::Option(Box::new(val)));
@@ -527,6 +878,13 @@ impl Type {
             } else if let Ok((range, val)) = Type::from_meta_data("arr", convert, ignored) {
                 convert.update(range);
                 ty = Some(Type::Array(Box::new(val)));
+            } else if let Ok((range, ns)) = convert.meta_string("ty_alias_ns") {
+                convert.update(range);
+                let (range, val) = convert.meta_string("ty_alias")?;
+                convert.update(range);
+                // Resolved when aliases from other namespaces are known.
+                let name = Arc::new(format!("{}::{}", ns, val));
+                ty = Some(Type::AdHoc(name, Box::new(Type::Object)));
             } else if let Ok((range, val)) = convert.meta_string("ad_hoc") {
                 convert.update(range);
                 let inner_ty =
@@ -593,6 +951,12 @@ impl Type {
             }
         }
 
-        Ok((convert.subtract(start), ty.ok_or(())?))
+        let ty = ty.ok_or(())?;
+        if union.is_empty() {
+            Ok((convert.subtract(start), ty))
+        } else {
+            union.insert(0, ty);
+            Ok((convert.subtract(start), Type::Union(union)))
+        }
     }
 }
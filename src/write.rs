@@ -1,6 +1,6 @@
 use piston_meta::json;
 use std::io;
-use crate::{ast, Runtime, Variable};
+use crate::{ast, Key, Runtime, Variable};
 
 use std::sync::Arc;
 
@@ -30,6 +30,10 @@ where
         Variable::F64(x, _) => {
             write!(w, "{}", x)?;
         }
+        Variable::I64(x) => match escape_string {
+            EscapeString::Json => write!(w, "{}i", x)?,
+            EscapeString::None => write!(w, "{}", x)?,
+        },
         Variable::Vec4(v) => {
             write!(w, "({}, {}", v[0], v[1])?;
             if v[2] != 0.0 || v[3] != 0.0 {
@@ -95,6 +99,19 @@ where
                 }
             }
         }
+        Variable::Bytes(ref bytes) => {
+            write!(w, "bytes([")?;
+            for (i, b) in bytes.iter().enumerate() {
+                if i > 0 {
+                    write!(w, ", ")?;
+                }
+                write!(w, "{}", b)?;
+            }
+            write!(w, "])")?;
+        }
+        Variable::Range(a, b) => {
+            write!(w, "{}..{}", a, b)?;
+        }
         Variable::Object(ref obj) => {
             write!(w, "{{")?;
             let n = obj.len();
@@ -112,6 +129,34 @@ where
             }
             write!(w, "}}")?;
         }
+        Variable::Map(ref map) => {
+            // Sort keys to write entries in a predictable order.
+            let mut keys: Vec<&Key> = map.keys().collect();
+            keys.sort();
+            write!(w, "map {{")?;
+            for (i, k) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    write!(w, ", ")?;
+                }
+                write_variable(w, rt, &k.to_variable(), EscapeString::Json, tabs)?;
+                write!(w, ": ")?;
+                write_variable(w, rt, &map[k], EscapeString::Json, tabs)?;
+            }
+            write!(w, "}}")?;
+        }
+        Variable::Set(ref set) => {
+            // Sort items to write them in a predictable order.
+            let mut items: Vec<&Key> = set.iter().collect();
+            items.sort();
+            write!(w, "set {{")?;
+            for (i, x) in items.into_iter().enumerate() {
+                if i > 0 {
+                    write!(w, ", ")?;
+                }
+                write_variable(w, rt, &x.to_variable(), EscapeString::Json, tabs)?;
+            }
+            write!(w, "}}")?;
+        }
         Variable::Array(ref arr) => {
             write!(w, "[")?;
             let n = arr.len();
@@ -123,6 +168,12 @@ where
             }
             write!(w, "]")?;
         }
+        Variable::Variant(ref tag, None) => write!(w, "{}", tag)?,
+        Variable::Variant(ref tag, Some(ref v)) => {
+            write!(w, "{}(", tag)?;
+            write_variable(w, rt, v, EscapeString::Json, tabs)?;
+            write!(w, ")")?;
+        }
         Variable::Option(ref opt) => match *opt {
             None => write!(w, "none()")?,
             Some(ref v) => {
@@ -151,6 +202,8 @@ where
         Variable::Closure(ref closure, _) => write_closure(w, rt, closure, tabs)?,
         #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         Variable::In(_) => write!(w, "_in")?,
+        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+        Variable::Gen(_) => write!(w, "_in")?,
         // ref x => panic!("Could not print out `{:?}`", x)
     }
     Ok(())
@@ -210,21 +263,25 @@ fn write_expr<W: io::Write>(
         }
         E::Link(ref link) => write_link(w, rt, link, tabs)?,
         E::Object(ref obj) => write_obj(w, rt, obj, tabs)?,
+        E::Map(ref map) => write_map(w, rt, map, tabs)?,
+        E::Set(ref set) => write_set(w, rt, set, tabs)?,
         E::Array(ref arr) => write_arr(w, rt, arr, tabs)?,
         E::ArrayFill(ref arr_fill) => write_arr_fill(w, rt, arr_fill, tabs)?,
-        E::Call(ref call) => write_call(w, rt, &call.info.name, &call.args, tabs)?,
-        E::CallVoid(ref call) => write_call(w, rt, &call.info.name, &call.args, tabs)?,
-        E::CallReturn(ref call) => write_call(w, rt, &call.info.name, &call.args, tabs)?,
+        E::Call(ref call) => write_call(w, rt, &call.info, &call.args, tabs)?,
+        E::CallVoid(ref call) => write_call(w, rt, &call.info, &call.args, tabs)?,
+        E::CallReturn(ref call) => write_call(w, rt, &call.info, &call.args, tabs)?,
         E::CallBinOp(ref call) => write_call(
             w,
             rt,
-            &call.info.name,
+            &call.info,
             &[call.left.clone(), call.right.clone()],
             tabs,
         )?,
-        E::CallUnOp(ref call) => write_call(w, rt, &call.info.name, &[call.arg.clone()], tabs)?,
-        E::CallLazy(ref call) => write_call(w, rt, &call.info.name, &call.args, tabs)?,
-        E::CallLoaded(ref call) => write_call(w, rt, &call.info.name, &call.args, tabs)?,
+        E::CallUnOp(ref call) => {
+            write_call(w, rt, &call.info, std::slice::from_ref(&call.arg), tabs)?
+        }
+        E::CallLazy(ref call) => write_call(w, rt, &call.info, &call.args, tabs)?,
+        E::CallLoaded(ref call) => write_call(w, rt, &call.info, &call.args, tabs)?,
         E::Return(ref expr) => {
             write!(w, "return ")?;
             write_expr(w, rt, expr, tabs)?;
@@ -245,13 +302,30 @@ fn write_expr<W: io::Write>(
             }
         }
         E::Block(ref b) => write_block(w, rt, b, tabs)?,
+        E::Defer(ref defer) => {
+            write!(w, "defer ")?;
+            write_block(w, rt, &defer.block, tabs)?;
+        }
+        E::TryCatch(ref try_catch) => {
+            write!(w, "try ")?;
+            write_block(w, rt, &try_catch.block, tabs)?;
+            write!(w, " catch {} ", try_catch.name)?;
+            write_block(w, rt, &try_catch.catch_block, tabs)?;
+        }
         #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::Go(ref go) => {
             write!(w, "go ")?;
-            write_call(w, rt, &go.call.info.name, &go.call.args, tabs)?;
+            write_call(w, rt, &go.call.info, &go.call.args, tabs)?;
         }
         #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
         E::Go(ref go) => match **go {},
+        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
+        E::Yield(ref y) => {
+            write!(w, "yield ")?;
+            write_expr(w, rt, &y.expr, tabs)?;
+        }
+        #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
+        E::Yield(ref y) => match **y {},
         E::Assign(ref assign) => write_assign(w, rt, assign, tabs)?,
         E::Vec4(ref vec4) => write_vec4(w, rt, vec4, tabs)?,
         E::Mat4(ref mat4) => write_mat4(w, rt, mat4, tabs)?,
@@ -260,26 +334,14 @@ fn write_expr<W: io::Write>(
             write!(w, "for ")?;
             write_for_n(w, rt, for_n, tabs)?;
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::ForIn(ref for_in) => {
             write!(w, "for ")?;
             write_for_in(w, rt, for_in, tabs)?;
         }
-        #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
-        E::ForIn(ref for_in) |
-        E::SumIn(ref for_in) |
-        E::ProdIn(ref for_in) |
-        E::MinIn(ref for_in) |
-        E::MaxIn(ref for_in) |
-        E::SiftIn(ref for_in) |
-        E::AnyIn(ref for_in) |
-        E::AllIn(ref for_in) |
-        E::LinkIn(ref for_in) => match **for_in {},
         E::Sum(ref for_n) => {
             write!(w, "sum ")?;
             write_for_n(w, rt, for_n, tabs)?;
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::SumIn(ref for_in) => {
             write!(w, "sum ")?;
             write_for_in(w, rt, for_in, tabs)?;
@@ -292,7 +354,6 @@ fn write_expr<W: io::Write>(
             write!(w, "prod ")?;
             write_for_n(w, rt, for_n, tabs)?;
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::ProdIn(ref for_in) => {
             write!(w, "prod ")?;
             write_for_in(w, rt, for_in, tabs)?;
@@ -305,7 +366,6 @@ fn write_expr<W: io::Write>(
             write!(w, "min ")?;
             write_for_n(w, rt, for_n, tabs)?;
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::MinIn(ref for_in) => {
             write!(w, "min ")?;
             write_for_in(w, rt, for_in, tabs)?;
@@ -314,7 +374,6 @@ fn write_expr<W: io::Write>(
             write!(w, "max ")?;
             write_for_n(w, rt, for_n, tabs)?;
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::MaxIn(ref for_in) => {
             write!(w, "max ")?;
             write_for_in(w, rt, for_in, tabs)?;
@@ -323,7 +382,6 @@ fn write_expr<W: io::Write>(
             write!(w, "sift ")?;
             write_for_n(w, rt, for_n, tabs)?;
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::SiftIn(ref for_in) => {
             write!(w, "sift ")?;
             write_for_in(w, rt, for_in, tabs)?;
@@ -332,7 +390,6 @@ fn write_expr<W: io::Write>(
             write!(w, "any ")?;
             write_for_n(w, rt, for_n, tabs)?;
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::AnyIn(ref for_in) => {
             write!(w, "any ")?;
             write_for_in(w, rt, for_in, tabs)?;
@@ -341,7 +398,6 @@ fn write_expr<W: io::Write>(
             write!(w, "all ")?;
             write_for_n(w, rt, for_n, tabs)?;
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::AllIn(ref for_in) => {
             write!(w, "all ")?;
             write_for_in(w, rt, for_in, tabs)?;
@@ -350,7 +406,6 @@ fn write_expr<W: io::Write>(
             write!(w, "link ")?;
             write_for_n(w, rt, for_n, tabs)?;
         }
-        #[cfg(all(not(target_family = "wasm"), feature = "threading"))]
         E::LinkIn(ref for_in) => {
             write!(w, "link ")?;
             write_for_in(w, rt, for_in, tabs)?;
@@ -371,6 +426,18 @@ fn write_expr<W: io::Write>(
         } // x => panic!("Unimplemented `{:#?}`", x),
         #[cfg(not(all(not(target_family = "wasm"), feature = "threading")))]
         E::In(ref in_expr) => match **in_expr {},
+        E::Match(ref match_expr) => write_match(w, rt, match_expr, tabs)?,
+        E::Variant(ref variant) => {
+            write!(w, "{}(", variant.name)?;
+            write_expr(w, rt, &variant.expr, tabs)?;
+            write!(w, ")")?;
+        }
+        E::Interpolation(ref interp) => write_interpolation(w, rt, interp, tabs)?,
+        E::Destructure(ref destructure) => {
+            write_pattern(w, rt, &destructure.pattern)?;
+            write!(w, " := ")?;
+            write_expr(w, rt, &destructure.right, tabs)?;
+        }
     }
     Ok(())
 }
@@ -420,24 +487,27 @@ fn binop_needs_parens(op: ast::BinOp, expr: &ast::Expression, right: bool) -> bo
     use ast::Expression as E;
 
     let binop: ast::BinOp = match *expr {
-        E::Call(ref call) => match standard_binop(&call.info.name, &call.args) {
+        E::Call(ref call) => match call_binop(&call.info, &call.args) {
             Some(x) => x,
             None => return false,
         }
-        E::CallBinOp(ref call) => match standard_binop(&call.info.name,
+        E::CallBinOp(ref call) => match call_binop(&call.info,
             &[call.left.clone(), call.right.clone()])
         {
             Some(x) => x,
             None => return false,
         }
-        E::CallLazy(ref call) => match standard_binop(&call.info.name, &call.args) {
+        E::CallLazy(ref call) => match call_binop(&call.info, &call.args) {
             Some(x) => x,
             None => return false,
         }
-        E::CallReturn(ref call) => match standard_binop(&call.info.name, &call.args) {
+        E::CallReturn(ref call) => match call_binop(&call.info, &call.args) {
             Some(x) => x,
             None => return false,
         }
+        E::CallUnOp(ref call) if call.info.pipe => ast::BinOp::Pipe,
+        E::CallLoaded(ref call) if call.info.pipe => ast::BinOp::Pipe,
+        E::CallClosure(ref call) if call.pipe => ast::BinOp::Pipe,
         _ => return false
     };
     match (op.precedence(), binop.precedence()) {
@@ -446,6 +516,10 @@ fn binop_needs_parens(op: ast::BinOp, expr: &ast::Expression, right: bool) -> bo
         (ast::BINOP_PREC_MUL, ast::BINOP_PREC_MUL) if right => true,
         (ast::BINOP_PREC_ADD, ast::BINOP_PREC_ADD) if right => true,
         (ast::BINOP_PREC_ADD, ast::BINOP_PREC_EQ) => true,
+        (ast::BINOP_PREC_PIPE, ast::BINOP_PREC_EQ) => true,
+        (ast::BINOP_PREC_EQ | ast::BINOP_PREC_ADD | ast::BINOP_PREC_MUL, ast::BINOP_PREC_PIPE) => {
+            true
+        }
         _ => false,
     }
 }
@@ -512,6 +586,9 @@ fn write_item<W: io::Write>(
     }
     write!(w, "{}", item.name)?;
     for (i, id) in item.ids.iter().enumerate() {
+        if item.opt_ids.contains(&i) {
+            write!(w, "?")?;
+        }
         match *id {
             Id::String(_, ref prop) => write!(w, ".{}", prop)?,
             Id::F64(_, ind) => write!(w, "[{}]", ind)?,
@@ -566,14 +643,38 @@ fn write_obj<W: io::Write>(
     Ok(())
 }
 
+fn write_map<W: io::Write>(
+    w: &mut W,
+    rt: &Runtime,
+    map: &ast::Map,
+    tabs: u32,
+) -> Result<(), io::Error> {
+    write!(w, "map {{")?;
+    for (i, key_value) in map.key_values.iter().enumerate() {
+        write_expr(w, rt, &key_value.0, tabs + 1)?;
+        write!(w, ": ")?;
+        write_expr(w, rt, &key_value.1, tabs + 1)?;
+        if i + 1 < map.key_values.len() {
+            write!(w, ", ")?;
+        }
+    }
+    write!(w, "}}")?;
+    Ok(())
+}
+
 fn write_call<W: io::Write>(
     w: &mut W,
     rt: &Runtime,
-    name: &Arc<String>,
+    info: &ast::CallInfo,
     args: &[ast::Expression],
     tabs: u32,
 ) -> Result<(), io::Error> {
-    if &**name == "norm" && args.len() == 1 {
+    let name = &info.name;
+    if info.pipe && !args.is_empty() {
+        write_pipe(w, rt, &args[0], tabs)?;
+        let info = ast::CallInfo { pipe: false, ..info.clone() };
+        write_call(w, rt, &info, &args[1..], tabs)
+    } else if &**name == "norm" && args.len() == 1 {
         write_norm(w, rt, &args[0], tabs)
     } else if &**name == "not" && args.len() == 1 {
         write_not(w, rt, &args[0], tabs)
@@ -600,12 +701,20 @@ fn write_call_closure<W: io::Write>(
     call: &ast::CallClosure,
     tabs: u32,
 ) -> Result<(), io::Error> {
+    let mut args = &call.args[..];
+    if call.pipe && !args.is_empty() {
+        write_pipe(w, rt, &args[0], tabs)?;
+        args = &args[1..];
+    }
     write!(w, "\\")?;
     write_item(w, rt, &call.item, tabs)?;
+    if call.pipe && args.is_empty() {
+        return Ok(());
+    }
     write!(w, "(")?;
-    for (i, arg) in call.args.iter().enumerate() {
+    for (i, arg) in args.iter().enumerate() {
         write_expr(w, rt, arg, tabs + 1)?;
-        if i + 1 < call.args.len() {
+        if i + 1 < args.len() {
             write!(w, ", ")?;
         }
     }
@@ -613,6 +722,24 @@ fn write_call_closure<W: io::Write>(
     Ok(())
 }
 
+// Writes the left side of `|>`.
+fn write_pipe<W: io::Write>(
+    w: &mut W,
+    rt: &Runtime,
+    left: &ast::Expression,
+    tabs: u32,
+) -> Result<(), io::Error> {
+    let needs_parens = binop_needs_parens(ast::BinOp::Pipe, left, false);
+    if needs_parens {
+        write!(w, "(")?;
+    }
+    write_expr(w, rt, left, tabs)?;
+    if needs_parens {
+        write!(w, ")")?;
+    }
+    write!(w, " {} ", ast::BinOp::Pipe.symbol())
+}
+
 fn write_arr<W: io::Write>(
     w: &mut W,
     rt: &Runtime,
@@ -630,6 +757,55 @@ fn write_arr<W: io::Write>(
     Ok(())
 }
 
+fn write_set<W: io::Write>(
+    w: &mut W,
+    rt: &Runtime,
+    set: &ast::Set,
+    tabs: u32,
+) -> Result<(), io::Error> {
+    write!(w, "set {{")?;
+    for (i, item) in set.items.iter().enumerate() {
+        write_expr(w, rt, item, tabs + 1)?;
+        if i + 1 < set.items.len() {
+            write!(w, ", ")?;
+        }
+    }
+    write!(w, "}}")?;
+    Ok(())
+}
+
+fn write_interpolation<W: io::Write>(
+    w: &mut W,
+    rt: &Runtime,
+    interp: &ast::Interpolation,
+    tabs: u32,
+) -> Result<(), io::Error> {
+    fn write_text<W: io::Write>(w: &mut W, text: &str) -> Result<(), io::Error> {
+        for c in text.chars() {
+            match c {
+                '\n' => write!(w, "\\n")?,
+                '\t' => write!(w, "\\t")?,
+                '\\' | '"' | '{' | '}' => write!(w, "\\{}", c)?,
+                _ => write!(w, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+
+    write!(w, "$\"")?;
+    for (text, expr) in interp.texts.iter().zip(interp.exprs.iter()) {
+        write_text(w, text)?;
+        write!(w, "{{")?;
+        write_expr(w, rt, expr, tabs)?;
+        write!(w, "}}")?;
+    }
+    if let Some(text) = interp.texts.last() {
+        write_text(w, text)?;
+    }
+    write!(w, "\"")?;
+    Ok(())
+}
+
 fn write_arr_fill<W: io::Write>(
     w: &mut W,
     rt: &Runtime,
@@ -770,6 +946,15 @@ fn write_for<W: io::Write>(
     Ok(())
 }
 
+// Returns the binary operator that a call is printed with, if any.
+fn call_binop(info: &ast::CallInfo, args: &[ast::Expression]) -> Option<ast::BinOp> {
+    if info.pipe {
+        Some(ast::BinOp::Pipe)
+    } else {
+        standard_binop(&info.name, args)
+    }
+}
+
 fn standard_binop(name: &Arc<String>, args: &[ast::Expression]) -> Option<ast::BinOp> {
     use crate::ast::BinOp::*;
 
@@ -820,14 +1005,17 @@ fn write_for_n<W: io::Write>(
     Ok(())
 }
 
-#[cfg(all(not(target_family = "wasm"), feature = "threading"))]
 fn write_for_in<W: io::Write>(
     w: &mut W,
     rt: &Runtime,
     for_in: &ast::ForIn,
     tabs: u32,
 ) -> Result<(), io::Error> {
-    write!(w, "{} in ", for_in.name)?;
+    write!(w, "{}", for_in.name)?;
+    if let Some(ref value) = for_in.value {
+        write!(w, ", {}", value)?;
+    }
+    write!(w, " in ")?;
     write_expr(w, rt, &for_in.iter, tabs)?;
     write!(w, " ")?;
     write_block(w, rt, &for_in.block, tabs + 1)?;
@@ -861,6 +1049,94 @@ fn write_if<W: io::Write>(
     Ok(())
 }
 
+fn write_match<W: io::Write>(
+    w: &mut W,
+    rt: &Runtime,
+    match_expr: &ast::Match,
+    tabs: u32,
+) -> Result<(), io::Error> {
+    write!(w, "match ")?;
+    write_expr(w, rt, &match_expr.expr, tabs)?;
+    writeln!(w, " {{")?;
+    for arm in &match_expr.arms {
+        write_tabs(w, tabs + 1)?;
+        write_pattern(w, rt, &arm.pattern)?;
+        write!(w, " => ")?;
+        write_expr(w, rt, &arm.expr, tabs + 1)?;
+        writeln!(w, ",")?;
+    }
+    write_tabs(w, tabs)?;
+    write!(w, "}}")?;
+    Ok(())
+}
+
+fn write_pattern<W: io::Write>(
+    w: &mut W,
+    rt: &Runtime,
+    pattern: &ast::Pattern,
+) -> Result<(), io::Error> {
+    use ast::Pattern as P;
+
+    match *pattern {
+        P::Wildcard(_) => write!(w, "_")?,
+        P::Bind(_, ref name) => write!(w, "{}", name)?,
+        P::Value(_, ref v) => write_variable(w, rt, v, EscapeString::Json, 0)?,
+        P::Some(_, ref pat) => {
+            write!(w, "some(")?;
+            write_pattern(w, rt, pat)?;
+            write!(w, ")")?;
+        }
+        P::None(_) => write!(w, "none()")?,
+        P::Ok(_, ref pat) => {
+            write!(w, "ok(")?;
+            write_pattern(w, rt, pat)?;
+            write!(w, ")")?;
+        }
+        P::Err(_, ref pat) => {
+            write!(w, "err(")?;
+            write_pattern(w, rt, pat)?;
+            write!(w, ")")?;
+        }
+        P::Array(_, ref pats) => {
+            write!(w, "[")?;
+            for (i, pat) in pats.iter().enumerate() {
+                write_pattern(w, rt, pat)?;
+                if i + 1 < pats.len() {
+                    write!(w, ", ")?;
+                }
+            }
+            write!(w, "]")?;
+        }
+        P::Vec4(_, ref pats) => {
+            write!(w, "(")?;
+            for (i, pat) in pats.iter().enumerate() {
+                write_pattern(w, rt, pat)?;
+                if i + 1 < pats.len() {
+                    write!(w, ", ")?;
+                }
+            }
+            write!(w, ")")?;
+        }
+        P::Variant(_, ref name, ref pat) => {
+            write!(w, "{}(", name)?;
+            write_pattern(w, rt, pat)?;
+            write!(w, ")")?;
+        }
+        P::Object(_, ref key_values) => {
+            write!(w, "{{")?;
+            for (i, (key, pat)) in key_values.iter().enumerate() {
+                write!(w, "{}: ", key)?;
+                write_pattern(w, rt, pat)?;
+                if i + 1 < key_values.len() {
+                    write!(w, ", ")?;
+                }
+            }
+            write!(w, "}}")?;
+        }
+    }
+    Ok(())
+}
+
 fn write_grab<W: io::Write>(
     w: &mut W,
     rt: &Runtime,
@@ -35,6 +35,12 @@ pub fn run_src(source: &str) {
     });
 }
 
+pub fn run_fail_src(source: &str) {
+    if run(source).is_ok() {
+        panic!("`{}` should fail", source);
+    }
+}
+
 #[cfg(feature = "file")]
 #[test]
 fn test_syntax() {
@@ -152,6 +158,76 @@ fn test_syntax() {
     test_src("source/syntax/lazy_pass_7.dyon");
     test_src("source/syntax/lazy_pass_8.dyon");
     test_src("source/syntax/returns.dyon");
+    test_src("source/syntax/match.dyon");
+    test_fail_src("source/syntax/match_fail_1.dyon");
+    test_fail_src("source/syntax/match_fail_2.dyon");
+    test_fail_src("source/syntax/match_fail_3.dyon");
+    test_fail_src("source/syntax/match_fail_4.dyon");
+    test_src("source/syntax/enum.dyon");
+    test_fail_src("source/syntax/enum_fail_1.dyon");
+    test_fail_src("source/syntax/enum_fail_2.dyon");
+    test_fail_src("source/syntax/enum_fail_3.dyon");
+    test_src("source/syntax/interp.dyon");
+    test_fail_src("source/syntax/interp_fail_1.dyon");
+    test_src("source/syntax/destructure.dyon");
+    test_fail_src("source/syntax/destructure_fail_1.dyon");
+    test_src("source/syntax/for_in_collections.dyon");
+    test_fail_src("source/syntax/for_in_fail_1.dyon");
+    test_fail_src("source/syntax/for_in_fail_2.dyon");
+    test_src("source/syntax/default_args.dyon");
+    test_fail_src("source/syntax/default_args_fail_1.dyon");
+    test_fail_src("source/syntax/default_args_fail_2.dyon");
+    test_fail_src("source/syntax/default_args_fail_3.dyon");
+    test_fail_src("source/syntax/default_args_fail_4.dyon");
+    test_src("source/syntax/const.dyon");
+    test_fail_src("source/syntax/const_fail_1.dyon");
+    test_fail_src("source/syntax/const_fail_2.dyon");
+    test_fail_src("source/syntax/const_fail_3.dyon");
+    test_fail_src("source/syntax/const_fail_4.dyon");
+    test_src("source/syntax/method_call.dyon");
+    test_fail_src("source/syntax/method_call_fail_1.dyon");
+    test_fail_src("source/syntax/method_call_fail_2.dyon");
+    test_src("source/syntax/generator.dyon");
+    test_fail_src("source/syntax/generator_fail_1.dyon");
+    test_fail_src("source/syntax/generator_fail_2.dyon");
+    test_fail_src("source/syntax/generator_fail_3.dyon");
+    test_fail_src("source/syntax/generator_fail_4.dyon");
+    test_src("source/syntax/i64.dyon");
+    test_fail_src("source/syntax/i64_fail_1.dyon");
+    test_src("source/syntax/bytes.dyon");
+    test_fail_src("source/syntax/bytes_fail_1.dyon");
+    test_src("source/syntax/map.dyon");
+    test_fail_src("source/syntax/map_fail_1.dyon");
+    test_src("source/syntax/set.dyon");
+    test_fail_src("source/syntax/set_fail_1.dyon");
+    test_src("source/syntax/opt_chain.dyon");
+    test_fail_src("source/syntax/opt_chain_fail_1.dyon");
+    test_fail_src("source/syntax/opt_chain_fail_2.dyon");
+    test_fail_src("source/syntax/opt_chain_fail_4.dyon");
+    test_src("source/syntax/defer.dyon");
+    test_src("source/syntax/try_catch.dyon");
+    test_fail_src("source/syntax/try_catch_fail_1.dyon");
+    test_fail_src("source/syntax/try_catch_fail_2.dyon");
+    test_src("source/syntax/record.dyon");
+    test_fail_src("source/syntax/record_fail_1.dyon");
+    test_fail_src("source/syntax/record_fail_2.dyon");
+    test_fail_src("source/syntax/record_fail_3.dyon");
+    test_src("source/syntax/generic.dyon");
+    test_fail_src("source/syntax/generic_fail_1.dyon");
+    test_fail_src("source/syntax/generic_fail_2.dyon");
+    test_src("source/syntax/type_alias.dyon");
+    test_fail_src("source/syntax/type_alias_fail_1.dyon");
+    test_fail_src("source/syntax/type_alias_fail_2.dyon");
+    test_src("source/syntax/union.dyon");
+    test_fail_src("source/syntax/union_fail_1.dyon");
+    test_fail_src("source/syntax/union_fail_2.dyon");
+    test_fail_src("source/syntax/union_fail_3.dyon");
+    test_src("source/syntax/pipe.dyon");
+    test_fail_src("source/syntax/pipe_fail_1.dyon");
+    test_src("source/syntax/range.dyon");
+    test_fail_src("source/syntax/range_fail_2.dyon");
+    test_src("source/syntax/assert.dyon");
+    test_fail_src("source/syntax/assert_fail_3.dyon");
 }
 
 #[cfg(feature = "file")]
@@ -211,6 +287,8 @@ fn test_typechk() {
     test_fail_src("source/typechk/add_ad_hoc_3.dyon");
     test_fail_src("source/typechk/add_ad_hoc_4.dyon");
     test_fail_src("source/typechk/mul_ad_hoc.dyon");
+    test_src("source/typechk/overload.dyon");
+    test_fail_src("source/typechk/overload_2.dyon");
     test_src("source/typechk/unop.dyon");
     test_fail_src("source/typechk/prod.dyon");
     test_src("source/typechk/closure.dyon");
@@ -293,6 +371,42 @@ fn test_typechk() {
 #[test]
 fn test_runs() {
     run_src("source/print_closure/binop.dyon");
+    run_src("source/syntax/match.dyon");
+    run_src("source/syntax/enum.dyon");
+    run_src("source/syntax/interp.dyon");
+    run_src("source/syntax/destructure.dyon");
+    run_fail_src("source/syntax/destructure_fail_2.dyon");
+    run_src("source/syntax/for_in_collections.dyon");
+    run_src("source/syntax/default_args.dyon");
+    run_src("source/syntax/const.dyon");
+    run_src("source/syntax/method_call.dyon");
+    run_src("source/typechk/overload.dyon");
+    run_src("source/syntax/generator.dyon");
+    run_src("source/syntax/i64.dyon");
+    run_fail_src("source/syntax/i64_fail_2.dyon");
+    run_src("source/syntax/bytes.dyon");
+    run_fail_src("source/syntax/bytes_fail_2.dyon");
+    run_fail_src("source/syntax/bytes_fail_3.dyon");
+    run_fail_src("source/syntax/bytes_fail_4.dyon");
+    run_src("source/syntax/map.dyon");
+    run_fail_src("source/syntax/map_fail_2.dyon");
+    run_src("source/syntax/set.dyon");
+    run_fail_src("source/syntax/set_fail_2.dyon");
+    run_src("source/syntax/opt_chain.dyon");
+    run_fail_src("source/syntax/opt_chain_fail_3.dyon");
+    run_src("source/syntax/defer.dyon");
+    run_fail_src("source/syntax/defer_fail_1.dyon");
+    run_src("source/syntax/try_catch.dyon");
+    run_src("source/syntax/record.dyon");
+    run_src("source/syntax/generic.dyon");
+    run_src("source/syntax/type_alias.dyon");
+    run_src("source/syntax/union.dyon");
+    run_src("source/syntax/pipe.dyon");
+    run_src("source/syntax/range.dyon");
+    run_fail_src("source/syntax/range_fail_1.dyon");
+    run_src("source/syntax/assert.dyon");
+    run_fail_src("source/syntax/assert_fail_1.dyon");
+    run_fail_src("source/syntax/assert_fail_2.dyon");
 }
 
 #[cfg(feature = "file")]
@@ -313,6 +427,52 @@ fn test_error() {
     test_src("source/error/option.dyon");
 }
 
+#[cfg(feature = "file")]
+#[test]
+fn test_strip_asserts() {
+    use std::sync::Arc;
+
+    for source in ["source/syntax/assert_fail_1.dyon", "source/syntax/assert_fail_2.dyon"] {
+        let mut module = Module::new();
+        module.strip_asserts(true);
+        load(source, &mut module).unwrap_or_else(|err| {
+            panic!("In `{}`:\n{}", source, err);
+        });
+        Runtime::new().run(&Arc::new(module)).unwrap_or_else(|err| {
+            panic!("In `{}`:\n{}", source, err);
+        });
+    }
+}
+
+#[cfg(feature = "file")]
+#[test]
+fn test_warnings() {
+    let source = "source/syntax/warnings.dyon";
+    let mut module = Module::new();
+    let report = load_report(source, &mut module).unwrap_or_else(|err| {
+        panic!("In `{}`:\n{}", source, err);
+    });
+    let warnings = report.warnings.join("\n");
+    assert!(warnings.contains("Unused argument `b`"));
+    assert!(!warnings.contains("Unused argument `_c`"));
+    assert!(warnings.contains("Unused variable `y`"));
+    assert!(warnings.contains("Declaration of `x` shadows an earlier declaration"));
+    assert!(warnings.contains("Unreachable statement\n7,9:"));
+    assert_eq!(report.warnings.len(), 4);
+}
+
+#[cfg(feature = "file")]
+#[test]
+fn test_match_warnings() {
+    let source = "source/syntax/match_warn_1.dyon";
+    let mut module = Module::new();
+    let report = load_report(source, &mut module).unwrap_or_else(|err| {
+        panic!("In `{}`:\n{}", source, err);
+    });
+    assert_eq!(report.warnings.len(), 1);
+    assert!(report.warnings[0].contains("Pattern `err(_)` is not covered"));
+}
+
 #[test]
 fn variable_size() {
     use std::mem::size_of;